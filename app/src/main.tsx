@@ -1,5 +1,5 @@
 import App from "@/App";
-import { SqueueRow } from "@/AppContext";
+import { OCELExtractionProgress, SqueueRow } from "@/AppContext";
 import { invoke } from "@tauri-apps/api/core";
 import { listen } from "@tauri-apps/api/event";
 import React from "react";
@@ -23,8 +23,14 @@ ReactDOM.createRoot(document.getElementById("root") as HTMLElement).render(
       getSqueue: async () => {
         return await invoke("get_squeue");
       },
-      extractOCEL: async () => {
-        return await invoke("extract_ocel");
+      extractOCEL: async ({ format, includeResourceUsage }) => {
+        return await invoke("extract_ocel", { format, includeResourceUsage });
+      },
+      cancelOCELExtraction: async () => {
+        return await invoke("cancel_ocel_extraction");
+      },
+      listenOCELExtractionProgress: (listener) => {
+        return listen<OCELExtractionProgress>("ocel-extraction-progress", (e) => listener(e.payload))
       },
       login: async (cfg) => {
         return await invoke("login", { cfg });
@@ -44,6 +50,9 @@ ReactDOM.createRoot(document.getElementById("root") as HTMLElement).render(
       checkJobStatus: async (jobID: string) => {
         return await invoke("check_job_status",{jobId: jobID})
       },
+      getJobEfficiency: async (jobID: string) => {
+        return await invoke("get_job_efficiency",{jobId: jobID})
+      },
     }} />
   </React.StrictMode>,
 );
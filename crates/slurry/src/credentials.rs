@@ -0,0 +1,179 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+use sha1_smol::Sha1;
+
+use crate::ConnectionAuth;
+
+/// Service name under which credentials are stored in the OS keychain by default
+pub const DEFAULT_KEYRING_SERVICE: &str = "slurry";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// Kind of a stored credential
+pub enum CredentialKind {
+    /// Password for logging in
+    Password,
+    /// Passphrase of an SSH key
+    Passphrase,
+    /// TOTP secret (base32 encoded, as shown by most MFA setup pages), used to generate MFA codes
+    TotpSecret,
+}
+
+impl std::fmt::Display for CredentialKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CredentialKind::Password => write!(f, "password"),
+            CredentialKind::Passphrase => write!(f, "passphrase"),
+            CredentialKind::TotpSecret => write!(f, "totp-secret"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Credentials stored in the OS keychain (e.g., macOS Keychain, Windows Credential Manager or the Secret Service on Linux)
+///
+/// Credentials are identified by an ID (e.g., `hpc`), which is referenced by [`ConnectionConfig::credential_id`](crate::ConnectionConfig::credential_id)
+/// instead of storing secrets in the (serialized) connection config.
+pub struct CredentialStore {
+    service: String,
+}
+
+impl Default for CredentialStore {
+    fn default() -> Self {
+        Self::new(DEFAULT_KEYRING_SERVICE)
+    }
+}
+
+impl CredentialStore {
+    /// Use the given service name in the OS keychain
+    pub fn new(service: impl Into<String>) -> Self {
+        Self {
+            service: service.into(),
+        }
+    }
+
+    fn entry(&self, id: &str, kind: CredentialKind) -> Result<keyring::Entry, Error> {
+        Ok(keyring::Entry::new(&self.service, &format!("{id}/{kind}"))?)
+    }
+
+    /// Store a credential, replacing an existing one
+    pub fn store(&self, id: &str, kind: CredentialKind, secret: &str) -> Result<(), Error> {
+        Ok(self.entry(id, kind)?.set_password(secret)?)
+    }
+
+    /// Get a credential, or `None` if it is not stored
+    pub fn get(&self, id: &str, kind: CredentialKind) -> Result<Option<String>, Error> {
+        match self.entry(id, kind)?.get_password() {
+            Ok(secret) => Ok(Some(secret)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Delete a credential (if it is stored)
+    pub fn delete(&self, id: &str, kind: CredentialKind) -> Result<(), Error> {
+        match self.entry(id, kind)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Generate the current MFA code from the stored TOTP secret
+    pub fn totp_code(&self, id: &str) -> Result<String, Error> {
+        let secret = self
+            .get(id, CredentialKind::TotpSecret)?
+            .ok_or_else(|| Error::msg(format!("No TOTP secret stored for {id}.")))?;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        totp(&secret, now)
+    }
+
+    /// Fill in missing secrets of `auth` from the credentials stored for `id`
+    ///
+    /// For [`ConnectionAuth::PasswordMFA`], an empty password is replaced by the stored password,
+    /// and an empty MFA code (without MFA handler) by a code generated from the stored TOTP secret.
+    /// For [`ConnectionAuth::SSHKey`], a missing passphrase is replaced by the stored passphrase.
+    pub fn fill_auth(&self, id: &str, auth: &ConnectionAuth) -> Result<ConnectionAuth, Error> {
+        let mut auth = auth.clone();
+        match &mut auth {
+            ConnectionAuth::PasswordMFA {
+                password,
+                mfa_code,
+                mfa_handler,
+            } => {
+                if password.is_empty() {
+                    *password = self
+                        .get(id, CredentialKind::Password)?
+                        .ok_or_else(|| Error::msg(format!("No password stored for {id}.")))?;
+                }
+                if mfa_code.is_empty() && mfa_handler.is_none() {
+                    *mfa_code = self.totp_code(id)?;
+                }
+            }
+            ConnectionAuth::SSHKey { passphrase, .. } => {
+                if passphrase.is_none() {
+                    *passphrase = self.get(id, CredentialKind::Passphrase)?;
+                }
+            }
+            ConnectionAuth::Interactive { .. } => {}
+        }
+        Ok(auth)
+    }
+}
+
+/// Generate a TOTP code (RFC 6238, i.e., 6 digits, 30 second period, HMAC-SHA1) for the base32 encoded `secret` at `unix_time`
+pub fn totp(secret: &str, unix_time: u64) -> Result<String, Error> {
+    let secret: String = secret
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '=')
+        .collect::<String>()
+        .to_uppercase();
+    let key = base32::decode(base32::Alphabet::Rfc4648 { padding: false }, &secret)
+        .ok_or_else(|| Error::msg("Invalid TOTP secret (expected base32)."))?;
+    let mac = hmac_sha1(&key, &(unix_time / 30).to_be_bytes());
+    // Dynamic truncation
+    let offset = (mac[19] & 0x0f) as usize;
+    let code = u32::from_be_bytes([
+        mac[offset],
+        mac[offset + 1],
+        mac[offset + 2],
+        mac[offset + 3],
+    ]) & 0x7fff_ffff;
+    Ok(format!("{:06}", code % 1_000_000))
+}
+
+fn hmac_sha1(key: &[u8], message: &[u8]) -> [u8; 20] {
+    const BLOCK_SIZE: usize = 64;
+    let mut block = [0u8; BLOCK_SIZE];
+    if key.len() > BLOCK_SIZE {
+        block[..20].copy_from_slice(&Sha1::from(key).digest().bytes());
+    } else {
+        block[..key.len()].copy_from_slice(key);
+    }
+    let mut inner = Sha1::new();
+    inner.update(&block.map(|b| b ^ 0x36));
+    inner.update(message);
+    let mut outer = Sha1::new();
+    outer.update(&block.map(|b| b ^ 0x5c));
+    outer.update(&inner.digest().bytes());
+    outer.digest().bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::totp;
+
+    #[test]
+    fn test_totp() {
+        // Test vectors of RFC 6238 (secret `12345678901234567890`), truncated to 6 digits
+        let secret = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+        assert_eq!(totp(secret, 59).unwrap(), "287082");
+        assert_eq!(totp(secret, 1111111109).unwrap(), "081804");
+        assert_eq!(totp(secret, 2000000000).unwrap(), "279037");
+        assert_eq!(
+            totp("gezd gnbv gy3t qojq gezd gnbv gy3t qojq", 59).unwrap(),
+            "287082"
+        );
+        assert!(totp("not base32!", 59).is_err());
+    }
+}
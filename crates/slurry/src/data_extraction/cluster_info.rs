@@ -0,0 +1,132 @@
+use anyhow::Error;
+use chrono::{DateTime, FixedOffset, NaiveDateTime, Utc};
+use serde::{Deserialize, Deserializer};
+
+#[cfg(feature = "ssh")]
+use async_ssh2_tokio::Client;
+
+use crate::executor::{CommandExecutor, LocalExecutor};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// General information about a SLURM cluster
+pub struct ClusterInfo {
+    /// The UTC offset of the cluster's local time
+    ///
+    /// SLURM commands (e.g., `squeue`) report timestamps in this local time, without any timezone information.
+    pub timezone: FixedOffset,
+}
+
+impl Default for ClusterInfo {
+    fn default() -> Self {
+        Self {
+            timezone: FixedOffset::east_opt(0).unwrap(),
+        }
+    }
+}
+
+impl ClusterInfo {
+    /// Detect the UTC offset of the cluster's local time (using `date +%z`) with the provided `executor`
+    ///
+    /// Note that the offset is only valid at the current point in time (e.g., it changes with daylight saving time).
+    pub async fn detect_timezone_with(
+        executor: &impl CommandExecutor,
+    ) -> Result<FixedOffset, Error> {
+        let output = executor.execute("date +%z").await?.stdout;
+        parse_utc_offset(output.trim())
+    }
+
+    /// Detect the UTC offset of the local time (i.e., not via SSH)
+    pub async fn detect_timezone_locally() -> Result<FixedOffset, Error> {
+        Self::detect_timezone_with(&LocalExecutor).await
+    }
+
+    #[cfg(feature = "ssh")]
+    /// Detect the UTC offset of the cluster's local time over SSH
+    pub async fn detect_timezone(client: &Client) -> Result<FixedOffset, Error> {
+        Self::detect_timezone_with(client).await
+    }
+}
+
+// e.g., +0100 or -0530
+pub(crate) fn parse_utc_offset(s: &str) -> Result<FixedOffset, Error> {
+    let invalid = || Error::msg(format!("Invalid UTC offset {s:?}."));
+    let (sign, digits) = match s.split_at_checked(1) {
+        Some(("+", digits)) => (1, digits),
+        Some(("-", digits)) => (-1, digits),
+        _ => return Err(invalid()),
+    };
+    let digits = digits.replace(":", "");
+    if digits.len() != 4 {
+        return Err(invalid());
+    }
+    let hours: i32 = digits[..2].parse()?;
+    let mins: i32 = digits[2..].parse()?;
+    FixedOffset::east_opt(sign * (hours * 60 * 60 + mins * 60)).ok_or_else(invalid)
+}
+
+/// Interpret a timestamp as reported by SLURM (e.g., `2025-01-14T10:23:45`) in the given cluster timezone
+pub(crate) fn parse_slurm_time(s: &str, tz: &FixedOffset) -> Result<DateTime<Utc>, Error> {
+    let naive = NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S")?;
+    Ok(naive
+        .and_local_timezone(*tz)
+        .single()
+        .ok_or_else(|| Error::msg(format!("Ambiguous timestamp {s}.")))?
+        .to_utc())
+}
+
+// Recordings created before timestamps were converted to UTC contain timestamps without timezone,
+// which are interpreted in the local timezone.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum MaybeNaiveDateTime {
+    Utc(DateTime<Utc>),
+    Naive(NaiveDateTime),
+}
+
+impl From<MaybeNaiveDateTime> for DateTime<Utc> {
+    fn from(value: MaybeNaiveDateTime) -> Self {
+        match value {
+            MaybeNaiveDateTime::Utc(dt) => dt,
+            MaybeNaiveDateTime::Naive(naive) => naive
+                .and_local_timezone(chrono::Local)
+                .earliest()
+                .map(|dt| dt.to_utc())
+                .unwrap_or_else(|| naive.and_utc()),
+        }
+    }
+}
+
+/// Deserialize a UTC timestamp, also accepting timestamps without timezone (interpreted in the local timezone)
+pub(crate) fn deserialize_utc<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<DateTime<Utc>, D::Error> {
+    Ok(MaybeNaiveDateTime::deserialize(deserializer)?.into())
+}
+
+/// Deserialize an optional UTC timestamp, also accepting timestamps without timezone (interpreted in the local timezone)
+pub(crate) fn deserialize_utc_opt<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<DateTime<Utc>>, D::Error> {
+    Ok(Option::<MaybeNaiveDateTime>::deserialize(deserializer)?.map(|dt| dt.into()))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{FixedOffset, TimeZone, Utc};
+
+    use super::{parse_slurm_time, parse_utc_offset};
+
+    #[test]
+    fn test_parse_timezone() {
+        let cet = parse_utc_offset("+0100").unwrap();
+        assert_eq!(cet, FixedOffset::east_opt(3600).unwrap());
+        assert_eq!(
+            parse_utc_offset("-0530").unwrap(),
+            FixedOffset::west_opt(5 * 3600 + 30 * 60).unwrap()
+        );
+        assert!(parse_utc_offset("CET").is_err());
+
+        let time = parse_slurm_time("2025-01-14T10:23:45", &cet).unwrap();
+        assert_eq!(time, Utc.with_ymd_and_hms(2025, 1, 14, 9, 23, 45).unwrap());
+    }
+}
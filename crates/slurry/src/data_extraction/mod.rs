@@ -1,7 +1,93 @@
 /// Module for extracting data using the `squeue` command
 pub mod squeue;
 
-pub use squeue::{get_squeue_res, get_squeue_res_locally, squeue_diff, SqueueMode};
+/// Module for extracting node and partition data using the `sinfo` command
+pub mod sinfo;
+
+/// Module for extracting detailed job and reservation data using the `scontrol` command
+pub mod scontrol;
+
+/// Module for extracting resource usage statistics of running jobs using the `sstat` command
+pub mod sstat;
+
+/// Module for extracting priority factors of pending jobs using the `sprio` command
+pub mod sprio;
+
+/// Module for extracting scheduler statistics using the `sdiag` command
+pub mod sdiag;
+
+/// Module for extracting accounting data of finished jobs using the `sacct` command
+pub mod sacct;
+
+/// Module for extracting accounting configuration (e.g., QOS limits) using the `sacctmgr` command
+pub mod sacctmgr;
+
+/// Module for general information about SLURM clusters (e.g., their timezone)
+pub mod cluster_info;
+
+pub use squeue::{
+    get_estimated_start_times, get_estimated_start_times_locally, get_squeue_report,
+    get_squeue_report_locally, get_squeue_res, get_squeue_res_locally,
+    get_squeue_res_locally_with_source, get_squeue_res_with_source, parse_squeue_output,
+    squeue_diff, squeue_diff_stream, squeue_diff_with_final_states, squeue_diff_with_store,
+    RowParseError, SqueueDiffEvent, SqueueMode, SqueueParseReport, SqueueSource,
+};
+
+pub use sinfo::{
+    get_sinfo_nodes, get_sinfo_nodes_locally, get_sinfo_partitions, get_sinfo_partitions_locally,
+    sinfo_diff, sinfo_diff_with_store, NodeState, SinfoNodeRow, SinfoPartitionRow,
+};
+
+pub use scontrol::{
+    get_job_details, get_job_details_locally, get_license_usage, get_license_usage_locally,
+    get_reservations, get_reservations_locally, JobDetails, LicenseUsage, Reservation,
+};
+
+pub use sstat::{get_sstat_for_job, get_sstat_for_job_locally, SstatStepRow};
+
+pub use sprio::{get_sprio_res, get_sprio_res_locally, SprioRow};
+
+pub use sdiag::{get_sdiag_res, get_sdiag_res_locally, SdiagStats};
+
+pub use sacct::{
+    get_job_efficiency, get_job_efficiency_locally, get_job_resource_usage,
+    get_job_resource_usage_locally, get_sacct_res, get_sacct_res_locally, JobEfficiency,
+    JobResourceUsage, SacctHistoryRow, SacctJobRow, SACCT_HISTORY_FORMAT_STR,
+};
+
+pub use sacctmgr::{
+    get_associations, get_associations_locally, get_qos_list, get_qos_list_locally, AccountNode,
+    AccountTree, AssociationRow, QosRow,
+};
+
+pub use cluster_info::ClusterInfo;
+
+#[cfg(feature = "stream")]
+pub use squeue::{parse_squeue_stream, stream_squeue_res_locally};
+
+#[cfg(feature = "ssh")]
+pub use squeue::{
+    get_estimated_start_times_ssh, get_squeue_report_ssh, get_squeue_res_ssh,
+    get_squeue_res_ssh_with_source, stream_squeue_res_ssh,
+};
+
+#[cfg(feature = "ssh")]
+pub use sinfo::{get_sinfo_nodes_ssh, get_sinfo_partitions_ssh};
+
+#[cfg(feature = "ssh")]
+pub use scontrol::{get_job_details_ssh, get_license_usage_ssh, get_reservations_ssh};
+
+#[cfg(feature = "ssh")]
+pub use sstat::get_sstat_for_job_ssh;
+
+#[cfg(feature = "ssh")]
+pub use sprio::get_sprio_res_ssh;
+
+#[cfg(feature = "ssh")]
+pub use sdiag::get_sdiag_res_ssh;
+
+#[cfg(feature = "ssh")]
+pub use sacct::{get_job_efficiency_ssh, get_job_resource_usage_ssh, get_sacct_res_ssh};
 
 #[cfg(feature = "ssh")]
-pub use squeue::get_squeue_res_ssh;
+pub use sacctmgr::{get_associations_ssh, get_qos_list_ssh};
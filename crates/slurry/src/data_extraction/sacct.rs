@@ -0,0 +1,639 @@
+use std::{collections::HashMap, path::PathBuf, time::Duration};
+
+use anyhow::Error;
+use chrono::{DateTime, FixedOffset, Utc};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssh")]
+use async_ssh2_tokio::Client;
+
+use super::sacctmgr::parse_tres_map;
+use super::{cluster_info::parse_slurm_time, squeue::SqueueRow};
+use crate::{
+    executor::{shell_quote, CommandExecutor, LocalExecutor},
+    parse_slurm_duration, parse_slurm_size, ArrayJobId, JobState, MemorySpec, SlurmDuration,
+};
+
+// https://slurm.schedmd.com/sacct.html
+pub(crate) const SACCT_FORMAT_STR: &str = "JobIDRaw,State,ExitCode,End,Elapsed";
+pub(crate) const SACCT_USAGE_FORMAT_STR: &str = "JobIDRaw,ConsumedEnergyRaw,MaxRSS,TRESUsageInTot";
+pub(crate) const SACCT_EFFICIENCY_FORMAT_STR: &str =
+    "JobIDRaw,JobID,State,AllocCPUS,NNodes,Elapsed,TotalCPU,ReqMem,MaxRSS";
+/// Format of `sacct` history dumps (see [`SacctHistoryRow`])
+pub const SACCT_HISTORY_FORMAT_STR: &str = "JobIDRaw,JobID,Account,User,Group,Partition,Submit,Start,End,State,ExitCode,NodeList,AllocCPUS,NNodes,Timelimit,ReqMem,WorkDir,JobName";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+/// Struct for parsed output row of `sacct` command
+///
+/// Containing the accounting information of a (finished) SLURM job, which is no longer listed by `squeue`
+pub struct SacctJobRow {
+    /// "`JobIDRaw`", matching the job ID reported by `squeue`
+    pub job_id: String,
+    /// "State", e.g., `COMPLETED` or `CANCELLED`
+    pub state: JobState,
+    /// "`ExitCode`", the exit code of the job script
+    pub exit_code: i32,
+    /// "`ExitCode`", the signal which terminated the job (or `0`)
+    pub signal: i32,
+    /// "End"
+    pub end_time: Option<DateTime<Utc>>,
+    /// "Elapsed", the time the job was running
+    pub elapsed: Option<Duration>,
+}
+
+impl SacctJobRow {
+    fn parse_from_strs(vals: &[&str], tz: &FixedOffset) -> Result<Self, Error> {
+        if vals.len() != 5 {
+            return Err(Error::msg("Invalid length of values."));
+        }
+        let (exit_code, signal) = vals[2]
+            .split_once(":")
+            .ok_or_else(|| Error::msg(format!("Invalid exit code {:?}.", vals[2])))?;
+        Ok(Self {
+            job_id: vals[0].to_string(),
+            // e.g., `CANCELLED by 12345`
+            state: vals[1].split(" ").next().unwrap_or_default().parse()?,
+            exit_code: exit_code.parse()?,
+            signal: signal.parse()?,
+            end_time: match vals[3] {
+                "Unknown" | "None" | "" => None,
+                s => Some(parse_slurm_time(s, tz)?),
+            },
+            elapsed: parse_slurm_duration(vals[4]).ok(),
+        })
+    }
+
+    /// The final `squeue` row of a job, given its `last_row` as reported by `squeue`
+    pub fn apply_to(&self, last_row: &SqueueRow) -> SqueueRow {
+        let mut row = last_row.clone();
+        row.state = self.state.clone();
+        row.exit_code = Some(self.exit_code);
+        if self.end_time.is_some() {
+            row.end_time = self.end_time;
+        }
+        row
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+/// Struct for a parsed row of a `sacct` history dump, containing the accounting information of a single job
+///
+/// Dumps can be created using `sacct -a -X -P --format=<SACCT_HISTORY_FORMAT_STR> -S <start> -E <end>` (see [`SACCT_HISTORY_FORMAT_STR`]).
+pub struct SacctHistoryRow {
+    /// "`JobIDRaw`", matching the job ID reported by `squeue`
+    pub job_id: String,
+    /// "`JobID`", e.g., `49869434_2` for array tasks
+    pub step_job_id: ArrayJobId,
+    /// "Account"
+    pub account: String,
+    /// "User"
+    pub user: String,
+    /// "Group"
+    pub group: String,
+    /// "Partition"
+    pub partition: String,
+    /// "Submit"
+    pub submit_time: DateTime<Utc>,
+    /// "Start", if the job was started
+    pub start_time: Option<DateTime<Utc>>,
+    /// "End", if the job ended
+    pub end_time: Option<DateTime<Utc>>,
+    /// "State", e.g., `COMPLETED` or `CANCELLED`
+    pub state: JobState,
+    /// "`ExitCode`", the exit code of the job script
+    pub exit_code: i32,
+    /// "`NodeList`", the allocated nodes (e.g., `n23m[0001-0002]`), if the job was started
+    pub node_list: Option<String>,
+    /// "`AllocCPUS`"
+    pub cpus: usize,
+    /// "`NNodes`"
+    pub nodes: usize,
+    /// "Timelimit"
+    pub time_limit: Option<Duration>,
+    /// "`ReqMem`"
+    pub min_memory: Option<MemorySpec>,
+    /// "`WorkDir`"
+    pub work_dir: PathBuf,
+    /// "`JobName`"
+    pub name: String,
+}
+
+impl SacctHistoryRow {
+    /// Parse a single line of a `sacct` history dump (using the [`SACCT_HISTORY_FORMAT_STR`] format)
+    ///
+    /// Timestamps are interpreted in the cluster timezone `tz` (see [`ClusterInfo`](super::ClusterInfo)).
+    pub fn parse_line(line: &str, tz: &FixedOffset) -> Result<Self, Error> {
+        let vals: Vec<_> = line.split("|").map(|s| s.trim()).collect();
+        if vals.len() != 18 {
+            return Err(Error::msg("Invalid length of values."));
+        }
+        let parse_time = |s: &str| match s {
+            "Unknown" | "None" | "" => Ok(None),
+            s => parse_slurm_time(s, tz).map(Some),
+        };
+        let (exit_code, _signal) = vals[10]
+            .split_once(":")
+            .ok_or_else(|| Error::msg(format!("Invalid exit code {:?}.", vals[10])))?;
+        Ok(Self {
+            job_id: vals[0].to_string(),
+            step_job_id: vals[1].parse()?,
+            account: vals[2].to_string(),
+            user: vals[3].to_string(),
+            group: vals[4].to_string(),
+            partition: vals[5].to_string(),
+            submit_time: parse_slurm_time(vals[6], tz)?,
+            start_time: parse_time(vals[7])?,
+            end_time: parse_time(vals[8])?,
+            // e.g., `CANCELLED by 12345`
+            state: vals[9].split(" ").next().unwrap_or_default().parse()?,
+            exit_code: exit_code.parse()?,
+            node_list: match vals[11] {
+                "None assigned" | "" => None,
+                s => Some(s.to_string()),
+            },
+            cpus: vals[12].parse()?,
+            nodes: vals[13].parse()?,
+            time_limit: parse_slurm_duration(vals[14]).ok(),
+            min_memory: vals[15].parse().ok(),
+            work_dir: PathBuf::from(vals[16]),
+            name: vals[17].to_string(),
+        })
+    }
+
+    /// The first allocated node (e.g., `n23m0001` for `n23m[0001-0002]`), corresponding to the `EXEC_HOST` reported by `squeue`
+    pub fn first_node(&self) -> Option<String> {
+        let first = self.node_list.as_deref()?.split(",").next()?;
+        match first.split_once("[") {
+            Some((prefix, ranges)) => {
+                let start = ranges.split(['-', ']']).next()?;
+                Some(format!("{prefix}{start}"))
+            }
+            None => Some(first.to_string()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+/// Resource usage of a (finished) SLURM job, aggregated over all its steps (as reported by `sacct`)
+pub struct JobResourceUsage {
+    /// "`JobIDRaw`" (without step suffix)
+    pub job_id: String,
+    /// "`ConsumedEnergyRaw`", energy consumed by the job in joules (if energy accounting is enabled)
+    pub consumed_energy: Option<u64>,
+    /// "`MaxRSS`", maximal resident set size of all steps in bytes
+    pub max_rss: Option<u64>,
+    /// "`TRESUsageInTot`" per step (e.g., `batch`, `extern`, or `0`), e.g., `{"cpu": "00:01:00", "mem": "1234K"}`
+    pub tres_usage_in_tot: HashMap<String, HashMap<String, String>>,
+}
+
+impl JobResourceUsage {
+    /// Add a single output line of `sacct` (using the [`SACCT_USAGE_FORMAT_STR`] format) of this job
+    fn add_line(&mut self, step: Option<&str>, vals: &[&str]) -> Result<(), Error> {
+        if let Ok(energy) = vals[1].parse::<u64>() {
+            // The allocation reports the total energy of all steps
+            self.consumed_energy = self.consumed_energy.max(Some(energy));
+        }
+        if !vals[2].is_empty() {
+            self.max_rss = self.max_rss.max(Some(parse_slurm_size(vals[2])?));
+        }
+        if let Some(step) = step {
+            if !vals[3].is_empty() {
+                self.tres_usage_in_tot
+                    .insert(step.to_string(), parse_tres_map(vals[3]));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Get the accounting information of the jobs with the given `job_ids` using `sacct` and the provided `executor`
+///
+/// Timestamps reported by `sacct` are interpreted in the cluster timezone `tz` (see [`ClusterInfo`](super::ClusterInfo)).
+pub async fn get_sacct_res(
+    job_ids: &[String],
+    tz: &FixedOffset,
+    executor: &impl CommandExecutor,
+) -> Result<Vec<SacctJobRow>, Error> {
+    if job_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let result = executor
+        .execute(&format!(
+            "sacct -X -n -P --format='{SACCT_FORMAT_STR}' -j {}",
+            shell_quote(&job_ids.join(","))
+        ))
+        .await?
+        .stdout;
+    let rows = result
+        .split("\n")
+        .filter_map(|line| {
+            if line.is_empty() {
+                return None;
+            }
+            let vals = line.split("|").map(|s| s.trim()).collect::<Vec<_>>();
+            match SacctJobRow::parse_from_strs(&vals, tz) {
+                Ok(row) => Some(row),
+                Err(err) => {
+                    println!("[!] {:?} for {:?}", err, &line);
+                    None
+                }
+            }
+        })
+        .collect();
+    Ok(rows)
+}
+
+/// Get the resource usage of the jobs with the given `job_ids` using `sacct` and the provided `executor`
+///
+/// Usage data is only reported for job steps, so all steps of a job are aggregated into a single [`JobResourceUsage`].
+pub async fn get_job_resource_usage(
+    job_ids: &[String],
+    executor: &impl CommandExecutor,
+) -> Result<Vec<JobResourceUsage>, Error> {
+    if job_ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let result = executor
+        .execute(&format!(
+            "sacct -n -P --format='{SACCT_USAGE_FORMAT_STR}' -j {}",
+            shell_quote(&job_ids.join(","))
+        ))
+        .await?
+        .stdout;
+    let mut usage: Vec<JobResourceUsage> = Vec::new();
+    for line in result.lines().filter(|line| !line.is_empty()) {
+        let vals = line.split("|").map(|s| s.trim()).collect::<Vec<_>>();
+        if vals.len() != 4 {
+            println!("[!] Invalid length of values for {:?}", &line);
+            continue;
+        }
+        let (job_id, step) = match vals[0].split_once(".") {
+            Some((job_id, step)) => (job_id, Some(step)),
+            None => (vals[0], None),
+        };
+        // Steps are listed directly after their job
+        if usage.last().is_none_or(|u| u.job_id != job_id) {
+            usage.push(JobResourceUsage {
+                job_id: job_id.to_string(),
+                ..Default::default()
+            });
+        }
+        let job = usage.last_mut().unwrap();
+        job.add_line(step, &vals)
+            .unwrap_or_else(|err| println!("[!] {:?} for {:?}", err, &line));
+    }
+    Ok(usage)
+}
+
+/// Run and parse `sacct` resource usage locally (i.e., not via SSH)
+pub async fn get_job_resource_usage_locally(
+    job_ids: &[String],
+) -> Result<Vec<JobResourceUsage>, Error> {
+    get_job_resource_usage(job_ids, &LocalExecutor).await
+}
+
+#[cfg(feature = "ssh")]
+/// Run and parse `sacct` resource usage over SSH
+pub async fn get_job_resource_usage_ssh(
+    client: &Client,
+    job_ids: &[String],
+) -> Result<Vec<JobResourceUsage>, Error> {
+    get_job_resource_usage(job_ids, client).await
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+/// CPU and memory efficiency of a (finished) SLURM job, as reported by `seff`
+///
+/// Computed from the `sacct` fields of the job allocation and all its steps.
+pub struct JobEfficiency {
+    /// "`JobIDRaw`"
+    pub job_id: String,
+    /// "State"
+    pub state: JobState,
+    /// "`AllocCPUS`", number of allocated CPU cores
+    pub num_cpus: u64,
+    /// "Elapsed", the time the job was running
+    pub elapsed: Duration,
+    /// "`TotalCPU`", (system + user) CPU time used by all steps
+    pub cpu_time: Duration,
+    /// "`ReqMem`", requested memory of the whole job in bytes
+    pub requested_memory: Option<u64>,
+    /// "`MaxRSS`", maximum resident set size of all steps in bytes
+    pub max_rss: Option<u64>,
+}
+
+impl JobEfficiency {
+    /// Fraction of the allocated core-walltime (i.e., elapsed time × allocated cores) which was actually used
+    ///
+    /// Returns `None` if the job did not run.
+    pub fn cpu_efficiency(&self) -> Option<f64> {
+        let core_walltime = self.elapsed.as_secs_f64() * self.num_cpus as f64;
+        (core_walltime > 0.0).then(|| self.cpu_time.as_secs_f64() / core_walltime)
+    }
+
+    /// Fraction of the requested memory which was actually used (at peak)
+    pub fn memory_efficiency(&self) -> Option<f64> {
+        match (self.max_rss, self.requested_memory) {
+            (Some(max_rss), Some(requested)) if requested > 0 => {
+                Some(max_rss as f64 / requested as f64)
+            }
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Display for JobEfficiency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        const GIB: f64 = 1024.0 * 1024.0 * 1024.0;
+        let percent =
+            |e: Option<f64>| e.map_or(String::from("-"), |e| format!("{:.2}%", e * 100.0));
+        writeln!(f, "Job ID: {}", self.job_id)?;
+        writeln!(f, "State: {:?}", self.state)?;
+        writeln!(f, "Cores: {}", self.num_cpus)?;
+        writeln!(f, "CPU Utilized: {}", SlurmDuration::from(self.cpu_time))?;
+        writeln!(
+            f,
+            "CPU Efficiency: {} of {} core-walltime",
+            percent(self.cpu_efficiency()),
+            SlurmDuration::from(self.elapsed * self.num_cpus as u32)
+        )?;
+        let gib =
+            |b: Option<u64>| b.map_or(String::from("-"), |b| format!("{:.2} GiB", b as f64 / GIB));
+        writeln!(f, "Memory Utilized: {}", gib(self.max_rss))?;
+        write!(
+            f,
+            "Memory Efficiency: {} of {}",
+            percent(self.memory_efficiency()),
+            gib(self.requested_memory)
+        )
+    }
+}
+
+/// Parse the requested memory reported by `sacct` (`ReqMem`) into the total number of bytes of a job
+///
+/// Older SLURM versions report the memory per CPU (e.g., `4000Mc`) or per node (e.g., `4000Mn`).
+fn parse_requested_memory(s: &str, num_cpus: u64, num_nodes: u64) -> Option<u64> {
+    if let Some(per_cpu) = s.strip_suffix('c') {
+        Some(parse_slurm_size(per_cpu).ok()? * num_cpus)
+    } else if let Some(per_node) = s.strip_suffix('n') {
+        Some(parse_slurm_size(per_node).ok()? * num_nodes)
+    } else {
+        parse_slurm_size(s).ok()
+    }
+}
+
+/// Get the CPU and memory efficiency of the job with the given `job_id` using `sacct` and the provided `executor`
+///
+/// This is equivalent to the `seff` command, which is not available on all clusters.
+/// Array tasks can be passed by their array ID (e.g., `49869434_2`) or their raw job ID.
+/// For heterogeneous jobs (e.g., `49848561`, with components `49848561+0` and `49848561+1`), the usage of all components is combined.
+pub async fn get_job_efficiency(
+    job_id: &str,
+    executor: &impl CommandExecutor,
+) -> Result<JobEfficiency, Error> {
+    let result = executor
+        .execute(&format!(
+            "sacct -n -P --format='{SACCT_EFFICIENCY_FORMAT_STR}' -j {}",
+            shell_quote(job_id)
+        ))
+        .await?
+        .stdout;
+    let mut rows = Vec::new();
+    for line in result.lines().filter(|line| !line.is_empty()) {
+        let vals = line.split("|").map(|s| s.trim()).collect::<Vec<_>>();
+        if vals.len() != 9 {
+            println!("[!] Invalid length of values for {:?}", &line);
+            continue;
+        }
+        rows.push(vals);
+    }
+    // Allocation rows of the job (i.e., not steps like `49848561.batch`), identified by the raw ID or the (array or heterogeneous) job ID
+    // `sacct -j` also lists other tasks when passing the ID of an array job, which are skipped
+    let allocations: Vec<&Vec<&str>> = rows
+        .iter()
+        .filter(|vals| {
+            !vals[0].contains('.')
+                && (vals[0] == job_id
+                    || vals[1] == job_id
+                    || vals[1]
+                        .strip_prefix(job_id)
+                        .is_some_and(|rest| rest.starts_with('+')))
+        })
+        .collect();
+    let mut efficiency: Option<JobEfficiency> = None;
+    for vals in &allocations {
+        let num_cpus: u64 = vals[3].parse()?;
+        let num_nodes: u64 = vals[4].parse().unwrap_or(1);
+        let elapsed = parse_slurm_duration(vals[5])?;
+        let cpu_time = parse_slurm_duration(vals[6])?;
+        let requested_memory = parse_requested_memory(vals[7], num_cpus, num_nodes);
+        match &mut efficiency {
+            // Further components of a heterogeneous job
+            Some(efficiency) => {
+                efficiency.num_cpus += num_cpus;
+                efficiency.elapsed = efficiency.elapsed.max(elapsed);
+                efficiency.cpu_time += cpu_time;
+                efficiency.requested_memory = efficiency
+                    .requested_memory
+                    .zip(requested_memory)
+                    .map(|(a, b)| a + b);
+            }
+            None => {
+                efficiency = Some(JobEfficiency {
+                    job_id: vals[0].to_string(),
+                    state: vals[2].split(" ").next().unwrap_or_default().parse()?,
+                    num_cpus,
+                    elapsed,
+                    cpu_time,
+                    requested_memory,
+                    max_rss: None,
+                })
+            }
+        }
+    }
+    let mut efficiency = efficiency
+        .ok_or_else(|| Error::msg(format!("No accounting data found for job {job_id}.")))?;
+    // Steps (e.g., `49848561.batch`) only contribute their memory usage
+    efficiency.max_rss = rows
+        .iter()
+        .filter(|vals| {
+            let raw_id = vals[0].split('.').next().unwrap_or_default();
+            !vals[8].is_empty() && allocations.iter().any(|a| a[0] == raw_id)
+        })
+        .filter_map(|vals| parse_slurm_size(vals[8]).ok())
+        .max();
+    Ok(efficiency)
+}
+
+/// Run `sacct` and compute the job efficiency locally (i.e., not via SSH)
+pub async fn get_job_efficiency_locally(job_id: &str) -> Result<JobEfficiency, Error> {
+    get_job_efficiency(job_id, &LocalExecutor).await
+}
+
+#[cfg(feature = "ssh")]
+/// Run `sacct` and compute the job efficiency over SSH
+pub async fn get_job_efficiency_ssh(client: &Client, job_id: &str) -> Result<JobEfficiency, Error> {
+    get_job_efficiency(job_id, client).await
+}
+
+/// Run and parse `sacct` result locally (i.e., not via SSH)
+pub async fn get_sacct_res_locally(
+    job_ids: &[String],
+    tz: &FixedOffset,
+) -> Result<Vec<SacctJobRow>, Error> {
+    get_sacct_res(job_ids, tz, &LocalExecutor).await
+}
+
+#[cfg(feature = "ssh")]
+/// Run and parse `sacct` result over SSH
+pub async fn get_sacct_res_ssh(
+    client: &Client,
+    job_ids: &[String],
+    tz: &FixedOffset,
+) -> Result<Vec<SacctJobRow>, Error> {
+    get_sacct_res(job_ids, tz, client).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::{FixedOffset, TimeZone, Utc};
+
+    use super::{get_job_efficiency, get_job_resource_usage, get_sacct_res, SacctHistoryRow};
+    use crate::JobState;
+
+    #[tokio::test]
+    async fn test_parse_sacct() {
+        let output = "49848561|COMPLETED|0:0|2025-01-14T10:23:45|01:02:03
+49869434|CANCELLED by 12345|0:15|2025-01-14T11:00:00|1-00:00:00
+49869435|FAILED|2:0|Unknown|00:00:00
+invalid|row
+";
+        let tz = FixedOffset::east_opt(3600).unwrap();
+        let rows = get_sacct_res(&["49848561".to_string()], &tz, &|_| async {
+            Ok(output.to_string())
+        })
+        .await
+        .unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].state, JobState::COMPLETED);
+        assert_eq!(
+            rows[0].end_time,
+            Some(Utc.with_ymd_and_hms(2025, 1, 14, 9, 23, 45).unwrap())
+        );
+        assert_eq!(rows[0].elapsed, Some(Duration::from_secs(3723)));
+        assert_eq!(rows[1].state, JobState::CANCELLED);
+        assert_eq!(rows[1].signal, 15);
+        assert_eq!(rows[2].exit_code, 2);
+        assert_eq!(rows[2].end_time, None);
+    }
+
+    #[test]
+    fn test_parse_sacct_history() {
+        let tz = FixedOffset::east_opt(3600).unwrap();
+        let row = SacctHistoryRow::parse_line(
+            "49869435|49869434_2|default|ab123456|ab123456|c23ms|2025-01-14T10:30:00|2025-01-14T10:31:00|Unknown|RUNNING|0:0|n23m[0003-0004,0007],n23g0001|8|3|UNLIMITED|3900Mc|/home/ab123456|my job",
+            &tz,
+        )
+        .unwrap();
+        assert_eq!(row.step_job_id.single_task(), Some(2));
+        assert_eq!(row.end_time, None);
+        assert_eq!(row.time_limit, None);
+        assert_eq!(row.first_node().as_deref(), Some("n23m0003"));
+        assert!(SacctHistoryRow::parse_line("49869435|RUNNING", &tz).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_parse_job_resource_usage() {
+        let output = "49848561|15000||
+49848561.batch|14000|2048K|cpu=00:01:00,energy=14000,mem=2048K
+49848561.extern|0|1024K|cpu=00:00:00,mem=1024K
+49848562|N/A||
+";
+        let usage = get_job_resource_usage(&["49848561".to_string()], &|_| async {
+            Ok(output.to_string())
+        })
+        .await
+        .unwrap();
+        assert_eq!(usage.len(), 2);
+        assert_eq!(usage[0].consumed_energy, Some(15000));
+        assert_eq!(usage[0].max_rss, Some(2048 * 1024));
+        assert_eq!(usage[0].tres_usage_in_tot["batch"]["cpu"], "00:01:00");
+        assert_eq!(usage[1].consumed_energy, None);
+        assert_eq!(usage[1].max_rss, None);
+    }
+
+    #[tokio::test]
+    async fn test_job_efficiency() {
+        let output = "49848561|49848561|COMPLETED|4|1|00:10:00|00:20:00|4Gn|
+49848561.batch|49848561.batch|COMPLETED|4|1|00:10:00|00:19:59||1G
+49848561.extern|49848561.extern|COMPLETED|4|1|00:10:00|00:00:01||1024K
+";
+        let efficiency = get_job_efficiency("49848561", &|_| async { Ok(output.to_string()) })
+            .await
+            .unwrap();
+        assert_eq!(efficiency.state, JobState::COMPLETED);
+        assert_eq!(efficiency.num_cpus, 4);
+        assert_eq!(efficiency.cpu_time, Duration::from_secs(20 * 60));
+        assert_eq!(efficiency.cpu_efficiency(), Some(0.5));
+        assert_eq!(efficiency.requested_memory, Some(4 * 1024 * 1024 * 1024));
+        assert_eq!(efficiency.max_rss, Some(1024 * 1024 * 1024));
+        assert_eq!(efficiency.memory_efficiency(), Some(0.25));
+        assert!(efficiency
+            .to_string()
+            .contains("CPU Efficiency: 50.00% of 00:40:00 core-walltime"));
+
+        let output = "49848562|49848562|PENDING|4|1|00:00:00|00:00:00|1000Mc|\n";
+        let efficiency = get_job_efficiency("49848562", &|_| async { Ok(output.to_string()) })
+            .await
+            .unwrap();
+        assert_eq!(efficiency.cpu_efficiency(), None);
+        assert_eq!(efficiency.requested_memory, Some(4000 * 1024 * 1024));
+        assert!(get_job_efficiency("1", &|_| async { Ok(String::new()) })
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_array_and_het_job_efficiency() {
+        // `sacct -j 49869434_2` lists the task by its raw ID
+        let output = "49869436|49869434_2|COMPLETED|2|1|00:10:00|00:05:00|2Gn|
+49869436.batch|49869434_2.batch|COMPLETED|2|1|00:10:00|00:05:00||512M
+";
+        for job_id in ["49869434_2", "49869436"] {
+            let efficiency = get_job_efficiency(job_id, &|_| async { Ok(output.to_string()) })
+                .await
+                .unwrap();
+            assert_eq!(efficiency.job_id, "49869436");
+            assert_eq!(efficiency.cpu_efficiency(), Some(0.25));
+            assert_eq!(efficiency.max_rss, Some(512 * 1024 * 1024));
+        }
+
+        // `sacct -j 49869434` lists all tasks, but only the task with the raw ID of the array job is reported
+        let output = "49869435|49869434_1|COMPLETED|2|1|00:10:00|00:20:00|2Gn|
+49869435.batch|49869434_1.batch|COMPLETED|2|1|00:10:00|00:20:00||2G
+49869434|49869434_2|COMPLETED|2|1|00:10:00|00:05:00|2Gn|
+49869434.batch|49869434_2.batch|COMPLETED|2|1|00:10:00|00:05:00||512M
+";
+        let efficiency = get_job_efficiency("49869434", &|_| async { Ok(output.to_string()) })
+            .await
+            .unwrap();
+        assert_eq!(efficiency.num_cpus, 2);
+        assert_eq!(efficiency.max_rss, Some(512 * 1024 * 1024));
+
+        let output = "49848561|49848561+0|COMPLETED|4|1|00:10:00|00:20:00|4Gn|
+49848561.batch|49848561+0.batch|COMPLETED|4|1|00:10:00|00:20:00||1G
+49848562|49848561+1|COMPLETED|4|1|00:05:00|00:10:00|4Gn|
+49848562.0|49848561+1.0|COMPLETED|4|1|00:05:00|00:10:00||3G
+";
+        let efficiency = get_job_efficiency("49848561", &|_| async { Ok(output.to_string()) })
+            .await
+            .unwrap();
+        assert_eq!(efficiency.num_cpus, 8);
+        assert_eq!(efficiency.elapsed, Duration::from_secs(10 * 60));
+        assert_eq!(efficiency.cpu_time, Duration::from_secs(30 * 60));
+        assert_eq!(efficiency.requested_memory, Some(8 * 1024 * 1024 * 1024));
+        assert_eq!(efficiency.max_rss, Some(3 * 1024 * 1024 * 1024));
+    }
+}
@@ -0,0 +1,439 @@
+use std::{collections::HashMap, time::Duration};
+
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssh")]
+use async_ssh2_tokio::Client;
+
+use crate::{
+    executor::{CommandExecutor, LocalExecutor},
+    parse_slurm_duration, MemorySpec, SlurmDuration,
+};
+
+// https://slurm.schedmd.com/sacctmgr.html
+pub(crate) const SACCTMGR_QOS_FORMAT_STR: &str =
+    "Name,Priority,MaxWall,MaxTRES,MaxTRESPerNode,MaxTRESPU,MaxJobsPU,MaxSubmitPU,GrpTRES,Flags";
+pub(crate) const SACCTMGR_ASSOC_FORMAT_STR: &str =
+    "Cluster,Account,User,ParentName,Share,GrpTRES,MaxTRES,MaxJobs,MaxSubmit,MaxWall,QOS";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Struct for parsed output row of `sacctmgr show qos` command
+///
+/// Containing the priority and limits of a quality of service (QOS)
+pub struct QosRow {
+    /// "Name"
+    pub name: String,
+    /// "Priority"
+    pub priority: u64,
+    /// "`MaxWall`", the maximum time limit of a job, `None` if unlimited
+    pub max_wall: Option<Duration>,
+    /// "`MaxTRES`", the maximum TRES per job, e.g., `cpu` -> `96`
+    pub max_tres_per_job: HashMap<String, String>,
+    /// "`MaxTRESPerNode`"
+    pub max_tres_per_node: HashMap<String, String>,
+    /// "`MaxTRESPU`", the maximum TRES of all running jobs of a user
+    pub max_tres_per_user: HashMap<String, String>,
+    /// "`MaxJobsPU`", the maximum number of running jobs of a user
+    pub max_jobs_per_user: Option<usize>,
+    /// "`MaxSubmitPU`", the maximum number of submitted (i.e., running or pending) jobs of a user
+    pub max_submit_per_user: Option<usize>,
+    /// "`GrpTRES`", the maximum TRES of all running jobs using this QOS
+    pub grp_tres: HashMap<String, String>,
+    /// "Flags", e.g., `DenyOnLimit`
+    pub flags: Vec<String>,
+}
+
+/// Parse a list of TRES amounts, e.g., `cpu=96,gres/gpu=4,mem=500G`
+pub(crate) fn parse_tres_map(s: &str) -> HashMap<String, String> {
+    s.split(",")
+        .filter_map(|pair| pair.split_once("="))
+        .map(|(tres, amount)| (tres.to_string(), amount.to_string()))
+        .collect()
+}
+
+impl QosRow {
+    fn parse_from_strs(vals: &[&str]) -> Result<Self, Error> {
+        if vals.len() != 10 {
+            return Err(Error::msg("Invalid length of values."));
+        }
+        Ok(Self {
+            name: vals[0].to_string(),
+            priority: vals[1].parse()?,
+            max_wall: match vals[2] {
+                "" => None,
+                s => Some(parse_slurm_duration(s)?),
+            },
+            max_tres_per_job: parse_tres_map(vals[3]),
+            max_tres_per_node: parse_tres_map(vals[4]),
+            max_tres_per_user: parse_tres_map(vals[5]),
+            max_jobs_per_user: vals[6].parse().ok(),
+            max_submit_per_user: vals[7].parse().ok(),
+            grp_tres: parse_tres_map(vals[8]),
+            flags: vals[9]
+                .split(",")
+                .filter(|f| !f.is_empty())
+                .map(|f| f.to_string())
+                .collect(),
+        })
+    }
+
+    /// Check if a job requesting the given `time` limit, number of `cpus`, and `memory` (on a single node)
+    /// is within the per-job and per-user limits of this QOS
+    pub fn check_request(
+        &self,
+        time: &SlurmDuration,
+        cpus: usize,
+        memory: Option<&MemorySpec>,
+    ) -> Result<(), Error> {
+        if let Some(max_wall) = self.max_wall {
+            let exceeds = match time {
+                SlurmDuration::Finite(time) => *time > max_wall,
+                SlurmDuration::Unlimited => true,
+                SlurmDuration::NotSet => false,
+            };
+            if exceeds {
+                return Err(Error::msg(format!(
+                    "Time limit {time} exceeds MaxWall {} of QOS {}.",
+                    SlurmDuration::from(max_wall),
+                    self.name
+                )));
+            }
+        }
+        for (limit, tres) in [
+            ("MaxTRES", &self.max_tres_per_job),
+            ("MaxTRESPU", &self.max_tres_per_user),
+        ] {
+            if let Some(max_cpus) = tres.get("cpu").and_then(|c| c.parse::<usize>().ok()) {
+                if cpus > max_cpus {
+                    return Err(Error::msg(format!(
+                        "{cpus} CPUs exceed {limit} of {max_cpus} CPUs of QOS {}.",
+                        self.name
+                    )));
+                }
+            }
+            let max_mem = tres.get("mem").and_then(|m| m.parse::<MemorySpec>().ok());
+            if let (Some(max_mem), Some(memory)) = (max_mem, memory) {
+                if memory.total_bytes(cpus, 1) > max_mem.bytes {
+                    return Err(Error::msg(format!(
+                        "Memory {memory} exceeds {limit} of {max_mem} of QOS {}.",
+                        self.name
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "ssh")]
+    /// Check if the given job options are within the per-job and per-user limits of this QOS (see [`QosRow::check_request`])
+    pub fn check_job_options(
+        &self,
+        options: &crate::job_management::JobOptions,
+    ) -> Result<(), Error> {
+        self.check_request(&options.time, options.num_cpus, options.memory.as_ref())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Struct for parsed output row of `sacctmgr show associations` command
+///
+/// An association either belongs to an account (if [`AssociationRow::user`] is `None`) or to a user within an account
+pub struct AssociationRow {
+    /// "Cluster"
+    pub cluster: String,
+    /// "Account"
+    pub account: String,
+    /// "User"
+    pub user: Option<String>,
+    /// "`ParentName`", the parent account (only set for account associations)
+    pub parent_account: Option<String>,
+    /// "Share", the fairshare value, `None` if the shares of the parent are used (i.e., `parent`)
+    pub shares: Option<u64>,
+    /// "`GrpTRES`"
+    pub grp_tres: HashMap<String, String>,
+    /// "`MaxTRES`"
+    pub max_tres: HashMap<String, String>,
+    /// "`MaxJobs`"
+    pub max_jobs: Option<usize>,
+    /// "`MaxSubmit`"
+    pub max_submit: Option<usize>,
+    /// "`MaxWall`"
+    pub max_wall: Option<Duration>,
+    /// "QOS", the QOS this association may use
+    pub qos: Vec<String>,
+}
+
+impl AssociationRow {
+    fn parse_from_strs(vals: &[&str]) -> Result<Self, Error> {
+        if vals.len() != 11 {
+            return Err(Error::msg("Invalid length of values."));
+        }
+        let non_empty = |s: &str| (!s.is_empty()).then(|| s.to_string());
+        Ok(Self {
+            cluster: vals[0].to_string(),
+            account: vals[1].to_string(),
+            user: non_empty(vals[2]),
+            parent_account: non_empty(vals[3]),
+            shares: vals[4].parse().ok(),
+            grp_tres: parse_tres_map(vals[5]),
+            max_tres: parse_tres_map(vals[6]),
+            max_jobs: vals[7].parse().ok(),
+            max_submit: vals[8].parse().ok(),
+            max_wall: match vals[9] {
+                "" => None,
+                s => Some(parse_slurm_duration(s)?),
+            },
+            qos: vals[10]
+                .split(",")
+                .filter(|q| !q.is_empty())
+                .map(|q| q.to_string())
+                .collect(),
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A single account in an [`AccountTree`]
+pub struct AccountNode {
+    /// Name of the account
+    pub name: String,
+    /// Name of the parent account (`None` for the root account)
+    pub parent: Option<String>,
+    /// Names of the child accounts
+    pub children: Vec<String>,
+    /// The association of the account itself (containing its shares and limits)
+    pub association: Option<AssociationRow>,
+    /// The associations of all users of this account
+    pub users: Vec<AssociationRow>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+/// Hierarchy of accounts and their users, as built from the associations of a single cluster
+pub struct AccountTree {
+    /// All accounts by name
+    pub accounts: HashMap<String, AccountNode>,
+}
+
+impl AccountTree {
+    /// Build the account hierarchy from the given `associations`
+    ///
+    /// For multi-cluster setups, only the associations of a single cluster should be passed.
+    pub fn from_associations(associations: impl IntoIterator<Item = AssociationRow>) -> Self {
+        let mut accounts: HashMap<String, AccountNode> = HashMap::new();
+        let node = |name: &str| AccountNode {
+            name: name.to_string(),
+            parent: None,
+            children: Vec::new(),
+            association: None,
+            users: Vec::new(),
+        };
+        for assoc in associations {
+            let account = accounts
+                .entry(assoc.account.clone())
+                .or_insert_with(|| node(&assoc.account));
+            if assoc.user.is_some() {
+                account.users.push(assoc);
+            } else {
+                account.parent = assoc.parent_account.clone();
+                account.association = Some(assoc);
+            }
+        }
+        let edges: Vec<(String, String)> = accounts
+            .values()
+            .filter_map(|a| a.parent.clone().map(|p| (p, a.name.clone())))
+            .collect();
+        for (parent, child) in edges {
+            accounts
+                .entry(parent.clone())
+                .or_insert_with(|| node(&parent))
+                .children
+                .push(child);
+        }
+        Self { accounts }
+    }
+
+    /// All accounts without a parent (usually only `root`)
+    pub fn roots(&self) -> impl Iterator<Item = &AccountNode> {
+        self.accounts.values().filter(|a| a.parent.is_none())
+    }
+
+    /// The ancestors of the given `account`, starting with its parent
+    pub fn ancestors(&self, account: &str) -> Vec<&AccountNode> {
+        let mut ancestors = Vec::new();
+        let mut current = self.accounts.get(account);
+        while let Some(parent) = current
+            .and_then(|a| a.parent.as_ref())
+            .and_then(|p| self.accounts.get(p))
+        {
+            // Guard against (invalid) cyclic hierarchies
+            if ancestors
+                .iter()
+                .any(|a: &&AccountNode| a.name == parent.name)
+            {
+                break;
+            }
+            ancestors.push(parent);
+            current = Some(parent);
+        }
+        ancestors
+    }
+
+    /// The accounts of the given `user`
+    pub fn accounts_of_user<'a>(&'a self, user: &'a str) -> impl Iterator<Item = &'a AccountNode> {
+        self.accounts
+            .values()
+            .filter(move |a| a.users.iter().any(|u| u.user.as_deref() == Some(user)))
+    }
+}
+
+/// Get all associations using `sacctmgr` and the provided `executor`
+pub async fn get_associations(
+    executor: &impl CommandExecutor,
+) -> Result<Vec<AssociationRow>, Error> {
+    let result = executor
+        .execute(&format!(
+            "sacctmgr show associations -P -n format={SACCTMGR_ASSOC_FORMAT_STR}"
+        ))
+        .await?
+        .stdout;
+    let rows = result
+        .split("\n")
+        .filter_map(|line| {
+            if line.is_empty() {
+                return None;
+            }
+            let vals = line.split("|").map(|s| s.trim()).collect::<Vec<_>>();
+            match AssociationRow::parse_from_strs(&vals) {
+                Ok(row) => Some(row),
+                Err(err) => {
+                    println!("[!] {:?} for {:?}", err, &line);
+                    None
+                }
+            }
+        })
+        .collect();
+    Ok(rows)
+}
+
+/// Run and parse `sacctmgr show associations` locally (i.e., not via SSH)
+pub async fn get_associations_locally() -> Result<Vec<AssociationRow>, Error> {
+    get_associations(&LocalExecutor).await
+}
+
+#[cfg(feature = "ssh")]
+/// Run and parse `sacctmgr show associations` over SSH
+pub async fn get_associations_ssh(client: &Client) -> Result<Vec<AssociationRow>, Error> {
+    get_associations(client).await
+}
+
+/// Get all QOS using `sacctmgr` and the provided `executor`
+pub async fn get_qos_list(executor: &impl CommandExecutor) -> Result<Vec<QosRow>, Error> {
+    let result = executor
+        .execute(&format!(
+            "sacctmgr show qos -P -n format={SACCTMGR_QOS_FORMAT_STR}"
+        ))
+        .await?
+        .stdout;
+    let rows = result
+        .split("\n")
+        .filter_map(|line| {
+            if line.is_empty() {
+                return None;
+            }
+            let vals = line.split("|").map(|s| s.trim()).collect::<Vec<_>>();
+            match QosRow::parse_from_strs(&vals) {
+                Ok(row) => Some(row),
+                Err(err) => {
+                    println!("[!] {:?} for {:?}", err, &line);
+                    None
+                }
+            }
+        })
+        .collect();
+    Ok(rows)
+}
+
+/// Run and parse `sacctmgr show qos` locally (i.e., not via SSH)
+pub async fn get_qos_list_locally() -> Result<Vec<QosRow>, Error> {
+    get_qos_list(&LocalExecutor).await
+}
+
+#[cfg(feature = "ssh")]
+/// Run and parse `sacctmgr show qos` over SSH
+pub async fn get_qos_list_ssh(client: &Client) -> Result<Vec<QosRow>, Error> {
+    get_qos_list(client).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{AccountTree, AssociationRow, QosRow};
+    use crate::{MemorySpec, SlurmDuration};
+
+    #[test]
+    fn test_parse_qos() {
+        let qos = QosRow::parse_from_strs(
+            &"normal|10|2-00:00:00|cpu=96,mem=500G||cpu=192,gres/gpu=4|50|100||DenyOnLimit"
+                .split("|")
+                .collect::<Vec<_>>(),
+        )
+        .unwrap();
+        assert_eq!(qos.priority, 10);
+        assert_eq!(qos.max_wall, Some(Duration::from_secs(2 * 24 * 60 * 60)));
+        assert_eq!(qos.max_tres_per_user["gres/gpu"], "4");
+        assert!(qos.max_tres_per_node.is_empty());
+        assert_eq!(qos.max_jobs_per_user, Some(50));
+        assert_eq!(qos.flags, vec!["DenyOnLimit"]);
+
+        let hour = SlurmDuration::Finite(Duration::from_secs(60 * 60));
+        assert!(qos.check_request(&hour, 96, None).is_ok());
+        assert!(qos.check_request(&hour, 97, None).is_err());
+        assert!(qos
+            .check_request(&SlurmDuration::Unlimited, 1, None)
+            .is_err());
+        assert!(qos
+            .check_request(&hour, 96, Some(&MemorySpec::per_cpu_mb(8000)))
+            .is_err());
+
+        let unlimited =
+            QosRow::parse_from_strs(&"low|0||||||||".split("|").collect::<Vec<_>>()).unwrap();
+        assert_eq!(unlimited.max_wall, None);
+        assert!(unlimited.flags.is_empty());
+        assert!(unlimited
+            .check_request(&SlurmDuration::Unlimited, 1000, None)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_account_tree() {
+        let output = "claix|root|||1||||||normal
+claix|root|root||1||||||normal
+claix|rwth||root|100|cpu=5000|||||normal
+claix|thes1234||rwth|10||cpu=96|||2-00:00:00|normal,low
+claix|thes1234|ab123456||parent||||50||normal";
+        let rows: Vec<_> = output
+            .lines()
+            .map(|l| AssociationRow::parse_from_strs(&l.split("|").collect::<Vec<_>>()))
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let user = &rows[4];
+        assert_eq!(user.user.as_deref(), Some("ab123456"));
+        assert_eq!(user.shares, None);
+        assert_eq!(rows[3].qos, vec!["normal", "low"]);
+
+        let tree = AccountTree::from_associations(rows);
+        assert_eq!(tree.roots().count(), 1);
+        assert_eq!(tree.accounts["rwth"].children, vec!["thes1234"]);
+        let ancestors: Vec<_> = tree
+            .ancestors("thes1234")
+            .iter()
+            .map(|a| a.name.as_str())
+            .collect();
+        assert_eq!(ancestors, vec!["rwth", "root"]);
+        let accounts: Vec<_> = tree.accounts_of_user("ab123456").collect();
+        assert_eq!(accounts.len(), 1);
+        assert_eq!(accounts[0].users[0].max_submit, Some(50));
+    }
+}
@@ -0,0 +1,515 @@
+use std::{collections::HashMap, path::PathBuf, time::Duration};
+
+use anyhow::Error;
+use chrono::{DateTime, FixedOffset, Utc};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssh")]
+use async_ssh2_tokio::Client;
+
+use super::{cluster_info::parse_slurm_time, sacctmgr::parse_tres_map};
+use crate::{
+    executor::{shell_quote, CommandExecutor, LocalExecutor},
+    parse_slurm_duration, JobState, MemorySpec,
+};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Detailed information about a SLURM job, as reported by `scontrol show job`
+///
+/// Contains many fields not available through `squeue` (e.g., `TRES` per node, licenses, or the output paths).
+/// All (also unmodelled) key-value pairs are additionally available in [`JobDetails::raw`].
+/// Timestamps are converted from the cluster timezone (see [`get_job_details`]).
+pub struct JobDetails {
+    /// "`JobId`"
+    pub job_id: String,
+    /// "`ArrayJobId`"
+    pub array_job_id: Option<String>,
+    /// "`ArrayTaskId`"
+    pub array_task_id: Option<String>,
+    /// "`JobName`"
+    pub name: String,
+    /// "`UserId`", e.g., `user(1234)`
+    pub user_id: String,
+    /// "`GroupId`", e.g., `group(5678)`
+    pub group_id: String,
+    /// "Account"
+    pub account: Option<String>,
+    /// "QOS"
+    pub qos: Option<String>,
+    /// "Priority"
+    pub priority: Option<u64>,
+    /// "Nice"
+    pub nice: Option<i64>,
+    /// "`JobState`"
+    pub state: JobState,
+    /// "Reason"
+    pub reason: Option<String>,
+    /// "Dependency"
+    pub dependency: Option<String>,
+    /// "Requeue"
+    pub requeue: bool,
+    /// "Restarts"
+    pub restarts: usize,
+    /// "`ExitCode`", e.g., `0:0`
+    pub exit_code: Option<String>,
+    /// "`RunTime`"
+    pub run_time: Option<Duration>,
+    /// "`TimeLimit`"
+    pub time_limit: Option<Duration>,
+    /// "`SubmitTime`"
+    pub submit_time: Option<DateTime<Utc>>,
+    /// "`EligibleTime`"
+    pub eligible_time: Option<DateTime<Utc>>,
+    /// "`StartTime`"
+    pub start_time: Option<DateTime<Utc>>,
+    /// "`EndTime`"
+    pub end_time: Option<DateTime<Utc>>,
+    /// "Partition"
+    pub partition: Option<String>,
+    /// "`NodeList`"
+    pub node_list: Option<String>,
+    /// "`BatchHost`"
+    pub batch_host: Option<String>,
+    /// "`NumNodes`"
+    pub num_nodes: Option<String>,
+    /// "`NumCPUs`"
+    pub num_cpus: Option<usize>,
+    /// "`NumTasks`"
+    pub num_tasks: Option<usize>,
+    /// "`CPUs/Task`"
+    pub cpus_per_task: Option<usize>,
+    /// "TRES", the allocated (or requested) TRES, e.g., `cpu` -> `1` and `mem` -> `3900M`
+    pub tres: HashMap<String, String>,
+    /// "`TresPerNode`", e.g., `gres/gpu:2`
+    pub tres_per_node: Option<String>,
+    /// "`MinMemoryNode`" or "`MinMemoryCPU`" (whichever is set)
+    pub min_memory: Option<MemorySpec>,
+    /// "Licenses"
+    pub licenses: Option<String>,
+    /// "Command"
+    pub command: Option<String>,
+    /// "`WorkDir`"
+    pub work_dir: Option<PathBuf>,
+    /// "`StdOut`"
+    pub std_out: Option<PathBuf>,
+    /// "`StdErr`"
+    pub std_err: Option<PathBuf>,
+    /// "`StdIn`"
+    pub std_in: Option<PathBuf>,
+    /// All raw key-value pairs as reported by `scontrol`
+    pub raw: HashMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// A SLURM reservation, as reported by `scontrol show reservation`
+///
+/// Reservations block nodes for a given time window (e.g., for maintenance), which can delay pending jobs.
+/// All (also unmodelled) key-value pairs are additionally available in [`Reservation::raw`].
+pub struct Reservation {
+    /// "`ReservationName`"
+    pub name: String,
+    /// "`StartTime`"
+    pub start_time: DateTime<Utc>,
+    /// "`EndTime`"
+    pub end_time: DateTime<Utc>,
+    /// "Nodes", e.g., `n23m[0001-0100]`
+    pub nodes: Option<String>,
+    /// "`NodeCnt`"
+    pub node_count: Option<usize>,
+    /// "`PartitionName`"
+    pub partition: Option<String>,
+    /// "Users"
+    pub users: Vec<String>,
+    /// "Accounts"
+    pub accounts: Vec<String>,
+    /// "Flags", e.g., `MAINT` or `IGNORE_JOBS`
+    pub flags: Vec<String>,
+    /// "State", i.e., `ACTIVE` or `INACTIVE`
+    pub state: Option<String>,
+    /// All raw key-value pairs as reported by `scontrol`
+    pub raw: HashMap<String, String>,
+}
+
+impl Reservation {
+    fn parse_from_line(line: &str, tz: &FixedOffset) -> Result<Self, Error> {
+        let raw = parse_scontrol_key_values(line);
+        let get = |key: &str| scontrol_value(&raw, key).map(|s| s.to_string());
+        let get_list = |key: &str| {
+            scontrol_value(&raw, key)
+                .map(|s| s.split(",").map(|v| v.to_string()).collect())
+                .unwrap_or_default()
+        };
+        let get_time = |key: &str| {
+            parse_slurm_time(
+                scontrol_value(&raw, key)
+                    .ok_or_else(|| Error::msg(format!("No {key} in scontrol output.")))?,
+                tz,
+            )
+        };
+        Ok(Self {
+            name: get("ReservationName")
+                .ok_or(Error::msg("No ReservationName in scontrol output."))?,
+            start_time: get_time("StartTime")?,
+            end_time: get_time("EndTime")?,
+            nodes: get("Nodes"),
+            node_count: scontrol_value(&raw, "NodeCnt").and_then(|s| s.parse().ok()),
+            partition: get("PartitionName"),
+            users: get_list("Users"),
+            accounts: get_list("Accounts"),
+            flags: get_list("Flags"),
+            state: get("State"),
+            raw,
+        })
+    }
+
+    /// If this is a maintenance reservation (i.e., has the `MAINT` flag)
+    pub fn is_maintenance(&self) -> bool {
+        self.flags.iter().any(|f| f == "MAINT")
+    }
+
+    /// If the reservation overlaps with the time window from `start` to `end`
+    ///
+    /// E.g., a job starting now with a given time limit can only run if it does not overlap with a maintenance reservation of its nodes.
+    pub fn overlaps(&self, start: &DateTime<Utc>, end: &DateTime<Utc>) -> bool {
+        self.start_time < *end && *start < self.end_time
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+/// Usage of a license, as reported by `scontrol show licenses`
+pub struct LicenseUsage {
+    /// "`LicenseName`", e.g., `ansys` or `comsol@db` for remote licenses
+    pub name: String,
+    /// "Total"
+    pub total: u32,
+    /// "Used"
+    pub used: u32,
+    /// "Free"
+    pub free: u32,
+    /// "Reserved" (not reported by older SLURM versions)
+    pub reserved: Option<u32>,
+    /// "Remote", i.e., if the license is managed by a remote license server (`slurmdbd`)
+    pub remote: bool,
+}
+
+impl LicenseUsage {
+    fn parse_from_line(line: &str) -> Result<Self, Error> {
+        let raw = parse_scontrol_key_values(line);
+        let get_num = |key: &str| -> Result<u32, Error> {
+            Ok(scontrol_value(&raw, key)
+                .ok_or_else(|| Error::msg(format!("No {key} in scontrol output.")))?
+                .parse()?)
+        };
+        Ok(Self {
+            name: scontrol_value(&raw, "LicenseName")
+                .ok_or(Error::msg("No LicenseName in scontrol output."))?
+                .to_string(),
+            total: get_num("Total")?,
+            used: get_num("Used")?,
+            free: get_num("Free")?,
+            reserved: get_num("Reserved").ok(),
+            remote: scontrol_value(&raw, "Remote") == Some("yes"),
+        })
+    }
+}
+
+/// Split a single line of `scontrol show <entity> -o` output into its key-value pairs
+///
+/// Values containing spaces (e.g., a `Reason` or `Comment`) are joined back together.
+pub(crate) fn parse_scontrol_key_values(line: &str) -> HashMap<String, String> {
+    let mut pairs: Vec<(String, String)> = Vec::new();
+    for token in line.split_whitespace() {
+        match token.split_once("=") {
+            Some((key, value)) if !key.is_empty() => {
+                pairs.push((key.to_string(), value.to_string()))
+            }
+            _ => {
+                if let Some((_, value)) = pairs.last_mut() {
+                    value.push(' ');
+                    value.push_str(token);
+                }
+            }
+        }
+    }
+    pairs.into_iter().collect()
+}
+
+/// Get a value from parsed `scontrol` output, treating the usual placeholders as missing
+pub(crate) fn scontrol_value<'a>(map: &'a HashMap<String, String>, key: &str) -> Option<&'a str> {
+    match map.get(key).map(|s| s.as_str()) {
+        None | Some("(null)") | Some("None") | Some("N/A") | Some("Unknown") | Some("") => None,
+        Some(s) => Some(s),
+    }
+}
+
+/// Parse a timestamp as reported by `scontrol` in the cluster timezone `tz`, treating the usual placeholders as missing
+pub(crate) fn scontrol_time(
+    map: &HashMap<String, String>,
+    key: &str,
+    tz: &FixedOffset,
+) -> Result<Option<DateTime<Utc>>, Error> {
+    Ok(match scontrol_value(map, key) {
+        Some(s) => Some(parse_slurm_time(s, tz)?),
+        None => None,
+    })
+}
+
+impl JobDetails {
+    fn parse_from_line(line: &str, tz: &FixedOffset) -> Result<Self, Error> {
+        let raw = parse_scontrol_key_values(line);
+        let get = |key: &str| scontrol_value(&raw, key).map(|s| s.to_string());
+        let get_duration = |key: &str| match scontrol_value(&raw, key) {
+            Some("UNLIMITED") | Some("INVALID") | None => None,
+            Some(s) => parse_slurm_duration(s).ok(),
+        };
+        Ok(Self {
+            job_id: get("JobId").ok_or(Error::msg("No JobId in scontrol output."))?,
+            array_job_id: get("ArrayJobId"),
+            array_task_id: get("ArrayTaskId"),
+            name: get("JobName").unwrap_or_default(),
+            user_id: get("UserId").unwrap_or_default(),
+            group_id: get("GroupId").unwrap_or_default(),
+            account: get("Account"),
+            qos: get("QOS"),
+            priority: scontrol_value(&raw, "Priority").and_then(|s| s.parse().ok()),
+            nice: scontrol_value(&raw, "Nice").and_then(|s| s.parse().ok()),
+            state: scontrol_value(&raw, "JobState")
+                .ok_or(Error::msg("No JobState in scontrol output."))?
+                .parse()?,
+            reason: get("Reason"),
+            dependency: get("Dependency"),
+            requeue: scontrol_value(&raw, "Requeue") == Some("1"),
+            restarts: scontrol_value(&raw, "Restarts")
+                .and_then(|s| s.parse().ok())
+                .unwrap_or_default(),
+            exit_code: get("ExitCode"),
+            run_time: get_duration("RunTime"),
+            time_limit: get_duration("TimeLimit"),
+            submit_time: scontrol_time(&raw, "SubmitTime", tz)?,
+            eligible_time: scontrol_time(&raw, "EligibleTime", tz)?,
+            start_time: scontrol_time(&raw, "StartTime", tz)?,
+            end_time: scontrol_time(&raw, "EndTime", tz)?,
+            partition: get("Partition"),
+            node_list: get("NodeList"),
+            batch_host: get("BatchHost"),
+            num_nodes: get("NumNodes"),
+            num_cpus: scontrol_value(&raw, "NumCPUs").and_then(|s| s.parse().ok()),
+            num_tasks: scontrol_value(&raw, "NumTasks").and_then(|s| s.parse().ok()),
+            cpus_per_task: scontrol_value(&raw, "CPUs/Task").and_then(|s| s.parse().ok()),
+            tres: scontrol_value(&raw, "TRES")
+                .map(parse_tres_map)
+                .unwrap_or_default(),
+            tres_per_node: get("TresPerNode"),
+            min_memory: match (get("MinMemoryNode"), get("MinMemoryCPU")) {
+                (Some(mem), _) => mem.parse().ok(),
+                (None, Some(mem)) => mem.parse().ok().map(|mem: MemorySpec| MemorySpec {
+                    per_cpu: true,
+                    ..mem
+                }),
+                (None, None) => None,
+            },
+            licenses: get("Licenses"),
+            command: get("Command"),
+            work_dir: get("WorkDir").map(PathBuf::from),
+            std_out: get("StdOut").map(PathBuf::from),
+            std_err: get("StdErr").map(PathBuf::from),
+            std_in: get("StdIn").map(PathBuf::from),
+            raw,
+        })
+    }
+}
+
+/// Get detailed job information using the provided `executor`
+///
+/// For job arrays, one [`JobDetails`] entry is returned per array task.
+/// Timestamps reported by `scontrol` are interpreted in the cluster timezone `tz` (see [`ClusterInfo`](super::ClusterInfo)).
+pub async fn get_job_details(
+    job_id: &str,
+    tz: &FixedOffset,
+    executor: &impl CommandExecutor,
+) -> Result<Vec<JobDetails>, Error> {
+    let r = executor
+        .execute(&format!("scontrol show job -o {}", shell_quote(job_id)))
+        .await?;
+    if r.exit_status != 0 {
+        return Err(Error::msg(format!(
+            "scontrol show job failed for job {job_id}: {}",
+            r.stderr.trim()
+        )));
+    }
+    let details = r
+        .stdout
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| JobDetails::parse_from_line(line, tz))
+        .collect::<Result<Vec<_>, _>>()?;
+    if details.is_empty() {
+        return Err(Error::msg(format!("Could not find job {job_id}.")));
+    }
+    Ok(details)
+}
+
+/// Run and parse `scontrol show job` locally (i.e., not via SSH)
+pub async fn get_job_details_locally(
+    job_id: &str,
+    tz: &FixedOffset,
+) -> Result<Vec<JobDetails>, Error> {
+    get_job_details(job_id, tz, &LocalExecutor).await
+}
+
+#[cfg(feature = "ssh")]
+/// Run and parse `scontrol show job` over SSH
+pub async fn get_job_details_ssh(
+    client: &Client,
+    job_id: &str,
+    tz: &FixedOffset,
+) -> Result<Vec<JobDetails>, Error> {
+    get_job_details(job_id, tz, client).await
+}
+
+/// Get all reservations using the provided `executor`
+///
+/// Timestamps reported by `scontrol` are interpreted in the cluster timezone `tz` (see [`ClusterInfo`](super::ClusterInfo)).
+pub async fn get_reservations(
+    tz: &FixedOffset,
+    executor: &impl CommandExecutor,
+) -> Result<Vec<Reservation>, Error> {
+    let result = executor
+        .execute(&String::from("scontrol show reservation -o"))
+        .await?
+        .stdout;
+    // Without reservations, `scontrol` reports "No reservations in the system"
+    result
+        .lines()
+        .filter(|line| line.starts_with("ReservationName="))
+        .map(|line| Reservation::parse_from_line(line, tz))
+        .collect()
+}
+
+/// Run and parse `scontrol show reservation` locally (i.e., not via SSH)
+pub async fn get_reservations_locally(tz: &FixedOffset) -> Result<Vec<Reservation>, Error> {
+    get_reservations(tz, &LocalExecutor).await
+}
+
+#[cfg(feature = "ssh")]
+/// Run and parse `scontrol show reservation` over SSH
+pub async fn get_reservations_ssh(
+    client: &Client,
+    tz: &FixedOffset,
+) -> Result<Vec<Reservation>, Error> {
+    get_reservations(tz, client).await
+}
+
+/// Get the usage of all configured licenses using the provided `executor`
+pub async fn get_license_usage(
+    executor: &impl CommandExecutor,
+) -> Result<Vec<LicenseUsage>, Error> {
+    let result = executor
+        .execute(&String::from("scontrol show licenses -o"))
+        .await?
+        .stdout;
+    // Without licenses, `scontrol` reports "No licenses configured in Slurm."
+    result
+        .lines()
+        .filter(|line| line.starts_with("LicenseName="))
+        .map(LicenseUsage::parse_from_line)
+        .collect()
+}
+
+/// Run and parse `scontrol show licenses` locally (i.e., not via SSH)
+pub async fn get_license_usage_locally() -> Result<Vec<LicenseUsage>, Error> {
+    get_license_usage(&LocalExecutor).await
+}
+
+#[cfg(feature = "ssh")]
+/// Run and parse `scontrol show licenses` over SSH
+pub async fn get_license_usage_ssh(client: &Client) -> Result<Vec<LicenseUsage>, Error> {
+    get_license_usage(client).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::{get_license_usage, JobDetails, Reservation};
+    use crate::{JobState, MemorySpec};
+
+    #[test]
+    fn test_parse_job_details() {
+        use chrono::{FixedOffset, TimeZone, Utc};
+
+        let line = "JobId=49848561 JobName=my job UserId=ab123456(12345) GroupId=ab123456(12345) MCS_label=N/A Priority=1201 Nice=0 Account=default QOS=normal JobState=PENDING Reason=Resources Dependency=(null) Requeue=1 Restarts=0 BatchFlag=1 Reboot=0 ExitCode=0:0 RunTime=00:00:00 TimeLimit=1-00:00:00 TimeMin=N/A SubmitTime=2025-01-14T10:23:45 EligibleTime=2025-01-14T10:23:45 StartTime=Unknown EndTime=Unknown Partition=c23ms NodeList=(null) BatchHost=n23m0001 NumNodes=1 NumCPUs=4 NumTasks=1 CPUs/Task=4 TRES=cpu=4,mem=15600M,node=1,billing=4 MinMemoryCPU=3900M TresPerNode=gres/gpu:2 Command=/home/ab123456/start.sh WorkDir=/home/ab123456 StdErr=/home/ab123456/stdout.txt StdOut=/home/ab123456/stdout.txt";
+        let tz = FixedOffset::east_opt(3600).unwrap();
+        let details = JobDetails::parse_from_line(line, &tz).unwrap();
+        assert_eq!(details.job_id, "49848561");
+        assert_eq!(details.name, "my job");
+        assert_eq!(details.state, JobState::PENDING);
+        assert_eq!(details.priority, Some(1201));
+        assert_eq!(
+            details.submit_time,
+            Some(Utc.with_ymd_and_hms(2025, 1, 14, 9, 23, 45).unwrap())
+        );
+        assert_eq!(details.start_time, None);
+        assert_eq!(details.dependency, None);
+        assert!(details.requeue);
+        assert_eq!(details.num_cpus, Some(4));
+        assert_eq!(details.time_limit, Some(Duration::from_secs(24 * 60 * 60)));
+        assert_eq!(details.tres.len(), 4);
+        assert_eq!(details.tres["cpu"], "4");
+        assert_eq!(details.tres["mem"], "15600M");
+        assert_eq!(details.tres_per_node.as_deref(), Some("gres/gpu:2"));
+        assert_eq!(details.min_memory, Some(MemorySpec::per_cpu_mb(3900)));
+        assert_eq!(details.batch_host.as_deref(), Some("n23m0001"));
+    }
+
+    #[test]
+    fn test_parse_reservation() {
+        use chrono::{FixedOffset, TimeZone, Utc};
+
+        let line = "ReservationName=maint_feb StartTime=2025-02-01T08:00:00 EndTime=2025-02-01T18:00:00 Duration=10:00:00 Nodes=n23m[0001-0100] NodeCnt=100 CoreCnt=9600 Features=(null) PartitionName=(null) Flags=MAINT,IGNORE_JOBS,SPEC_NODES TRES=cpu=9600 Users=root,admin Groups=(null) Accounts=(null) Licenses=(null) State=INACTIVE BurstBuffer=(null) Watts=n/a MaxStartDelay=(null)";
+        let tz = FixedOffset::east_opt(3600).unwrap();
+        let reservation = Reservation::parse_from_line(line, &tz).unwrap();
+        assert_eq!(reservation.name, "maint_feb");
+        assert_eq!(
+            reservation.start_time,
+            Utc.with_ymd_and_hms(2025, 2, 1, 7, 0, 0).unwrap()
+        );
+        assert_eq!(reservation.node_count, Some(100));
+        assert_eq!(reservation.partition, None);
+        assert_eq!(reservation.users, vec!["root", "admin"]);
+        assert!(reservation.accounts.is_empty());
+        assert!(reservation.is_maintenance());
+        assert!(reservation.overlaps(
+            &Utc.with_ymd_and_hms(2025, 2, 1, 0, 0, 0).unwrap(),
+            &Utc.with_ymd_and_hms(2025, 2, 1, 8, 0, 0).unwrap()
+        ));
+        assert!(!reservation.overlaps(
+            &Utc.with_ymd_and_hms(2025, 2, 1, 0, 0, 0).unwrap(),
+            &Utc.with_ymd_and_hms(2025, 2, 1, 7, 0, 0).unwrap()
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_parse_license_usage() {
+        let output = "LicenseName=ansys Total=10 Used=2 Free=8 Reserved=0 Remote=no LastConsumed=0 LastDeficit=0 LastUpdate=2025-01-14T10:23:45
+LicenseName=comsol@db Total=4 Used=4 Free=0 Remote=yes
+";
+        let licenses = get_license_usage(&|_| async { Ok(output.to_string()) })
+            .await
+            .unwrap();
+        assert_eq!(licenses.len(), 2);
+        assert_eq!(licenses[0].name, "ansys");
+        assert_eq!(licenses[0].free, 8);
+        assert_eq!(licenses[0].reserved, Some(0));
+        assert!(!licenses[0].remote);
+        assert_eq!(licenses[1].reserved, None);
+        assert!(licenses[1].remote);
+
+        let none = get_license_usage(&|_| async {
+            Ok(String::from("No licenses configured in Slurm.\n"))
+        })
+        .await
+        .unwrap();
+        assert!(none.is_empty());
+    }
+}
@@ -0,0 +1,343 @@
+use std::{collections::HashMap, time::SystemTime};
+
+use anyhow::Error;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssh")]
+use async_ssh2_tokio::Client;
+
+use crate::executor::{CommandExecutor, LocalExecutor};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+/// Scheduler statistics as reported by the `sdiag` command
+///
+/// Counters which are not reported by the installed SLURM version are set to `None`.
+pub struct SdiagStats {
+    /// "Server thread count"
+    pub server_thread_count: Option<u64>,
+    /// "Agent queue size"
+    pub agent_queue_size: Option<u64>,
+    /// "DBD Agent queue size"
+    pub dbd_agent_queue_size: Option<u64>,
+    /// "Jobs submitted" (since the statistics were last reset)
+    pub jobs_submitted: Option<u64>,
+    /// "Jobs started" (since the statistics were last reset)
+    pub jobs_started: Option<u64>,
+    /// "Jobs completed" (since the statistics were last reset)
+    pub jobs_completed: Option<u64>,
+    /// "Jobs canceled" (since the statistics were last reset)
+    pub jobs_canceled: Option<u64>,
+    /// "Jobs failed" (since the statistics were last reset)
+    pub jobs_failed: Option<u64>,
+    /// "Jobs pending" (i.e., the current queue depth)
+    pub jobs_pending: Option<u64>,
+    /// "Jobs running"
+    pub jobs_running: Option<u64>,
+    /// "Main schedule statistics"
+    pub main_schedule: SdiagScheduleStats,
+    /// "Backfilling stats"
+    pub backfill: SdiagBackfillStats,
+    /// "Remote Procedure Call statistics by message type"
+    pub rpc_by_message_type: Vec<SdiagRpcStats>,
+    /// "Remote Procedure Call statistics by user"
+    pub rpc_by_user: Vec<SdiagRpcStats>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+/// Statistics of the main scheduling loop (reported by `sdiag`)
+///
+/// All cycle times are given in microseconds.
+pub struct SdiagScheduleStats {
+    /// "Last cycle"
+    pub last_cycle: Option<u64>,
+    /// "Max cycle"
+    pub max_cycle: Option<u64>,
+    /// "Total cycles"
+    pub total_cycles: Option<u64>,
+    /// "Mean cycle"
+    pub mean_cycle: Option<u64>,
+    /// "Mean depth cycle"
+    pub mean_depth_cycle: Option<u64>,
+    /// "Cycles per minute"
+    pub cycles_per_minute: Option<u64>,
+    /// "Last queue length"
+    pub last_queue_length: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+/// Statistics of the backfill scheduler (reported by `sdiag`)
+///
+/// All cycle times are given in microseconds.
+pub struct SdiagBackfillStats {
+    /// "Total backfilled jobs (since last slurm start)"
+    pub total_backfilled_jobs: Option<u64>,
+    /// "Total backfilled jobs (since last stats cycle start)"
+    pub total_backfilled_jobs_since_stats_reset: Option<u64>,
+    /// "Total cycles"
+    pub total_cycles: Option<u64>,
+    /// "Last cycle"
+    pub last_cycle: Option<u64>,
+    /// "Max cycle"
+    pub max_cycle: Option<u64>,
+    /// "Mean cycle"
+    pub mean_cycle: Option<u64>,
+    /// "Last depth cycle"
+    pub last_depth_cycle: Option<u64>,
+    /// "Depth Mean"
+    pub depth_mean: Option<u64>,
+    /// "Last queue length"
+    pub last_queue_length: Option<u64>,
+    /// "Queue length mean"
+    pub queue_length_mean: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Remote Procedure Call (RPC) statistics for a message type or user (reported by `sdiag`)
+pub struct SdiagRpcStats {
+    /// Message type (e.g., `REQUEST_PARTITION_INFO`) or user name
+    pub name: String,
+    /// Message type ID or user ID
+    pub id: u64,
+    /// Number of RPCs
+    pub count: u64,
+    /// Average time per RPC (in microseconds)
+    pub ave_time: u64,
+    /// Total time of all RPCs (in microseconds)
+    pub total_time: u64,
+}
+
+impl SdiagRpcStats {
+    // e.g., `REQUEST_PARTITION_INFO ( 2009) count:1234 ave_time:100 total_time:123400`
+    fn parse_from_line(line: &str) -> Result<Self, Error> {
+        let (name, rest) = line
+            .trim()
+            .split_once("(")
+            .ok_or(Error::msg("Missing ID in RPC statistics."))?;
+        let (id, rest) = rest
+            .split_once(")")
+            .ok_or(Error::msg("Missing ID in RPC statistics."))?;
+        let values: HashMap<&str, &str> = rest
+            .split_whitespace()
+            .filter_map(|kv| kv.split_once(":"))
+            .collect();
+        let get = |key: &str| -> Result<u64, Error> {
+            Ok(values
+                .get(key)
+                .ok_or(Error::msg(format!("Missing {key} in RPC statistics.")))?
+                .parse()?)
+        };
+        Ok(Self {
+            name: name.trim().to_string(),
+            id: id.trim().parse()?,
+            count: get("count")?,
+            ave_time: get("ave_time")?,
+            total_time: get("total_time")?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SdiagSection {
+    General,
+    MainSchedule,
+    Backfill,
+    RpcByMessageType,
+    RpcByUser,
+    Other,
+}
+
+impl SdiagStats {
+    fn parse(output: &str) -> Self {
+        let mut stats = Self::default();
+        let mut section = SdiagSection::General;
+        for line in output.lines() {
+            if line.trim().is_empty() || line.starts_with("*") {
+                continue;
+            }
+            if !line.starts_with(char::is_whitespace) {
+                // Section headers are not indented
+                let header = line.trim();
+                let new_section = if header.starts_with("Main schedule statistics") {
+                    Some(SdiagSection::MainSchedule)
+                } else if header.starts_with("Backfilling stats") {
+                    Some(SdiagSection::Backfill)
+                } else if header.starts_with("Remote Procedure Call statistics by message type") {
+                    Some(SdiagSection::RpcByMessageType)
+                } else if header.starts_with("Remote Procedure Call statistics by user") {
+                    Some(SdiagSection::RpcByUser)
+                } else if header.starts_with("Remote Procedure Call")
+                    || header.starts_with("Pending RPC")
+                {
+                    Some(SdiagSection::Other)
+                } else {
+                    None
+                };
+                if let Some(new_section) = new_section {
+                    section = new_section;
+                    continue;
+                }
+                // Non-indented key-value lines belong to the general section
+                section = SdiagSection::General;
+            }
+            match section {
+                SdiagSection::RpcByMessageType | SdiagSection::RpcByUser => {
+                    let rpc = match SdiagRpcStats::parse_from_line(line) {
+                        Ok(rpc) => rpc,
+                        Err(err) => {
+                            println!("[!] {:?} for {:?}", err, &line);
+                            continue;
+                        }
+                    };
+                    if section == SdiagSection::RpcByUser {
+                        stats.rpc_by_user.push(rpc);
+                    } else {
+                        stats.rpc_by_message_type.push(rpc);
+                    }
+                }
+                SdiagSection::Other => {}
+                _ => {
+                    let Some((key, value)) = line.split_once(":") else {
+                        continue;
+                    };
+                    let value: Option<u64> =
+                        value.split_whitespace().next().and_then(|v| v.parse().ok());
+                    stats.set_value(section, key.trim(), value);
+                }
+            }
+        }
+        stats
+    }
+
+    fn set_value(&mut self, section: SdiagSection, key: &str, value: Option<u64>) {
+        match section {
+            SdiagSection::General => match key {
+                "Server thread count" => self.server_thread_count = value,
+                "Agent queue size" => self.agent_queue_size = value,
+                "DBD Agent queue size" => self.dbd_agent_queue_size = value,
+                "Jobs submitted" => self.jobs_submitted = value,
+                "Jobs started" => self.jobs_started = value,
+                "Jobs completed" => self.jobs_completed = value,
+                "Jobs canceled" => self.jobs_canceled = value,
+                "Jobs failed" => self.jobs_failed = value,
+                "Jobs pending" => self.jobs_pending = value,
+                "Jobs running" => self.jobs_running = value,
+                _ => {}
+            },
+            SdiagSection::MainSchedule => {
+                let s = &mut self.main_schedule;
+                match key {
+                    "Last cycle" => s.last_cycle = value,
+                    "Max cycle" => s.max_cycle = value,
+                    "Total cycles" => s.total_cycles = value,
+                    "Mean cycle" => s.mean_cycle = value,
+                    "Mean depth cycle" => s.mean_depth_cycle = value,
+                    "Cycles per minute" => s.cycles_per_minute = value,
+                    "Last queue length" => s.last_queue_length = value,
+                    _ => {}
+                }
+            }
+            SdiagSection::Backfill => {
+                let s = &mut self.backfill;
+                match key {
+                    "Total backfilled jobs (since last slurm start)" => {
+                        s.total_backfilled_jobs = value
+                    }
+                    "Total backfilled jobs (since last stats cycle start)" => {
+                        s.total_backfilled_jobs_since_stats_reset = value
+                    }
+                    "Total cycles" => s.total_cycles = value,
+                    "Last cycle" => s.last_cycle = value,
+                    "Max cycle" => s.max_cycle = value,
+                    "Mean cycle" => s.mean_cycle = value,
+                    "Last depth cycle" => s.last_depth_cycle = value,
+                    "Depth Mean" => s.depth_mean = value,
+                    "Last queue length" => s.last_queue_length = value,
+                    "Queue length mean" => s.queue_length_mean = value,
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Get `sdiag` scheduler statistics using the provided `executor`
+pub async fn get_sdiag_res(
+    executor: &impl CommandExecutor,
+) -> Result<(DateTime<Utc>, SdiagStats), Error> {
+    let result = executor.execute(&String::from("sdiag")).await?.stdout;
+    let time: DateTime<Utc> = SystemTime::now().into();
+    Ok((time, SdiagStats::parse(&result)))
+}
+
+/// Run and parse `sdiag` result locally (i.e., not via SSH)
+pub async fn get_sdiag_res_locally() -> Result<(DateTime<Utc>, SdiagStats), Error> {
+    get_sdiag_res(&LocalExecutor).await
+}
+
+#[cfg(feature = "ssh")]
+/// Run and parse `sdiag` result over SSH
+pub async fn get_sdiag_res_ssh(client: &Client) -> Result<(DateTime<Utc>, SdiagStats), Error> {
+    get_sdiag_res(client).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SdiagStats;
+
+    #[test]
+    fn test_parse_sdiag() {
+        let output = "*******************************************************
+sdiag output at Tue Jan 14 10:00:00 2025 (1736845200)
+Data since      Tue Jan 14 01:00:00 2025 (1736812800)
+*******************************************************
+Server thread count:  3
+Agent queue size:     0
+DBD Agent queue size: 0
+
+Jobs submitted: 1234
+Jobs started:   1000
+Jobs completed: 900
+Jobs canceled:  10
+Jobs failed:    2
+
+Job states ts:  Tue Jan 14 10:00:00 2025 (1736845190)
+Jobs pending:   500
+Jobs running:   200
+
+Main schedule statistics (microseconds):
+\tLast cycle:   12345
+\tMax cycle:    99999
+\tTotal cycles: 500
+\tLast queue length: 480
+
+Backfilling stats
+\tTotal backfilled jobs (since last slurm start): 300
+\tTotal cycles: 40
+\tLast cycle when: Tue Jan 14 09:59:00 2025 (1736845140)
+\tLast cycle: 2000000
+\tDepth Mean: 300
+
+Remote Procedure Call statistics by message type
+\tREQUEST_PARTITION_INFO                  ( 2009) count:1234   ave_time:100    total_time:123400
+\tREQUEST_JOB_INFO                        ( 2003) count:10     ave_time:2000   total_time:20000
+
+Remote Procedure Call statistics by user
+\troot            (       0) count:100    ave_time:50     total_time:5000
+";
+        let stats = SdiagStats::parse(output);
+        assert_eq!(stats.server_thread_count, Some(3));
+        assert_eq!(stats.jobs_submitted, Some(1234));
+        assert_eq!(stats.jobs_pending, Some(500));
+        assert_eq!(stats.main_schedule.last_cycle, Some(12345));
+        assert_eq!(stats.main_schedule.last_queue_length, Some(480));
+        assert_eq!(stats.backfill.last_cycle, Some(2000000));
+        assert_eq!(stats.backfill.total_cycles, Some(40));
+        assert_eq!(stats.backfill.total_backfilled_jobs, Some(300));
+        assert_eq!(stats.rpc_by_message_type.len(), 2);
+        assert_eq!(stats.rpc_by_message_type[0].id, 2009);
+        assert_eq!(stats.rpc_by_user[0].name, "root");
+        assert_eq!(stats.rpc_by_user[0].total_time, 5000);
+    }
+}
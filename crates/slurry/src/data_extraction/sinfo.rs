@@ -0,0 +1,395 @@
+use std::{
+    collections::HashMap, future::Future, path::Path, str::FromStr, time::Duration,
+    time::SystemTime,
+};
+
+use anyhow::Error;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssh")]
+use async_ssh2_tokio::Client;
+
+use crate::{
+    executor::{CommandExecutor, LocalExecutor},
+    parse_slurm_duration,
+    recording::{JsonDirStore, RecordingStore},
+};
+
+// https://slurm.schedmd.com/sinfo.html
+pub(crate) const SINFO_NODE_FORMAT_STR: &str = "%N|%P|%T|%c|%C|%m|%e|%O|%E";
+pub(crate) const SINFO_PARTITION_FORMAT_STR: &str = "%P|%a|%l|%D|%C|%m";
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+/// State of a SLURM node (according to `sinfo`)
+///
+/// Documentation taken from <https://slurm.schedmd.com/sinfo.html#SECTION_NODE-STATE-CODES>.
+pub enum NodeState {
+    /// The node is allocated to one or more jobs.
+    ALLOCATED,
+    /// The node has some of its CPUs allocated while others are idle.
+    MIXED,
+    /// The node is not allocated to any jobs and is available for use.
+    IDLE,
+    /// The node is unavailable for use per system administrator request.
+    DRAINED,
+    /// The node is currently executing a job, but will not be allocated additional jobs.
+    DRAINING,
+    /// The node is unavailable for use.
+    DOWN,
+    /// The node is currently in a reservation.
+    RESERVED,
+    /// The node is allocated to jobs which are in the process of completing.
+    COMPLETING,
+    /// The node is currently being powered down or is powered down.
+    #[allow(non_camel_case_types)]
+    POWERED_DOWN,
+    /// The node is under maintenance by the system administrator.
+    MAINTENANCE,
+    /// The node has failed and is unavailable for use.
+    FAIL,
+    /// Other node state, specifying the concrete node state as a [`String`]
+    OTHER(String),
+}
+
+impl FromStr for NodeState {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Strip flag suffixes, e.g., `down*` (not responding) or `idle~` (powered off)
+        let s = s.trim_end_matches(['*', '~', '#', '!', '%', '$', '@', '^', '-']);
+        match s.to_lowercase().as_str() {
+            "allocated" | "alloc" => Ok(Self::ALLOCATED),
+            "mixed" | "mix" => Ok(Self::MIXED),
+            "idle" => Ok(Self::IDLE),
+            "drained" | "drain" => Ok(Self::DRAINED),
+            "draining" | "drng" => Ok(Self::DRAINING),
+            "down" => Ok(Self::DOWN),
+            "reserved" | "resv" => Ok(Self::RESERVED),
+            "completing" | "comp" => Ok(Self::COMPLETING),
+            "powered_down" | "powered down" | "powered_off" => Ok(Self::POWERED_DOWN),
+            "maintenance" | "maint" => Ok(Self::MAINTENANCE),
+            "fail" | "failing" => Ok(Self::FAIL),
+            _ => Ok(Self::OTHER(s.to_string())),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Struct for parsed node-oriented output row of `sinfo` command
+///
+/// Containing information about a single node in a given partition
+pub struct SinfoNodeRow {
+    /// "NODELIST"
+    pub node_name: String,
+    /// "PARTITION"
+    ///
+    /// The default partition is marked with a trailing `*` by SLURM, which is stripped here
+    pub partition: String,
+    /// "STATE"
+    pub state: NodeState,
+    /// "CPUS"
+    pub cpus: usize,
+    /// "CPUS(A/I/O/T)", number of allocated CPUs
+    pub cpus_allocated: usize,
+    /// "CPUS(A/I/O/T)", number of idle CPUs
+    pub cpus_idle: usize,
+    /// "MEMORY", size of memory in megabytes
+    pub memory: usize,
+    /// "`FREE_MEM`", free memory in megabytes
+    pub free_memory: Option<usize>,
+    /// "`CPU_LOAD`"
+    pub cpu_load: Option<f64>,
+    /// "REASON"
+    pub reason: Option<String>,
+}
+
+impl SinfoNodeRow {
+    fn parse_from_strs(vals: &[&str]) -> Result<Self, Error> {
+        if vals.len() != 9 {
+            return Err(Error::msg("Invalid length of values."));
+        }
+        let (cpus_allocated, cpus_idle, _, _) = parse_cpu_counts(vals[4])?;
+        Ok(Self {
+            node_name: vals[0].to_string(),
+            partition: vals[1].trim_end_matches('*').to_string(),
+            state: vals[2].parse()?,
+            cpus: vals[3].parse()?,
+            cpus_allocated,
+            cpus_idle,
+            memory: vals[5].trim_end_matches('+').parse()?,
+            free_memory: vals[6].parse().ok(),
+            cpu_load: vals[7].parse().ok(),
+            reason: match vals[8] {
+                "none" | "(null)" | "" => None,
+                s => Some(s.to_string()),
+            },
+        })
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Struct for parsed partition-oriented output row of `sinfo` command
+///
+/// Containing summarized information about a partition
+pub struct SinfoPartitionRow {
+    /// "PARTITION"
+    pub partition: String,
+    /// If this partition is the default partition (marked with a trailing `*` by SLURM)
+    pub is_default: bool,
+    /// "AVAIL", e.g., `up` or `down`
+    pub availability: String,
+    /// "TIMELIMIT", `None` if the partition has no (or an infinite) time limit
+    pub time_limit: Option<Duration>,
+    /// "NODES"
+    pub nodes: usize,
+    /// "CPUS(A/I/O/T)", number of allocated CPUs
+    pub cpus_allocated: usize,
+    /// "CPUS(A/I/O/T)", number of idle CPUs
+    pub cpus_idle: usize,
+    /// "CPUS(A/I/O/T)", number of CPUs in other states (e.g., drained or down)
+    pub cpus_other: usize,
+    /// "CPUS(A/I/O/T)", total number of CPUs
+    pub cpus_total: usize,
+    /// "MEMORY", (minimal) size of memory per node in megabytes
+    pub memory: usize,
+}
+
+impl SinfoPartitionRow {
+    fn parse_from_strs(vals: &[&str]) -> Result<Self, Error> {
+        if vals.len() != 6 {
+            return Err(Error::msg("Invalid length of values."));
+        }
+        let (cpus_allocated, cpus_idle, cpus_other, cpus_total) = parse_cpu_counts(vals[4])?;
+        Ok(Self {
+            partition: vals[0].trim_end_matches('*').to_string(),
+            is_default: vals[0].ends_with('*'),
+            availability: vals[1].to_string(),
+            time_limit: match vals[2] {
+                "infinite" | "n/a" => None,
+                s => parse_slurm_duration(s).map(Some).unwrap_or_default(),
+            },
+            nodes: vals[3].parse()?,
+            cpus_allocated,
+            cpus_idle,
+            cpus_other,
+            cpus_total,
+            memory: vals[5].trim_end_matches('+').parse()?,
+        })
+    }
+}
+
+/// Parse CPU counts in the `allocated/idle/other/total` format
+fn parse_cpu_counts(s: &str) -> Result<(usize, usize, usize, usize), Error> {
+    let counts = s
+        .split("/")
+        .map(|c| c.parse())
+        .collect::<Result<Vec<usize>, _>>()?;
+    if counts.len() != 4 {
+        return Err(Error::msg(format!("Invalid CPU count format: {s}")));
+    }
+    Ok((counts[0], counts[1], counts[2], counts[3]))
+}
+
+fn parse_sinfo_lines<T>(
+    output: &str,
+    parse: impl Fn(&[&str]) -> Result<T, Error>,
+) -> (DateTime<Utc>, Vec<T>) {
+    let time: DateTime<Utc> = SystemTime::now().into();
+    let rows = output
+        .split("\n")
+        .filter_map(|line| {
+            if line.is_empty() {
+                return None;
+            }
+            match parse(&line.split("|").collect::<Vec<_>>()) {
+                Ok(row) => Some(row),
+                Err(err) => {
+                    println!("[!] {:?} for {:?}", err, &line);
+                    None
+                }
+            }
+        })
+        .collect();
+    (time, rows)
+}
+
+/// Get node-oriented `sinfo` results using the provided `executor`
+pub async fn get_sinfo_nodes(
+    executor: &impl CommandExecutor,
+) -> Result<(DateTime<Utc>, Vec<SinfoNodeRow>), Error> {
+    let result = executor
+        .execute(&format!(
+            "sinfo -h -a -N --format='{SINFO_NODE_FORMAT_STR}'"
+        ))
+        .await?
+        .stdout;
+    Ok(parse_sinfo_lines(&result, SinfoNodeRow::parse_from_strs))
+}
+
+/// Get partition-oriented `sinfo` results using the provided `executor`
+pub async fn get_sinfo_partitions(
+    executor: &impl CommandExecutor,
+) -> Result<(DateTime<Utc>, Vec<SinfoPartitionRow>), Error> {
+    let result = executor
+        .execute(&format!(
+            "sinfo -h -a --format='{SINFO_PARTITION_FORMAT_STR}'"
+        ))
+        .await?
+        .stdout;
+    Ok(parse_sinfo_lines(
+        &result,
+        SinfoPartitionRow::parse_from_strs,
+    ))
+}
+
+/// Run and parse node-oriented `sinfo` result locally (i.e., not via SSH)
+pub async fn get_sinfo_nodes_locally() -> Result<(DateTime<Utc>, Vec<SinfoNodeRow>), Error> {
+    get_sinfo_nodes(&LocalExecutor).await
+}
+
+/// Run and parse partition-oriented `sinfo` result locally (i.e., not via SSH)
+pub async fn get_sinfo_partitions_locally() -> Result<(DateTime<Utc>, Vec<SinfoPartitionRow>), Error>
+{
+    get_sinfo_partitions(&LocalExecutor).await
+}
+
+#[cfg(feature = "ssh")]
+/// Run and parse node-oriented `sinfo` result over SSH
+pub async fn get_sinfo_nodes_ssh(
+    client: &Client,
+) -> Result<(DateTime<Utc>, Vec<SinfoNodeRow>), Error> {
+    get_sinfo_nodes(client).await
+}
+
+#[cfg(feature = "ssh")]
+/// Run and parse partition-oriented `sinfo` result over SSH
+pub async fn get_sinfo_partitions_ssh(
+    client: &Client,
+) -> Result<(DateTime<Utc>, Vec<SinfoPartitionRow>), Error> {
+    get_sinfo_partitions(client).await
+}
+
+/// Execute `sinfo` and compare the node states with (optional) data from previous executions
+///
+/// Nodes which are new or changed their state or reason are saved as JSON files in the directory at `path` (see [`JsonDirStore`]).
+pub async fn sinfo_diff<F, Fut>(
+    get_sinfo: F,
+    path: &Path,
+    known_nodes: &mut HashMap<String, SinfoNodeRow>,
+) -> Result<(DateTime<Utc>, Vec<SinfoNodeRow>), Error>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<(DateTime<Utc>, Vec<SinfoNodeRow>), Error>>,
+{
+    sinfo_diff_with_store(get_sinfo, &JsonDirStore::new(path), known_nodes).await
+}
+
+/// Execute `sinfo` and compare the node states with (optional) data from previous executions
+///
+/// Nodes which are new or changed their state or reason are saved using the provided [`RecordingStore`].
+pub async fn sinfo_diff_with_store<F, Fut, S>(
+    get_sinfo: F,
+    store: &S,
+    known_nodes: &mut HashMap<String, SinfoNodeRow>,
+) -> Result<(DateTime<Utc>, Vec<SinfoNodeRow>), Error>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<(DateTime<Utc>, Vec<SinfoNodeRow>), Error>>,
+    S: RecordingStore + ?Sized,
+{
+    let (time, rows) = get_sinfo().await?;
+    let mut changed = Vec::new();
+    for row in &rows {
+        // Nodes in multiple partitions are listed once per partition
+        match known_nodes.get(&row.node_name) {
+            Some(prev) if prev.state == row.state && prev.reason == row.reason => {}
+            _ => {
+                changed.push(row.clone());
+                known_nodes.insert(row.node_name.clone(), row.clone());
+            }
+        }
+    }
+    if !changed.is_empty() {
+        store.record_node_states(&time, &changed)?;
+    }
+    store.flush()?;
+    Ok((time, rows))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NodeState, SinfoNodeRow, SinfoPartitionRow};
+
+    #[test]
+    fn test_parse_sinfo_rows() {
+        let node = SinfoNodeRow::parse_from_strs(
+            &"n23m0001|c23ms*|mixed|96|48/48/0/96|256000|120443|47.93|none"
+                .split("|")
+                .collect::<Vec<_>>(),
+        )
+        .unwrap();
+        assert_eq!(node.partition, "c23ms");
+        assert_eq!(node.state, NodeState::MIXED);
+        assert_eq!(node.cpus_allocated, 48);
+        assert_eq!(node.reason, None);
+
+        let down = SinfoNodeRow::parse_from_strs(
+            &"n23m0002|c23ms*|down*|96|0/0/96/96|256000|N/A|N/A|Not responding"
+                .split("|")
+                .collect::<Vec<_>>(),
+        )
+        .unwrap();
+        assert_eq!(down.state, NodeState::DOWN);
+        assert_eq!(down.free_memory, None);
+        assert_eq!(down.reason.as_deref(), Some("Not responding"));
+
+        let partition = SinfoPartitionRow::parse_from_strs(
+            &"c23ms*|up|2-00:00:00|632|50000/10000/668/60668|256000+"
+                .split("|")
+                .collect::<Vec<_>>(),
+        )
+        .unwrap();
+        assert!(partition.is_default);
+        assert_eq!(partition.partition, "c23ms");
+        assert_eq!(partition.cpus_total, 60668);
+        assert_eq!(partition.memory, 256000);
+    }
+
+    #[tokio::test]
+    async fn test_sinfo_diff() {
+        use std::collections::HashMap;
+
+        use super::{parse_sinfo_lines, sinfo_diff_with_store};
+        use crate::recording::{jsonl::read_jsonl_records, JsonlRecord, JsonlStore};
+
+        let path = std::env::temp_dir().join(format!("slurry-sinfo-{}.jsonl", std::process::id()));
+        let store = JsonlStore::open(&path).unwrap();
+        let mut known_nodes = HashMap::default();
+        for output in [
+            "n23m0001|c23ms*|idle|96|0/96/0/96|256000|120443|0.01|none\nn23m0001|c23test|idle|96|0/96/0/96|256000|120443|0.01|none",
+            "n23m0001|c23ms*|mixed|96|48/48/0/96|256000|120443|47.93|none",
+            "n23m0001|c23ms*|mixed|96|60/36/0/96|256000|110443|59.93|none",
+            "n23m0001|c23ms*|down*|96|0/0/96/96|256000|N/A|N/A|Not responding",
+        ] {
+            sinfo_diff_with_store(
+                || async { Ok(parse_sinfo_lines(output, SinfoNodeRow::parse_from_strs)) },
+                &store,
+                &mut known_nodes,
+            )
+            .await
+            .unwrap();
+        }
+        let changes: Vec<_> = read_jsonl_records(&path)
+            .unwrap()
+            .filter_map(|r| match r {
+                JsonlRecord::Nodes { payload, .. } => Some(payload),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(changes.len(), 3);
+        assert_eq!(changes[0].len(), 1);
+        assert_eq!(changes[2][0].state, NodeState::DOWN);
+        std::fs::remove_file(path).unwrap();
+    }
+}
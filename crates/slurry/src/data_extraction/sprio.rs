@@ -0,0 +1,148 @@
+use anyhow::Error;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::time::SystemTime;
+
+#[cfg(feature = "ssh")]
+use async_ssh2_tokio::Client;
+
+use crate::executor::{shell_quote, CommandExecutor, LocalExecutor};
+
+// https://slurm.schedmd.com/sprio.html
+pub(crate) const SPRIO_FORMAT_STR: &str = "%i|%r|%u|%Y|%A|%F|%J|%P|%Q|%N|%T";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Struct for parsed output row of `sprio` command
+///
+/// Containing the (weighted) priority factors of a pending SLURM job
+pub struct SprioRow {
+    /// "JOBID"
+    pub job_id: String,
+    /// "PARTITION"
+    pub partition: String,
+    /// "USER"
+    pub user: String,
+    /// "PRIORITY", the overall (weighted) job priority
+    pub priority: f64,
+    /// "AGE"
+    pub age: f64,
+    /// "FAIRSHARE"
+    pub fairshare: f64,
+    /// "`JOBSIZE`"
+    pub job_size: f64,
+    /// "PARTITION", the priority factor contributed by the partition
+    pub partition_priority: f64,
+    /// "QOS"
+    pub qos: f64,
+    /// "NICE"
+    pub nice: i64,
+    /// "TRES", e.g., `cpu=12,mem=3`
+    pub tres: Option<String>,
+}
+
+impl SprioRow {
+    fn parse_from_strs(vals: &[&str]) -> Result<Self, Error> {
+        if vals.len() != 11 {
+            return Err(Error::msg("Invalid length of values."));
+        }
+        Ok(Self {
+            job_id: vals[0].to_string(),
+            partition: vals[1].to_string(),
+            user: vals[2].to_string(),
+            priority: vals[3].parse()?,
+            age: vals[4].parse()?,
+            fairshare: vals[5].parse()?,
+            job_size: vals[6].parse()?,
+            partition_priority: vals[7].parse()?,
+            qos: vals[8].parse()?,
+            nice: vals[9].parse()?,
+            tres: match vals[10] {
+                "" | "(null)" => None,
+                s => Some(s.to_string()),
+            },
+        })
+    }
+}
+
+/// Get `sprio` priority factors using the provided `executor`
+///
+/// If `job_ids` is empty, the priority factors of all pending jobs are returned.
+pub async fn get_sprio_res(
+    job_ids: &[String],
+    executor: &impl CommandExecutor,
+) -> Result<(DateTime<Utc>, Vec<SprioRow>), Error> {
+    let extra_arg = if job_ids.is_empty() {
+        String::default()
+    } else {
+        format!("-j {}", shell_quote(&job_ids.join(",")))
+    };
+    let result = executor
+        .execute(&format!(
+            "sprio -h --format='{SPRIO_FORMAT_STR}' {extra_arg}"
+        ))
+        .await?
+        .stdout;
+    let time: DateTime<Utc> = SystemTime::now().into();
+    let rows = result
+        .split("\n")
+        .filter_map(|line| {
+            if line.is_empty() {
+                return None;
+            }
+            let vals = line.split("|").map(|s| s.trim()).collect::<Vec<_>>();
+            match SprioRow::parse_from_strs(&vals) {
+                Ok(row) => Some(row),
+                Err(err) => {
+                    println!("[!] {:?} for {:?}", err, &line);
+                    None
+                }
+            }
+        })
+        .collect();
+    Ok((time, rows))
+}
+
+/// Run and parse `sprio` result locally (i.e., not via SSH)
+pub async fn get_sprio_res_locally(
+    job_ids: &[String],
+) -> Result<(DateTime<Utc>, Vec<SprioRow>), Error> {
+    get_sprio_res(job_ids, &LocalExecutor).await
+}
+
+#[cfg(feature = "ssh")]
+/// Run and parse `sprio` result over SSH
+pub async fn get_sprio_res_ssh(
+    client: &Client,
+    job_ids: &[String],
+) -> Result<(DateTime<Utc>, Vec<SprioRow>), Error> {
+    get_sprio_res(job_ids, client).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::get_sprio_res;
+
+    #[tokio::test]
+    async fn test_parse_sprio() {
+        let output = "49848561|c23ms|ab123456|1201|201|500|100|300|100|0|cpu=12,mem=3
+  49848562|c23g|cd654321|  950.5|  50| 400| 200| 300|   0| -10|
+49848563|c23ms|ab123456|0|0|0|0|0|0|0|(null)
+invalid|row
+";
+        let (_, rows) = get_sprio_res(&[], &|_| async { Ok(output.to_string()) })
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].job_id, "49848561");
+        assert_eq!(rows[0].partition, "c23ms");
+        assert_eq!(rows[0].priority, 1201.0);
+        assert_eq!(rows[0].fairshare, 500.0);
+        assert_eq!(rows[0].partition_priority, 300.0);
+        assert_eq!(rows[0].tres.as_deref(), Some("cpu=12,mem=3"));
+        assert_eq!(rows[1].user, "cd654321");
+        assert_eq!(rows[1].priority, 950.5);
+        assert_eq!(rows[1].nice, -10);
+        assert_eq!(rows[1].tres, None);
+        assert_eq!(rows[2].tres, None);
+    }
+}
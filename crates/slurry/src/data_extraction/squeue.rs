@@ -1,29 +1,34 @@
 use std::{path::PathBuf, time::Duration};
 
 use anyhow::Error;
-use chrono::NaiveDateTime;
 use serde::{Deserialize, Serialize};
 use structdiff::{Difference, StructDiff};
 
-use crate::{parse_slurm_duration, JobState};
+use super::{
+    cluster_info::{deserialize_utc, deserialize_utc_opt, parse_slurm_time},
+    sacct::SacctJobRow,
+};
+use crate::{
+    executor::{shell_quote, CommandExecutor, LocalExecutor},
+    parse_slurm_duration,
+    recording::{JsonDirStore, RecordingStore, SqueueRowDiff},
+    ArrayJobId, JobState, LicenseRequest, MemorySpec, TresRequest,
+};
 use std::{
     collections::{HashMap, HashSet},
-    fs::{create_dir_all, File},
     future::Future,
-    io::BufWriter,
     path::Path,
-    process::Command,
     time::{Instant, SystemTime},
 };
 
 #[cfg(feature = "ssh")]
 use async_ssh2_tokio::Client;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, FixedOffset, Utc};
 use rayon::iter::IntoParallelRefIterator;
 
 // https://slurm.schedmd.com/squeue.html
 pub(crate) const SQUEUE_FORMAT_STR: &str =
-    "%a|%A|%B|%c|%C|%D|%e|%E|%f|%F|%G|%i|%l|%L|%j|%m|%M|%p|%P|%T|%r|%S|%V|%Z|%o";
+    "%a|%A|%B|%c|%C|%D|%e|%E|%f|%F|%G|%i|%l|%L|%j|%m|%M|%p|%P|%T|%r|%S|%V|%Z|%o|%b|%W|%u|%N";
 // const SQUEUE_EXPECTED_COLS: &[&str] = &[
 //     "ACCOUNT",
 //     "JOBID",
@@ -50,6 +55,9 @@ pub(crate) const SQUEUE_FORMAT_STR: &str =
 //     "SUBMIT_TIME",
 //     "WORK_DIR",
 //     "COMMAND",
+//     "TRES_PER_NODE",
+//     "LICENSES",
+//     "USER",
 // ];
 
 #[derive(Debug, Clone, Serialize, Deserialize, Difference)]
@@ -70,7 +78,8 @@ pub struct SqueueRow {
     /// "NODES",
     pub nodes: usize,
     /// "`END_TIME`",
-    pub end_time: Option<NaiveDateTime>,
+    #[serde(deserialize_with = "deserialize_utc_opt")]
+    pub end_time: Option<DateTime<Utc>>,
     /// "DEPENDENCY",
     pub dependency: Option<String>,
     /// "FEATURES",
@@ -81,7 +90,7 @@ pub struct SqueueRow {
     pub group: String,
     /// "STEPJOBID",
     /// 49848561 or `49869434_2` or 49616001_[3-10%1]
-    pub step_job_id: (String, Option<String>),
+    pub step_job_id: ArrayJobId,
     /// "`TIME_LIMIT`",
     pub time_limit: Option<Duration>,
     /// "`TIME_LEFT`",
@@ -90,7 +99,7 @@ pub struct SqueueRow {
     /// "NAME",
     pub name: String,
     /// "`MIN_MEMORY`",
-    pub min_memory: String,
+    pub min_memory: MemorySpec,
     /// "TIME",
     #[difference(skip)]
     pub time: Option<Duration>,
@@ -103,21 +112,83 @@ pub struct SqueueRow {
     /// "REASON",
     pub reason: String,
     /// "`START_TIME`",
-    pub start_time: Option<NaiveDateTime>,
+    #[serde(deserialize_with = "deserialize_utc_opt")]
+    pub start_time: Option<DateTime<Utc>>,
     /// "`SUBMIT_TIME`",
-    pub submit_time: NaiveDateTime,
+    #[serde(deserialize_with = "deserialize_utc")]
+    pub submit_time: DateTime<Utc>,
     /// "`WORK_DIR`",
     pub work_dir: PathBuf,
     /// "COMMAND",
     pub command: String,
+    /// "`TRES_PER_NODE`", e.g., `gres/gpu:a100:2`
+    #[serde(default)]
+    pub tres_per_node: Vec<TresRequest>,
+    /// "LICENSES", e.g., `ansys:2`
+    #[serde(default)]
+    pub licenses: Vec<LicenseRequest>,
+    /// "USER", name of the user who submitted the job
+    ///
+    /// Not available for rows recorded before this field was added.
+    #[serde(default)]
+    pub user: Option<String>,
+    /// "NODELIST", the allocated nodes (e.g., `n23m[0001-0002]`), if the job was started
+    ///
+    /// Not available for rows recorded before this field was added.
+    #[serde(default)]
+    pub node_list: Option<String>,
+    /// Exit code of the job script
+    ///
+    /// Not reported by `squeue`, but set for jobs which disappeared from `squeue` (see [`squeue_diff_with_final_states`]).
+    #[serde(default)]
+    pub exit_code: Option<i32>,
+    /// Name of the cluster the job belongs to
+    ///
+    /// Only set for `squeue -M` output listing multiple clusters (i.e., with `CLUSTER: <name>` header lines)
+    #[serde(default)]
+    pub cluster: Option<String>,
+    /// The original unparsed `squeue` output line (or JSON object, see [`SqueueSource::Json`]) of this row
+    ///
+    /// Only saved by recording stores if enabled (e.g., using [`JsonDirStore::with_raw_lines`]),
+    /// allowing to reprocess recordings if a parsing issue is discovered later.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    #[difference(skip)]
+    pub raw_line: Option<String>,
 }
 
 impl SqueueRow {
-    fn parse_from_strs(vals: &[&str]) -> Result<Self, Error> {
-        if vals.len() != 25 {
+    /// The jobs this job depends on, as pairs of dependency type and job ID (e.g., `("afterok", "12345")`), parsed from [`SqueueRow::dependency`]
+    ///
+    /// Both `,` (all dependencies) and `?` (any dependency) separated lists are supported.
+    /// Status suffixes (e.g., `(unfulfilled)`) and delays (e.g., `+10`) are removed.
+    /// Job IDs of array jobs are kept as reported (e.g., `12345_*` or `12345_3`).
+    /// Dependencies without jobs (i.e., `singleton`) are skipped.
+    pub fn dependency_targets(&self) -> Vec<(String, String)> {
+        let Some(dependency) = &self.dependency else {
+            return Vec::new();
+        };
+        let mut targets = Vec::new();
+        for part in dependency.split([',', '?']) {
+            let part = part.split('(').next().unwrap_or_default().trim();
+            let mut fields = part.split(':');
+            let Some(dependency_type) = fields.next().filter(|t| !t.is_empty()) else {
+                continue;
+            };
+            for job_id in fields {
+                let job_id = job_id.split('+').next().unwrap_or_default();
+                if !job_id.is_empty() {
+                    targets.push((dependency_type.to_string(), job_id.to_string()));
+                }
+            }
+        }
+        targets
+    }
+
+    fn parse_from_strs(vals: &[&str], tz: &FixedOffset) -> Result<Self, Error> {
+        // Lines without the user or node list (e.g., raw lines of older recordings) are still accepted
+        if !(27..=29).contains(&vals.len()) {
             return Err(Error::msg("Invalid length of values."));
         }
-        let mut step_job_id = vals[11].split("_");
         Ok(Self {
             account: vals[0].to_string(),
             job_id: vals[1].to_string(),
@@ -130,7 +201,7 @@ impl SqueueRow {
             nodes: vals[5].parse()?,
             end_time: match vals[6] {
                 "N/A" => None,
-                s => Some(NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S")?),
+                s => Some(parse_slurm_time(s, tz)?),
             },
             dependency: match vals[7] {
                 "(null)" => None,
@@ -139,10 +210,7 @@ impl SqueueRow {
             features: vals[8].to_string(),
             array_job_id: vals[9].to_string(),
             group: vals[10].to_string(),
-            step_job_id: (
-                step_job_id.next().unwrap().to_string(),
-                step_job_id.next().map(|s| s.to_string()),
-            ), // todo!(), // 11
+            step_job_id: vals[11].parse()?,
             time_limit: match vals[12] {
                 "INVALID" => None,
                 s => parse_slurm_duration(s).map(Some).unwrap_or_default(),
@@ -151,8 +219,8 @@ impl SqueueRow {
                 "INVALID" => None,
                 s => parse_slurm_duration(s).map(Some).unwrap_or_default(),
             }, // 13
-            name: vals[14].to_string(),       // 14
-            min_memory: vals[15].to_string(), // 15
+            name: vals[14].to_string(),    // 14
+            min_memory: vals[15].parse()?, // 15
             time: match vals[16] {
                 "INVALID" => None,
                 s => parse_slurm_duration(s).map(Some).unwrap_or_default(),
@@ -165,15 +233,92 @@ impl SqueueRow {
             reason: vals[20].to_string(),
             start_time: match vals[21] {
                 "N/A" => None,
-                s => Some(NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S")?),
+                s => Some(parse_slurm_time(s, tz)?),
             },
-            submit_time: NaiveDateTime::parse_from_str(vals[22], "%Y-%m-%dT%H:%M:%S")?,
+            submit_time: parse_slurm_time(vals[22], tz)?,
             work_dir: vals[23].parse()?,
             command: vals[24].to_string(),
+            tres_per_node: TresRequest::parse_list(vals[25])?,
+            licenses: LicenseRequest::parse_list(vals[26])?,
+            user: vals.get(27).map(|u| u.to_string()),
+            node_list: vals
+                .get(28)
+                .filter(|n| !n.is_empty() && **n != "(null)")
+                .map(|n| n.to_string()),
+            exit_code: None,
+            cluster: None,
+            raw_line: Some(vals.join("|")),
         })
     }
 }
 
+/// Get the cluster name from a `CLUSTER: <name>` header line, as printed by `squeue -M`
+fn parse_cluster_header(line: &str) -> Option<&str> {
+    line.strip_prefix("CLUSTER:").map(|name| name.trim())
+}
+
+/// Parse a single line of `squeue` output (using the [`SQUEUE_FORMAT_STR`] format)
+///
+/// Cluster header lines update the `cluster` (and yield `None`), which is then assigned to all following rows.
+fn parse_squeue_line(
+    line: &str,
+    cluster: &mut Option<String>,
+    tz: &FixedOffset,
+) -> Option<Result<SqueueRow, Error>> {
+    if line.is_empty() {
+        return None;
+    }
+    if let Some(name) = parse_cluster_header(line) {
+        *cluster = Some(name.to_string());
+        return None;
+    }
+    Some(
+        SqueueRow::parse_from_strs(&line.split("|").collect::<Vec<_>>(), tz).map(|mut row| {
+            row.cluster = cluster.clone();
+            row
+        }),
+    )
+}
+
+#[cfg(test)]
+/// A pending job row, e.g., for testing recordings
+pub(crate) fn test_row(time: DateTime<Utc>) -> SqueueRow {
+    SqueueRow {
+        account: "default".to_string(),
+        job_id: "49848561".to_string(),
+        exec_host: None,
+        min_cpus: 1,
+        cpus: 1,
+        nodes: 1,
+        end_time: None,
+        dependency: None,
+        features: String::new(),
+        array_job_id: "49848561".to_string(),
+        group: "ab123456".to_string(),
+        step_job_id: "49848561".parse().unwrap(),
+        time_limit: None,
+        time_left: None,
+        name: "my job".to_string(),
+        min_memory: MemorySpec::per_cpu_mb(3900),
+        time: None,
+        priority: 0.5,
+        partition: "c23ms".to_string(),
+        state: JobState::PENDING,
+        reason: "Priority".to_string(),
+        start_time: None,
+        submit_time: time,
+        work_dir: PathBuf::from("/home/ab123456"),
+        command: "start.sh".to_string(),
+        tres_per_node: Vec::new(),
+        licenses: Vec::new(),
+        user: Some("ab123456".to_string()),
+        node_list: None,
+        exit_code: None,
+        cluster: None,
+        raw_line: None,
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 /// Parameter for `squeue` extraction, specifying what SLURM jobs to include
 pub enum SqueueMode {
@@ -184,26 +329,157 @@ pub enum SqueueMode {
     MINE,
     /// Include only the specified SLURM jobs (given by their IDs)
     JOBIDS(Vec<String>),
+    /// Include only SLURM jobs in the specified partitions
+    PARTITIONS(Vec<String>),
+    /// Include only SLURM jobs of the specified accounts
+    ACCOUNTS(Vec<String>),
+    /// Include only SLURM jobs in the specified states
+    STATES(Vec<JobState>),
+    /// Include only SLURM jobs of the specified users (given by their user names)
+    USERS(Vec<String>),
+    /// Include only SLURM jobs matching all of the specified modes
+    ///
+    /// e.g., `COMBINED(vec![PARTITIONS(vec!["gpu".into()]), STATES(vec![JobState::PENDING])])`
+    /// includes only pending jobs in the `gpu` partition.
+    COMBINED(Vec<SqueueMode>),
+}
+
+impl SqueueMode {
+    /// Get the `squeue` arguments corresponding to this mode
+    ///
+    /// If no states are filtered for, jobs in all states are included (`-t all`).
+    pub(crate) fn to_squeue_args(&self) -> String {
+        let mut args = self.filter_args();
+        if !self.filters_states() {
+            args.push(String::from("-t all"));
+        }
+        args.join(" ")
+    }
+
+    fn filter_args(&self) -> Vec<String> {
+        let quoted = |vals: &[String]| shell_quote(&vals.join(","));
+        match self {
+            SqueueMode::ALL => Vec::new(),
+            SqueueMode::MINE => vec![String::from("--me")],
+            SqueueMode::JOBIDS(vec) => vec![format!("-j {}", quoted(vec))],
+            SqueueMode::PARTITIONS(vec) => vec![format!("-p {}", quoted(vec))],
+            SqueueMode::ACCOUNTS(vec) => vec![format!("-A {}", quoted(vec))],
+            SqueueMode::STATES(vec) => vec![format!(
+                "-t {}",
+                quoted(&vec.iter().map(|s| s.to_string()).collect::<Vec<_>>())
+            )],
+            SqueueMode::USERS(vec) => vec![format!("-u {}", quoted(vec))],
+            SqueueMode::COMBINED(modes) => modes.iter().flat_map(|m| m.filter_args()).collect(),
+        }
+    }
+
+    fn filters_states(&self) -> bool {
+        match self {
+            SqueueMode::STATES(_) => true,
+            SqueueMode::COMBINED(modes) => modes.iter().any(|m| m.filters_states()),
+            _ => false,
+        }
+    }
+
+    /// Check if the given row matches this mode
+    ///
+    /// Filters on fields not included in [`SqueueRow`] (e.g., the active user) are assumed to match,
+    /// as are user filters for rows without a user.
+    pub(crate) fn matches(&self, row: &SqueueRow) -> bool {
+        match self {
+            SqueueMode::ALL | SqueueMode::MINE => true,
+            SqueueMode::USERS(users) => row.user.as_ref().is_none_or(|u| users.contains(u)),
+            SqueueMode::JOBIDS(ids) => ids.contains(&row.job_id),
+            SqueueMode::PARTITIONS(partitions) => partitions.contains(&row.partition),
+            SqueueMode::ACCOUNTS(accounts) => accounts.contains(&row.account),
+            SqueueMode::STATES(states) => states.contains(&row.state),
+            SqueueMode::COMBINED(modes) => modes.iter().all(|m| m.matches(row)),
+        }
+    }
 }
-/// Get squeue results using the provided `execute_cmd` function
-pub async fn get_squeue_res<F, Fut>(
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, Default, PartialEq, Eq)]
+/// Parameter for `squeue` extraction, specifying how the `squeue` output is obtained and parsed
+pub enum SqueueSource {
+    #[default]
+    /// Parse the pipe-delimited output of `squeue --format` (supported by all SLURM versions)
+    FormatString,
+    /// Deserialize the output of `squeue --json` (requires a recent SLURM version)
+    ///
+    /// If the JSON output is unavailable or cannot be parsed, the format string parser is used as a fallback.
+    ///
+    /// Note, that `squeue --json` reports the absolute (instead of normalized) job priority,
+    /// does not include the elapsed/remaining time of jobs, and reports timestamps as UNIX epochs
+    /// (i.e., independent of the cluster timezone).
+    Json,
+}
+
+#[derive(Debug)]
+/// A line of `squeue` output which could not be parsed
+pub struct RowParseError {
+    /// Line number in the `squeue` output (starting at 1)
+    pub line_number: usize,
+    /// The unparsed line
+    pub line: String,
+    /// The reason why the line could not be parsed
+    pub error: Error,
+}
+
+impl std::fmt::Display for RowParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Failed to parse line {} ({:?}): {}",
+            self.line_number, self.line, self.error
+        )
+    }
+}
+
+#[derive(Debug, Default)]
+/// Result of parsing `squeue` output, containing both the parsed rows and the lines which failed to parse
+pub struct SqueueParseReport {
+    /// Successfully parsed rows
+    pub rows: Vec<SqueueRow>,
+    /// Lines which could not be parsed
+    pub errors: Vec<RowParseError>,
+}
+
+/// Parse the output of `squeue` (using the [`SQUEUE_FORMAT_STR`] format), collecting all parse errors
+///
+/// Timestamps are interpreted in the cluster timezone `tz`.
+pub fn parse_squeue_output(output: &str, tz: &FixedOffset) -> SqueueParseReport {
+    let mut report = SqueueParseReport::default();
+    let mut cluster = None;
+    for (i, line) in output.split("\n").enumerate() {
+        match parse_squeue_line(line, &mut cluster, tz) {
+            Some(Ok(row)) => report.rows.push(row),
+            Some(Err(error)) => report.errors.push(RowParseError {
+                line_number: i + 1,
+                line: line.to_string(),
+                error,
+            }),
+            None => {}
+        }
+    }
+    report
+}
+
+/// Get squeue results using the provided `executor`, including all rows which failed to parse
+///
+/// Timestamps reported by `squeue` are interpreted in the cluster timezone `tz`
+/// (see [`ClusterInfo::detect_timezone`](super::ClusterInfo::detect_timezone)).
+pub async fn get_squeue_report(
     mode: &SqueueMode,
-    execute_cmd: F,
-) -> Result<(DateTime<Utc>, Vec<SqueueRow>), Error>
-where
-    F: FnOnce(String) -> Fut,
-    Fut: Future<Output = Result<String, Error>>,
-{
-    let extra_arg = match mode {
-        SqueueMode::ALL => String::default(),
-        SqueueMode::MINE => String::from("--me"),
-        SqueueMode::JOBIDS(vec) => format!("-j {}", vec.join(",")),
-    };
-    let result = execute_cmd(format!(
-        "squeue -h -a -M all -t all --format='{SQUEUE_FORMAT_STR}' {extra_arg}"
-    ))
-    .await?;
-    let res_lines = result.split("\n");
+    tz: &FixedOffset,
+    executor: &impl CommandExecutor,
+) -> Result<(DateTime<Utc>, SqueueParseReport), Error> {
+    let extra_arg = mode.to_squeue_args();
+    let result = executor
+        .execute(&format!(
+            "squeue -h -a -M all --format='{SQUEUE_FORMAT_STR}' {extra_arg}"
+        ))
+        .await?
+        .stdout;
 
     // For checking columns:
     // let _column_str = res_lines
@@ -218,59 +494,388 @@ where
     // }
 
     let time: DateTime<Utc> = SystemTime::now().into();
-    let d: Vec<SqueueRow> = res_lines
-        .filter_map(|line| {
-            if line.is_empty() {
-                return None;
+    Ok((time, parse_squeue_output(&result, tz)))
+}
+
+/// Run and parse `squeue` result locally (i.e., not via SSH), including all rows which failed to parse
+pub async fn get_squeue_report_locally(
+    mode: &SqueueMode,
+    tz: &FixedOffset,
+) -> Result<(DateTime<Utc>, SqueueParseReport), Error> {
+    get_squeue_report(mode, tz, &LocalExecutor).await
+}
+
+#[cfg(feature = "ssh")]
+/// Run and parse `squeue` result over SSH, including all rows which failed to parse
+pub async fn get_squeue_report_ssh(
+    client: &Client,
+    mode: &SqueueMode,
+    tz: &FixedOffset,
+) -> Result<(DateTime<Utc>, SqueueParseReport), Error> {
+    get_squeue_report(mode, tz, client).await
+}
+
+/// Get squeue results using the provided `executor`
+///
+/// Rows which fail to parse are skipped (and printed);
+/// use [`get_squeue_report`] to handle them instead.
+///
+/// Timestamps reported by `squeue` are interpreted in the cluster timezone `tz`
+/// (see [`ClusterInfo::detect_timezone`](super::ClusterInfo::detect_timezone)).
+pub async fn get_squeue_res(
+    mode: &SqueueMode,
+    tz: &FixedOffset,
+    executor: &impl CommandExecutor,
+) -> Result<(DateTime<Utc>, Vec<SqueueRow>), Error> {
+    let (time, report) = get_squeue_report(mode, tz, executor).await?;
+    for err in &report.errors {
+        println!("[!] {:?} for {:?}", err.error, &err.line);
+    }
+    Ok((time, report.rows))
+}
+
+/// Run and parse `squeue` result locally (i.e., not via SSH)
+pub async fn get_squeue_res_locally(
+    mode: &SqueueMode,
+    tz: &FixedOffset,
+) -> Result<(DateTime<Utc>, Vec<SqueueRow>), Error> {
+    let d = Instant::now();
+    let res = get_squeue_res(mode, tz, &LocalExecutor).await;
+    println!("Running squeue took {:?}", d.elapsed());
+    res
+}
+
+#[cfg(feature = "ssh")]
+/// Run and parse `squeue` result over SSH
+pub async fn get_squeue_res_ssh(
+    client: &Client,
+    mode: &SqueueMode,
+    tz: &FixedOffset,
+) -> Result<(DateTime<Utc>, Vec<SqueueRow>), Error> {
+    get_squeue_res(mode, tz, client).await
+}
+
+/// Get the start times of the pending jobs with the given `job_ids`, as estimated by the scheduler (using `squeue --start`)
+///
+/// Jobs without an estimate (e.g., because the backfill scheduler did not consider them yet) are not included in the result.
+/// Timestamps are interpreted in the cluster timezone `tz`.
+pub async fn get_estimated_start_times(
+    job_ids: &[String],
+    tz: &FixedOffset,
+    executor: &impl CommandExecutor,
+) -> Result<HashMap<String, DateTime<Utc>>, Error> {
+    if job_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let result = executor
+        .execute(&format!(
+            "squeue --start -h -a --format='%i|%S' -j {}",
+            job_ids.join(",")
+        ))
+        .await?
+        .stdout;
+    let mut start_times = HashMap::new();
+    for line in result.lines().filter(|line| !line.trim().is_empty()) {
+        match line.split_once("|") {
+            Some((_, "N/A" | "" | "(null)")) => {}
+            Some((job_id, start_time)) => match parse_slurm_time(start_time.trim(), tz) {
+                Ok(start_time) => {
+                    start_times.insert(job_id.trim().to_string(), start_time);
+                }
+                Err(err) => println!("[!] {:?} for {:?}", err, &line),
+            },
+            None => println!("[!] Invalid line {:?}", &line),
+        }
+    }
+    Ok(start_times)
+}
+
+/// Run and parse `squeue --start` locally (i.e., not via SSH)
+pub async fn get_estimated_start_times_locally(
+    job_ids: &[String],
+    tz: &FixedOffset,
+) -> Result<HashMap<String, DateTime<Utc>>, Error> {
+    get_estimated_start_times(job_ids, tz, &LocalExecutor).await
+}
+
+#[cfg(feature = "ssh")]
+/// Run and parse `squeue --start` over SSH
+pub async fn get_estimated_start_times_ssh(
+    client: &Client,
+    job_ids: &[String],
+    tz: &FixedOffset,
+) -> Result<HashMap<String, DateTime<Utc>>, Error> {
+    get_estimated_start_times(job_ids, tz, client).await
+}
+
+/// Get a (potentially unset or infinite) number from `squeue --json` output
+///
+/// Newer SLURM versions wrap numbers as `{"set": true, "infinite": false, "number": 42}`
+fn json_number(val: Option<&serde_json::Value>) -> Option<i64> {
+    match val? {
+        serde_json::Value::Number(n) => n.as_i64(),
+        serde_json::Value::Object(o) => {
+            let is_set = o.get("set").and_then(|s| s.as_bool()).unwrap_or(true);
+            let is_infinite = o.get("infinite").and_then(|s| s.as_bool()).unwrap_or(false);
+            if is_set && !is_infinite {
+                o.get("number").and_then(|n| n.as_i64())
+            } else {
+                None
             }
-            let res = SqueueRow::parse_from_strs(&line.split("|").collect::<Vec<_>>());
-            match res {
-                Ok(row) => Some(row),
-                Err(err) => {
-                    println!("[!] {:?} for {:?}", err, &line);
-                    None
+        }
+        _ => None,
+    }
+}
+
+/// Get a non-empty string from `squeue --json` output
+fn json_string(val: Option<&serde_json::Value>) -> Option<String> {
+    match val? {
+        serde_json::Value::String(s) if !s.is_empty() => Some(s.clone()),
+        // Newer SLURM versions report some fields (e.g., the job state) as arrays
+        serde_json::Value::Array(a) => a.first().and_then(|v| json_string(Some(v))),
+        _ => None,
+    }
+}
+
+/// Get a timestamp (UNIX epoch) from `squeue --json` output, where `0` indicates an unset value
+fn json_time(val: Option<&serde_json::Value>) -> Option<DateTime<Utc>> {
+    json_number(val)
+        .filter(|t| *t > 0)
+        .and_then(|t| DateTime::from_timestamp(t, 0))
+}
+
+impl SqueueRow {
+    fn parse_from_json(job: &serde_json::Value) -> Result<Self, Error> {
+        let get_str = |key: &str| json_string(job.get(key)).unwrap_or_default();
+        let get_num = |key: &str| json_number(job.get(key));
+        let job_id = get_num("job_id")
+            .ok_or(Error::msg("No job_id in JSON output."))?
+            .to_string();
+        let array_job_id = get_num("array_job_id")
+            .filter(|id| *id != 0)
+            .map(|id| id.to_string());
+        let step_job_id = match &array_job_id {
+            Some(array_job_id) => {
+                match get_num("array_task_id")
+                    .map(|t| t.to_string())
+                    .or_else(|| json_string(job.get("array_task_string")))
+                {
+                    Some(tasks) => format!("{array_job_id}_{tasks}").parse()?,
+                    None => array_job_id.parse()?,
                 }
             }
+            None => job_id.parse()?,
+        };
+        let min_memory = match (get_num("memory_per_node"), get_num("memory_per_cpu")) {
+            (Some(mem), _) => MemorySpec::per_node_mb(mem as u64),
+            (None, Some(mem)) => MemorySpec::per_cpu_mb(mem as u64),
+            (None, None) => MemorySpec::per_node_mb(0),
+        };
+        Ok(Self {
+            account: get_str("account"),
+            exec_host: json_string(job.get("batch_host")),
+            min_cpus: get_num("minimum_cpus_per_node").unwrap_or_default() as usize,
+            cpus: get_num("cpus").unwrap_or_default() as usize,
+            nodes: get_num("node_count").unwrap_or_default() as usize,
+            end_time: json_time(job.get("end_time")),
+            dependency: json_string(job.get("dependency")),
+            features: get_str("features"),
+            array_job_id: array_job_id.unwrap_or(job_id.clone()),
+            group: get_str("group_name"),
+            step_job_id,
+            time_limit: get_num("time_limit").map(|mins| Duration::from_secs(mins as u64 * 60)),
+            time_left: None,
+            name: get_str("name"),
+            min_memory,
+            time: None,
+            priority: get_num("priority").unwrap_or_default() as f64,
+            partition: get_str("partition"),
+            state: json_string(job.get("job_state"))
+                .ok_or(Error::msg("No job_state in JSON output."))?
+                .parse()?,
+            reason: get_str("state_reason"),
+            start_time: json_time(job.get("start_time")),
+            submit_time: json_time(job.get("submit_time"))
+                .ok_or(Error::msg("No submit_time in JSON output."))?,
+            work_dir: PathBuf::from(get_str("current_working_directory")),
+            command: get_str("command"),
+            tres_per_node: TresRequest::parse_list(&get_str("tres_per_node"))?,
+            licenses: LicenseRequest::parse_list(&get_str("licenses"))?,
+            user: json_string(job.get("user_name")),
+            node_list: json_string(job.get("nodes")),
+            exit_code: None,
+            cluster: json_string(job.get("cluster")),
+            raw_line: Some(job.to_string()),
+            job_id,
+        })
+    }
+}
+
+/// Parse the output of `squeue --json`
+fn parse_squeue_json(output: &str, mode: &SqueueMode) -> Result<Vec<SqueueRow>, Error> {
+    parse_squeue_json_value(&serde_json::from_str(output)?, mode)
+}
+
+/// Parse the jobs of (already deserialized) `squeue --json` output or of a Slurm REST API response
+pub(crate) fn parse_squeue_json_value(
+    val: &serde_json::Value,
+    mode: &SqueueMode,
+) -> Result<Vec<SqueueRow>, Error> {
+    let jobs = val
+        .get("jobs")
+        .and_then(|jobs| jobs.as_array())
+        .ok_or(Error::msg("No jobs array in JSON output."))?;
+    let rows = jobs
+        .iter()
+        .filter_map(|job| match SqueueRow::parse_from_json(job) {
+            Ok(row) => Some(row),
+            Err(err) => {
+                println!("[!] {:?} for {:?}", err, job);
+                None
+            }
         })
+        // Older SLURM versions ignore filters when using `--json`
+        .filter(|row| mode.matches(row))
         .collect();
-    Ok((time, d))
+    Ok(rows)
 }
 
-/// Run and parse `squeue` result locally (i.e., not via SSH)
-pub async fn get_squeue_res_locally(
+/// Get squeue results from the specified [`SqueueSource`] using the provided `executor`
+pub async fn get_squeue_res_with_source(
     mode: &SqueueMode,
+    source: &SqueueSource,
+    tz: &FixedOffset,
+    executor: &impl CommandExecutor,
 ) -> Result<(DateTime<Utc>, Vec<SqueueRow>), Error> {
-    get_squeue_res(mode, |cmd_s| async move {
-        // let splits: Vec<&str> = cmd.split(" ").collect();
-        // println!("{:#?}",splits);
-        // cmd.args(splits.iter().skip(1));
-        let mut cmd = Command::new("sh");
-        cmd.arg("-c").arg(&cmd_s);
-        let d = Instant::now();
-        let out = cmd.output()?;
-        let s = String::from_utf8(out.stdout)?;
-        // println!("{:?}",out);
-        println!("Running squeue took {:?}", d.elapsed());
-        Ok(s)
-    })
-    .await
+    if *source == SqueueSource::Json {
+        let extra_arg = mode.to_squeue_args();
+        let result = executor
+            .execute(&format!("squeue -a --json {extra_arg}"))
+            .await
+            .map(|output| output.stdout);
+        let time: DateTime<Utc> = SystemTime::now().into();
+        match result.and_then(|output| parse_squeue_json(&output, mode)) {
+            Ok(rows) => return Ok((time, rows)),
+            Err(err) => {
+                eprintln!("squeue --json failed, falling back to format string: {err:?}");
+            }
+        }
+    }
+    get_squeue_res(mode, tz, executor).await
+}
+
+/// Run and parse `squeue` result from the specified [`SqueueSource`] locally (i.e., not via SSH)
+pub async fn get_squeue_res_locally_with_source(
+    mode: &SqueueMode,
+    source: &SqueueSource,
+    tz: &FixedOffset,
+) -> Result<(DateTime<Utc>, Vec<SqueueRow>), Error> {
+    get_squeue_res_with_source(mode, source, tz, &LocalExecutor).await
 }
 
 #[cfg(feature = "ssh")]
-/// Run and parse `squeue` result over SSH
-pub async fn get_squeue_res_ssh(
+/// Run and parse `squeue` result from the specified [`SqueueSource`] over SSH
+pub async fn get_squeue_res_ssh_with_source(
     client: &Client,
     mode: &SqueueMode,
+    source: &SqueueSource,
+    tz: &FixedOffset,
 ) -> Result<(DateTime<Utc>, Vec<SqueueRow>), Error> {
-    get_squeue_res(mode, |cmd| async move {
-        let r = client.execute(&cmd).await?;
-        Ok(r.stdout)
+    get_squeue_res_with_source(mode, source, tz, client).await
+}
+
+#[cfg(feature = "stream")]
+/// Parse `squeue` output rows as they arrive from the given `reader`
+///
+/// The `reader` is expected to provide the output of `squeue` using the [`SQUEUE_FORMAT_STR`] format.
+/// Rows that fail to parse are yielded as errors, without ending the stream.
+/// Timestamps are interpreted in the cluster timezone `tz`.
+pub fn parse_squeue_stream<R>(
+    reader: R,
+    tz: &FixedOffset,
+) -> impl futures::Stream<Item = Result<SqueueRow, Error>> + use<R>
+where
+    R: tokio::io::AsyncBufRead + Unpin,
+{
+    use tokio::io::AsyncBufReadExt;
+    let tz = *tz;
+    futures::stream::unfold(Some((reader.lines(), None)), move |state| async move {
+        let (mut lines, mut cluster) = state?;
+        loop {
+            match lines.next_line().await {
+                Ok(Some(line)) => {
+                    let Some(res) = parse_squeue_line(&line, &mut cluster, &tz) else {
+                        continue;
+                    };
+                    let res = res.map_err(|err| err.context(format!("Failed to parse {line:?}")));
+                    return Some((res, Some((lines, cluster))));
+                }
+                Ok(None) => return None,
+                // End the stream after reporting I/O errors
+                Err(err) => return Some((Err(err.into()), None)),
+            }
+        }
     })
-    .await
 }
+
+#[cfg(feature = "stream")]
+/// Run `squeue` locally (i.e., not via SSH), parsing rows as they are written to stdout
+///
+/// The `squeue` process is killed once the returned stream is dropped.
+pub fn stream_squeue_res_locally(
+    mode: &SqueueMode,
+    tz: &FixedOffset,
+) -> Result<impl futures::Stream<Item = Result<SqueueRow, Error>> + use<>, Error> {
+    use futures::StreamExt;
+    let extra_arg = mode.to_squeue_args();
+    let mut child = tokio::process::Command::new("sh")
+        .arg("-c")
+        .arg(format!(
+            "squeue -h -a -M all --format='{SQUEUE_FORMAT_STR}' {extra_arg}"
+        ))
+        .stdout(std::process::Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()?;
+    let stdout = child
+        .stdout
+        .take()
+        .ok_or(Error::msg("Could not capture squeue stdout."))?;
+    let rows = parse_squeue_stream(tokio::io::BufReader::new(stdout), tz);
+    // Keep the child process alive for as long as the stream is used
+    Ok(rows.map(move |row| {
+        let _ = &child;
+        row
+    }))
+}
+
+#[cfg(feature = "ssh")]
+/// Run `squeue` over SSH, parsing rows as they arrive from the SSH channel
+///
+/// This avoids buffering the complete `squeue` output, which can be very large on big clusters.
+pub async fn stream_squeue_res_ssh(
+    client: &Client,
+    mode: &SqueueMode,
+    tz: &FixedOffset,
+) -> Result<impl futures::Stream<Item = Result<SqueueRow, Error>> + use<>, Error> {
+    let extra_arg = mode.to_squeue_args();
+    let channel = client.get_channel().await?;
+    channel
+        .exec(
+            true,
+            format!("squeue -h -a -M all --format='{SQUEUE_FORMAT_STR}' {extra_arg}"),
+        )
+        .await?;
+    Ok(parse_squeue_stream(
+        tokio::io::BufReader::new(channel.into_stream()),
+        tz,
+    ))
+}
+
 use rayon::prelude::*;
 
 /// Execute `squeue` and compare the output with (optional) data from previous executions
+///
+/// New jobs and changes of known jobs are saved as JSON files in the directory at `path` (see [`JsonDirStore`]).
 pub async fn squeue_diff<'b, F, Fut>(
     get_squeue: F,
     path: &Path,
@@ -280,10 +885,158 @@ pub async fn squeue_diff<'b, F, Fut>(
 where
     F: FnOnce() -> Fut,
     Fut: Future<Output = Result<(DateTime<Utc>, Vec<SqueueRow>), Error>>,
+{
+    squeue_diff_with_store(get_squeue, &JsonDirStore::new(path), known_jobs, all_ids).await
+}
+
+/// Execute `squeue` and compare the output with (optional) data from previous executions
+///
+/// New jobs and changes of known jobs are saved using the provided [`RecordingStore`].
+/// Jobs which disappeared from `squeue` are not recorded; see [`squeue_diff_with_final_states`] for that.
+pub async fn squeue_diff_with_store<'b, F, Fut, S>(
+    get_squeue: F,
+    store: &S,
+    known_jobs: &'b mut HashMap<String, SqueueRow>,
+    all_ids: &'b mut HashSet<String>,
+) -> Result<(DateTime<Utc>, Vec<SqueueRow>), Error>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<(DateTime<Utc>, Vec<SqueueRow>), Error>>,
+    S: RecordingStore + ?Sized,
+{
+    squeue_diff_with_final_states(
+        get_squeue,
+        |_| async { Ok(Vec::new()) },
+        store,
+        known_jobs,
+        all_ids,
+    )
+    .await
+}
+
+/// Execute `squeue` and compare the output with (optional) data from previous executions
+///
+/// New jobs and changes of known jobs are saved using the provided [`RecordingStore`].
+/// For jobs which disappeared from `squeue`, the final state and exit code are queried using `get_final_states`
+/// (e.g., with [`get_sacct_res`](super::sacct::get_sacct_res)) and saved as a final delta.
+pub async fn squeue_diff_with_final_states<'b, F, Fut, G, GFut, S>(
+    get_squeue: F,
+    get_final_states: G,
+    store: &S,
+    known_jobs: &'b mut HashMap<String, SqueueRow>,
+    all_ids: &'b mut HashSet<String>,
+) -> Result<(DateTime<Utc>, Vec<SqueueRow>), Error>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<(DateTime<Utc>, Vec<SqueueRow>), Error>>,
+    G: FnOnce(Vec<String>) -> GFut,
+    GFut: Future<Output = Result<Vec<SacctJobRow>, Error>>,
+    S: RecordingStore + ?Sized,
+{
+    let (time, rows) = get_squeue().await?;
+    let row_ids = squeue_row_ids(&rows);
+    store.record_job_ids(&time, &row_ids)?;
+    let events = diff_squeue_rows(&rows, row_ids, known_jobs, all_ids);
+    events.par_iter().for_each(|event| match event {
+        SqueueDiffEvent::New(row) => {
+            // Save job
+            if let Err(e) = store.record_new_job(&time, row) {
+                eprintln!("Failed to record job {}: {:?}", row.job_id, e);
+            }
+        }
+        SqueueDiffEvent::Changed { job_id, diff } => {
+            // Save job delta
+            if let Err(e) = store.record_job_delta(&time, job_id, diff) {
+                eprintln!("Failed to record delta for {}: {:?}", job_id, e);
+            }
+        }
+        SqueueDiffEvent::Disappeared(_) => {}
+    });
+    let disappeared: HashMap<&str, &SqueueRow> = events
+        .iter()
+        .filter_map(|event| match event {
+            SqueueDiffEvent::Disappeared(row) => Some((row.job_id.as_str(), row.as_ref())),
+            _ => None,
+        })
+        .collect();
+    if !disappeared.is_empty() {
+        match get_final_states(disappeared.keys().map(|id| id.to_string()).collect()).await {
+            Ok(final_states) => {
+                for final_state in final_states {
+                    let Some(last_row) = disappeared.get(final_state.job_id.as_str()) else {
+                        continue;
+                    };
+                    let diff = last_row.diff(&final_state.apply_to(last_row));
+                    if diff.is_empty() {
+                        continue;
+                    }
+                    store
+                        .record_job_delta(&time, &last_row.job_id, &diff)
+                        .unwrap_or_else(|e| {
+                            eprintln!(
+                                "Failed to record final delta for {}: {:?}",
+                                last_row.job_id, e
+                            )
+                        });
+                }
+            }
+            Err(e) => eprintln!("Failed to get final states of disappeared jobs: {:?}", e),
+        }
+    }
+    store.flush()?;
+    Ok((time, rows))
+}
+
+#[derive(Debug, Clone)]
+/// A change between two consecutive `squeue` executions, as computed by [`squeue_diff_stream`]
+pub enum SqueueDiffEvent {
+    /// A job which was not known before
+    New(Box<SqueueRow>),
+    /// Changed fields of a known job
+    Changed {
+        /// ID of the job
+        job_id: String,
+        /// The changed fields
+        diff: Vec<SqueueRowDiff>,
+    },
+    /// A known job which is no longer listed by `squeue` (with its last known row)
+    Disappeared(Box<SqueueRow>),
+}
+
+impl SqueueDiffEvent {
+    /// The ID of the job this event belongs to
+    pub fn job_id(&self) -> &str {
+        match self {
+            SqueueDiffEvent::New(row) | SqueueDiffEvent::Disappeared(row) => &row.job_id,
+            SqueueDiffEvent::Changed { job_id, .. } => job_id,
+        }
+    }
+}
+
+/// Execute `squeue` and compare the output with (optional) data from previous executions, without writing any files
+///
+/// All new jobs, changes of known jobs and disappeared jobs are passed to `on_event`.
+/// To consume the events elsewhere, `on_event` can, e.g., send them over a channel.
+pub async fn squeue_diff_stream<'b, F, Fut, C>(
+    get_squeue: F,
+    known_jobs: &'b mut HashMap<String, SqueueRow>,
+    all_ids: &'b mut HashSet<String>,
+    mut on_event: C,
+) -> Result<(DateTime<Utc>, Vec<SqueueRow>), Error>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<(DateTime<Utc>, Vec<SqueueRow>), Error>>,
+    C: FnMut(&DateTime<Utc>, SqueueDiffEvent),
 {
     let (time, rows) = get_squeue().await?;
-    // let (time, rows) = get_squeue_res(client).await?;
-    let cleaned_time = time.to_rfc3339().replace(":", "_");
+    let row_ids = squeue_row_ids(&rows);
+    for event in diff_squeue_rows(&rows, row_ids, known_jobs, all_ids) {
+        on_event(&time, event);
+    }
+    Ok((time, rows))
+}
+
+fn squeue_row_ids(rows: &[SqueueRow]) -> HashSet<String> {
     let row_ids = rows
         .iter()
         .map(|r| r.job_id.clone())
@@ -292,80 +1045,74 @@ where
     if rows.len() != row_ids.len() {
         eprintln!("Count mismatch: {} != {}", rows.len(), row_ids.len());
     }
-    create_dir_all(path)?;
-    let id_save_path = path.join(format!("{cleaned_time}.json"));
-    if let Err(e) = serde_json::to_writer(
-        BufWriter::new(File::create(id_save_path).unwrap()),
-        &row_ids,
-    ) {
-        eprintln!("Failed to create file for all jobs ids: {e:?}");
-    }
-    *known_jobs = rows
+    row_ids
+}
+
+/// Compare `rows` with the previously `known_jobs`, updating `known_jobs` and `all_ids` afterwards
+fn diff_squeue_rows(
+    rows: &[SqueueRow],
+    row_ids: HashSet<String>,
+    known_jobs: &mut HashMap<String, SqueueRow>,
+    all_ids: &mut HashSet<String>,
+) -> Vec<SqueueDiffEvent> {
+    let mut events: Vec<SqueueDiffEvent> = rows
         .par_iter()
-        .map(|row| {
+        .filter_map(|row| {
             if let Some(prev_row) = known_jobs.get(&row.job_id) {
                 // Job is known!
                 // Compute delta
                 let diff = prev_row.diff(row);
-                if !diff.is_empty() {
-                    // Save job delta (e.g., as JSON)
-                    let save_path = path
-                        .join(&row.job_id)
-                        .join(format!("DELTA-{cleaned_time}.json"));
-                    if let Err(e) = serde_json::to_writer(
-                        BufWriter::new(File::create(save_path).unwrap()),
-                        &diff,
-                    ) {
-                        eprintln!("Failed to create file for {}: {:?}", row.job_id, e);
-                    }
-                }
-                // Update prev_row in known_jobs
-                (row.job_id.clone(), row.clone())
-                // rw.write().unwrap().insert(row.job_id.clone(), row.clone());
-                // *prev_row = row.clone();
+                (!diff.is_empty()).then(|| SqueueDiffEvent::Changed {
+                    job_id: row.job_id.clone(),
+                    diff,
+                })
             } else {
                 // Job is new!
                 // Double check with all_ids:
                 if all_ids.contains(&row.job_id) {
                     eprintln!("Job re-appeared! Maybe IDs get reused?");
                 }
-                let folder_path = path.join(&row.job_id);
-                create_dir_all(&folder_path).unwrap();
-                // Save job (e.g., as JSON)
-                let save_path = folder_path.join(format!("{cleaned_time}.json"));
-                if let Err(e) =
-                    serde_json::to_writer(BufWriter::new(File::create(save_path).unwrap()), &row)
-                {
-                    eprintln!("Failed to create file for {}: {:?}", row.job_id, e);
-                }
-                // rw.write().unwrap().insert(row.job_id.clone(), row.clone());
-                (row.job_id.clone(), row.clone())
+                Some(SqueueDiffEvent::New(Box::new(row.clone())))
             }
         })
         .collect();
-    // let known_jobs = rw.into_inner().unwrap();
-    // Remove all known jobs which
-    // known_jobs.retain(|j_id, _| row_ids.contains(j_id));
+    // Update known_jobs
+    let prev_jobs = std::mem::replace(
+        known_jobs,
+        rows.iter()
+            .map(|row| (row.job_id.clone(), row.clone()))
+            .collect(),
+    );
+    events.extend(
+        prev_jobs
+            .into_values()
+            .filter(|row| !row_ids.contains(&row.job_id))
+            .map(|row| SqueueDiffEvent::Disappeared(Box::new(row))),
+    );
     all_ids.extend(row_ids);
-    Ok((time, rows))
+    events
 }
 
 #[cfg(test)]
 mod tests {
-    use std::{
-        collections::{HashMap, HashSet},
-        path::PathBuf,
-    };
+    use std::collections::{HashMap, HashSet};
+    #[cfg(feature = "ssh")]
+    use std::path::PathBuf;
+
+    use chrono::FixedOffset;
 
-    use crate::data_extraction::{get_squeue_res_locally, SqueueMode};
+    use super::{get_estimated_start_times, get_squeue_res, parse_squeue_output};
+    use crate::data_extraction::{get_squeue_res_locally, ClusterInfo, SqueueMode};
     #[cfg(feature = "ssh")]
     use crate::login_with_cfg;
+    use crate::{ArrayJobId, MemorySpec};
 
     #[cfg(feature = "ssh")]
     #[tokio::test]
     async fn test_squeue_loop() {
         let login_cfg = crate::misc::get_config_from_env();
         let client = login_with_cfg(&login_cfg).await.unwrap();
+        let tz = ClusterInfo::detect_timezone(&client).await.unwrap();
         let mut known_jobs = HashMap::default();
         let mut all_ids = HashSet::default();
         let path = PathBuf::new().join("test_squeue_loop-14-01-2025");
@@ -374,7 +1121,7 @@ mod tests {
             use crate::data_extraction::{get_squeue_res_ssh, squeue_diff};
 
             squeue_diff(
-                || get_squeue_res_ssh(&client, &SqueueMode::ALL),
+                || get_squeue_res_ssh(&client, &SqueueMode::ALL, &tz),
                 &path,
                 &mut known_jobs,
                 &mut all_ids,
@@ -387,9 +1134,200 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_dependency_targets() {
+        let mut row = super::test_row(chrono::Utc::now());
+        assert!(row.dependency_targets().is_empty());
+        row.dependency =
+            Some("afterok:123:124(unfulfilled),after:125+10?aftercorr:126_*,singleton".to_string());
+        assert_eq!(
+            row.dependency_targets(),
+            [
+                ("afterok", "123"),
+                ("afterok", "124"),
+                ("after", "125"),
+                ("aftercorr", "126_*")
+            ]
+            .map(|(t, id)| (t.to_string(), id.to_string()))
+        );
+    }
+
+    #[test]
+    fn test_diff_squeue_rows() {
+        use super::{diff_squeue_rows, squeue_row_ids, SqueueDiffEvent};
+        use crate::JobState;
+
+        let tz = chrono::FixedOffset::east_opt(3600).unwrap();
+        let line = "default|49848561|n/a|1|1|1|N/A|(null)|(null)|49848561|ab123456|49848561|1:00:00|1:00:00|my job|3900M|0:00|0.00001234|c23ms|PENDING|Priority|N/A|2025-01-14T10:23:45|/home/ab123456|/home/ab123456/start.sh|N/A|(null)";
+        let pending =
+            super::SqueueRow::parse_from_strs(&line.split('|').collect::<Vec<_>>(), &tz).unwrap();
+        let mut running = pending.clone();
+        running.state = JobState::RUNNING;
+        let mut other = pending.clone();
+        other.job_id = "49848562".to_string();
+
+        let mut known_jobs = HashMap::default();
+        let mut all_ids = HashSet::default();
+        let rows = vec![pending.clone(), other];
+        let events = diff_squeue_rows(&rows, squeue_row_ids(&rows), &mut known_jobs, &mut all_ids);
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().all(|e| matches!(e, SqueueDiffEvent::New(_))));
+
+        let rows = vec![running];
+        let events = diff_squeue_rows(&rows, squeue_row_ids(&rows), &mut known_jobs, &mut all_ids);
+        assert_eq!(events.len(), 2);
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, SqueueDiffEvent::Changed { diff, .. } if diff.len() == 1)));
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, SqueueDiffEvent::Disappeared(r) if r.job_id == "49848562")));
+        assert_eq!(known_jobs.len(), 1);
+        assert_eq!(all_ids.len(), 2);
+    }
+
+    #[test]
+    fn test_parse_squeue_json() {
+        use crate::data_extraction::squeue::parse_squeue_json;
+        let output = r#"{"jobs": [
+            {"account": "default", "job_id": 49869434, "array_job_id": {"set": true, "infinite": false, "number": 49869430},
+             "array_task_id": {"set": true, "infinite": false, "number": 2}, "batch_host": "n23m0001",
+             "cpus": {"set": true, "infinite": false, "number": 4}, "node_count": {"set": true, "infinite": false, "number": 1},
+             "minimum_cpus_per_node": {"set": true, "infinite": false, "number": 4},
+             "end_time": {"set": true, "infinite": false, "number": 1736848800}, "dependency": "", "features": "",
+             "group_name": "ab123456", "time_limit": {"set": true, "infinite": false, "number": 60}, "name": "sweep",
+             "memory_per_cpu": {"set": true, "infinite": false, "number": 3900}, "memory_per_node": {"set": false, "infinite": false, "number": 0},
+             "priority": {"set": true, "infinite": false, "number": 1201}, "partition": "c23ms", "job_state": ["RUNNING"],
+             "state_reason": "None", "start_time": {"set": true, "infinite": false, "number": 1736845200},
+             "submit_time": {"set": true, "infinite": false, "number": 1736841600},
+             "current_working_directory": "/home/ab123456", "command": "/home/ab123456/start.sh", "nodes": "n23m0001"},
+            {"account": "default", "job_id": 49848561, "array_job_id": 0, "cpus": 1, "node_count": 1, "minimum_cpus_per_node": 1,
+             "end_time": 0, "group_name": "ab123456", "time_limit": 30, "name": "single", "memory_per_node": 1000,
+             "priority": 5, "partition": "c23ms", "job_state": "PENDING", "state_reason": "Priority",
+             "start_time": 0, "submit_time": 1736841600, "current_working_directory": "/home/ab123456", "command": "start.sh"}
+        ]}"#;
+        let rows = parse_squeue_json(output, &SqueueMode::ALL).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].job_id, "49869434");
+        assert_eq!(
+            rows[0].step_job_id,
+            "49869430_2".parse::<ArrayJobId>().unwrap()
+        );
+        assert_eq!(rows[0].min_memory, MemorySpec::per_cpu_mb(3900));
+        assert_eq!(rows[0].state, crate::JobState::RUNNING);
+        assert_eq!(rows[0].node_list.as_deref(), Some("n23m0001"));
+        assert_eq!(rows[1].array_job_id, "49848561");
+        assert_eq!(rows[1].start_time, None);
+        assert_eq!(rows[1].min_memory, MemorySpec::per_node_mb(1000));
+
+        let filtered =
+            parse_squeue_json(output, &SqueueMode::JOBIDS(vec!["49848561".to_string()])).unwrap();
+        assert_eq!(filtered.len(), 1);
+    }
+
+    #[cfg(feature = "stream")]
+    #[tokio::test]
+    async fn test_parse_squeue_stream() {
+        use chrono::{TimeZone, Utc};
+        use futures::StreamExt;
+        let output = "default|49848561|n/a|1|1|1|N/A|(null)|(null)|49848561|ab123456|49848561|1:00:00|1:00:00|my job|3900M|0:00|0.00001234|c23ms|PENDING|Priority|N/A|2025-01-14T10:23:45|/home/ab123456|/home/ab123456/start.sh|N/A|(null)
+
+default|49869434|n23m0001|4|4|1|2025-01-14T11:23:45|(null)|(null)|49869430|ab123456|49869430_2|1:00:00|59:00|sweep|3900M|1:00|0.00001234|c23ms|RUNNING|None|2025-01-14T10:23:45|2025-01-14T10:20:00|/home/ab123456|/home/ab123456/start.sh|gres/gpu:2|ansys:2
+invalid|row
+";
+        let tz = FixedOffset::east_opt(3600).unwrap();
+        let rows: Vec<_> =
+            crate::data_extraction::squeue::parse_squeue_stream(output.as_bytes(), &tz)
+                .collect()
+                .await;
+        assert_eq!(rows.len(), 3);
+        let first = rows[0].as_ref().unwrap();
+        assert_eq!(first.job_id, "49848561");
+        assert_eq!(
+            first.submit_time,
+            Utc.with_ymd_and_hms(2025, 1, 14, 9, 23, 45).unwrap()
+        );
+        let second = rows[1].as_ref().unwrap();
+        assert_eq!(
+            second.step_job_id,
+            "49869430_2".parse::<ArrayJobId>().unwrap()
+        );
+        assert_eq!(second.tres_per_node[0].count, 2);
+        assert_eq!(second.licenses[0].name, "ansys");
+        assert!(rows[2].is_err());
+    }
+
+    #[tokio::test]
+    async fn test_parse_squeue_clusters() {
+        let output = "CLUSTER: hpc
+default|49848561|n/a|1|1|1|N/A|(null)|(null)|49848561|ab123456|49848561|1:00:00|1:00:00|my job|3900M|0:00|0.00001234|c23ms|PENDING|Priority|N/A|2025-01-14T10:23:45|/home/ab123456|/home/ab123456/start.sh|N/A|(null)
+CLUSTER: gpu
+default|49869434|n23m0001|4|4|1|2025-01-14T11:23:45|(null)|(null)|49869430|ab123456|49869430_2|1:00:00|59:00|sweep|3900M|1:00|0.00001234|c23ms|RUNNING|None|2025-01-14T10:23:45|2025-01-14T10:20:00|/home/ab123456|/home/ab123456/start.sh|gres/gpu:2|ansys:2|ab123456|n23m0001
+";
+        let tz = FixedOffset::east_opt(3600).unwrap();
+        let (_, rows) =
+            get_squeue_res(&SqueueMode::ALL, &tz, &|_| async { Ok(output.to_string()) })
+                .await
+                .unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].cluster.as_deref(), Some("hpc"));
+        assert_eq!(rows[1].cluster.as_deref(), Some("gpu"));
+        assert_eq!(rows[0].user, None);
+        assert_eq!(rows[1].user.as_deref(), Some("ab123456"));
+        assert_eq!(rows[0].node_list, None);
+        assert_eq!(rows[1].node_list.as_deref(), Some("n23m0001"));
+
+        // Single-cluster output has no headers
+        let (_, rows) = get_squeue_res(&SqueueMode::ALL, &tz, &|_| async {
+            Ok(output.lines().nth(1).unwrap().to_string())
+        })
+        .await
+        .unwrap();
+        assert_eq!(rows[0].cluster, None);
+    }
+
+    #[tokio::test]
+    async fn test_estimated_start_times() {
+        use chrono::{TimeZone, Utc};
+
+        let output = "49848561|2025-01-14T12:00:00
+49848562|N/A
+";
+        let tz = FixedOffset::east_opt(3600).unwrap();
+        let start_times = get_estimated_start_times(
+            &["49848561".to_string(), "49848562".to_string()],
+            &tz,
+            &|_| async { Ok(output.to_string()) },
+        )
+        .await
+        .unwrap();
+        assert_eq!(start_times.len(), 1);
+        assert_eq!(
+            start_times["49848561"],
+            Utc.with_ymd_and_hms(2025, 1, 14, 11, 0, 0).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_parse_squeue_report() {
+        let output = "default|49848561|n/a|1|1|1|N/A|(null)|(null)|49848561|ab123456|49848561|1:00:00|1:00:00|my job|3900M|0:00|0.00001234|c23ms|PENDING|Priority|N/A|2025-01-14T10:23:45|/home/ab123456|/home/ab123456/start.sh|N/A|(null)
+invalid|row
+
+default|49848562|n/a|1|1|1|N/A|(null)|(null)|49848562|ab123456|49848562|1:00:00|1:00:00|my job|3900M|0:00|not a number|c23ms|PENDING|Priority|N/A|2025-01-14T10:23:45|/home/ab123456|/home/ab123456/start.sh|N/A|(null)
+";
+        let tz = FixedOffset::east_opt(3600).unwrap();
+        let report = parse_squeue_output(output, &tz);
+        assert_eq!(report.rows.len(), 1);
+        assert_eq!(report.errors.len(), 2);
+        assert_eq!(report.errors[0].line_number, 2);
+        assert_eq!(report.errors[0].line, "invalid|row");
+        assert_eq!(report.errors[1].line_number, 4);
+    }
+
     #[tokio::test]
     async fn test_local() {
-        let res = get_squeue_res_locally(&SqueueMode::ALL).await.unwrap();
+        let tz = ClusterInfo::detect_timezone_locally().await.unwrap();
+        let res = get_squeue_res_locally(&SqueueMode::ALL, &tz).await.unwrap();
         println!("Got {} results", res.1.len())
     }
 }
@@ -0,0 +1,147 @@
+use std::time::Duration;
+
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+
+#[cfg(feature = "ssh")]
+use async_ssh2_tokio::Client;
+
+use crate::{
+    executor::{shell_quote, CommandExecutor, LocalExecutor},
+    parse_slurm_duration, parse_slurm_size,
+};
+
+// https://slurm.schedmd.com/sstat.html
+pub(crate) const SSTAT_FORMAT_STR: &str = "JobID,NTasks,AveCPU,MinCPU,AveRSS,MaxRSS,MaxVMSize,AveDiskRead,AveDiskWrite,MaxDiskRead,MaxDiskWrite";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Struct for parsed output row of `sstat` command
+///
+/// Containing resource usage statistics of a single step of a running SLURM job.
+/// All memory and I/O sizes are given in bytes.
+pub struct SstatStepRow {
+    /// "`JobID`", e.g., `49848561.batch` or `49848561.0`
+    pub job_step_id: String,
+    /// "`NTasks`"
+    pub num_tasks: Option<usize>,
+    /// "`AveCPU`", average (system + user) CPU time of all tasks
+    pub ave_cpu: Option<Duration>,
+    /// "`MinCPU`", minimum (system + user) CPU time of all tasks
+    pub min_cpu: Option<Duration>,
+    /// "`AveRSS`", average resident set size of all tasks
+    pub ave_rss: Option<u64>,
+    /// "`MaxRSS`", maximum resident set size of all tasks
+    pub max_rss: Option<u64>,
+    /// "`MaxVMSize`", maximum virtual memory size of all tasks
+    pub max_vm_size: Option<u64>,
+    /// "`AveDiskRead`", average number of bytes read by all tasks
+    pub ave_disk_read: Option<u64>,
+    /// "`AveDiskWrite`", average number of bytes written by all tasks
+    pub ave_disk_write: Option<u64>,
+    /// "`MaxDiskRead`", maximum number of bytes read by all tasks
+    pub max_disk_read: Option<u64>,
+    /// "`MaxDiskWrite`", maximum number of bytes written by all tasks
+    pub max_disk_write: Option<u64>,
+}
+
+/// Parse CPU times as reported by `sstat`/`sacct`, which may include a fractional seconds part (e.g., `01:23.456`)
+pub(crate) fn parse_cpu_time(s: &str) -> Option<Duration> {
+    parse_slurm_duration(s).ok()
+}
+
+impl SstatStepRow {
+    fn parse_from_strs(vals: &[&str]) -> Result<Self, Error> {
+        if vals.len() != 11 {
+            return Err(Error::msg("Invalid length of values."));
+        }
+        let size = |s: &str| parse_slurm_size(s).ok();
+        Ok(Self {
+            job_step_id: vals[0].to_string(),
+            num_tasks: vals[1].parse().ok(),
+            ave_cpu: parse_cpu_time(vals[2]),
+            min_cpu: parse_cpu_time(vals[3]),
+            ave_rss: size(vals[4]),
+            max_rss: size(vals[5]),
+            max_vm_size: size(vals[6]),
+            ave_disk_read: size(vals[7]),
+            ave_disk_write: size(vals[8]),
+            max_disk_read: size(vals[9]),
+            max_disk_write: size(vals[10]),
+        })
+    }
+}
+
+/// Get `sstat` step statistics of a running job using the provided `executor`
+pub async fn get_sstat_for_job(
+    job_id: &str,
+    executor: &impl CommandExecutor,
+) -> Result<Vec<SstatStepRow>, Error> {
+    let result = executor
+        .execute(&format!(
+            "sstat -n -a --parsable2 --format={SSTAT_FORMAT_STR} -j {}",
+            shell_quote(job_id)
+        ))
+        .await?
+        .stdout;
+    let rows = result
+        .split("\n")
+        .filter_map(|line| {
+            if line.is_empty() {
+                return None;
+            }
+            match SstatStepRow::parse_from_strs(&line.split("|").collect::<Vec<_>>()) {
+                Ok(row) => Some(row),
+                Err(err) => {
+                    println!("[!] {:?} for {:?}", err, &line);
+                    None
+                }
+            }
+        })
+        .collect();
+    Ok(rows)
+}
+
+/// Run and parse `sstat` result locally (i.e., not via SSH)
+pub async fn get_sstat_for_job_locally(job_id: &str) -> Result<Vec<SstatStepRow>, Error> {
+    get_sstat_for_job(job_id, &LocalExecutor).await
+}
+
+#[cfg(feature = "ssh")]
+/// Run and parse `sstat` result over SSH
+pub async fn get_sstat_for_job_ssh(
+    client: &Client,
+    job_id: &str,
+) -> Result<Vec<SstatStepRow>, Error> {
+    get_sstat_for_job(job_id, client).await
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::get_sstat_for_job;
+
+    #[tokio::test]
+    async fn test_parse_sstat() {
+        let output = "49848561.extern|1|00:00:00|00:00:00|1024K|1024K|217204K|0|0|0|0
+49848561.batch|1|00:10:01|00:10:01|1.50G|2G|4194304K|10M|1M|10M|1M
+49848561.0|4|01:23.456|01:20.000|512M|600M|1G|0.50M|0|1M|0
+invalid|row
+";
+        let rows = get_sstat_for_job("49848561", &|_| async { Ok(output.to_string()) })
+            .await
+            .unwrap();
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].job_step_id, "49848561.extern");
+        assert_eq!(rows[0].max_rss, Some(1024 * 1024));
+        assert_eq!(rows[1].num_tasks, Some(1));
+        assert_eq!(rows[1].ave_cpu, Some(Duration::from_secs(601)));
+        assert_eq!(rows[1].max_rss, Some(2 * 1024 * 1024 * 1024));
+        assert_eq!(rows[1].max_vm_size, Some(4 * 1024 * 1024 * 1024));
+        assert_eq!(rows[1].ave_disk_read, Some(10 * 1024 * 1024));
+        assert_eq!(rows[2].num_tasks, Some(4));
+        assert_eq!(rows[2].ave_cpu, Some(Duration::from_millis(83_456)));
+        assert_eq!(rows[2].min_cpu, Some(Duration::from_secs(80)));
+        assert_eq!(rows[2].ave_disk_write, Some(0));
+    }
+}
@@ -0,0 +1,151 @@
+use std::path::Path;
+
+use anyhow::Error;
+use chrono::FixedOffset;
+use structdiff::StructDiff;
+
+use super::{
+    extraction::{extract_ocel_from_jobs_with_progress, CancellationToken, ExtractionConfig},
+    ocel::Ocel,
+    report::ExtractionReport,
+};
+use crate::{
+    data_extraction::{sacct::SacctHistoryRow, squeue::SqueueRow},
+    recording::JobRecording,
+    JobState, MemorySpec,
+};
+
+/// Convert a job of a `sacct` history dump into a recording of its lifecycle
+///
+/// The job is recorded as `PENDING` at its submission, as `RUNNING` at its start (if it was started),
+/// and in its final state at its end (if it ended).
+/// Fields not reported by `sacct` (e.g., the command or priority) are left empty.
+pub fn job_recording_from_sacct(row: &SacctHistoryRow) -> JobRecording {
+    let pending = SqueueRow {
+        account: row.account.clone(),
+        job_id: row.job_id.clone(),
+        exec_host: None,
+        min_cpus: row.cpus,
+        cpus: row.cpus,
+        nodes: row.nodes,
+        end_time: None,
+        dependency: None,
+        features: String::new(),
+        array_job_id: row.step_job_id.base_id.clone(),
+        group: row.group.clone(),
+        step_job_id: row.step_job_id.clone(),
+        time_limit: row.time_limit,
+        time_left: None,
+        name: row.name.clone(),
+        min_memory: row.min_memory.unwrap_or_else(|| MemorySpec::per_node_mb(0)),
+        time: None,
+        priority: 0.0,
+        partition: row.partition.clone(),
+        state: JobState::PENDING,
+        reason: "None".to_string(),
+        start_time: None,
+        submit_time: row.submit_time,
+        work_dir: row.work_dir.clone(),
+        command: String::new(),
+        tres_per_node: Vec::new(),
+        licenses: Vec::new(),
+        user: Some(row.user.clone()),
+        node_list: None,
+        exit_code: None,
+        cluster: None,
+        raw_line: None,
+    };
+    let mut deltas = Vec::new();
+    let mut current = pending.clone();
+    if let Some(start) = row.start_time {
+        let mut running = current.clone();
+        running.state = JobState::RUNNING;
+        running.start_time = Some(start);
+        running.exec_host = row.first_node();
+        running.node_list = row.node_list.clone();
+        deltas.push((start, current.diff(&running)));
+        current = running;
+    }
+    if let Some(end) = row.end_time {
+        let mut ended = current.clone();
+        ended.state = row.state.clone();
+        ended.end_time = Some(end);
+        ended.exit_code = Some(row.exit_code);
+        deltas.push((end, current.diff(&ended)));
+    }
+    JobRecording {
+        job_id: row.job_id.clone(),
+        initial_time: row.submit_time,
+        initial: pending,
+        deltas,
+    }
+}
+
+/// Extract an object-centric event log (OCEL) from a `sacct` history dump at `path` (see [`SacctHistoryRow`]), e.g., for jobs which were not recorded
+///
+/// Timestamps of the dump are interpreted in the cluster timezone `tz`.
+/// Each job is converted into a recording of its lifecycle (see [`job_recording_from_sacct`]),
+/// so the OCEL only contains the submission, start and end of each job.
+/// Header lines and job steps (e.g., `49848561.batch`) are skipped. Lines failing to parse are listed in the returned report.
+/// See [`extract_ocel_from_jobs_and_nodes`](super::extract_ocel_from_jobs_and_nodes).
+pub fn extract_ocel_from_sacct_dump(
+    path: &Path,
+    tz: &FixedOffset,
+    config: &ExtractionConfig,
+) -> Result<(Ocel, ExtractionReport), Error> {
+    let content = std::fs::read_to_string(path)?;
+    let mut jobs = Vec::new();
+    let mut load_failures = Vec::new();
+    for line in content.lines() {
+        let job_id = line.split("|").next().unwrap_or_default().trim();
+        if job_id.is_empty() || job_id == "JobIDRaw" || job_id.contains(".") {
+            continue;
+        }
+        let res = SacctHistoryRow::parse_line(line, tz);
+        match res {
+            Ok(row) => jobs.push(job_recording_from_sacct(&row)),
+            Err(e) => load_failures.push(format!("Failed to parse {line:?}: {e:?}")),
+        }
+    }
+    let (ocel, mut report) =
+        extract_ocel_from_jobs_with_progress(jobs, &[], config, |_| {}, &CancellationToken::new())?;
+    report.load_failures = load_failures;
+    Ok((ocel, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::FixedOffset;
+
+    use super::extract_ocel_from_sacct_dump;
+    use crate::event_data_extraction::ExtractionConfig;
+
+    #[test]
+    fn test_extract_ocel_from_sacct_dump() {
+        let dump = "JobIDRaw|JobID|Account|User|Group|Partition|Submit|Start|End|State|ExitCode|NodeList|AllocCPUS|NNodes|Timelimit|ReqMem|WorkDir|JobName
+49848561|49848561|default|ab123456|ab123456|c23ms|2025-01-14T10:23:45|2025-01-14T10:25:00|2025-01-14T11:25:00|COMPLETED|0:0|n23m[0001-0002]|4|2|01:00:00|4G|/home/ab123456|my job
+49848561.batch|49848561.batch|default|||||2025-01-14T10:25:00|2025-01-14T11:25:00|COMPLETED|0:0|n23m0001|4|1||||batch
+49869435|49869434_2|default|ab123456|ab123456|c23ms|2025-01-14T10:30:00|None|2025-01-14T10:40:00|CANCELLED by 12345|0:0|None assigned|0|1|01:00:00|4G|/home/ab123456|array
+invalid|row
+";
+        let path = std::env::temp_dir().join(format!("slurry-sacct-{}.txt", std::process::id()));
+        std::fs::write(&path, dump).unwrap();
+        let tz = FixedOffset::east_opt(3600).unwrap();
+        let (ocel, report) =
+            extract_ocel_from_sacct_dump(&path, &tz, &ExtractionConfig::default()).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(report.jobs_processed, 2);
+        assert_eq!(report.load_failures.len(), 1);
+        assert_eq!(report.events_per_type["Job Completed"], 1);
+        assert_eq!(report.events_per_type["Job Cancelled"], 1);
+        assert_eq!(report.events_per_type["Job Started"], 1);
+        assert!(ocel.objects.iter().any(|o| o.id == "host_n23m0001"));
+        assert!(ocel.objects.iter().any(|o| o.id == "array_49869434"));
+        let started = ocel
+            .events
+            .iter()
+            .find(|e| e.event_type == "Job Started")
+            .unwrap();
+        assert_eq!(started.time.to_rfc3339(), "2025-01-14T09:25:00+00:00");
+    }
+}
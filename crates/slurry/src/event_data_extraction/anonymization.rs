@@ -0,0 +1,110 @@
+use std::{
+    fmt::Write,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    data_extraction::squeue::SqueueRow,
+    recording::{JobRecording, SqueueRowDiff},
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// Pseudonymization of personal data in recorded jobs, e.g., for sharing recordings for research
+///
+/// Account names, group names, usernames, commands and working directories are replaced by salted hashes.
+/// Using the same salt, the same value is always replaced by the same pseudonym (also across exports),
+/// so jobs of the same user (or account) can still be related to each other.
+/// The salt should be kept secret, as otherwise known values can be matched against the pseudonyms.
+pub struct Pseudonymizer {
+    /// Salt prepended to all values before hashing them
+    pub salt: String,
+}
+
+impl Pseudonymizer {
+    /// Pseudonymize using the given (secret) salt
+    pub fn new(salt: impl Into<String>) -> Self {
+        Self { salt: salt.into() }
+    }
+
+    /// Pseudonym of `value` (the first 16 hex digits of its salted SHA-256 hash)
+    pub fn pseudonymize(&self, value: &str) -> String {
+        let mut hasher = Sha256::new();
+        hasher.update(self.salt.as_bytes());
+        // Separate the salt from the value, so that shifting characters between both changes the hash
+        hasher.update([0]);
+        hasher.update(value.as_bytes());
+        hasher.finalize()[..8]
+            .iter()
+            .fold(String::with_capacity(16), |mut s, b| {
+                let _ = write!(s, "{b:02x}");
+                s
+            })
+    }
+
+    /// Pseudonymize all personal data of a `squeue` row (also removing the original output line)
+    pub fn pseudonymize_row(&self, row: &mut SqueueRow) {
+        row.account = self.pseudonymize(&row.account);
+        row.group = self.pseudonymize(&row.group);
+        row.user = row.user.as_deref().map(|u| self.pseudonymize(u));
+        row.command = self.pseudonymize(&row.command);
+        row.work_dir = self.pseudonymize_path(&row.work_dir);
+        row.raw_line = None;
+    }
+
+    /// Pseudonymize all personal data of a recorded job (both in its initial row and all recorded changes)
+    pub fn pseudonymize_job(&self, job: &mut JobRecording) {
+        self.pseudonymize_row(&mut job.initial);
+        for diff in job.deltas.iter_mut().flat_map(|(_, delta)| delta) {
+            match diff {
+                SqueueRowDiff::account(account) => *account = self.pseudonymize(account),
+                SqueueRowDiff::group(group) => *group = self.pseudonymize(group),
+                SqueueRowDiff::user(Some(user)) => *user = self.pseudonymize(user),
+                SqueueRowDiff::command(command) => *command = self.pseudonymize(command),
+                SqueueRowDiff::work_dir(work_dir) => *work_dir = self.pseudonymize_path(work_dir),
+                _ => {}
+            }
+        }
+    }
+
+    fn pseudonymize_path(&self, path: &Path) -> PathBuf {
+        PathBuf::from(self.pseudonymize(&path.to_string_lossy()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use structdiff::StructDiff;
+
+    use super::Pseudonymizer;
+    use crate::event_data_extraction::tests::test_recording;
+
+    #[test]
+    fn test_pseudonymize_job() {
+        let pseudonymizer = Pseudonymizer::new("secret");
+        let mut job = test_recording();
+        job.initial.user = Some("ab123456".to_string());
+        let mut moved = job.latest_row();
+        moved.work_dir = "/home/ab123456/other".into();
+        let (time, _) = job.deltas[1];
+        job.deltas.push((time, job.latest_row().diff(&moved)));
+        pseudonymizer.pseudonymize_job(&mut job);
+
+        let row = job.latest_row();
+        let user = pseudonymizer.pseudonymize("ab123456");
+        assert_eq!(user.len(), 16);
+        assert_eq!(row.group, user);
+        assert_eq!(row.user, Some(user));
+        assert_ne!(row.account, "default");
+        assert_eq!(
+            row.work_dir,
+            std::path::PathBuf::from(pseudonymizer.pseudonymize("/home/ab123456/other"))
+        );
+        assert_ne!(
+            Pseudonymizer::new("other").pseudonymize("ab123456"),
+            pseudonymizer.pseudonymize("ab123456")
+        );
+    }
+}
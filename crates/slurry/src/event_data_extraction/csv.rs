@@ -0,0 +1,227 @@
+use std::{
+    borrow::Cow,
+    collections::BTreeSet,
+    fs::{create_dir_all, File},
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use anyhow::Error;
+use chrono::SecondsFormat;
+
+use super::{job_events, Pseudonymizer};
+use crate::recording::JobRecording;
+
+/// Export recorded jobs as CSV tables into the directory at `path` (see [`write_csv_from_slurm_diffs`])
+///
+/// The tables are written to `events.csv`, `objects.csv` and `relations.csv`.
+pub fn export_csv_from_slurm_diffs(
+    jobs: &[JobRecording],
+    path: impl AsRef<Path>,
+    pseudonymizer: Option<&Pseudonymizer>,
+) -> Result<(), Error> {
+    let path = path.as_ref();
+    create_dir_all(path)?;
+    let mut events = BufWriter::new(File::create(path.join("events.csv"))?);
+    let mut objects = BufWriter::new(File::create(path.join("objects.csv"))?);
+    let mut relations = BufWriter::new(File::create(path.join("relations.csv"))?);
+    write_csv_from_slurm_diffs(
+        jobs,
+        &mut events,
+        &mut objects,
+        &mut relations,
+        pseudonymizer,
+    )?;
+    events.flush()?;
+    objects.flush()?;
+    relations.flush()?;
+    Ok(())
+}
+
+/// Write recorded jobs as CSV tables (in long format), e.g., for loading them using `pandas`
+///
+/// - `events`: One row per lifecycle event of a job (see [`job_events`]), with columns `event_id`, `activity`, `time`, `job_id`, `state` and `exec_host`
+/// - `objects`: One row per job, account, group, partition and host, with columns `object_id` and `object_type`,
+///   followed by the job attributes (as last recorded) `name`, `account`, `group`, `partition`, `state`, `exit_code`, `cpus` and `submit_time`
+///   (empty for all other objects)
+/// - `relations`: One row per relation of an event or job to an object, with columns `source_id`, `target_id` and `qualifier`
+///
+/// Object IDs of accounts, groups, partitions and hosts are prefixed (`acc_`, `group_`, `part_` and `host_`) to distinguish them from job IDs.
+/// Times are formatted according to RFC 3339.
+/// If a `pseudonymizer` is given, personal data (e.g., account names) is pseudonymized before writing (see [`Pseudonymizer`]).
+pub fn write_csv_from_slurm_diffs(
+    jobs: &[JobRecording],
+    events: &mut impl Write,
+    objects: &mut impl Write,
+    relations: &mut impl Write,
+    pseudonymizer: Option<&Pseudonymizer>,
+) -> Result<(), Error> {
+    write_row(
+        events,
+        &[
+            "event_id",
+            "activity",
+            "time",
+            "job_id",
+            "state",
+            "exec_host",
+        ],
+    )?;
+    write_row(
+        objects,
+        &[
+            "object_id",
+            "object_type",
+            "name",
+            "account",
+            "group",
+            "partition",
+            "state",
+            "exit_code",
+            "cpus",
+            "submit_time",
+        ],
+    )?;
+    write_row(relations, &["source_id", "target_id", "qualifier"])?;
+    let mut other_objects = BTreeSet::new();
+    for job in jobs {
+        let job = match pseudonymizer {
+            Some(pseudonymizer) => {
+                let mut job = job.clone();
+                pseudonymizer.pseudonymize_job(&mut job);
+                Cow::Owned(job)
+            }
+            None => Cow::Borrowed(job),
+        };
+        let row = job.latest_row();
+        write_row(
+            objects,
+            &[
+                &job.job_id,
+                "Job",
+                &row.name,
+                &row.account,
+                &row.group,
+                &row.partition,
+                &row.state.to_string(),
+                &row.exit_code.map(|c| c.to_string()).unwrap_or_default(),
+                &row.cpus.to_string(),
+                &row.submit_time.to_rfc3339_opts(SecondsFormat::Millis, true),
+            ],
+        )?;
+        let mut job_relations = vec![
+            (format!("acc_{}", row.account), "Account", "submitted by"),
+            (
+                format!("group_{}", row.group),
+                "Group",
+                "submitted by group",
+            ),
+            (
+                format!("part_{}", row.partition),
+                "Partition",
+                "submitted on",
+            ),
+        ];
+        if let Some(exec_host) = &row.exec_host {
+            job_relations.push((format!("host_{exec_host}"), "Host", "executed on"));
+        }
+        for (object_id, object_type, qualifier) in job_relations {
+            write_row(relations, &[&job.job_id, &object_id, qualifier])?;
+            other_objects.insert((object_id, object_type));
+        }
+        for (i, event) in job_events(&job).into_iter().enumerate() {
+            let event_id = format!("{}-{i}", job.job_id);
+            write_row(
+                events,
+                &[
+                    &event_id,
+                    &event.activity,
+                    &event.time.to_rfc3339_opts(SecondsFormat::Millis, true),
+                    &event.job_id,
+                    &event.state.to_string(),
+                    event.exec_host.as_deref().unwrap_or_default(),
+                ],
+            )?;
+            write_row(relations, &[&event_id, &event.job_id, "job"])?;
+            if let Some(exec_host) = &event.exec_host {
+                let object_id = format!("host_{exec_host}");
+                write_row(relations, &[&event_id, &object_id, "host"])?;
+                other_objects.insert((object_id, "Host"));
+            }
+        }
+    }
+    for (object_id, object_type) in other_objects {
+        write_row(objects, &[&object_id, object_type])?;
+    }
+    Ok(())
+}
+
+/// Write a CSV row, quoting values if necessary
+fn write_row(w: &mut impl Write, values: &[&str]) -> Result<(), Error> {
+    let row: Vec<String> = values.iter().map(|v| quote_csv(v)).collect();
+    writeln!(w, "{}", row.join(","))?;
+    Ok(())
+}
+
+fn quote_csv(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::write_csv_from_slurm_diffs;
+    use crate::event_data_extraction::{tests::test_recording, Pseudonymizer};
+
+    #[test]
+    fn test_csv_export() {
+        let mut job = test_recording();
+        job.initial.name = "train, \"large\"".to_string();
+        let (mut events, mut objects, mut relations) = (Vec::new(), Vec::new(), Vec::new());
+        write_csv_from_slurm_diffs(
+            &[job.clone()],
+            &mut events,
+            &mut objects,
+            &mut relations,
+            None,
+        )
+        .unwrap();
+        let events = String::from_utf8(events).unwrap();
+        let objects = String::from_utf8(objects).unwrap();
+        let relations = String::from_utf8(relations).unwrap();
+
+        let event_lines: Vec<_> = events.lines().collect();
+        assert_eq!(event_lines.len(), 4);
+        assert_eq!(
+            event_lines[2],
+            "49848561-1,Job Started,2025-01-14T09:24:05.000Z,49848561,RUNNING,n23m0001"
+        );
+        assert!(objects.contains(
+            "49848561,Job,\"train, \"\"large\"\"\",default,ab123456,c23ms,COMPLETED,0,1,2025-01-14T09:23:45.000Z\n"
+        ));
+        assert!(objects.contains("\nhost_n23m0001,Host\n"));
+        assert_eq!(objects.lines().count(), 6);
+        assert!(relations.contains("49848561,part_c23ms,submitted on\n"));
+        assert!(relations.contains("49848561-2,host_n23m0001,host\n"));
+
+        let pseudonymizer = Pseudonymizer::new("secret");
+        let (mut events, mut objects, mut relations) = (Vec::new(), Vec::new(), Vec::new());
+        write_csv_from_slurm_diffs(
+            &[job],
+            &mut events,
+            &mut objects,
+            &mut relations,
+            Some(&pseudonymizer),
+        )
+        .unwrap();
+        let objects = String::from_utf8(objects).unwrap();
+        assert!(!objects.contains("ab123456"));
+        assert!(objects.contains(&format!(
+            "\ngroup_{},Group\n",
+            pseudonymizer.pseudonymize("ab123456")
+        )));
+    }
+}
@@ -0,0 +1,1918 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use anyhow::Error;
+use chrono::{DateTime, FixedOffset, Utc};
+use rayon::prelude::*;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use structdiff::StructDiff;
+
+use super::{
+    default_state_activity,
+    hooks::{merge_object, merge_types, HookList, JobExtraction},
+    job_events_with, job_metrics,
+    ocel::{
+        Ocel, OcelAttributeType, OcelAttributeValue, OcelEvent, OcelEventAttribute, OcelObject,
+        OcelObjectAttribute, OcelRelationship, OcelType, OcelTypeAttribute,
+    },
+    report::ExtractionReport,
+    JobEventKind, Pseudonymizer, ALLOCATE_ACTIVITY, NODE_DOWN_ACTIVITY, NODE_DRAINED_ACTIVITY,
+    SUBMIT_ACTIVITY, SUBMIT_ARRAY_ACTIVITY,
+};
+use crate::{
+    data_extraction::{cluster_info::parse_utc_offset, sinfo::NodeState, squeue::SqueueRow},
+    recording::{
+        jsonl::read_jsonl_node_states, merge_job_recordings, read_jsonl_recording, JobRecording,
+        JsonDirStore, RecordedNodeStates, RecordingStore, SqueueRowDiff,
+    },
+    JobState,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+/// Field of `squeue` rows which can be included as attribute of `Job` objects (see [`ExtractionConfig::job_attributes`])
+pub enum JobAttribute {
+    /// Job state (`state`)
+    State,
+    /// Job name (`name`)
+    Name,
+    /// Executed command, without its directory (`command`)
+    Command,
+    /// Working directory (`work_dir`)
+    WorkDir,
+    /// Number of CPUs (`cpus`)
+    Cpus,
+    /// Number of nodes (`nodes`)
+    Nodes,
+    /// Minimum memory in bytes (`min_memory`), and whether it is specified per CPU (`min_memory_per_cpu`)
+    MinMemory,
+    /// Requested TRES per node (`tres_per_node`)
+    TresPerNode,
+    /// Requested licenses (`licenses`)
+    Licenses,
+    /// Exit code (`exit_code`)
+    ExitCode,
+    /// ID of the array job, for array tasks (`array_job_id`)
+    ArrayJobId,
+    /// Array task ID, for array tasks (`array_task_id`)
+    ArrayTaskId,
+    /// Cluster name (`cluster`)
+    Cluster,
+    /// Scheduling priority (`priority`)
+    Priority,
+    /// Time limit in seconds (`time_limit`)
+    TimeLimit,
+    /// Reason for the current state (`reason`)
+    Reason,
+}
+
+impl JobAttribute {
+    /// Attributes included by default
+    pub fn defaults() -> Vec<Self> {
+        vec![
+            JobAttribute::State,
+            JobAttribute::Command,
+            JobAttribute::WorkDir,
+            JobAttribute::Cpus,
+            JobAttribute::MinMemory,
+            JobAttribute::TresPerNode,
+            JobAttribute::ExitCode,
+            JobAttribute::ArrayJobId,
+            JobAttribute::ArrayTaskId,
+            JobAttribute::Cluster,
+            JobAttribute::Licenses,
+            JobAttribute::Priority,
+        ]
+    }
+
+    /// Declarations of the OCEL attributes
+    pub fn declarations(&self) -> Vec<OcelTypeAttribute> {
+        use OcelAttributeType::{Boolean, Float, Integer, String};
+        let declare = |name: &str, value_type| vec![OcelTypeAttribute::new(name, value_type)];
+        match self {
+            JobAttribute::State => declare("state", String),
+            JobAttribute::Name => declare("name", String),
+            JobAttribute::Command => declare("command", String),
+            JobAttribute::WorkDir => declare("work_dir", String),
+            JobAttribute::Cpus => declare("cpus", Integer),
+            JobAttribute::Nodes => declare("nodes", Integer),
+            JobAttribute::MinMemory => vec![
+                OcelTypeAttribute::new("min_memory", Integer),
+                OcelTypeAttribute::new("min_memory_per_cpu", Boolean),
+            ],
+            JobAttribute::TresPerNode => declare("tres_per_node", String),
+            JobAttribute::Licenses => declare("licenses", String),
+            JobAttribute::ExitCode => declare("exit_code", Integer),
+            JobAttribute::ArrayJobId => declare("array_job_id", String),
+            JobAttribute::ArrayTaskId => declare("array_task_id", Integer),
+            JobAttribute::Cluster => declare("cluster", String),
+            JobAttribute::Priority => declare("priority", Float),
+            JobAttribute::TimeLimit => declare("time_limit", Integer),
+            JobAttribute::Reason => declare("reason", String),
+        }
+    }
+
+    /// Values of the OCEL attributes for `row` (`None` if not available)
+    fn values(&self, row: &SqueueRow) -> Vec<(&'static str, Option<OcelAttributeValue>)> {
+        let join = |values: Vec<String>| values.join(",");
+        let value: (&'static str, Option<OcelAttributeValue>) = match self {
+            JobAttribute::State => ("state", Some(row.state.to_string().into())),
+            JobAttribute::Name => ("name", Some(row.name.clone().into())),
+            JobAttribute::Command => (
+                "command",
+                Some(
+                    row.command
+                        .split('/')
+                        .next_back()
+                        .unwrap_or_default()
+                        .into(),
+                ),
+            ),
+            JobAttribute::WorkDir => (
+                "work_dir",
+                Some(row.work_dir.to_string_lossy().to_string().into()),
+            ),
+            JobAttribute::Cpus => ("cpus", Some((row.cpus as i64).into())),
+            JobAttribute::Nodes => ("nodes", Some((row.nodes as i64).into())),
+            JobAttribute::MinMemory => {
+                return vec![
+                    ("min_memory", Some((row.min_memory.bytes as i64).into())),
+                    ("min_memory_per_cpu", Some(row.min_memory.per_cpu.into())),
+                ]
+            }
+            JobAttribute::TresPerNode => (
+                "tres_per_node",
+                Some(join(row.tres_per_node.iter().map(|t| t.to_string()).collect()).into()),
+            ),
+            JobAttribute::Licenses => (
+                "licenses",
+                Some(join(row.licenses.iter().map(|l| l.to_string()).collect()).into()),
+            ),
+            JobAttribute::ExitCode => ("exit_code", row.exit_code.map(|c| (c as i64).into())),
+            JobAttribute::ArrayJobId => (
+                "array_job_id",
+                row.step_job_id
+                    .is_array()
+                    .then(|| row.step_job_id.base_id.clone().into()),
+            ),
+            JobAttribute::ArrayTaskId => (
+                "array_task_id",
+                row.step_job_id.single_task().map(|t| (t as i64).into()),
+            ),
+            JobAttribute::Cluster => ("cluster", row.cluster.clone().map(Into::into)),
+            JobAttribute::Priority => ("priority", Some(row.priority.into())),
+            JobAttribute::TimeLimit => (
+                "time_limit",
+                row.time_limit.map(|t| (t.as_secs() as i64).into()),
+            ),
+            JobAttribute::Reason => ("reason", Some(row.reason.clone().into())),
+        };
+        vec![value]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// Event type emitted for state changes of jobs to `state` (see [`ExtractionConfig::state_events`])
+pub struct StateEvent {
+    /// New state of the job
+    pub state: JobState,
+    /// Name of the event type (e.g., `Job Completed`)
+    pub event_type: String,
+}
+
+impl StateEvent {
+    /// Emit events of the given type for state changes to `state`
+    pub fn new(state: JobState, event_type: impl Into<String>) -> Self {
+        Self {
+            state,
+            event_type: event_type.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+/// Qualifiers of the relationships created during extraction (see [`ExtractionConfig::qualifiers`])
+pub struct RelationshipQualifiers {
+    /// Job to its account (default: `submitted by`)
+    pub job_account: String,
+    /// Job to its group (default: `submitted by group`)
+    pub job_group: String,
+    /// Job to the user who submitted it (default: `submitted by user`)
+    pub job_user: String,
+    /// Job to its partition (default: `submitted on`)
+    pub job_partition: String,
+    /// Job to the hosts executing it (default: `executed on`)
+    pub job_host: String,
+    /// Event to its job (default: `job`)
+    pub event_job: String,
+    /// Submission event to the account of the job (default: `submitter`)
+    pub submit_account: String,
+    /// Submission event to the user who submitted the job (default: `user`)
+    pub submit_user: String,
+    /// User to the accounts used by their jobs (default: `member of`)
+    pub user_account: String,
+    /// Start event to the group of the job (default: `for`)
+    pub start_group: String,
+    /// Start event to the host executing the job (default: `host`)
+    pub start_host: String,
+    /// Allocation event to the newly allocated host (default: `allocated on`)
+    pub allocated_host: String,
+    /// Host to the partitions it belongs to (default: `member of`)
+    pub host_partition: String,
+    /// Array job to its tasks, and array submission event to all tasks (default: `task`)
+    pub array_task: String,
+    /// Array submission event to the array job (default: `array`)
+    pub submit_array: String,
+    /// Node failure event to the affected host (default: `host`)
+    pub node_event_host: String,
+    /// Node failure event to the jobs running on the affected host (default: `affected job`)
+    pub node_event_job: String,
+}
+
+impl Default for RelationshipQualifiers {
+    fn default() -> Self {
+        Self {
+            job_account: "submitted by".to_string(),
+            job_group: "submitted by group".to_string(),
+            job_user: "submitted by user".to_string(),
+            job_partition: "submitted on".to_string(),
+            job_host: "executed on".to_string(),
+            event_job: "job".to_string(),
+            submit_account: "submitter".to_string(),
+            submit_user: "user".to_string(),
+            user_account: "member of".to_string(),
+            start_group: "for".to_string(),
+            start_host: "host".to_string(),
+            allocated_host: "allocated on".to_string(),
+            host_partition: "member of".to_string(),
+            array_task: "task".to_string(),
+            submit_array: "array".to_string(),
+            node_event_host: "host".to_string(),
+            node_event_job: "affected job".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+/// Filter selecting the jobs to extract (see [`ExtractionConfig::filter`])
+///
+/// All given criteria have to match. Jobs are filtered before their conversion, so they are also not related to other objects (e.g., array jobs).
+pub struct ExtractionFilter {
+    /// Only jobs submitted at or after this time
+    pub submitted_after: Option<DateTime<Utc>>,
+    /// Only jobs submitted before this time
+    pub submitted_before: Option<DateTime<Utc>>,
+    /// Only jobs submitted to one of these partitions (all partitions if empty)
+    pub partitions: Vec<String>,
+    /// Only jobs submitted by one of these accounts (all accounts if empty)
+    pub accounts: Vec<String>,
+    /// Skip jobs which never left the `PENDING` state (e.g., jobs still waiting at the end of the recording)
+    pub exclude_pending: bool,
+}
+
+impl ExtractionFilter {
+    /// Check if the recorded `job` matches this filter
+    ///
+    /// Partitions and accounts are matched against the values at submission.
+    pub fn matches(&self, job: &JobRecording) -> bool {
+        let row = &job.initial;
+        self.submitted_after.is_none_or(|t| row.submit_time >= t)
+            && self.submitted_before.is_none_or(|t| row.submit_time < t)
+            && (self.partitions.is_empty() || self.partitions.contains(&row.partition))
+            && (self.accounts.is_empty() || self.accounts.contains(&row.account))
+            && (!self.exclude_pending
+                || row.state != JobState::PENDING
+                || job.deltas.iter().flat_map(|(_, delta)| delta).any(
+                    |diff| matches!(diff, SqueueRowDiff::state(state) if *state != JobState::PENDING),
+                ))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+/// Configuration of the OCEL extraction (see [`extract_ocel_from_slurm_diffs`])
+///
+/// Object IDs of accounts, users, groups, partitions, hosts and array jobs are prefixed (`acc_`, `user_`, `group_`, `part_`, `host_` and `array_`) to distinguish them from job IDs.
+pub struct ExtractionConfig {
+    /// Emit `Account` objects, related to jobs and their submission events
+    pub accounts: bool,
+    /// Emit `User` objects, related to jobs, their submission events and the accounts used by the user
+    ///
+    /// Requires the user to be recorded (see [`SqueueRow::user`]).
+    pub users: bool,
+    /// Emit `Group` objects, related to jobs and their start events
+    pub groups: bool,
+    /// Emit `Host` objects, related to jobs and their start events
+    ///
+    /// If node states were recorded (see [`load_node_recordings`]), hosts carry the attributes `partitions`, `cpus` and `memory` (in megabytes)
+    /// and are related to their partitions.
+    pub hosts: bool,
+    /// Emit `Partition` objects, related to jobs
+    pub partitions: bool,
+    /// Emit `Job Allocated` events whenever the executing host of a job changes, related to the job and the new host
+    ///
+    /// Only applies if `hosts` are enabled.
+    /// The time of the first allocation after a (re)start is the start time reported by SLURM.
+    pub allocation_events: bool,
+    /// Emit `Node Down` and `Node Drained` events whenever a recorded node becomes unavailable (or is drained),
+    /// related to its host and all jobs running on it at that time
+    ///
+    /// Only applies if `hosts` are enabled and node states were recorded (see [`load_node_recordings`]).
+    /// Nodes already unavailable when first recorded are skipped.
+    /// Jobs are matched by their executing host, i.e., only the first node of multi-node jobs is considered.
+    pub node_events: bool,
+    /// Emit `ArrayJob` objects, related to all their tasks, and a `Submit Array` event per array job, related to the array job and all its tasks
+    ///
+    /// The time of the `Submit Array` event is the earliest submission time of all tasks.
+    pub array_jobs: bool,
+    /// Relate jobs to the jobs they depend on, qualified by the dependency type (e.g., `afterok`, see [`SqueueRow::dependency_targets`])
+    ///
+    /// Dependencies on array jobs (or single tasks of them) are related to the `ArrayJob` object (if `array_jobs` are enabled, skipped otherwise).
+    /// Jobs which were not recorded themselves are included as `Job` objects without attributes.
+    pub dependencies: bool,
+    /// Include the metrics `queue_wait` and `runtime` (in seconds, if available) and `requeues` as attributes of `Job` objects (see [`job_metrics`])
+    ///
+    /// The metrics are derived from the whole recording of a job, so their values are valid from the start.
+    pub metrics: bool,
+    /// Include the attributes `reason`, `exec_host`, `elapsed` (in seconds) and `exit_code` in start and state change events (if available)
+    ///
+    /// Values are taken from the job at the time of the event.
+    pub event_attributes: bool,
+    /// Fields of `squeue` rows included as attributes of `Job` objects (changes are recorded over time)
+    pub job_attributes: Vec<JobAttribute>,
+    /// State changes emitted as events (all other state changes are skipped)
+    ///
+    /// The time of state changes to `RUNNING` is the start time reported by SLURM.
+    pub state_events: Vec<StateEvent>,
+    /// Only extract the jobs matching this filter (by default, all jobs)
+    pub filter: ExtractionFilter,
+    /// Custom hooks called for each job, e.g., to add object types, events or attributes (see [`ExtractionHooks`](super::ExtractionHooks))
+    ///
+    /// Hooks are not serialized.
+    #[serde(skip)]
+    pub hooks: HookList,
+    /// Pseudonymize personal data (e.g., account names) of all jobs before their extraction (see [`Pseudonymizer`])
+    ///
+    /// Jobs are filtered before, i.e., the filter refers to the original values.
+    pub pseudonymization: Option<Pseudonymizer>,
+    /// Qualifiers of the created relationships
+    pub qualifiers: RelationshipQualifiers,
+    /// UTC offset in which all timestamps are written (UTC if not set)
+    ///
+    /// E.g., the timezone of the cluster (see [`ClusterInfo::detect_timezone`](crate::data_extraction::ClusterInfo::detect_timezone)).
+    /// Serialized as `+HH:MM`.
+    #[serde(
+        serialize_with = "serialize_utc_offset",
+        deserialize_with = "deserialize_utc_offset"
+    )]
+    pub timezone: Option<FixedOffset>,
+}
+
+fn serialize_utc_offset<S: Serializer>(
+    offset: &Option<FixedOffset>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    offset.map(|o| o.to_string()).serialize(serializer)
+}
+
+fn deserialize_utc_offset<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<FixedOffset>, D::Error> {
+    Option::<String>::deserialize(deserializer)?
+        .map(|s| parse_utc_offset(&s).map_err(serde::de::Error::custom))
+        .transpose()
+}
+
+impl Default for ExtractionConfig {
+    fn default() -> Self {
+        let states = [
+            JobState::RUNNING,
+            JobState::COMPLETING,
+            JobState::COMPLETED,
+            JobState::CANCELLED,
+            JobState::FAILED,
+            JobState::TIMEOUT,
+            JobState::OUT_OF_MEMORY,
+            JobState::NODE_FAIL,
+        ];
+        Self {
+            accounts: true,
+            users: true,
+            groups: true,
+            hosts: true,
+            partitions: true,
+            allocation_events: true,
+            node_events: true,
+            array_jobs: true,
+            dependencies: true,
+            event_attributes: true,
+            metrics: true,
+            job_attributes: JobAttribute::defaults(),
+            state_events: states
+                .into_iter()
+                .map(|state| {
+                    let event_type = default_state_activity(&state);
+                    StateEvent::new(state, event_type)
+                })
+                .collect(),
+            filter: ExtractionFilter::default(),
+            hooks: HookList::default(),
+            pseudonymization: None,
+            qualifiers: RelationshipQualifiers::default(),
+            timezone: None,
+        }
+    }
+}
+
+impl ExtractionConfig {
+    /// Convert `time` into the configured timezone
+    fn localize(&self, time: &DateTime<Utc>) -> DateTime<FixedOffset> {
+        match &self.timezone {
+            Some(tz) => time.with_timezone(tz),
+            None => time.fixed_offset(),
+        }
+    }
+
+    /// Keep only the jobs matching the filter, pseudonymizing them if configured
+    ///
+    /// Returns the number of skipped jobs.
+    pub(super) fn prepare_jobs(&self, jobs: &mut Vec<JobRecording>) -> usize {
+        let total_jobs = jobs.len();
+        jobs.retain(|job| self.filter.matches(job));
+        if let Some(pseudonymizer) = &self.pseudonymization {
+            jobs.par_iter_mut()
+                .for_each(|job| pseudonymizer.pseudonymize_job(job));
+        }
+        total_jobs - jobs.len()
+    }
+
+    /// Event type emitted for state changes to `state` (if any)
+    fn state_event_type(&self, state: &JobState) -> Option<String> {
+        self.state_events
+            .iter()
+            .find(|e| &e.state == state)
+            .map(|e| e.event_type.clone())
+    }
+}
+
+/// Load all jobs of a recording (see [`extract_ocel_from_slurm_diffs`])
+///
+/// `path` can either be a JSON recording directory (with one folder per job, see [`JsonDirStore`]), a directory containing JSONL recordings, or a single JSONL recording.
+/// Jobs failing to load are skipped (printing the error).
+pub fn load_job_recordings(path: &Path) -> Result<Vec<JobRecording>, Error> {
+    let (jobs, failures) = load_job_recordings_with_failures(path)?;
+    for failure in failures {
+        eprintln!("{failure}");
+    }
+    Ok(jobs)
+}
+
+/// Load all jobs of a recording (see [`load_job_recordings`]), also returning the errors of all jobs (or JSONL files) failing to load
+pub fn load_job_recordings_with_failures(
+    path: &Path,
+) -> Result<(Vec<JobRecording>, Vec<String>), Error> {
+    if path.is_file() {
+        return Ok((read_jsonl_recording(path)?, Vec::new()));
+    }
+    let jsonl_files = jsonl_files_in(path)?;
+    if !jsonl_files.is_empty() {
+        let mut jobs = Vec::new();
+        let mut failures = Vec::new();
+        for p in jsonl_files {
+            let res = read_jsonl_recording(&p);
+            match res {
+                Ok(file_jobs) => jobs.extend(file_jobs),
+                Err(e) => failures.push(format!("Failed to read {p:?}: {e:?}")),
+            }
+        }
+        return Ok((jobs, failures));
+    }
+    JsonDirStore::new(path).load_jobs_with_failures()
+}
+
+/// Load all recorded node states of a recording, ordered by time
+///
+/// `path` can either be a JSON recording directory (with `NODES-<time>.json` files), a directory containing JSONL recordings, or a single JSONL recording.
+pub fn load_node_recordings(path: &Path) -> Result<Vec<RecordedNodeStates>, Error> {
+    if path.is_file() {
+        return read_jsonl_node_states(path);
+    }
+    let mut node_states = JsonDirStore::new(path).load_node_states()?;
+    for file_path in jsonl_files_in(path)? {
+        node_states.extend(read_jsonl_node_states(&file_path)?);
+    }
+    node_states.sort_by_key(|(time, _)| *time);
+    Ok(node_states)
+}
+
+/// All JSONL recordings (including compressed ones) in the directory at `path`
+pub(super) fn jsonl_files_in(path: &Path) -> Result<Vec<PathBuf>, Error> {
+    let mut jsonl_files = Vec::new();
+    for entry in std::fs::read_dir(path)? {
+        let file_path = entry?.path();
+        if file_path.is_file()
+            && file_path
+                .file_name()
+                .is_some_and(|n| n.to_string_lossy().contains(".jsonl"))
+        {
+            jsonl_files.push(file_path);
+        }
+    }
+    Ok(jsonl_files)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// Progress of an OCEL extraction (see [`extract_ocel_from_jobs_with_progress`])
+pub struct ExtractionProgress {
+    /// Number of jobs converted so far
+    pub jobs_processed: usize,
+    /// Total number of jobs
+    pub total_jobs: usize,
+}
+
+#[derive(Debug, Clone, Default)]
+/// Token to cancel a running OCEL extraction (see [`extract_ocel_from_jobs_with_progress`])
+///
+/// All clones share the same state, i.e., the extraction can be cancelled from another thread.
+pub struct CancellationToken(Arc<AtomicBool>);
+
+impl CancellationToken {
+    /// Create a new (not yet cancelled) token
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Request the cancellation of all extractions using this token
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    /// Check if the cancellation was requested
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// Extract an object-centric event log (OCEL) from the recording at `path` (see [`load_job_recordings`])
+///
+/// Recorded node states are used to enrich `Host` objects (see [`load_node_recordings`]).
+/// See [`extract_ocel_from_jobs_and_nodes`].
+pub fn extract_ocel_from_slurm_diffs(
+    path: &Path,
+    config: &ExtractionConfig,
+) -> Result<Ocel, Error> {
+    let (ocel, _) = extract_ocel_from_slurm_diffs_with_progress(
+        path,
+        config,
+        |_| {},
+        &CancellationToken::new(),
+    )?;
+    Ok(ocel)
+}
+
+/// Extract an object-centric event log (OCEL) from multiple recordings (see [`load_job_recordings`]), e.g., recorded on multiple login nodes or by restarted recording loops
+///
+/// Jobs included in multiple recordings are merged (see [`merge_job_recordings`]), and the node states of all recordings are combined.
+/// See [`extract_ocel_from_jobs_and_nodes`].
+pub fn extract_ocel_from_merged_slurm_diffs(
+    paths: &[impl AsRef<Path>],
+    config: &ExtractionConfig,
+) -> Result<Ocel, Error> {
+    let mut jobs = Vec::new();
+    let mut node_states = Vec::new();
+    for path in paths {
+        jobs.extend(load_job_recordings(path.as_ref())?);
+        if config.hosts {
+            node_states.extend(load_node_recordings(path.as_ref())?);
+        }
+    }
+    node_states.sort_by_key(|(time, _)| *time);
+    Ok(extract_ocel_from_jobs_and_nodes(
+        merge_job_recordings(jobs),
+        &node_states,
+        config,
+    ))
+}
+
+/// Extract an object-centric event log (OCEL) from the jobs and node states recorded in `store`
+///
+/// The store has to support reading (see [`RecordingStore::load_jobs`]).
+/// See [`extract_ocel_from_jobs_and_nodes`].
+pub fn extract_ocel_from_store<S: RecordingStore + ?Sized>(
+    store: &S,
+    config: &ExtractionConfig,
+) -> Result<Ocel, Error> {
+    let jobs = store.load_jobs()?;
+    let node_states = if config.hosts {
+        store.load_node_states()?
+    } else {
+        Vec::new()
+    };
+    Ok(extract_ocel_from_jobs_and_nodes(jobs, &node_states, config))
+}
+
+/// Extract an object-centric event log (OCEL) from the recording at `path`, reporting the progress to `on_progress`
+///
+/// Jobs failing to load are skipped and listed in the returned report.
+/// See [`extract_ocel_from_slurm_diffs`] and [`extract_ocel_from_jobs_with_progress`].
+pub fn extract_ocel_from_slurm_diffs_with_progress(
+    path: &Path,
+    config: &ExtractionConfig,
+    on_progress: impl Fn(ExtractionProgress) + Sync,
+    cancel: &CancellationToken,
+) -> Result<(Ocel, ExtractionReport), Error> {
+    let (jobs, load_failures) = load_job_recordings_with_failures(path)?;
+    let node_states = if config.hosts {
+        load_node_recordings(path)?
+    } else {
+        Vec::new()
+    };
+    let (ocel, mut report) =
+        extract_ocel_from_jobs_with_progress(jobs, &node_states, config, on_progress, cancel)?;
+    report.load_failures = load_failures;
+    Ok((ocel, report))
+}
+
+/// Extract an object-centric event log (OCEL) from recorded jobs
+///
+/// See [`extract_ocel_from_jobs_and_nodes`].
+pub fn extract_ocel_from_jobs(jobs: Vec<JobRecording>, config: &ExtractionConfig) -> Ocel {
+    extract_ocel_from_jobs_and_nodes(jobs, &[], config)
+}
+
+/// Extract an object-centric event log (OCEL) from recorded jobs and node states (ordered by time)
+///
+/// Only jobs matching the configured filter are extracted (see [`ExtractionConfig::filter`]), pseudonymized if configured.
+/// Each job becomes a `Job` object, with the configured attributes tracked over time.
+/// Its submission, start, and state changes become events (see [`ExtractionConfig::state_events`]).
+/// Accounts, users, groups, partitions and hosts become objects related to the jobs (if enabled in the config).
+/// Users are related to the accounts used by their jobs, and array jobs to their tasks.
+/// All recorded nodes become `Host` objects, with their partitions, CPUs and memory tracked over time.
+/// Nodes going down (or being drained) become events related to their host and the jobs running on it (see [`ExtractionConfig::node_events`]).
+pub fn extract_ocel_from_jobs_and_nodes(
+    mut jobs: Vec<JobRecording>,
+    node_states: &[RecordedNodeStates],
+    config: &ExtractionConfig,
+) -> Ocel {
+    config.prepare_jobs(&mut jobs);
+    let extracted: Vec<_> = jobs
+        .par_iter()
+        .map(|job| job_to_ocel(job, config))
+        .collect();
+    assemble_ocel(
+        jobs.iter().zip(extracted),
+        node_states,
+        config,
+        &mut ExtractionReport::default(),
+    )
+}
+
+/// Extract an object-centric event log (OCEL) from recorded jobs and node states (see [`extract_ocel_from_jobs_and_nodes`])
+///
+/// The progress is reported to `on_progress` (about every percent of converted jobs, from multiple threads),
+/// counting only jobs matching the configured filter.
+/// If `cancel` is cancelled, the extraction stops early and an error is returned.
+///
+/// Returns the OCEL together with a summary of the extraction (e.g., the number of events per type and timestamp anomalies).
+pub fn extract_ocel_from_jobs_with_progress(
+    mut jobs: Vec<JobRecording>,
+    node_states: &[RecordedNodeStates],
+    config: &ExtractionConfig,
+    on_progress: impl Fn(ExtractionProgress) + Sync,
+    cancel: &CancellationToken,
+) -> Result<(Ocel, ExtractionReport), Error> {
+    let mut report = ExtractionReport {
+        jobs_skipped: config.prepare_jobs(&mut jobs),
+        ..Default::default()
+    };
+    let total_jobs = jobs.len();
+    let step = (total_jobs / 100).max(1);
+    let processed = AtomicUsize::new(0);
+    let extracted: Vec<_> = jobs
+        .par_iter()
+        .map(|job| {
+            if cancel.is_cancelled() {
+                return None;
+            }
+            let extracted = job_to_ocel(job, config);
+            let jobs_processed = processed.fetch_add(1, Ordering::Relaxed) + 1;
+            if jobs_processed.is_multiple_of(step) || jobs_processed == total_jobs {
+                on_progress(ExtractionProgress {
+                    jobs_processed,
+                    total_jobs,
+                });
+            }
+            Some(extracted)
+        })
+        .while_some()
+        .collect();
+    if cancel.is_cancelled() {
+        return Err(Error::msg("OCEL extraction was cancelled."));
+    }
+    let ocel = assemble_ocel(jobs.iter().zip(extracted), node_states, config, &mut report);
+    Ok((ocel, report))
+}
+
+/// Combine converted jobs with related objects and the declared types into an OCEL, adding all jobs and events to `report`
+pub(super) fn assemble_ocel<'a>(
+    jobs: impl IntoIterator<Item = (&'a JobRecording, ExtractedJob)>,
+    node_states: &[RecordedNodeStates],
+    config: &ExtractionConfig,
+    report: &mut ExtractionReport,
+) -> Ocel {
+    let mut ocel = Ocel::default();
+    (ocel.event_types, ocel.object_types) = declared_types(config);
+    let mut related_objects = RelatedObjects::default();
+    for (job, extracted) in jobs {
+        report.add_job(job, &extracted.events);
+        related_objects.add(job, extracted.related, extracted.objects, config);
+        ocel.objects.push(extracted.object);
+        ocel.events.extend(extracted.events);
+    }
+    let array_events = related_objects.array_events(config);
+    report.add_events(&array_events);
+    ocel.events.extend(array_events);
+    let node_events = related_objects.node_events(node_states, config);
+    report.add_events(&node_events);
+    ocel.events.extend(node_events);
+    ocel.objects
+        .extend(related_objects.into_objects(node_states, config));
+    ocel
+}
+
+/// Event and object types declared for the given config
+pub(super) fn declared_types(config: &ExtractionConfig) -> (Vec<OcelType>, Vec<OcelType>) {
+    let mut job_type = OcelType {
+        name: "Job".to_string(),
+        attributes: config
+            .job_attributes
+            .iter()
+            .flat_map(|a| a.declarations())
+            .collect(),
+    };
+    if config.metrics {
+        for name in ["queue_wait", "runtime", "requeues"] {
+            job_type
+                .attributes
+                .push(OcelTypeAttribute::new(name, OcelAttributeType::Integer));
+        }
+    }
+    let mut object_types = vec![job_type];
+    for (enabled, name) in [
+        (config.accounts, "Account"),
+        (config.users, "User"),
+        (config.groups, "Group"),
+        (config.hosts, "Host"),
+        (config.partitions, "Partition"),
+        (config.array_jobs, "ArrayJob"),
+    ] {
+        if enabled {
+            let attributes = match name {
+                "Host" => vec![
+                    OcelTypeAttribute::new("partitions", OcelAttributeType::String),
+                    OcelTypeAttribute::new("cpus", OcelAttributeType::Integer),
+                    OcelTypeAttribute::new("memory", OcelAttributeType::Integer),
+                ],
+                _ => Vec::new(),
+            };
+            object_types.push(OcelType {
+                name: name.to_string(),
+                attributes,
+            });
+        }
+    }
+    let mut event_types = vec![SUBMIT_ACTIVITY.to_string()];
+    if config.hosts && config.allocation_events {
+        event_types.push(ALLOCATE_ACTIVITY.to_string());
+    }
+    if config.array_jobs {
+        event_types.push(SUBMIT_ARRAY_ACTIVITY.to_string());
+    }
+    let node_events = config.hosts && config.node_events;
+    if node_events {
+        event_types.push(NODE_DOWN_ACTIVITY.to_string());
+        event_types.push(NODE_DRAINED_ACTIVITY.to_string());
+    }
+    for state_event in &config.state_events {
+        if !event_types.contains(&state_event.event_type) {
+            event_types.push(state_event.event_type.clone());
+        }
+    }
+    let mut event_types: Vec<_> = event_types
+        .into_iter()
+        .map(|name| {
+            let is_state_event = config.state_events.iter().any(|e| e.event_type == name);
+            let is_node_event = name == NODE_DOWN_ACTIVITY || name == NODE_DRAINED_ACTIVITY;
+            let attributes = if node_events && is_node_event {
+                vec![OcelTypeAttribute::new("reason", OcelAttributeType::String)]
+            } else if config.event_attributes && is_state_event {
+                vec![
+                    OcelTypeAttribute::new("reason", OcelAttributeType::String),
+                    OcelTypeAttribute::new("exec_host", OcelAttributeType::String),
+                    OcelTypeAttribute::new("elapsed", OcelAttributeType::Integer),
+                    OcelTypeAttribute::new("exit_code", OcelAttributeType::Integer),
+                ]
+            } else {
+                Vec::new()
+            };
+            OcelType { name, attributes }
+        })
+        .collect();
+    for hook in config.hooks.iter() {
+        merge_types(&mut event_types, hook.event_types());
+        merge_types(&mut object_types, hook.object_types());
+    }
+    (event_types, object_types)
+}
+
+#[derive(Debug, Default)]
+/// Objects related to converted jobs (e.g., accounts and hosts), collected over all jobs
+pub(super) struct RelatedObjects {
+    /// IDs and types of the related objects
+    objects: BTreeSet<(String, &'static str)>,
+    /// Accounts used by each user (by their IDs)
+    user_accounts: BTreeMap<String, BTreeSet<String>>,
+    /// Earliest submission time and IDs of the tasks of each array job (by its ID)
+    array_tasks: BTreeMap<String, (DateTime<Utc>, BTreeSet<String>)>,
+    /// IDs of all converted jobs (which are not included as related objects again)
+    job_ids: BTreeSet<String>,
+    /// Additional objects added by hooks (by their ID, see [`ExtractedJob::objects`])
+    custom: BTreeMap<String, OcelObject>,
+    /// Jobs running on each host (by its ID), with the start and end (if any) of the time they were running there
+    host_jobs: BTreeMap<String, Vec<(String, RunningInterval)>>,
+}
+
+impl RelatedObjects {
+    /// Add the objects related to `job` (see [`ExtractedJob::related`])
+    pub(super) fn add(
+        &mut self,
+        job: &JobRecording,
+        related: Vec<(String, &'static str)>,
+        objects: Vec<OcelObject>,
+        config: &ExtractionConfig,
+    ) {
+        self.objects.extend(related);
+        for object in objects {
+            match self.custom.get_mut(&object.id) {
+                Some(existing) => merge_object(existing, object),
+                None => {
+                    self.custom.insert(object.id.clone(), object);
+                }
+            }
+        }
+        self.job_ids.insert(job.job_id.clone());
+        if let (true, true, Some(user)) = (config.users, config.accounts, &job.initial.user) {
+            self.user_accounts
+                .entry(format!("user_{user}"))
+                .or_default()
+                .insert(format!("acc_{}", job.initial.account));
+        }
+        let step_job_id = &job.initial.step_job_id;
+        if config.array_jobs && step_job_id.is_array() {
+            let submit_time = job.initial.submit_time;
+            let (time, tasks) = self
+                .array_tasks
+                .entry(format!("array_{}", step_job_id.base_id))
+                .or_insert_with(|| (submit_time, BTreeSet::new()));
+            *time = submit_time.min(*time);
+            tasks.insert(job.job_id.clone());
+        }
+        if config.hosts && config.node_events {
+            for (host, interval) in running_intervals(job) {
+                self.host_jobs
+                    .entry(format!("host_{host}"))
+                    .or_default()
+                    .push((job.job_id.clone(), interval));
+            }
+        }
+    }
+
+    /// `Node Down` and `Node Drained` events of all recorded nodes (see [`ExtractionConfig::node_events`])
+    pub(super) fn node_events(
+        &self,
+        node_states: &[RecordedNodeStates],
+        config: &ExtractionConfig,
+    ) -> Vec<OcelEvent> {
+        if !config.hosts || !config.node_events {
+            return Vec::new();
+        }
+        let q = &config.qualifiers;
+        let mut events = Vec::new();
+        let mut last_states: BTreeMap<&str, &NodeState> = BTreeMap::new();
+        let mut event_counts: BTreeMap<String, usize> = BTreeMap::new();
+        for (time, nodes) in node_states {
+            for node in nodes {
+                let event_type = node_event_type(&node.state);
+                // Nodes are listed once per partition, so only changes of the state are considered
+                let Some(previous) = last_states.insert(&node.node_name, &node.state) else {
+                    continue;
+                };
+                let Some(event_type) = event_type.filter(|t| node_event_type(previous) != Some(t))
+                else {
+                    continue;
+                };
+                let host_id = format!("host_{}", node.node_name);
+                let mut relationships = vec![OcelRelationship::new(&host_id, &q.node_event_host)];
+                relationships.extend(
+                    self.host_jobs
+                        .get(&host_id)
+                        .into_iter()
+                        .flatten()
+                        .filter(|(_, (start, end))| {
+                            start <= time && end.is_none_or(|end| *time < end)
+                        })
+                        .map(|(job_id, _)| OcelRelationship::new(job_id, &q.node_event_job)),
+                );
+                let attributes = node
+                    .reason
+                    .iter()
+                    .filter(|r| !r.is_empty())
+                    .map(|r| OcelEventAttribute::new("reason", r.clone()))
+                    .collect();
+                let count = event_counts.entry(host_id.clone()).or_default();
+                events.push(OcelEvent {
+                    id: format!("{host_id}-{count}"),
+                    event_type: event_type.to_string(),
+                    time: config.localize(time),
+                    attributes,
+                    relationships,
+                });
+                *count += 1;
+            }
+        }
+        events
+    }
+
+    /// `Submit Array` events of all array jobs (see [`ExtractionConfig::array_jobs`])
+    pub(super) fn array_events(&self, config: &ExtractionConfig) -> Vec<OcelEvent> {
+        let q = &config.qualifiers;
+        self.array_tasks
+            .iter()
+            .map(|(id, (time, tasks))| {
+                let mut relationships = vec![OcelRelationship::new(id, &q.submit_array)];
+                relationships.extend(
+                    tasks
+                        .iter()
+                        .map(|t| OcelRelationship::new(t, &q.array_task)),
+                );
+                OcelEvent {
+                    id: format!("{id}-0"),
+                    event_type: SUBMIT_ARRAY_ACTIVITY.to_string(),
+                    time: config.localize(time),
+                    attributes: Vec::new(),
+                    relationships,
+                }
+            })
+            .collect()
+    }
+
+    /// Convert into OCEL objects (ordered by ID), also including all recorded hosts and their partitions, followed by the remaining objects added by hooks
+    pub(super) fn into_objects(
+        self,
+        node_states: &[RecordedNodeStates],
+        config: &ExtractionConfig,
+    ) -> Vec<OcelObject> {
+        let Self {
+            mut objects,
+            mut user_accounts,
+            array_tasks,
+            job_ids,
+            mut custom,
+            host_jobs: _,
+        } = self;
+        let mut hosts = if config.hosts {
+            hosts_to_ocel(node_states, config)
+        } else {
+            BTreeMap::new()
+        };
+        let mut array_jobs: BTreeMap<_, _> = array_tasks
+            .into_iter()
+            .map(|(id, (_, tasks))| {
+                let mut object = OcelObject::new(&id, "ArrayJob");
+                object.relationships.extend(
+                    tasks
+                        .into_iter()
+                        .map(|t| OcelRelationship::new(t, &config.qualifiers.array_task)),
+                );
+                objects.insert((id.clone(), "ArrayJob"));
+                (id, object)
+            })
+            .collect();
+        for host in hosts.values() {
+            objects.insert((host.id.clone(), "Host"));
+            for relationship in &host.relationships {
+                objects.insert((relationship.object_id.clone(), "Partition"));
+            }
+        }
+        let mut ocel_objects: Vec<_> = objects
+            .into_iter()
+            .filter(|(id, object_type)| *object_type != "Job" || !job_ids.contains(id))
+            .map(|(id, object_type)| {
+                let mut object = hosts
+                    .remove(&id)
+                    .or_else(|| array_jobs.remove(&id))
+                    .unwrap_or_else(|| OcelObject::new(&id, object_type));
+                if let Some(accounts) = user_accounts.remove(&id) {
+                    object.relationships.extend(
+                        accounts
+                            .into_iter()
+                            .map(|a| OcelRelationship::new(a, &config.qualifiers.user_account)),
+                    );
+                }
+                if let Some(custom_object) = custom.remove(&id) {
+                    merge_object(&mut object, custom_object);
+                }
+                object
+            })
+            .collect();
+        ocel_objects.extend(custom.into_values());
+        ocel_objects
+    }
+}
+
+/// Event type emitted when a node changes into `state` (see [`ExtractionConfig::node_events`])
+fn node_event_type(state: &NodeState) -> Option<&'static str> {
+    match state {
+        NodeState::DOWN | NodeState::FAIL => Some(NODE_DOWN_ACTIVITY),
+        NodeState::DRAINED | NodeState::DRAINING => Some(NODE_DRAINED_ACTIVITY),
+        _ => None,
+    }
+}
+
+/// Start and end (if any) of the time a job was running on a host
+type RunningInterval = (DateTime<Utc>, Option<DateTime<Utc>>);
+
+/// Executing hosts of a recorded job while it was running, with the time it was running there
+///
+/// The first interval after a (re)start begins at the start time reported by SLURM.
+fn running_intervals(job: &JobRecording) -> Vec<(String, RunningInterval)> {
+    let mut intervals = Vec::new();
+    let mut current: Option<(String, DateTime<Utc>)> = None;
+    let mut last_end: Option<DateTime<Utc>> = None;
+    let mut row = job.initial.clone();
+    let changes = std::iter::once((job.initial_time, Vec::new())).chain(job.deltas.iter().cloned());
+    for (time, delta) in changes {
+        row.apply_mut(delta);
+        let host = match row.state {
+            JobState::RUNNING | JobState::COMPLETING => row.exec_host.as_ref(),
+            _ => None,
+        };
+        if current.as_ref().map(|(h, _)| h) == host {
+            continue;
+        }
+        if let Some((h, start)) = current.take() {
+            intervals.push((h, (start, Some(time))));
+            last_end = Some(time);
+        }
+        if let Some(host) = host {
+            let start = match row.start_time {
+                Some(start) if start <= time && last_end.is_none_or(|end| start > end) => start,
+                _ => time,
+            };
+            current = Some((host.clone(), start));
+        }
+    }
+    if let Some((host, start)) = current {
+        intervals.push((host, (start, None)));
+    }
+    intervals
+}
+
+/// Convert recorded node states into `Host` objects (by ID)
+///
+/// Nodes are listed once per partition, so the partitions of a node are collected over all its recorded rows.
+fn hosts_to_ocel(
+    node_states: &[RecordedNodeStates],
+    config: &ExtractionConfig,
+) -> BTreeMap<String, OcelObject> {
+    let mut hosts: BTreeMap<String, (OcelObject, BTreeSet<String>, DateTime<Utc>)> =
+        BTreeMap::new();
+    for (time, nodes) in node_states {
+        for node in nodes {
+            let id = format!("host_{}", node.node_name);
+            let (object, partitions, first_seen) = hosts
+                .entry(id.clone())
+                .or_insert_with(|| (OcelObject::new(id, "Host"), BTreeSet::new(), *time));
+            // Values of the first recording are valid from the start
+            let time = if first_seen == time {
+                config.localize(&DateTime::UNIX_EPOCH)
+            } else {
+                config.localize(time)
+            };
+            let mut update = |name: &str, value: OcelAttributeValue| {
+                let current = object.attributes.iter().rev().find(|a| a.name == name);
+                if current.is_none_or(|a| a.value != value) {
+                    object
+                        .attributes
+                        .push(OcelObjectAttribute::new(name, value, time));
+                }
+            };
+            if partitions.insert(node.partition.clone()) {
+                let partitions: Vec<_> = partitions.iter().cloned().collect();
+                update("partitions", partitions.join(",").into());
+            }
+            update("cpus", (node.cpus as i64).into());
+            update("memory", (node.memory as i64).into());
+        }
+    }
+    hosts
+        .into_iter()
+        .map(|(id, (mut object, partitions, _))| {
+            if config.partitions {
+                object.relationships.extend(partitions.into_iter().map(|p| {
+                    OcelRelationship::new(format!("part_{p}"), &config.qualifiers.host_partition)
+                }));
+            }
+            (id, object)
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+/// A recorded job converted into its object and events
+pub(super) struct ExtractedJob {
+    pub(super) object: OcelObject,
+    pub(super) events: Vec<OcelEvent>,
+    /// IDs and types of related objects
+    pub(super) related: Vec<(String, &'static str)>,
+    /// Additional objects added by hooks (see [`JobExtraction::objects`])
+    pub(super) objects: Vec<OcelObject>,
+}
+
+/// Convert a recorded job into its object, events, and the IDs and types of related objects
+pub(super) fn job_to_ocel(job: &JobRecording, config: &ExtractionConfig) -> ExtractedJob {
+    let q = &config.qualifiers;
+    let initial = &job.initial;
+    let mut extraction = JobExtraction {
+        object: OcelObject::new(&job.job_id, "Job"),
+        events: Vec::new(),
+        objects: Vec::new(),
+    };
+    let mut related = Vec::new();
+
+    // Initial attribute values (valid from the start, except for the state)
+    for attribute in &config.job_attributes {
+        let time = match attribute {
+            JobAttribute::State => config.localize(&job.initial_time),
+            _ => config.localize(&DateTime::UNIX_EPOCH),
+        };
+        for (name, value) in attribute.values(initial) {
+            if let Some(value) = value {
+                extraction
+                    .object
+                    .attributes
+                    .push(OcelObjectAttribute::new(name, value, time));
+            }
+        }
+    }
+    if config.metrics {
+        let metrics = job_metrics(job);
+        let time = config.localize(&DateTime::UNIX_EPOCH);
+        let secs = |d: std::time::Duration| d.as_secs() as i64;
+        for (name, value) in [
+            ("queue_wait", metrics.queue_wait.map(secs)),
+            ("runtime", metrics.runtime.map(secs)),
+            ("requeues", Some(metrics.requeues as i64)),
+        ] {
+            if let Some(value) = value {
+                extraction
+                    .object
+                    .attributes
+                    .push(OcelObjectAttribute::new(name, value, time));
+            }
+        }
+    }
+
+    let account_id = format!("acc_{}", initial.account);
+    let user_id = initial.user.as_ref().map(|user| format!("user_{user}"));
+    let group_id = format!("group_{}", initial.group);
+    let mut relate = |object: &mut OcelObject,
+                      enabled: bool,
+                      id: &str,
+                      object_type: &'static str,
+                      qualifier: &str| {
+        if enabled {
+            object
+                .relationships
+                .push(OcelRelationship::new(id, qualifier));
+            related.push((id.to_string(), object_type));
+        }
+    };
+    relate(
+        &mut extraction.object,
+        config.accounts,
+        &account_id,
+        "Account",
+        &q.job_account,
+    );
+    if let Some(user_id) = &user_id {
+        relate(
+            &mut extraction.object,
+            config.users,
+            user_id,
+            "User",
+            &q.job_user,
+        );
+    }
+    relate(
+        &mut extraction.object,
+        config.groups,
+        &group_id,
+        "Group",
+        &q.job_group,
+    );
+    relate(
+        &mut extraction.object,
+        config.partitions,
+        &format!("part_{}", initial.partition),
+        "Partition",
+        &q.job_partition,
+    );
+    for hook in config.hooks.iter() {
+        hook.on_initial_row(initial, config.localize(&job.initial_time), &mut extraction);
+    }
+
+    // Attribute changes, executing hosts and dependencies over time
+    let mut row = initial.clone();
+    let mut dependencies = initial.dependency_targets();
+    let mut hosts: Vec<String> = initial.exec_host.iter().cloned().collect();
+    // Allocations to a new host, with the time of the allocation
+    let mut allocations: Vec<(DateTime<Utc>, String)> = initial
+        .exec_host
+        .iter()
+        .map(|host| (initial.start_time.unwrap_or(job.initial_time), host.clone()))
+        .collect();
+    for (time, delta) in &job.deltas {
+        let exec_host = row.exec_host.clone();
+        let before: Vec<_> = config
+            .job_attributes
+            .iter()
+            .map(|a| a.values(&row))
+            .collect();
+        let previous = (!config.hooks.is_empty()).then(|| row.clone());
+        row.apply_mut(delta.clone());
+        if let Some(previous) = previous {
+            for hook in config.hooks.iter() {
+                hook.on_delta(
+                    &previous,
+                    &row,
+                    delta,
+                    config.localize(time),
+                    &mut extraction,
+                );
+            }
+        }
+        for dependency in row.dependency_targets() {
+            if !dependencies.contains(&dependency) {
+                dependencies.push(dependency);
+            }
+        }
+        for (attribute, before) in config.job_attributes.iter().zip(before) {
+            for ((name, old), (_, new)) in before.into_iter().zip(attribute.values(&row)) {
+                if let (true, Some(new)) = (old != new, new) {
+                    extraction.object.attributes.push(OcelObjectAttribute::new(
+                        name,
+                        new,
+                        config.localize(time),
+                    ));
+                }
+            }
+        }
+        if let Some(host) = &row.exec_host {
+            if !hosts.contains(host) {
+                hosts.push(host.clone());
+            }
+            if exec_host.as_ref() != Some(host) {
+                // The first allocation after a (re)start happened at the start of the job
+                let time = match (row.start_time, allocations.last()) {
+                    (Some(start), last)
+                        if start <= *time && last.is_none_or(|(t, _)| start > *t) =>
+                    {
+                        start
+                    }
+                    _ => *time,
+                };
+                allocations.push((time, host.clone()));
+            }
+        }
+    }
+    for host in &hosts {
+        relate(
+            &mut extraction.object,
+            config.hosts,
+            &format!("host_{host}"),
+            "Host",
+            &q.job_host,
+        );
+    }
+    for (dependency_type, target) in &dependencies {
+        match target.split_once('_') {
+            Some((array_id, _)) => relate(
+                &mut extraction.object,
+                config.dependencies && config.array_jobs,
+                &format!("array_{array_id}"),
+                "ArrayJob",
+                dependency_type,
+            ),
+            None => relate(
+                &mut extraction.object,
+                config.dependencies,
+                target,
+                "Job",
+                dependency_type,
+            ),
+        }
+    }
+
+    let mut events: Vec<OcelEvent> = job_events_with(job, |state| config.state_event_type(state))
+        .into_iter()
+        .map(|e| {
+            let mut attributes = Vec::new();
+            if config.event_attributes && e.kind != JobEventKind::Submission {
+                // SLURM reports `None` if there is no reason
+                if !e.reason.is_empty() && e.reason != "None" {
+                    attributes.push(OcelEventAttribute::new("reason", e.reason.clone()));
+                }
+                if let Some(exec_host) = &e.exec_host {
+                    attributes.push(OcelEventAttribute::new("exec_host", exec_host.clone()));
+                }
+                if let Some(elapsed) = e.elapsed {
+                    attributes.push(OcelEventAttribute::new("elapsed", elapsed.as_secs() as i64));
+                }
+                if let Some(exit_code) = e.exit_code {
+                    attributes.push(OcelEventAttribute::new("exit_code", exit_code as i64));
+                }
+            }
+            let mut relationships = vec![OcelRelationship::new(&job.job_id, &q.event_job)];
+            match e.kind {
+                JobEventKind::Submission => {
+                    if config.accounts {
+                        relationships.push(OcelRelationship::new(&account_id, &q.submit_account));
+                    }
+                    if let (true, Some(user_id)) = (config.users, &user_id) {
+                        relationships.push(OcelRelationship::new(user_id, &q.submit_user));
+                    }
+                }
+                JobEventKind::Start => {
+                    if config.groups {
+                        relationships.push(OcelRelationship::new(&group_id, &q.start_group));
+                    }
+                    if let (true, Some(host)) = (config.hosts, &e.exec_host) {
+                        relationships
+                            .push(OcelRelationship::new(format!("host_{host}"), &q.start_host));
+                    }
+                }
+                _ => {}
+            }
+            OcelEvent {
+                id: String::new(),
+                event_type: e.activity,
+                time: config.localize(&e.time),
+                attributes,
+                relationships,
+            }
+        })
+        .collect();
+    if config.hosts && config.allocation_events {
+        events.extend(allocations.into_iter().map(|(time, host)| OcelEvent {
+            id: String::new(),
+            event_type: ALLOCATE_ACTIVITY.to_string(),
+            time: config.localize(&time),
+            attributes: Vec::new(),
+            relationships: vec![
+                OcelRelationship::new(&job.job_id, &q.event_job),
+                OcelRelationship::new(format!("host_{host}"), &q.allocated_host),
+            ],
+        }));
+        events.sort_by_key(|e| e.time);
+    }
+    if !config.hooks.is_empty() {
+        // Events added by hooks so far follow the built-in events at the same time
+        events.append(&mut extraction.events);
+        extraction.events = events;
+        for hook in config.hooks.iter() {
+            hook.finalize_job(job, &mut extraction);
+        }
+        extraction.events.sort_by_key(|e| e.time);
+    } else {
+        extraction.events = events;
+    }
+    for (i, event) in extraction.events.iter_mut().enumerate() {
+        event.id = format!("{}-{i}", job.job_id);
+    }
+    ExtractedJob {
+        object: extraction.object,
+        events: extraction.events,
+        related,
+        objects: extraction.objects,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, FixedOffset};
+    use structdiff::StructDiff;
+
+    use super::{
+        extract_ocel_from_jobs, extract_ocel_from_jobs_and_nodes,
+        extract_ocel_from_jobs_with_progress, CancellationToken, ExtractionConfig, JobAttribute,
+    };
+    use crate::{
+        data_extraction::sinfo::{NodeState, SinfoNodeRow},
+        event_data_extraction::{ocel::OcelAttributeValue, tests::test_recording},
+    };
+
+    #[test]
+    fn test_extract_ocel() {
+        let job = test_recording();
+        let ocel = extract_ocel_from_jobs(vec![job.clone()], &ExtractionConfig::default());
+        assert_eq!(ocel.events.len(), 4);
+        assert_eq!(ocel.events[1].event_type, "Job Started");
+        assert_eq!(ocel.events[2].event_type, "Job Allocated");
+        assert_eq!(ocel.events[2].time, ocel.events[1].time);
+        assert!(ocel.events[1]
+            .relationships
+            .iter()
+            .any(|r| r.object_id == "host_n23m0001" && r.qualifier == "host"));
+        assert!(ocel.events[0].attributes.is_empty());
+        let completed: Vec<_> = ocel.events[3]
+            .attributes
+            .iter()
+            .map(|a| (a.name.as_str(), a.value.clone()))
+            .collect();
+        assert_eq!(
+            completed,
+            [
+                ("exec_host", "n23m0001".into()),
+                ("elapsed", 70i64.into()),
+                ("exit_code", 0i64.into())
+            ]
+        );
+        let completed_type = ocel
+            .event_types
+            .iter()
+            .find(|t| t.name == "Job Completed")
+            .unwrap();
+        assert_eq!(completed_type.attributes.len(), 4);
+        let object_ids: Vec<_> = ocel.objects.iter().map(|o| o.id.as_str()).collect();
+        assert_eq!(
+            object_ids,
+            [
+                "49848561",
+                "acc_default",
+                "group_ab123456",
+                "host_n23m0001",
+                "part_c23ms",
+                "user_ab123456"
+            ]
+        );
+        let states: Vec<_> = ocel.objects[0]
+            .attributes
+            .iter()
+            .filter(|a| a.name == "state")
+            .map(|a| a.value.clone())
+            .collect();
+        assert_eq!(
+            states,
+            ["PENDING", "RUNNING", "COMPLETED"].map(OcelAttributeValue::from)
+        );
+        let metrics: Vec<_> = ocel.objects[0]
+            .attributes
+            .iter()
+            .filter(|a| ["queue_wait", "runtime", "requeues"].contains(&a.name.as_str()))
+            .map(|a| a.value.clone())
+            .collect();
+        assert_eq!(metrics, [20i64, 70, 0].map(OcelAttributeValue::from));
+
+        // Only jobs, accounts, users and completions
+        let config = ExtractionConfig {
+            groups: false,
+            hosts: false,
+            partitions: false,
+            array_jobs: false,
+            metrics: false,
+            job_attributes: vec![JobAttribute::ExitCode],
+            state_events: ExtractionConfig::default()
+                .state_events
+                .into_iter()
+                .filter(|e| e.event_type == "Job Completed")
+                .collect(),
+            ..Default::default()
+        };
+        let ocel = extract_ocel_from_jobs(vec![job.clone()], &config);
+        let event_types: Vec<_> = ocel.events.iter().map(|e| e.event_type.as_str()).collect();
+        assert_eq!(event_types, ["Submit Job", "Job Completed"]);
+        assert_eq!(ocel.objects.len(), 3);
+        assert_eq!(ocel.objects[2].id, "user_ab123456");
+        assert_eq!(ocel.objects[2].relationships[0].object_id, "acc_default");
+        assert_eq!(ocel.objects[2].relationships[0].qualifier, "member of");
+        assert_eq!(ocel.objects[0].attributes.len(), 1);
+        assert_eq!(ocel.object_types.len(), 3);
+        let json = serde_json::to_value(&ocel).unwrap();
+        assert_eq!(json["objects"][0]["attributes"][0]["name"], "exit_code");
+        assert_eq!(
+            json["events"][0]["relationships"][2]["objectId"],
+            "user_ab123456"
+        );
+
+        // Timestamps in the cluster timezone
+        let config: ExtractionConfig = serde_json::from_str(r#"{"timezone": "+02:00"}"#).unwrap();
+        assert_eq!(
+            config.timezone,
+            Some(FixedOffset::east_opt(2 * 3600).unwrap())
+        );
+        let ocel = extract_ocel_from_jobs(vec![job], &config);
+        assert_eq!(
+            ocel.events[0].time.to_rfc3339(),
+            "2025-01-14T11:23:45+02:00"
+        );
+        assert_eq!(serde_json::to_value(&config).unwrap()["timezone"], "+02:00");
+    }
+
+    #[test]
+    fn test_extract_host_objects() {
+        let mut job = test_recording();
+        // The job continues running on another host
+        let (time, _) = job.deltas[1];
+        let mut moved = job.latest_row();
+        let mut running = moved.clone();
+        running.state = crate::JobState::RUNNING;
+        running.exit_code = None;
+        moved.exec_host = Some("n23m0002".to_string());
+        job.deltas[1] = (time, running.diff(&moved));
+
+        let node = |name: &str, partition: &str, memory| SinfoNodeRow {
+            node_name: name.to_string(),
+            partition: partition.to_string(),
+            state: NodeState::IDLE,
+            cpus: 96,
+            cpus_allocated: 0,
+            cpus_idle: 96,
+            memory,
+            free_memory: None,
+            cpu_load: None,
+            reason: None,
+        };
+        let node_states = vec![
+            (
+                job.initial_time,
+                vec![
+                    node("n23m0001", "c23ms", 256000),
+                    node("n23m0001", "c23test", 256000),
+                ],
+            ),
+            (
+                job.initial_time + Duration::seconds(60),
+                vec![node("n23m0001", "c23ms", 512000)],
+            ),
+        ];
+        let ocel =
+            extract_ocel_from_jobs_and_nodes(vec![job], &node_states, &ExtractionConfig::default());
+        let allocations: Vec<_> = ocel
+            .events
+            .iter()
+            .filter(|e| e.event_type == "Job Allocated")
+            .map(|e| (e.time.to_utc(), e.relationships[1].object_id.as_str()))
+            .collect();
+        assert_eq!(
+            allocations,
+            [
+                (node_states[0].0 + Duration::seconds(20), "host_n23m0001"),
+                (time, "host_n23m0002")
+            ]
+        );
+        assert!(ocel
+            .events
+            .iter()
+            .all(|e| e.event_type != "Job Allocated"
+                || e.relationships[1].qualifier == "allocated on"));
+
+        let host = ocel
+            .objects
+            .iter()
+            .find(|o| o.id == "host_n23m0001")
+            .unwrap();
+        let attributes: Vec<_> = host
+            .attributes
+            .iter()
+            .map(|a| (a.name.as_str(), a.value.clone()))
+            .collect();
+        assert_eq!(
+            attributes,
+            [
+                ("partitions", "c23ms".into()),
+                ("cpus", 96i64.into()),
+                ("memory", 256000i64.into()),
+                ("partitions", "c23ms,c23test".into()),
+                ("memory", 512000i64.into()),
+            ]
+        );
+        assert_eq!(host.attributes[3].time.timestamp(), 0);
+        assert_eq!(host.relationships.len(), 2);
+        assert_eq!(host.relationships[1].object_id, "part_c23test");
+        assert_eq!(host.relationships[1].qualifier, "member of");
+        assert!(ocel.objects.iter().any(|o| o.id == "part_c23test"));
+        assert!(ocel.objects.iter().any(|o| o.id == "host_n23m0002"));
+    }
+
+    #[test]
+    fn test_extraction_progress() {
+        let jobs: Vec<_> = (0..3)
+            .map(|i| {
+                let mut job = test_recording();
+                job.job_id = format!("4984856{i}");
+                job
+            })
+            .collect();
+        let progress = std::sync::Mutex::new(Vec::new());
+        let (ocel, report) = extract_ocel_from_jobs_with_progress(
+            jobs.clone(),
+            &[],
+            &ExtractionConfig::default(),
+            |p| progress.lock().unwrap().push(p.jobs_processed),
+            &CancellationToken::new(),
+        )
+        .unwrap();
+        assert_eq!(ocel.events.len(), 12);
+        assert_eq!(report.jobs_processed, 3);
+        assert_eq!(report.events_per_type["Job Started"], 3);
+        assert_eq!(report.first_event, Some(ocel.events[0].time));
+        assert!(report.timestamp_anomalies.is_empty());
+        let mut progress = progress.into_inner().unwrap();
+        progress.sort();
+        assert_eq!(progress, [1, 2, 3]);
+
+        let cancel = CancellationToken::new();
+        cancel.clone().cancel();
+        let res = extract_ocel_from_jobs_with_progress(
+            jobs,
+            &[],
+            &ExtractionConfig::default(),
+            |_| panic!("No job should be processed"),
+            &cancel,
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn test_extract_ocel_from_store() {
+        use super::extract_ocel_from_store;
+        use crate::recording::{JsonDirStore, JsonlStore, RecordingStore};
+
+        let job = test_recording();
+        let node = SinfoNodeRow {
+            node_name: "n23m0001".to_string(),
+            partition: "c23ms".to_string(),
+            state: NodeState::MIXED,
+            cpus: 96,
+            cpus_allocated: 48,
+            cpus_idle: 48,
+            memory: 256000,
+            free_memory: None,
+            cpu_load: None,
+            reason: None,
+        };
+        let record = |store: &dyn RecordingStore| {
+            store
+                .record_new_job(&job.initial_time, &job.initial)
+                .unwrap();
+            for (time, delta) in &job.deltas {
+                store.record_job_delta(time, &job.job_id, delta).unwrap();
+            }
+            store
+                .record_node_states(&job.initial_time, std::slice::from_ref(&node))
+                .unwrap();
+        };
+        let dir = std::env::temp_dir().join(format!("slurry-extract-{}", std::process::id()));
+        let jsonl_store = JsonlStore::open(dir.join("recording.jsonl")).unwrap();
+        let dir_store = JsonDirStore::new(dir.join("json"));
+        for store in [&jsonl_store as &dyn RecordingStore, &dir_store] {
+            record(store);
+            let ocel = extract_ocel_from_store(store, &ExtractionConfig::default()).unwrap();
+            assert_eq!(ocel.events.len(), 4);
+            let host = ocel
+                .objects
+                .iter()
+                .find(|o| o.id == "host_n23m0001")
+                .unwrap();
+            assert_eq!(host.attributes.len(), 3);
+        }
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_extract_array_jobs() {
+        let jobs: Vec<_> = (1..=2)
+            .map(|task| {
+                let mut job = test_recording();
+                job.job_id = format!("4986943{task}");
+                job.initial.step_job_id = format!("49869430_{task}").parse().unwrap();
+                job.initial.submit_time += Duration::seconds(task);
+                job
+            })
+            .collect();
+        let ocel = extract_ocel_from_jobs(jobs.clone(), &ExtractionConfig::default());
+        let array = ocel
+            .objects
+            .iter()
+            .find(|o| o.id == "array_49869430")
+            .unwrap();
+        assert_eq!(array.object_type, "ArrayJob");
+        let tasks: Vec<_> = array
+            .relationships
+            .iter()
+            .map(|r| r.object_id.as_str())
+            .collect();
+        assert_eq!(tasks, ["49869431", "49869432"]);
+        let submit: Vec<_> = ocel
+            .events
+            .iter()
+            .filter(|e| e.event_type == "Submit Array")
+            .collect();
+        assert_eq!(submit.len(), 1);
+        assert_eq!(submit[0].time, jobs[0].initial.submit_time);
+        assert_eq!(submit[0].relationships.len(), 3);
+        assert_eq!(submit[0].relationships[0].qualifier, "array");
+    }
+
+    #[test]
+    fn test_extraction_filter() {
+        use super::ExtractionFilter;
+
+        let job = test_recording();
+        let mut pending = test_recording();
+        pending.job_id = "49848562".to_string();
+        pending.deltas.clear();
+        let mut other_partition = test_recording();
+        other_partition.job_id = "49848563".to_string();
+        other_partition.initial.partition = "c23g".to_string();
+        other_partition.initial.submit_time += Duration::days(1);
+        let jobs = vec![job.clone(), pending, other_partition];
+
+        let extracted_ids = |filter: ExtractionFilter| {
+            let config = ExtractionConfig {
+                filter,
+                ..Default::default()
+            };
+            let ocel = extract_ocel_from_jobs(jobs.clone(), &config);
+            ocel.objects
+                .into_iter()
+                .filter(|o| o.object_type == "Job")
+                .map(|o| o.id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(extracted_ids(ExtractionFilter::default()).len(), 3);
+        let filter = ExtractionFilter {
+            exclude_pending: true,
+            ..Default::default()
+        };
+        assert_eq!(extracted_ids(filter), ["49848561", "49848563"]);
+        let filter = ExtractionFilter {
+            partitions: vec!["c23ms".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(extracted_ids(filter), ["49848561", "49848562"]);
+        let filter = ExtractionFilter {
+            submitted_after: Some(job.initial.submit_time + Duration::hours(1)),
+            accounts: vec!["default".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(extracted_ids(filter), ["49848563"]);
+    }
+
+    #[test]
+    fn test_extract_merged_recordings() {
+        use super::extract_ocel_from_merged_slurm_diffs;
+        use crate::recording::{JsonDirStore, JsonlStore, RecordingStore};
+
+        let job = test_recording();
+        let dir = std::env::temp_dir().join(format!("slurry-merge-{}", std::process::id()));
+        let first = JsonDirStore::new(dir.join("first"));
+        let second = JsonlStore::open(dir.join("second").join("recording.jsonl")).unwrap();
+        for store in [&first as &dyn RecordingStore, &second] {
+            store
+                .record_new_job(&job.initial_time, &job.initial)
+                .unwrap();
+            for (time, delta) in &job.deltas {
+                store.record_job_delta(time, &job.job_id, delta).unwrap();
+            }
+            store.flush().unwrap();
+        }
+        let ocel = extract_ocel_from_merged_slurm_diffs(
+            &[dir.join("first"), dir.join("second")],
+            &ExtractionConfig::default(),
+        )
+        .unwrap();
+        assert_eq!(
+            ocel,
+            extract_ocel_from_jobs(vec![job], &ExtractionConfig::default())
+        );
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_extract_dependencies() {
+        let first = test_recording();
+        let mut second = test_recording();
+        second.job_id = "49848562".to_string();
+        second.initial.job_id = second.job_id.clone();
+        second.initial.dependency = Some("afterok:49848561(unfulfilled)".to_string());
+        // The dependency on the array job is only added later on
+        let mut updated = second.initial.clone();
+        updated.dependency = Some("afterany:49848560_*".to_string());
+        second.deltas[0].1 = second.initial.diff(&updated);
+        let ocel = extract_ocel_from_jobs(vec![first, second], &ExtractionConfig::default());
+        let dependent = ocel.objects.iter().find(|o| o.id == "49848562").unwrap();
+        let dependencies: Vec<_> = dependent
+            .relationships
+            .iter()
+            .filter(|r| r.qualifier.starts_with("after"))
+            .map(|r| (r.object_id.as_str(), r.qualifier.as_str()))
+            .collect();
+        assert_eq!(
+            dependencies,
+            [("49848561", "afterok"), ("array_49848560", "afterany")]
+        );
+        // Recorded jobs are not duplicated, but referenced array jobs are included
+        assert_eq!(
+            ocel.objects.iter().filter(|o| o.id == "49848561").count(),
+            1
+        );
+        let array = ocel
+            .objects
+            .iter()
+            .find(|o| o.id == "array_49848560")
+            .unwrap();
+        assert_eq!(array.object_type, "ArrayJob");
+    }
+
+    #[test]
+    fn test_node_events() {
+        let job = test_recording();
+        let node = |name: &str, partition: &str, state: NodeState| SinfoNodeRow {
+            node_name: name.to_string(),
+            partition: partition.to_string(),
+            state,
+            cpus: 96,
+            cpus_allocated: 0,
+            cpus_idle: 96,
+            memory: 256000,
+            free_memory: None,
+            cpu_load: None,
+            reason: Some("maintenance".to_string()),
+        };
+        let at = |secs| job.initial_time + Duration::seconds(secs);
+        let node_states = vec![
+            (
+                at(0),
+                vec![
+                    node("n23m0001", "c23ms", NodeState::IDLE),
+                    node("n23m0001", "c23test", NodeState::IDLE),
+                    // Already down when first recorded
+                    node("n23m0002", "c23ms", NodeState::DOWN),
+                ],
+            ),
+            (
+                at(40),
+                vec![
+                    node("n23m0001", "c23ms", NodeState::DRAINING),
+                    node("n23m0001", "c23test", NodeState::DRAINING),
+                ],
+            ),
+            (at(60), vec![node("n23m0001", "c23ms", NodeState::DRAINED)]),
+            (at(100), vec![node("n23m0001", "c23ms", NodeState::DOWN)]),
+        ];
+        let ocel =
+            extract_ocel_from_jobs_and_nodes(vec![job], &node_states, &ExtractionConfig::default());
+        let node_events: Vec<_> = ocel
+            .events
+            .iter()
+            .filter(|e| e.id.starts_with("host_"))
+            .collect();
+        assert_eq!(node_events.len(), 2);
+        assert_eq!(node_events[0].event_type, "Node Drained");
+        assert_eq!(node_events[0].time.to_utc(), node_states[1].0);
+        assert_eq!(node_events[0].attributes[0].value, "maintenance".into());
+        let related: Vec<_> = node_events[0]
+            .relationships
+            .iter()
+            .map(|r| (r.object_id.as_str(), r.qualifier.as_str()))
+            .collect();
+        assert_eq!(
+            related,
+            [("host_n23m0001", "host"), ("49848561", "affected job")]
+        );
+        // The job completed before the node went down
+        assert_eq!(node_events[1].event_type, "Node Down");
+        assert_eq!(node_events[1].id, "host_n23m0001-1");
+        assert_eq!(node_events[1].relationships.len(), 1);
+    }
+}
@@ -0,0 +1,155 @@
+use std::{
+    collections::HashSet,
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+
+use super::{ocel::Ocel, xes::escape_xml};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// File format of exported object graphs (see [`write_object_graph`])
+pub enum GraphFormat {
+    #[default]
+    /// Graphviz DOT
+    Dot,
+    /// `GraphML` (e.g., for Gephi)
+    GraphMl,
+}
+
+impl GraphFormat {
+    /// Usual file extension of the format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            GraphFormat::Dot => "dot",
+            GraphFormat::GraphMl => "graphml",
+        }
+    }
+}
+
+/// Export the object graph of an OCEL to the file at `path` (see [`write_object_graph`])
+pub fn export_object_graph_path(
+    ocel: &Ocel,
+    format: GraphFormat,
+    path: impl AsRef<Path>,
+) -> Result<(), Error> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_object_graph(ocel, format, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Write the object graph of an OCEL (e.g., jobs, accounts, hosts and partitions), for quick visualizations
+///
+/// Each object becomes a node, labeled with its ID and annotated with its type.
+/// Each object-to-object relationship becomes a directed edge, labeled with its qualifier.
+/// Relationships to objects not included in the OCEL are skipped. Events are not included.
+pub fn write_object_graph(
+    ocel: &Ocel,
+    format: GraphFormat,
+    w: &mut impl Write,
+) -> Result<(), Error> {
+    let object_ids: HashSet<&str> = ocel.objects.iter().map(|o| o.id.as_str()).collect();
+    let edges = ocel.objects.iter().flat_map(|o| {
+        o.relationships
+            .iter()
+            .filter(|r| object_ids.contains(r.object_id.as_str()))
+            .map(move |r| (o.id.as_str(), r.object_id.as_str(), r.qualifier.as_str()))
+    });
+    match format {
+        GraphFormat::Dot => {
+            writeln!(w, "digraph objects {{")?;
+            for object in &ocel.objects {
+                writeln!(
+                    w,
+                    "  {} [label={}, type={}];",
+                    quote_dot(&object.id),
+                    quote_dot(&object.id),
+                    quote_dot(&object.object_type)
+                )?;
+            }
+            for (source, target, qualifier) in edges {
+                writeln!(
+                    w,
+                    "  {} -> {} [label={}];",
+                    quote_dot(source),
+                    quote_dot(target),
+                    quote_dot(qualifier)
+                )?;
+            }
+            writeln!(w, "}}")?;
+        }
+        GraphFormat::GraphMl => {
+            writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+            writeln!(
+                w,
+                r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#
+            )?;
+            writeln!(
+                w,
+                r#"  <key id="type" for="node" attr.name="type" attr.type="string"/>"#
+            )?;
+            writeln!(
+                w,
+                r#"  <key id="qualifier" for="edge" attr.name="qualifier" attr.type="string"/>"#
+            )?;
+            writeln!(w, r#"  <graph id="objects" edgedefault="directed">"#)?;
+            for object in &ocel.objects {
+                writeln!(
+                    w,
+                    r#"    <node id="{}"><data key="type">{}</data></node>"#,
+                    escape_xml(&object.id),
+                    escape_xml(&object.object_type)
+                )?;
+            }
+            for (source, target, qualifier) in edges {
+                writeln!(
+                    w,
+                    r#"    <edge source="{}" target="{}"><data key="qualifier">{}</data></edge>"#,
+                    escape_xml(source),
+                    escape_xml(target),
+                    escape_xml(qualifier)
+                )?;
+            }
+            writeln!(w, "  </graph>")?;
+            writeln!(w, "</graphml>")?;
+        }
+    }
+    Ok(())
+}
+
+/// Quote a string as DOT identifier
+fn quote_dot(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write_object_graph, GraphFormat};
+    use crate::event_data_extraction::{
+        extract_ocel_from_jobs, tests::test_recording, ExtractionConfig,
+    };
+
+    #[test]
+    fn test_object_graph() {
+        let ocel = extract_ocel_from_jobs(vec![test_recording()], &ExtractionConfig::default());
+        let mut dot = Vec::new();
+        write_object_graph(&ocel, GraphFormat::Dot, &mut dot).unwrap();
+        let dot = String::from_utf8(dot).unwrap();
+        assert!(dot.starts_with("digraph objects {"));
+        assert!(dot.contains(r#"  "49848561" -> "part_c23ms" [label="submitted on"];"#));
+        assert!(dot.contains(r#"  "host_n23m0001" [label="host_n23m0001", type="Host"];"#));
+
+        let mut graphml = Vec::new();
+        write_object_graph(&ocel, GraphFormat::GraphMl, &mut graphml).unwrap();
+        let graphml = String::from_utf8(graphml).unwrap();
+        assert_eq!(graphml.matches("<node ").count(), ocel.objects.len());
+        assert!(graphml.contains(
+            r#"<edge source="49848561" target="acc_default"><data key="qualifier">submitted by</data></edge>"#
+        ));
+    }
+}
@@ -0,0 +1,257 @@
+use std::{fmt::Debug, sync::Arc};
+
+use chrono::{DateTime, FixedOffset};
+
+use super::ocel::{OcelEvent, OcelObject, OcelType};
+use crate::{
+    data_extraction::squeue::SqueueRow,
+    recording::{JobRecording, SqueueRowDiff},
+};
+
+#[derive(Debug, Clone)]
+/// Object, events and additional objects extracted for a single job, which can be extended by [`ExtractionHooks`]
+pub struct JobExtraction {
+    /// The `Job` object (e.g., to add attributes or relationships)
+    pub object: OcelObject,
+    /// Events of the job
+    ///
+    /// The built-in events (e.g., submission and state changes) are only added after all changes of the job were processed,
+    /// i.e., before [`ExtractionHooks::finalize_job`].
+    /// IDs of all events are assigned afterwards, so they can be left empty.
+    pub events: Vec<OcelEvent>,
+    /// Additional objects (e.g., projects the job belongs to)
+    ///
+    /// Objects with the same ID (e.g., added for multiple jobs) are merged, combining their attributes and relationships.
+    pub objects: Vec<OcelObject>,
+}
+
+/// Custom hooks called during the conversion of each recorded job (see [`ExtractionConfig::hooks`](super::ExtractionConfig::hooks)),
+/// e.g., to add object types, events or attributes derived from job names
+///
+/// All methods do nothing by default. Jobs are converted in parallel, so hooks have to be thread-safe.
+/// Timestamps are passed in the configured timezone (see [`ExtractionConfig::timezone`](super::ExtractionConfig::timezone)).
+pub trait ExtractionHooks: Send + Sync {
+    /// Called with the `initial` row of a job, recorded at `time`
+    fn on_initial_row(
+        &self,
+        initial: &SqueueRow,
+        time: DateTime<FixedOffset>,
+        extraction: &mut JobExtraction,
+    ) {
+        let _ = (initial, time, extraction);
+    }
+
+    /// Called for each recorded change of a job (`delta`) at `time`, with the rows before and after the change
+    fn on_delta(
+        &self,
+        before: &SqueueRow,
+        after: &SqueueRow,
+        delta: &[SqueueRowDiff],
+        time: DateTime<FixedOffset>,
+        extraction: &mut JobExtraction,
+    ) {
+        let _ = (before, after, delta, time, extraction);
+    }
+
+    /// Called once all built-in events of a job were added
+    fn finalize_job(&self, job: &JobRecording, extraction: &mut JobExtraction) {
+        let _ = (job, extraction);
+    }
+
+    /// Additional object types (or attributes of existing types, e.g., of `Job`) to declare
+    ///
+    /// Types with the name of an already declared type extend its attributes.
+    fn object_types(&self) -> Vec<OcelType> {
+        Vec::new()
+    }
+
+    /// Additional event types (or attributes of existing types) to declare (see [`ExtractionHooks::object_types`])
+    fn event_types(&self) -> Vec<OcelType> {
+        Vec::new()
+    }
+}
+
+#[derive(Clone, Default)]
+/// Custom hooks of an extraction, called in the order they were added (see [`ExtractionHooks`])
+///
+/// Two hook lists are equal if they contain the same hook instances. Hooks are not serialized.
+pub struct HookList(pub Vec<Arc<dyn ExtractionHooks>>);
+
+impl HookList {
+    /// Add a hook to the end of the list
+    pub fn push(&mut self, hook: impl ExtractionHooks + 'static) {
+        self.0.push(Arc::new(hook));
+    }
+
+    /// Iterate over all hooks
+    pub fn iter(&self) -> impl Iterator<Item = &dyn ExtractionHooks> {
+        self.0.iter().map(|h| h.as_ref())
+    }
+
+    /// Check if no hooks were added
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl Debug for HookList {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "HookList({} hooks)", self.0.len())
+    }
+}
+
+impl PartialEq for HookList {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.len() == other.0.len() && self.0.iter().zip(&other.0).all(|(a, b)| Arc::ptr_eq(a, b))
+    }
+}
+
+impl Eq for HookList {}
+
+/// Extend the declared `types` by `additional` ones, merging types with the same name
+pub(super) fn merge_types(types: &mut Vec<OcelType>, additional: Vec<OcelType>) {
+    for t in additional {
+        match types.iter_mut().find(|existing| existing.name == t.name) {
+            Some(existing) => existing.attributes.extend(t.attributes),
+            None => types.push(t),
+        }
+    }
+}
+
+/// Merge `object` into `existing` (with the same ID), skipping duplicate attributes and relationships
+pub(super) fn merge_object(existing: &mut OcelObject, object: OcelObject) {
+    for attribute in object.attributes {
+        if !existing.attributes.contains(&attribute) {
+            existing.attributes.push(attribute);
+        }
+    }
+    for relationship in object.relationships {
+        if !existing.relationships.contains(&relationship) {
+            existing.relationships.push(relationship);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, FixedOffset};
+
+    use super::{ExtractionHooks, JobExtraction};
+    use crate::{
+        data_extraction::squeue::SqueueRow,
+        event_data_extraction::{
+            extract_ocel_from_jobs,
+            ocel::{OcelEvent, OcelObject, OcelRelationship, OcelType},
+            tests::test_recording,
+            ExtractionConfig,
+        },
+        recording::{JobRecording, SqueueRowDiff},
+        JobState,
+    };
+
+    /// Relates jobs to projects parsed from their names (`<project>-<name>`), and adds an event when jobs start running
+    struct ProjectHooks;
+
+    impl ExtractionHooks for ProjectHooks {
+        fn on_initial_row(
+            &self,
+            initial: &SqueueRow,
+            _time: DateTime<FixedOffset>,
+            extraction: &mut JobExtraction,
+        ) {
+            if let Some((project, _)) = initial.name.split_once('-') {
+                let id = format!("project_{project}");
+                extraction
+                    .object
+                    .relationships
+                    .push(OcelRelationship::new(&id, "project"));
+                extraction.objects.push(OcelObject::new(id, "Project"));
+            }
+        }
+
+        fn on_delta(
+            &self,
+            _before: &SqueueRow,
+            after: &SqueueRow,
+            delta: &[SqueueRowDiff],
+            time: DateTime<FixedOffset>,
+            extraction: &mut JobExtraction,
+        ) {
+            if delta
+                .iter()
+                .any(|d| matches!(d, SqueueRowDiff::state(JobState::RUNNING)))
+            {
+                extraction.events.push(OcelEvent {
+                    id: String::new(),
+                    event_type: "Running Detected".to_string(),
+                    time,
+                    attributes: Vec::new(),
+                    relationships: vec![OcelRelationship::new(&after.job_id, "job")],
+                });
+            }
+        }
+
+        fn finalize_job(&self, job: &JobRecording, extraction: &mut JobExtraction) {
+            assert_eq!(extraction.object.id, job.job_id);
+            assert!(extraction.events.len() > 1);
+        }
+
+        fn object_types(&self) -> Vec<OcelType> {
+            vec![OcelType {
+                name: "Project".to_string(),
+                attributes: Vec::new(),
+            }]
+        }
+
+        fn event_types(&self) -> Vec<OcelType> {
+            vec![OcelType {
+                name: "Running Detected".to_string(),
+                attributes: Vec::new(),
+            }]
+        }
+    }
+
+    #[test]
+    fn test_extraction_hooks() {
+        let job = test_recording();
+        let mut other = test_recording();
+        other.job_id = "49848562".to_string();
+        other.initial.job_id = other.job_id.clone();
+        let mut jobs = vec![job, other];
+        for job in &mut jobs {
+            job.initial.name = "slurry-test".to_string();
+        }
+        let mut config = ExtractionConfig::default();
+        config.hooks.push(ProjectHooks);
+        let ocel = extract_ocel_from_jobs(jobs.clone(), &config);
+        let projects: Vec<_> = ocel
+            .objects
+            .iter()
+            .filter(|o| o.object_type == "Project")
+            .collect();
+        assert_eq!(projects.len(), 1);
+        assert_eq!(projects[0].id, "project_slurry");
+        assert!(ocel.object_types.iter().any(|t| t.name == "Project"));
+        let detected: Vec<_> = ocel
+            .events
+            .iter()
+            .filter(|e| e.event_type == "Running Detected")
+            .collect();
+        assert_eq!(detected.len(), 2);
+        assert!(detected.iter().all(|e| !e.id.is_empty()));
+        // Events stay ordered by time
+        let job_events: Vec<_> = ocel
+            .events
+            .iter()
+            .filter(|e| e.id.starts_with("49848561-"))
+            .collect();
+        assert!(job_events.windows(2).all(|w| w[0].time <= w[1].time));
+        assert_eq!(
+            extract_ocel_from_jobs(jobs, &ExtractionConfig::default())
+                .events
+                .len()
+                + 2,
+            ocel.events.len()
+        );
+    }
+}
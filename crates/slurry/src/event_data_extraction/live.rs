@@ -0,0 +1,198 @@
+use std::{
+    collections::{BTreeMap, BTreeSet, HashSet},
+    sync::Mutex,
+};
+
+use anyhow::Error;
+use chrono::{DateTime, Utc};
+use rayon::prelude::*;
+
+use super::{
+    extraction::{assemble_ocel, job_to_ocel, ExtractedJob, ExtractionConfig},
+    ocel::Ocel,
+    report::ExtractionReport,
+};
+use crate::{
+    data_extraction::{sinfo::SinfoNodeRow, squeue::SqueueRow},
+    recording::{JobRecording, RecordedNodeStates, RecordingStore, SqueueRowDiff},
+};
+
+#[derive(Debug, Default)]
+struct AccumulatorState {
+    /// All recorded jobs (by their ID)
+    jobs: BTreeMap<String, JobRecording>,
+    /// Jobs changed since their last conversion
+    dirty: BTreeSet<String>,
+    /// Converted jobs matching the filter, as prepared for the extraction (e.g., pseudonymized), by their ID
+    converted: BTreeMap<String, (JobRecording, ExtractedJob)>,
+    /// All recorded node states, ordered by time
+    node_states: Vec<RecordedNodeStates>,
+}
+
+#[derive(Debug)]
+/// Recording store wrapper maintaining an up-to-date OCEL of all recorded jobs, e.g., for continuous exports while recording
+///
+/// All data is passed on to the `inner` store. The OCEL is the same as extracted from the complete recording afterwards
+/// (see [`extract_ocel_from_jobs_and_nodes`](super::extract_ocel_from_jobs_and_nodes)),
+/// but only jobs which changed since the last call of [`OcelAccumulator::ocel`] are converted again.
+///
+/// Changes of jobs whose initial row was not recorded through the accumulator are skipped,
+/// so when resuming a recording, the already recorded jobs should be added first (see [`OcelAccumulator::add_jobs`]).
+pub struct OcelAccumulator<'a, S: RecordingStore + ?Sized> {
+    inner: &'a S,
+    config: ExtractionConfig,
+    state: Mutex<AccumulatorState>,
+}
+
+impl<'a, S: RecordingStore + ?Sized> OcelAccumulator<'a, S> {
+    /// Wrap the given `inner` store, extracting the OCEL using `config`
+    pub fn new(inner: &'a S, config: ExtractionConfig) -> Self {
+        Self {
+            inner,
+            config,
+            state: Mutex::default(),
+        }
+    }
+
+    /// Add already recorded jobs (e.g., loaded using [`load_job_recordings`](super::load_job_recordings))
+    pub fn add_jobs(&self, jobs: impl IntoIterator<Item = JobRecording>) {
+        let mut state = self.state.lock().unwrap();
+        for job in jobs {
+            state.dirty.insert(job.job_id.clone());
+            state.jobs.insert(job.job_id.clone(), job);
+        }
+    }
+
+    /// Number of recorded jobs
+    pub fn job_count(&self) -> usize {
+        self.state.lock().unwrap().jobs.len()
+    }
+
+    /// The OCEL of all jobs and node states recorded so far
+    pub fn ocel(&self) -> Ocel {
+        let mut state = self.state.lock().unwrap();
+        let AccumulatorState {
+            jobs,
+            dirty,
+            converted,
+            node_states,
+        } = &mut *state;
+        let mut changed: Vec<_> = std::mem::take(dirty)
+            .into_iter()
+            .filter_map(|job_id| {
+                converted.remove(&job_id);
+                jobs.get(&job_id).cloned()
+            })
+            .collect();
+        self.config.prepare_jobs(&mut changed);
+        let extracted: Vec<_> = changed
+            .par_iter()
+            .map(|job| job_to_ocel(job, &self.config))
+            .collect();
+        for (job, extracted) in changed.into_iter().zip(extracted) {
+            converted.insert(job.job_id.clone(), (job, extracted));
+        }
+        assemble_ocel(
+            converted
+                .values()
+                .map(|(job, extracted)| (job, extracted.clone())),
+            node_states,
+            &self.config,
+            &mut ExtractionReport::default(),
+        )
+    }
+}
+
+impl<S: RecordingStore + ?Sized> RecordingStore for OcelAccumulator<'_, S> {
+    fn record_job_ids(&self, time: &DateTime<Utc>, job_ids: &HashSet<String>) -> Result<(), Error> {
+        self.inner.record_job_ids(time, job_ids)
+    }
+
+    fn record_new_job(&self, time: &DateTime<Utc>, row: &SqueueRow) -> Result<(), Error> {
+        self.inner.record_new_job(time, row)?;
+        let mut initial = row.clone();
+        initial.raw_line = None;
+        self.add_jobs([JobRecording {
+            job_id: row.job_id.clone(),
+            initial_time: *time,
+            initial,
+            deltas: Vec::new(),
+        }]);
+        Ok(())
+    }
+
+    fn record_job_delta(
+        &self,
+        time: &DateTime<Utc>,
+        job_id: &str,
+        diff: &[SqueueRowDiff],
+    ) -> Result<(), Error> {
+        self.inner.record_job_delta(time, job_id, diff)?;
+        let mut state = self.state.lock().unwrap();
+        if let Some(job) = state.jobs.get_mut(job_id) {
+            job.deltas.push((*time, diff.to_vec()));
+            state.dirty.insert(job_id.to_string());
+        }
+        Ok(())
+    }
+
+    fn record_node_states(
+        &self,
+        time: &DateTime<Utc>,
+        nodes: &[SinfoNodeRow],
+    ) -> Result<(), Error> {
+        self.inner.record_node_states(time, nodes)?;
+        if self.config.hosts {
+            let mut state = self.state.lock().unwrap();
+            state.node_states.push((*time, nodes.to_vec()));
+        }
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<(), Error> {
+        self.inner.flush()
+    }
+
+    fn load_jobs(&self) -> Result<Vec<JobRecording>, Error> {
+        self.inner.load_jobs()
+    }
+
+    fn load_node_states(&self) -> Result<Vec<RecordedNodeStates>, Error> {
+        self.inner.load_node_states()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::OcelAccumulator;
+    use crate::{
+        event_data_extraction::{extract_ocel_from_jobs, tests::test_recording, ExtractionConfig},
+        recording::{JsonDirStore, RecordingStore},
+    };
+
+    #[test]
+    fn test_ocel_accumulator() {
+        let job = test_recording();
+        let dir = std::env::temp_dir().join(format!("slurry-live-{}", std::process::id()));
+        let store = JsonDirStore::new(&dir);
+        let accumulator = OcelAccumulator::new(&store, ExtractionConfig::default());
+        accumulator
+            .record_new_job(&job.initial_time, &job.initial)
+            .unwrap();
+        assert_eq!(accumulator.ocel().events.len(), 1);
+
+        for (time, delta) in &job.deltas {
+            accumulator
+                .record_job_delta(time, &job.job_id, delta)
+                .unwrap();
+        }
+        assert_eq!(accumulator.job_count(), 1);
+        assert_eq!(
+            accumulator.ocel(),
+            extract_ocel_from_jobs(vec![job], &ExtractionConfig::default())
+        );
+        // All data is also recorded by the inner store
+        assert_eq!(store.load_jobs().unwrap()[0].deltas.len(), 2);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
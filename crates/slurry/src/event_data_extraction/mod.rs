@@ -0,0 +1,329 @@
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use structdiff::StructDiff;
+
+use crate::{
+    data_extraction::squeue::SqueueRow,
+    recording::{JobRecording, SqueueRowDiff},
+    JobState,
+};
+
+/// Module for representing object-centric event logs (OCEL 2.0)
+pub mod ocel;
+
+/// Module for extracting OCEL from recorded jobs
+pub mod extraction;
+
+/// Module for exporting OCEL with bounded memory usage
+pub mod streaming;
+
+/// Module for exporting recorded job lifecycles as XES event logs
+pub mod xes;
+
+/// Module for exporting recorded job lifecycles as CSV tables
+pub mod csv;
+
+/// Module for pseudonymizing personal data of recorded jobs
+pub mod anonymization;
+
+/// Module for summarizing OCEL extractions
+pub mod report;
+
+/// Module for building OCEL live while recording
+pub mod live;
+
+/// Module for exporting the object graph of OCEL as DOT or `GraphML`
+pub mod graph;
+
+/// Module for extracting one OCEL per partition or account
+pub mod split;
+
+/// Module for custom hooks in the OCEL extraction
+pub mod hooks;
+
+/// Module for extracting OCEL from `sacct` history dumps
+pub mod accounting;
+
+pub use accounting::{extract_ocel_from_sacct_dump, job_recording_from_sacct};
+pub use anonymization::Pseudonymizer;
+pub use csv::{export_csv_from_slurm_diffs, write_csv_from_slurm_diffs};
+pub use extraction::{
+    extract_ocel_from_jobs, extract_ocel_from_jobs_and_nodes, extract_ocel_from_jobs_with_progress,
+    extract_ocel_from_merged_slurm_diffs, extract_ocel_from_slurm_diffs,
+    extract_ocel_from_slurm_diffs_with_progress, extract_ocel_from_store, load_job_recordings,
+    load_job_recordings_with_failures, load_node_recordings, CancellationToken, ExtractionConfig,
+    ExtractionFilter, ExtractionProgress, JobAttribute, RelationshipQualifiers, StateEvent,
+};
+pub use graph::{export_object_graph_path, write_object_graph, GraphFormat};
+pub use hooks::{ExtractionHooks, HookList, JobExtraction};
+pub use live::OcelAccumulator;
+pub use ocel::{Ocel, OcelFormat};
+pub use report::{ExtractionReport, TimestampAnomaly};
+pub use split::{export_split_ocels, extract_split_ocels, SplitBy};
+pub use streaming::{export_ocel_json_streaming, write_ocel_json_streaming};
+pub use xes::{export_xes_from_slurm_diffs, write_xes_from_slurm_diffs};
+
+/// Activity of job submissions
+pub const SUBMIT_ACTIVITY: &str = "Submit Job";
+
+/// Activity of job allocations to a (new) host
+pub const ALLOCATE_ACTIVITY: &str = "Job Allocated";
+
+/// Activity of array job submissions (relating all tasks of the array)
+pub const SUBMIT_ARRAY_ACTIVITY: &str = "Submit Array";
+
+/// Activity of nodes becoming unavailable (`DOWN` or `FAIL`)
+pub const NODE_DOWN_ACTIVITY: &str = "Node Down";
+
+/// Activity of nodes being drained (`DRAINING` or `DRAINED`)
+pub const NODE_DRAINED_ACTIVITY: &str = "Node Drained";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// Kind of a [`JobEvent`]
+pub enum JobEventKind {
+    /// Submission of the job
+    Submission,
+    /// Start of the job (i.e., its state changed to `RUNNING`)
+    Start,
+    /// Any other state change
+    StateChange,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// A lifecycle event of a recorded job (e.g., its submission or completion)
+pub struct JobEvent {
+    /// ID of the job
+    pub job_id: String,
+    /// Kind of the event
+    pub kind: JobEventKind,
+    /// Activity of the event (e.g., `Submit Job`, `Job Started` or `Job Completed`)
+    pub activity: String,
+    /// Time of the event
+    ///
+    /// For submissions and starts, the times reported by SLURM are used.
+    /// For all other state changes, the time the change was recorded is used.
+    pub time: DateTime<Utc>,
+    /// State of the job after the event
+    pub state: JobState,
+    /// Node executing the job at the time of the event (if available)
+    pub exec_host: Option<String>,
+    /// Reason for the state of the job after the event (as reported by SLURM, e.g., `None` or `TimeLimit`)
+    pub reason: String,
+    /// Time the job was running until the event (if it was started before)
+    pub elapsed: Option<Duration>,
+    /// Exit code of the job at the time of the event (if available)
+    pub exit_code: Option<i32>,
+}
+
+/// Default activity of a state change to `state` (e.g., `Job Completed` for `COMPLETED`)
+///
+/// A state change to `PENDING` (of a job which already left that state) is considered a requeue.
+pub fn default_state_activity(state: &JobState) -> String {
+    match state {
+        JobState::RUNNING => "Job Started".to_string(),
+        JobState::PENDING => "Job Requeued".to_string(),
+        JobState::COMPLETING => "Job Ending".to_string(),
+        JobState::COMPLETED => "Job Completed".to_string(),
+        JobState::CANCELLED => "Job Cancelled".to_string(),
+        JobState::FAILED => "Job Failed".to_string(),
+        JobState::TIMEOUT => "Job Timeout".to_string(),
+        JobState::OUT_OF_MEMORY => "Job Out Of Memory".to_string(),
+        JobState::NODE_FAIL => "Job Node Fail".to_string(),
+        JobState::OTHER(other) => format!("Job {other}"),
+    }
+}
+
+/// Extract the lifecycle events of a recorded job, ordered by time
+///
+/// Events are the submission (`Submit Job`), the start (`Job Started`) and all subsequent state changes
+/// (e.g., `Job Ending` and `Job Completed`, or `Job Requeued` if the job became pending again, see [`default_state_activity`]).
+pub fn job_events(job: &JobRecording) -> Vec<JobEvent> {
+    job_events_with(job, |state| Some(default_state_activity(state)))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// Metrics derived from the lifecycle of a recorded job (see [`job_metrics`])
+pub struct JobMetrics {
+    /// Time between the submission and the (first) start of the job (if it was started)
+    pub queue_wait: Option<Duration>,
+    /// Time between the last start of the job and its end (if it ended)
+    ///
+    /// The end is the first state change to any state except `RUNNING` and `COMPLETING` after the start.
+    pub runtime: Option<Duration>,
+    /// Number of times the job became pending again (i.e., was requeued)
+    pub requeues: usize,
+}
+
+/// Derive metrics (e.g., the queue waiting time) from the lifecycle events of a recorded job (see [`job_events`])
+pub fn job_metrics(job: &JobRecording) -> JobMetrics {
+    let events = job_events(job);
+    let submit_time = job.initial.submit_time;
+    let since = |start: DateTime<Utc>, time: DateTime<Utc>| (time - start).to_std().ok();
+    let queue_wait = events
+        .iter()
+        .find(|e| e.kind == JobEventKind::Start)
+        .and_then(|e| since(submit_time, e.time));
+    let runtime = events
+        .iter()
+        .rposition(|e| e.kind == JobEventKind::Start)
+        .and_then(|start| {
+            events[start + 1..]
+                .iter()
+                .find(|e| !matches!(e.state, JobState::RUNNING | JobState::COMPLETING))
+                .and_then(|end| since(events[start].time, end.time))
+        });
+    let requeues = events
+        .iter()
+        .filter(|e| e.kind == JobEventKind::StateChange && e.state == JobState::PENDING)
+        .count();
+    JobMetrics {
+        queue_wait,
+        runtime,
+        requeues,
+    }
+}
+
+/// Extract the lifecycle events of a recorded job, using `activity` to name state changes (or skip them, if `None` is returned)
+///
+/// The time of state changes to `RUNNING` is the start time reported by SLURM (if available).
+pub(crate) fn job_events_with(
+    job: &JobRecording,
+    activity: impl Fn(&JobState) -> Option<String>,
+) -> Vec<JobEvent> {
+    let mut row: SqueueRow = job.initial.clone();
+    let event = |row: &SqueueRow, kind, activity: &str, time: DateTime<Utc>| JobEvent {
+        job_id: job.job_id.clone(),
+        kind,
+        activity: activity.to_string(),
+        time,
+        state: row.state.clone(),
+        exec_host: row.exec_host.clone(),
+        reason: row.reason.clone(),
+        elapsed: row
+            .start_time
+            .and_then(|start_time| (time - start_time).to_std().ok()),
+        exit_code: row.exit_code,
+    };
+    let start_activity = activity(&JobState::RUNNING);
+    let mut events = vec![event(
+        &row,
+        JobEventKind::Submission,
+        SUBMIT_ACTIVITY,
+        row.submit_time,
+    )];
+    // Index of the start event, as its time is updated if SLURM reports a different start time later on
+    let mut start = None;
+    if let (true, Some(start_time), Some(start_activity)) = (
+        row.state != JobState::PENDING,
+        row.start_time,
+        &start_activity,
+    ) {
+        start = Some(events.len());
+        events.push(event(&row, JobEventKind::Start, start_activity, start_time));
+    }
+    for (time, delta) in &job.deltas {
+        row.apply_mut(delta.clone());
+        for diff in delta {
+            match diff {
+                SqueueRowDiff::state(state) => {
+                    let started = *state == JobState::RUNNING
+                        // The job started between two recordings
+                        || (start.is_none() && *state != JobState::PENDING && row.start_time.is_some());
+                    if let (true, Some(start_activity)) = (started, &start_activity) {
+                        start = Some(events.len());
+                        let start_time = row.start_time.unwrap_or(*time);
+                        events.push(event(&row, JobEventKind::Start, start_activity, start_time));
+                    }
+                    if *state != JobState::RUNNING {
+                        if let Some(activity) = activity(state) {
+                            events.push(event(&row, JobEventKind::StateChange, &activity, *time));
+                        }
+                    }
+                }
+                SqueueRowDiff::start_time(Some(start_time)) if row.state != JobState::PENDING => {
+                    if let Some(i) = start {
+                        events[i].time = *start_time;
+                        events[i].elapsed = Some(Duration::ZERO);
+                    }
+                }
+                SqueueRowDiff::exec_host(exec_host) => {
+                    if let Some(i) = start {
+                        events[i].exec_host.clone_from(exec_host);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+    events.sort_by_key(|e| e.time);
+    events
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+    use structdiff::StructDiff;
+
+    use super::{job_events, job_metrics};
+    use crate::{data_extraction::squeue::test_row, recording::JobRecording, JobState};
+
+    /// A recording of a job which is submitted, started, and completed
+    pub(crate) fn test_recording() -> JobRecording {
+        let time = Utc.with_ymd_and_hms(2025, 1, 14, 9, 23, 45).unwrap();
+        let initial = test_row(time);
+        let mut running = initial.clone();
+        running.state = JobState::RUNNING;
+        running.reason = "None".to_string();
+        running.start_time = Some(time + Duration::seconds(20));
+        running.exec_host = Some("n23m0001".to_string());
+        let mut completed = running.clone();
+        completed.state = JobState::COMPLETED;
+        completed.exit_code = Some(0);
+        JobRecording {
+            job_id: initial.job_id.clone(),
+            initial_time: time,
+            deltas: vec![
+                (time + Duration::seconds(30), initial.diff(&running)),
+                (time + Duration::seconds(90), running.diff(&completed)),
+            ],
+            initial,
+        }
+    }
+
+    #[test]
+    fn test_job_events() {
+        let job = test_recording();
+        let events = job_events(&job);
+        let activities: Vec<_> = events.iter().map(|e| e.activity.as_str()).collect();
+        assert_eq!(activities, ["Submit Job", "Job Started", "Job Completed"]);
+        assert_eq!(events[1].time, job.initial_time + Duration::seconds(20));
+        assert_eq!(events[1].exec_host.as_deref(), Some("n23m0001"));
+        assert_eq!(events[2].time, job.initial_time + Duration::seconds(90));
+        assert_eq!(events[2].state, JobState::COMPLETED);
+        assert_eq!(events[0].elapsed, None);
+        assert_eq!(events[2].elapsed, Some(std::time::Duration::from_secs(70)));
+        assert_eq!(events[2].exit_code, Some(0));
+    }
+
+    #[test]
+    fn test_job_metrics() {
+        let mut job = test_recording();
+        let metrics = job_metrics(&job);
+        assert_eq!(metrics.queue_wait, Some(std::time::Duration::from_secs(20)));
+        assert_eq!(metrics.runtime, Some(std::time::Duration::from_secs(70)));
+        assert_eq!(metrics.requeues, 0);
+
+        // The job is requeued instead of completing
+        let (time, _) = job.deltas[1];
+        let running = job.latest_row();
+        let mut requeued = job.initial.clone();
+        requeued.start_time = None;
+        job.deltas.pop();
+        job.deltas.push((time, running.diff(&requeued)));
+        let metrics = job_metrics(&job);
+        assert_eq!(metrics.requeues, 1);
+        assert_eq!(metrics.runtime, Some(std::time::Duration::from_secs(70)));
+    }
+}
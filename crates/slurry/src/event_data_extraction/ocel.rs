@@ -0,0 +1,465 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use anyhow::Error;
+use chrono::{DateTime, FixedOffset, SecondsFormat, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::xes::escape_xml;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// File format of exported OCEL (see [`Ocel::export_path`])
+pub enum OcelFormat {
+    #[default]
+    /// OCEL 2.0 JSON
+    Json,
+    /// OCEL 2.0 XML (e.g., for tools only supporting the XML flavor)
+    Xml,
+}
+
+impl OcelFormat {
+    /// Usual file extension of the format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            OcelFormat::Json => "json",
+            OcelFormat::Xml => "xml",
+        }
+    }
+
+    /// The format of a file, by its extension (`.xml` or `.xmlocel` for XML, JSON otherwise)
+    pub fn from_path(path: impl AsRef<Path>) -> Self {
+        match path.as_ref().extension().and_then(|e| e.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("xml") || ext.eq_ignore_ascii_case("xmlocel") => {
+                OcelFormat::Xml
+            }
+            _ => OcelFormat::Json,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// Type of an OCEL attribute
+pub enum OcelAttributeType {
+    /// Text
+    String,
+    /// Timestamp
+    Time,
+    /// Integer number
+    Integer,
+    /// Floating point number
+    Float,
+    /// Boolean
+    Boolean,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+/// Value of an OCEL attribute
+pub enum OcelAttributeValue {
+    /// Timestamp
+    Time(DateTime<FixedOffset>),
+    /// Integer number
+    Integer(i64),
+    /// Floating point number
+    Float(f64),
+    /// Boolean
+    Boolean(bool),
+    /// Text
+    String(String),
+    /// Missing value
+    Null,
+}
+
+impl From<String> for OcelAttributeValue {
+    fn from(value: String) -> Self {
+        Self::String(value)
+    }
+}
+
+impl From<&str> for OcelAttributeValue {
+    fn from(value: &str) -> Self {
+        Self::String(value.to_string())
+    }
+}
+
+impl From<i64> for OcelAttributeValue {
+    fn from(value: i64) -> Self {
+        Self::Integer(value)
+    }
+}
+
+impl From<f64> for OcelAttributeValue {
+    fn from(value: f64) -> Self {
+        Self::Float(value)
+    }
+}
+
+impl From<bool> for OcelAttributeValue {
+    fn from(value: bool) -> Self {
+        Self::Boolean(value)
+    }
+}
+
+impl From<DateTime<FixedOffset>> for OcelAttributeValue {
+    fn from(value: DateTime<FixedOffset>) -> Self {
+        Self::Time(value)
+    }
+}
+
+impl From<DateTime<Utc>> for OcelAttributeValue {
+    fn from(value: DateTime<Utc>) -> Self {
+        Self::Time(value.fixed_offset())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// Attribute declared by an object or event type
+pub struct OcelTypeAttribute {
+    /// Name of the attribute
+    pub name: String,
+    /// Type of the attribute values
+    #[serde(rename = "type")]
+    pub value_type: OcelAttributeType,
+}
+
+impl OcelTypeAttribute {
+    /// Declare an attribute
+    pub fn new(name: impl Into<String>, value_type: OcelAttributeType) -> Self {
+        Self {
+            name: name.into(),
+            value_type,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// An object or event type
+pub struct OcelType {
+    /// Name of the type (e.g., `Job`)
+    pub name: String,
+    /// Declared attributes
+    pub attributes: Vec<OcelTypeAttribute>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// Qualified relationship to an object (from an event or another object)
+pub struct OcelRelationship {
+    /// ID of the related object
+    #[serde(rename = "objectId")]
+    pub object_id: String,
+    /// Qualifier of the relationship (e.g., `submitted by`)
+    pub qualifier: String,
+}
+
+impl OcelRelationship {
+    /// Relationship to `object_id` with the given qualifier
+    pub fn new(object_id: impl Into<String>, qualifier: impl Into<String>) -> Self {
+        Self {
+            object_id: object_id.into(),
+            qualifier: qualifier.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// Value of an object attribute, which is valid from the given time on
+pub struct OcelObjectAttribute {
+    /// Name of the attribute
+    pub name: String,
+    /// Value of the attribute
+    pub value: OcelAttributeValue,
+    /// Time from which on the value is valid (the Unix epoch for initial values)
+    pub time: DateTime<FixedOffset>,
+}
+
+impl OcelObjectAttribute {
+    /// Attribute value valid from `time` on
+    pub fn new(
+        name: impl Into<String>,
+        value: impl Into<OcelAttributeValue>,
+        time: DateTime<FixedOffset>,
+    ) -> Self {
+        Self {
+            name: name.into(),
+            value: value.into(),
+            time,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// Value of an event attribute
+pub struct OcelEventAttribute {
+    /// Name of the attribute
+    pub name: String,
+    /// Value of the attribute
+    pub value: OcelAttributeValue,
+}
+
+impl OcelEventAttribute {
+    /// Attribute with the given value
+    pub fn new(name: impl Into<String>, value: impl Into<OcelAttributeValue>) -> Self {
+        Self {
+            name: name.into(),
+            value: value.into(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// An object (e.g., a job or an account)
+pub struct OcelObject {
+    /// ID of the object
+    pub id: String,
+    /// Object type
+    #[serde(rename = "type")]
+    pub object_type: String,
+    /// Attribute values over time
+    #[serde(default)]
+    pub attributes: Vec<OcelObjectAttribute>,
+    /// Relationships to other objects
+    #[serde(default)]
+    pub relationships: Vec<OcelRelationship>,
+}
+
+impl OcelObject {
+    /// Object without attributes and relationships
+    pub fn new(id: impl Into<String>, object_type: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            object_type: object_type.into(),
+            attributes: Vec::new(),
+            relationships: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// An event (e.g., the submission of a job)
+pub struct OcelEvent {
+    /// ID of the event
+    pub id: String,
+    /// Event type
+    #[serde(rename = "type")]
+    pub event_type: String,
+    /// Time of the event
+    pub time: DateTime<FixedOffset>,
+    /// Attribute values
+    #[serde(default)]
+    pub attributes: Vec<OcelEventAttribute>,
+    /// Relationships to objects
+    #[serde(default)]
+    pub relationships: Vec<OcelRelationship>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+/// Object-centric event log in the OCEL 2.0 format
+///
+/// Serializes to (and deserializes from) OCEL 2.0 JSON, as supported by most OCEL tooling (e.g., the `process_mining` crate).
+/// This is the only OCEL representation used by slurry and its app, and can also be exported as OCEL 2.0 XML (see [`Ocel::export_path`]).
+pub struct Ocel {
+    /// Declared event types
+    pub event_types: Vec<OcelType>,
+    /// Declared object types
+    pub object_types: Vec<OcelType>,
+    /// All events
+    pub events: Vec<OcelEvent>,
+    /// All objects
+    pub objects: Vec<OcelObject>,
+}
+
+impl Ocel {
+    /// Write the OCEL as OCEL 2.0 JSON
+    pub fn export_json(&self, writer: impl Write) -> Result<(), Error> {
+        serde_json::to_writer(writer, self)?;
+        Ok(())
+    }
+
+    /// Write the OCEL as OCEL 2.0 JSON file to `path`
+    pub fn export_json_path(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.export_json(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Read an OCEL 2.0 JSON file
+    pub fn import_json_path(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let reader = std::io::BufReader::new(File::open(path)?);
+        Ok(serde_json::from_reader(reader)?)
+    }
+
+    /// Write the OCEL as OCEL 2.0 XML
+    ///
+    /// Attributes without value (i.e., [`OcelAttributeValue::Null`]) are omitted.
+    pub fn export_xml(&self, w: &mut impl Write) -> Result<(), Error> {
+        writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+        writeln!(w, "<log>")?;
+        write_xml_types(w, "object-type", &self.object_types)?;
+        write_xml_types(w, "event-type", &self.event_types)?;
+        writeln!(w, "  <objects>")?;
+        for object in &self.objects {
+            writeln!(
+                w,
+                r#"    <object id="{}" type="{}">"#,
+                escape_xml(&object.id),
+                escape_xml(&object.object_type)
+            )?;
+            writeln!(w, "      <attributes>")?;
+            for attribute in &object.attributes {
+                if let Some(value) = xml_value(&attribute.value) {
+                    writeln!(
+                        w,
+                        r#"        <attribute name="{}" time="{}">{value}</attribute>"#,
+                        escape_xml(&attribute.name),
+                        xml_time(&attribute.time)
+                    )?;
+                }
+            }
+            writeln!(w, "      </attributes>")?;
+            write_xml_relationships(w, &object.relationships)?;
+            writeln!(w, "    </object>")?;
+        }
+        writeln!(w, "  </objects>")?;
+        writeln!(w, "  <events>")?;
+        for event in &self.events {
+            writeln!(
+                w,
+                r#"    <event id="{}" type="{}" time="{}">"#,
+                escape_xml(&event.id),
+                escape_xml(&event.event_type),
+                xml_time(&event.time)
+            )?;
+            writeln!(w, "      <attributes>")?;
+            for attribute in &event.attributes {
+                if let Some(value) = xml_value(&attribute.value) {
+                    writeln!(
+                        w,
+                        r#"        <attribute name="{}">{value}</attribute>"#,
+                        escape_xml(&attribute.name)
+                    )?;
+                }
+            }
+            writeln!(w, "      </attributes>")?;
+            write_xml_relationships(w, &event.relationships)?;
+            writeln!(w, "    </event>")?;
+        }
+        writeln!(w, "  </events>")?;
+        writeln!(w, "</log>")?;
+        Ok(())
+    }
+
+    /// Write the OCEL as OCEL 2.0 XML file to `path`
+    pub fn export_xml_path(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.export_xml(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Write the OCEL in the given format to `path`
+    pub fn export_path(&self, path: impl AsRef<Path>, format: OcelFormat) -> Result<(), Error> {
+        match format {
+            OcelFormat::Json => self.export_json_path(path),
+            OcelFormat::Xml => self.export_xml_path(path),
+        }
+    }
+}
+
+/// Write the declared object or event types (`element` is `object-type` or `event-type`) as OCEL 2.0 XML
+fn write_xml_types(w: &mut impl Write, element: &str, types: &[OcelType]) -> Result<(), Error> {
+    writeln!(w, "  <{element}s>")?;
+    for t in types {
+        writeln!(w, r#"    <{element} name="{}">"#, escape_xml(&t.name))?;
+        writeln!(w, "      <attributes>")?;
+        for attribute in &t.attributes {
+            let value_type = match attribute.value_type {
+                OcelAttributeType::String => "string",
+                OcelAttributeType::Time => "time",
+                OcelAttributeType::Integer => "integer",
+                OcelAttributeType::Float => "float",
+                OcelAttributeType::Boolean => "boolean",
+            };
+            writeln!(
+                w,
+                r#"        <attribute name="{}" type="{value_type}"/>"#,
+                escape_xml(&attribute.name)
+            )?;
+        }
+        writeln!(w, "      </attributes>")?;
+        writeln!(w, "    </{element}>")?;
+    }
+    writeln!(w, "  </{element}s>")?;
+    Ok(())
+}
+
+/// Write the relationships of an object or event as OCEL 2.0 XML
+fn write_xml_relationships(
+    w: &mut impl Write,
+    relationships: &[OcelRelationship],
+) -> Result<(), Error> {
+    writeln!(w, "      <objects>")?;
+    for relationship in relationships {
+        writeln!(
+            w,
+            r#"        <relationship object-id="{}" qualifier="{}"/>"#,
+            escape_xml(&relationship.object_id),
+            escape_xml(&relationship.qualifier)
+        )?;
+    }
+    writeln!(w, "      </objects>")?;
+    Ok(())
+}
+
+/// Format an attribute value as (escaped) OCEL 2.0 XML text, or `None` for missing values
+fn xml_value(value: &OcelAttributeValue) -> Option<String> {
+    match value {
+        OcelAttributeValue::Time(time) => Some(xml_time(time)),
+        OcelAttributeValue::Integer(i) => Some(i.to_string()),
+        OcelAttributeValue::Float(f) => Some(f.to_string()),
+        OcelAttributeValue::Boolean(b) => Some(b.to_string()),
+        OcelAttributeValue::String(s) => Some(escape_xml(s)),
+        OcelAttributeValue::Null => None,
+    }
+}
+
+fn xml_time(time: &DateTime<FixedOffset>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::AutoSi, true)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::OcelFormat;
+    use crate::event_data_extraction::{
+        extract_ocel_from_jobs, tests::test_recording, ExtractionConfig,
+    };
+
+    #[test]
+    fn test_export_xml() {
+        let ocel = extract_ocel_from_jobs(vec![test_recording()], &ExtractionConfig::default());
+        let mut xml = Vec::new();
+        ocel.export_xml(&mut xml).unwrap();
+        let xml = String::from_utf8(xml).unwrap();
+        assert!(xml.starts_with("<?xml"));
+        assert_eq!(xml.matches("<object id=").count(), ocel.objects.len());
+        assert_eq!(xml.matches("<event id=").count(), ocel.events.len());
+        assert!(xml.contains(r#"<object-type name="Job">"#));
+        assert!(xml.contains(r#"<relationship object-id="49848561" qualifier="job"/>"#));
+        assert_eq!(
+            OcelFormat::from_path(Path::new("ocel.XML")),
+            OcelFormat::Xml
+        );
+        assert_eq!(
+            OcelFormat::from_path(Path::new("ocel.json")),
+            OcelFormat::Json
+        );
+    }
+}
@@ -0,0 +1,135 @@
+use std::{collections::BTreeMap, fmt::Display};
+
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+
+use super::ocel::OcelEvent;
+use crate::recording::JobRecording;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// Suspicious timestamp of a recorded job (e.g., a change recorded before the previous one)
+pub struct TimestampAnomaly {
+    /// ID of the job
+    pub job_id: String,
+    /// Description of the anomaly
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+/// Summary of an OCEL extraction (see [`extract_ocel_from_jobs_with_progress`](super::extract_ocel_from_jobs_with_progress))
+pub struct ExtractionReport {
+    /// Number of converted jobs
+    pub jobs_processed: usize,
+    /// Number of jobs skipped, as they did not match the filter (see [`ExtractionConfig::filter`](super::ExtractionConfig::filter))
+    pub jobs_skipped: usize,
+    /// Errors of jobs (or recording files) which failed to load
+    pub load_failures: Vec<String>,
+    /// Number of extracted events per event type
+    pub events_per_type: BTreeMap<String, usize>,
+    /// Time of the earliest extracted event
+    pub first_event: Option<DateTime<FixedOffset>>,
+    /// Time of the latest extracted event
+    pub last_event: Option<DateTime<FixedOffset>>,
+    /// Timestamp anomalies of the converted jobs
+    pub timestamp_anomalies: Vec<TimestampAnomaly>,
+}
+
+impl ExtractionReport {
+    /// Add a converted job and its events
+    pub(super) fn add_job(&mut self, job: &JobRecording, events: &[OcelEvent]) {
+        self.jobs_processed += 1;
+        self.add_events(events);
+        let mut previous = job.initial_time;
+        for (time, _) in &job.deltas {
+            if *time < previous {
+                self.timestamp_anomalies.push(TimestampAnomaly {
+                    job_id: job.job_id.clone(),
+                    description: format!(
+                        "Change recorded at {time} is before the previous recording at {previous}"
+                    ),
+                });
+            }
+            previous = previous.max(*time);
+        }
+        for event in events {
+            if event.time < job.initial.submit_time {
+                self.timestamp_anomalies.push(TimestampAnomaly {
+                    job_id: job.job_id.clone(),
+                    description: format!(
+                        "Event {} ({}) at {} is before the submission at {}",
+                        event.id, event.event_type, event.time, job.initial.submit_time
+                    ),
+                });
+            }
+        }
+    }
+
+    /// Add events not belonging to a single job (e.g., array submissions)
+    pub(super) fn add_events(&mut self, events: &[OcelEvent]) {
+        for event in events {
+            *self
+                .events_per_type
+                .entry(event.event_type.clone())
+                .or_default() += 1;
+            if self.first_event.is_none_or(|t| event.time < t) {
+                self.first_event = Some(event.time);
+            }
+            if self.last_event.is_none_or(|t| event.time > t) {
+                self.last_event = Some(event.time);
+            }
+        }
+    }
+}
+
+impl Display for ExtractionReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "Processed {} jobs ({} skipped by the filter, {} failed to load)",
+            self.jobs_processed,
+            self.jobs_skipped,
+            self.load_failures.len()
+        )?;
+        if let (Some(first), Some(last)) = (self.first_event, self.last_event) {
+            writeln!(f, "Events from {first} to {last}:")?;
+        }
+        for (event_type, count) in &self.events_per_type {
+            writeln!(f, "  {event_type}: {count}")?;
+        }
+        for failure in &self.load_failures {
+            writeln!(f, "[!] {failure}")?;
+        }
+        for anomaly in &self.timestamp_anomalies {
+            writeln!(f, "[!] Job {}: {}", anomaly.job_id, anomaly.description)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use crate::event_data_extraction::{
+        extract_ocel_from_jobs_with_progress, tests::test_recording, CancellationToken,
+        ExtractionConfig,
+    };
+
+    #[test]
+    fn test_timestamp_anomalies() {
+        let mut job = test_recording();
+        // The completion was recorded before the start
+        job.deltas[1].0 = job.deltas[0].0 - Duration::seconds(5);
+        let (_, report) = extract_ocel_from_jobs_with_progress(
+            vec![job],
+            &[],
+            &ExtractionConfig::default(),
+            |_| {},
+            &CancellationToken::new(),
+        )
+        .unwrap();
+        assert_eq!(report.timestamp_anomalies.len(), 1);
+        assert_eq!(report.timestamp_anomalies[0].job_id, "49848561");
+        assert!(report.to_string().starts_with("Processed 1 jobs"));
+    }
+}
@@ -0,0 +1,157 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    extraction::{
+        extract_ocel_from_jobs_and_nodes, load_job_recordings, load_node_recordings,
+        ExtractionConfig,
+    },
+    ocel::Ocel,
+};
+use crate::recording::{JobRecording, RecordedNodeStates};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+/// Criterion by which jobs are split into separate OCEL (see [`extract_split_ocels`])
+pub enum SplitBy {
+    /// One OCEL per partition
+    Partition,
+    /// One OCEL per account
+    Account,
+}
+
+impl SplitBy {
+    /// Value of `job` by which it is split (at submission)
+    pub fn key<'a>(&self, job: &'a JobRecording) -> &'a str {
+        match self {
+            SplitBy::Partition => &job.initial.partition,
+            SplitBy::Account => &job.initial.account,
+        }
+    }
+}
+
+impl FromStr for SplitBy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "partition" => Ok(Self::Partition),
+            "account" => Ok(Self::Account),
+            _ => Err(Error::msg(format!("Unknown split criterion: {s}"))),
+        }
+    }
+}
+
+/// Extract one object-centric event log (OCEL) per partition (or account), by their name
+///
+/// Each job is included in the OCEL of its partition (or account) at submission.
+/// Only jobs matching the configured filter are extracted, so no OCEL is created for partitions (or accounts) without matching jobs.
+/// When splitting by partition, each OCEL only includes the hosts recorded in that partition.
+/// When splitting by account and pseudonymization is configured, the OCEL are named by the pseudonymized account.
+/// See [`extract_ocel_from_jobs_and_nodes`].
+pub fn extract_split_ocels(
+    jobs: Vec<JobRecording>,
+    node_states: &[RecordedNodeStates],
+    config: &ExtractionConfig,
+    split_by: SplitBy,
+) -> BTreeMap<String, Ocel> {
+    let mut groups: BTreeMap<String, Vec<JobRecording>> = BTreeMap::new();
+    for job in jobs {
+        if config.filter.matches(&job) {
+            groups
+                .entry(split_by.key(&job).to_string())
+                .or_default()
+                .push(job);
+        }
+    }
+    groups
+        .into_iter()
+        .map(|(key, jobs)| {
+            let ocel = match split_by {
+                SplitBy::Partition => {
+                    let partition_nodes: Vec<RecordedNodeStates> = node_states
+                        .iter()
+                        .map(|(time, nodes)| {
+                            let nodes = nodes.iter().filter(|n| n.partition == key).cloned();
+                            (*time, nodes.collect())
+                        })
+                        .collect();
+                    extract_ocel_from_jobs_and_nodes(jobs, &partition_nodes, config)
+                }
+                SplitBy::Account => extract_ocel_from_jobs_and_nodes(jobs, node_states, config),
+            };
+            let name = match (&config.pseudonymization, split_by) {
+                (Some(pseudonymizer), SplitBy::Account) => pseudonymizer.pseudonymize(&key),
+                _ => key,
+            };
+            (name, ocel)
+        })
+        .collect()
+}
+
+/// Extract one OCEL per partition (or account) from the recording at `path` (see [`extract_split_ocels`]), exporting them as OCEL 2.0 JSON into the directory `dest`
+///
+/// Files are named after the partition (or account), e.g., `c23ms.json`, replacing characters not allowed in file names.
+/// Returns the paths of all written files.
+pub fn export_split_ocels(
+    path: &Path,
+    dest: &Path,
+    config: &ExtractionConfig,
+    split_by: SplitBy,
+) -> Result<Vec<PathBuf>, Error> {
+    let jobs = load_job_recordings(path)?;
+    let node_states = if config.hosts {
+        load_node_recordings(path)?
+    } else {
+        Vec::new()
+    };
+    std::fs::create_dir_all(dest)?;
+    let mut paths = Vec::new();
+    for (name, ocel) in extract_split_ocels(jobs, &node_states, config, split_by) {
+        let file_name: String = name
+            .chars()
+            .map(|c| {
+                if c.is_alphanumeric() || c == '-' || c == '_' || c == '.' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .collect();
+        let file_path = dest.join(format!("{file_name}.json"));
+        ocel.export_json_path(&file_path)?;
+        paths.push(file_path);
+    }
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{extract_split_ocels, SplitBy};
+    use crate::event_data_extraction::{tests::test_recording, ExtractionConfig};
+
+    #[test]
+    fn test_extract_split_ocels() {
+        let job = test_recording();
+        let mut other = test_recording();
+        other.job_id = "49848562".to_string();
+        other.initial.job_id = other.job_id.clone();
+        other.initial.partition = "c23g".to_string();
+        let config = ExtractionConfig::default();
+
+        let ocels = extract_split_ocels(vec![job.clone(), other], &[], &config, SplitBy::Partition);
+        assert_eq!(ocels.keys().collect::<Vec<_>>(), ["c23g", "c23ms"]);
+        assert!(ocels["c23g"].objects.iter().any(|o| o.id == "49848562"));
+        assert!(!ocels["c23g"].objects.iter().any(|o| o.id == "49848561"));
+
+        let ocels = extract_split_ocels(vec![job], &[], &config, SplitBy::Account);
+        assert_eq!(ocels.keys().collect::<Vec<_>>(), ["default"]);
+        assert_eq!("account".parse::<SplitBy>().unwrap(), SplitBy::Account);
+    }
+}
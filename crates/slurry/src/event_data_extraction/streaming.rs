@@ -0,0 +1,231 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::{copy, BufReader, BufWriter, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+};
+
+use anyhow::Error;
+use rayon::prelude::*;
+use serde::Serialize;
+
+use super::{
+    extraction::{
+        declared_types, job_to_ocel, jsonl_files_in, load_job_recordings_with_failures,
+        load_node_recordings, ExtractionConfig, RelatedObjects,
+    },
+    report::ExtractionReport,
+};
+use crate::recording::{list_recorded_job_ids, JobRecording, RecordedNodeStates};
+
+/// Export the recording at `path` as OCEL 2.0 JSON file to `dest`, without building the complete OCEL in memory (see [`write_ocel_json_streaming`])
+///
+/// Jobs of JSON recording directories are loaded in chunks of `chunk_size` jobs.
+/// JSONL recordings are loaded at once, as the records of a job can be spread over the whole file.
+/// Jobs failing to load are skipped and listed in the returned report.
+pub fn export_ocel_json_streaming(
+    path: &Path,
+    config: &ExtractionConfig,
+    dest: impl AsRef<Path>,
+    chunk_size: usize,
+) -> Result<ExtractionReport, Error> {
+    let node_states = if config.hosts {
+        load_node_recordings(path)?
+    } else {
+        Vec::new()
+    };
+    let mut writer = BufWriter::new(File::create(dest)?);
+    let report = if path.is_dir() && jsonl_files_in(path)?.is_empty() {
+        let mut job_ids: Vec<_> = list_recorded_job_ids(path)?.into_iter().collect();
+        job_ids.sort();
+        let load_failures = Mutex::new(Vec::new());
+        let chunks = job_ids.chunks(chunk_size.max(1)).map(|chunk| {
+            chunk
+                .par_iter()
+                .filter_map(|job_id| {
+                    JobRecording::load_from_dir(path, job_id)
+                        .inspect_err(|e| {
+                            let failure = format!("Failed to load job {job_id}: {e:?}");
+                            load_failures.lock().unwrap().push(failure);
+                        })
+                        .ok()
+                })
+                .collect()
+        });
+        let mut report = write_ocel_json_streaming(chunks, &node_states, config, &mut writer)?;
+        report.load_failures = load_failures.into_inner().unwrap();
+        report
+    } else {
+        let (jobs, load_failures) = load_job_recordings_with_failures(path)?;
+        let mut report = write_ocel_json_streaming([jobs], &node_states, config, &mut writer)?;
+        report.load_failures = load_failures;
+        report
+    };
+    writer.flush()?;
+    Ok(report)
+}
+
+/// Write the OCEL of recorded jobs (given in chunks) and node states as OCEL 2.0 JSON, chunk by chunk
+///
+/// The resulting OCEL is the same as for [`extract_ocel_from_jobs_and_nodes`](super::extract_ocel_from_jobs_and_nodes) with all jobs,
+/// but only the jobs of a single chunk (and the IDs of related objects) are kept in memory.
+/// Events are written to a temporary file first, as they follow all objects in the output.
+///
+/// Returns a summary of the extraction (see [`ExtractionReport`]).
+pub fn write_ocel_json_streaming(
+    chunks: impl IntoIterator<Item = Vec<JobRecording>>,
+    node_states: &[RecordedNodeStates],
+    config: &ExtractionConfig,
+    mut writer: impl Write,
+) -> Result<ExtractionReport, Error> {
+    let (event_types, object_types) = declared_types(config);
+    write!(writer, r#"{{"eventTypes":"#)?;
+    serde_json::to_writer(&mut writer, &event_types)?;
+    write!(writer, r#","objectTypes":"#)?;
+    serde_json::to_writer(&mut writer, &object_types)?;
+    write!(writer, r#","objects":["#)?;
+
+    let spool = SpoolFile::create()?;
+    let mut events = BufWriter::new(&spool.file);
+    let (mut first_object, mut first_event) = (true, true);
+    let mut related_objects = RelatedObjects::default();
+    let mut report = ExtractionReport::default();
+    for mut jobs in chunks {
+        report.jobs_skipped += config.prepare_jobs(&mut jobs);
+        let extracted: Vec<_> = jobs
+            .par_iter()
+            .map(|job| job_to_ocel(job, config))
+            .collect();
+        for (job, extracted) in jobs.iter().zip(extracted) {
+            report.add_job(job, &extracted.events);
+            related_objects.add(job, extracted.related, extracted.objects, config);
+            write_element(&mut writer, &mut first_object, &extracted.object)?;
+            for event in &extracted.events {
+                write_element(&mut events, &mut first_event, event)?;
+            }
+        }
+    }
+    let array_events = related_objects.array_events(config);
+    report.add_events(&array_events);
+    for event in &array_events {
+        write_element(&mut events, &mut first_event, event)?;
+    }
+    let node_events = related_objects.node_events(node_states, config);
+    report.add_events(&node_events);
+    for event in &node_events {
+        write_element(&mut events, &mut first_event, event)?;
+    }
+    for object in related_objects.into_objects(node_states, config) {
+        write_element(&mut writer, &mut first_object, &object)?;
+    }
+    events.flush()?;
+    drop(events);
+
+    write!(writer, r#"],"events":["#)?;
+    let mut file = &spool.file;
+    file.seek(SeekFrom::Start(0))?;
+    copy(&mut BufReader::new(file), &mut writer)?;
+    write!(writer, "]}}")?;
+    Ok(report)
+}
+
+/// Write a JSON array element, preceded by a comma (except for the first element)
+fn write_element(
+    w: &mut impl Write,
+    first: &mut bool,
+    value: &impl Serialize,
+) -> Result<(), Error> {
+    if !std::mem::take(first) {
+        w.write_all(b",")?;
+    }
+    serde_json::to_writer(w, value)?;
+    Ok(())
+}
+
+/// Temporary file, which is removed when dropped
+struct SpoolFile {
+    path: PathBuf,
+    file: File,
+}
+
+impl SpoolFile {
+    fn create() -> Result<Self, Error> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "slurry-ocel-events-{}-{}.json",
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)?;
+        Ok(Self { path, file })
+    }
+}
+
+impl Drop for SpoolFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{export_ocel_json_streaming, write_ocel_json_streaming};
+    use crate::{
+        event_data_extraction::{
+            extract_ocel_from_jobs, tests::test_recording, ExtractionConfig, Ocel,
+        },
+        recording::{JsonDirStore, RecordingStore},
+    };
+
+    #[test]
+    fn test_streaming_export() {
+        let jobs: Vec<_> = (0..3)
+            .map(|i| {
+                let mut job = test_recording();
+                job.job_id = format!("4984856{i}");
+                job.initial.job_id = job.job_id.clone();
+                job
+            })
+            .collect();
+        let config = ExtractionConfig::default();
+        let mut out = Vec::new();
+        write_ocel_json_streaming(
+            [jobs[..2].to_vec(), jobs[2..].to_vec()],
+            &[],
+            &config,
+            &mut out,
+        )
+        .unwrap();
+        let streamed: Ocel = serde_json::from_slice(&out).unwrap();
+        assert_eq!(streamed, extract_ocel_from_jobs(jobs.clone(), &config));
+
+        // Chunk-wise loading from a JSON recording directory
+        let dir = std::env::temp_dir().join(format!("slurry-streaming-{}", std::process::id()));
+        let store = JsonDirStore::new(dir.join("recording"));
+        for job in &jobs {
+            store
+                .record_new_job(&job.initial_time, &job.initial)
+                .unwrap();
+            for (time, delta) in &job.deltas {
+                store.record_job_delta(time, &job.job_id, delta).unwrap();
+            }
+        }
+        let dest = dir.join("ocel.json");
+        let report = export_ocel_json_streaming(&dir.join("recording"), &config, &dest, 2).unwrap();
+        assert_eq!(report.jobs_processed, 3);
+        assert!(report.load_failures.is_empty());
+        let exported = Ocel::import_json_path(&dest).unwrap();
+        assert_eq!(exported.objects.len(), streamed.objects.len());
+        assert_eq!(exported.events.len(), 12);
+        assert_eq!(exported.objects[0].id, "49848560");
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,147 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use anyhow::Error;
+use chrono::{DateTime, SecondsFormat, Utc};
+
+use super::job_events;
+use crate::recording::JobRecording;
+
+/// Export recorded jobs as XES event log to the file at `path` (see [`write_xes_from_slurm_diffs`])
+pub fn export_xes_from_slurm_diffs(
+    jobs: &[JobRecording],
+    path: impl AsRef<Path>,
+) -> Result<(), Error> {
+    let mut writer = BufWriter::new(File::create(path)?);
+    write_xes_from_slurm_diffs(jobs, &mut writer)?;
+    writer.flush()?;
+    Ok(())
+}
+
+/// Write recorded jobs as XES event log, with one trace per job
+///
+/// The events of each trace are the lifecycle events of the job (see [`job_events`]).
+/// Traces are annotated with the job name, account, group, partition, final state and exit code (as last recorded).
+/// This flattened view is intended for tools not supporting OCEL.
+pub fn write_xes_from_slurm_diffs(jobs: &[JobRecording], w: &mut impl Write) -> Result<(), Error> {
+    writeln!(w, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(
+        w,
+        r#"<log xes.version="1.0" xes.features="nested-attributes" xmlns="http://www.xes-standard.org/">"#
+    )?;
+    writeln!(
+        w,
+        r#"  <extension name="Concept" prefix="concept" uri="http://www.xes-standard.org/concept.xesext"/>"#
+    )?;
+    writeln!(
+        w,
+        r#"  <extension name="Time" prefix="time" uri="http://www.xes-standard.org/time.xesext"/>"#
+    )?;
+    writeln!(w, r#"  <global scope="trace">"#)?;
+    writeln!(w, r#"    <string key="concept:name" value="__INVALID__"/>"#)?;
+    writeln!(w, r#"  </global>"#)?;
+    writeln!(w, r#"  <global scope="event">"#)?;
+    writeln!(w, r#"    <string key="concept:name" value="__INVALID__"/>"#)?;
+    writeln!(
+        w,
+        r#"    <date key="time:timestamp" value="1970-01-01T00:00:00.000+00:00"/>"#
+    )?;
+    writeln!(w, r#"  </global>"#)?;
+    writeln!(w, r#"  <classifier name="Activity" keys="concept:name"/>"#)?;
+    for job in jobs {
+        let row = job.latest_row();
+        writeln!(w, "  <trace>")?;
+        write_string(w, 4, "concept:name", &job.job_id)?;
+        write_string(w, 4, "name", &row.name)?;
+        write_string(w, 4, "account", &row.account)?;
+        write_string(w, 4, "group", &row.group)?;
+        write_string(w, 4, "partition", &row.partition)?;
+        write_string(w, 4, "state", &row.state.to_string())?;
+        if let Some(exit_code) = row.exit_code {
+            writeln!(w, r#"    <int key="exit_code" value="{exit_code}"/>"#)?;
+        }
+        for event in job_events(job) {
+            writeln!(w, "    <event>")?;
+            write_string(w, 6, "concept:name", &event.activity)?;
+            write_date(w, 6, "time:timestamp", &event.time)?;
+            write_string(w, 6, "state", &event.state.to_string())?;
+            if let Some(exec_host) = &event.exec_host {
+                write_string(w, 6, "exec_host", exec_host)?;
+            }
+            writeln!(w, "    </event>")?;
+        }
+        writeln!(w, "  </trace>")?;
+    }
+    writeln!(w, "</log>")?;
+    Ok(())
+}
+
+fn write_string(w: &mut impl Write, indent: usize, key: &str, value: &str) -> Result<(), Error> {
+    writeln!(
+        w,
+        r#"{:indent$}<string key="{}" value="{}"/>"#,
+        "",
+        escape_xml(key),
+        escape_xml(value)
+    )?;
+    Ok(())
+}
+
+fn write_date(
+    w: &mut impl Write,
+    indent: usize,
+    key: &str,
+    value: &DateTime<Utc>,
+) -> Result<(), Error> {
+    writeln!(
+        w,
+        r#"{:indent$}<date key="{}" value="{}"/>"#,
+        "",
+        escape_xml(key),
+        value.to_rfc3339_opts(SecondsFormat::Millis, false)
+    )?;
+    Ok(())
+}
+
+/// Escape a string for use in XML attribute values
+pub(super) fn escape_xml(s: &str) -> String {
+    let mut res = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => res.push_str("&amp;"),
+            '<' => res.push_str("&lt;"),
+            '>' => res.push_str("&gt;"),
+            '"' => res.push_str("&quot;"),
+            '\'' => res.push_str("&apos;"),
+            c => res.push(c),
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::write_xes_from_slurm_diffs;
+    use crate::event_data_extraction::tests::test_recording;
+
+    #[test]
+    fn test_xes_export() {
+        let mut job = test_recording();
+        job.initial.name = "a<b & \"c\"".to_string();
+        let mut out = Vec::new();
+        write_xes_from_slurm_diffs(&[job], &mut out).unwrap();
+        let xes = String::from_utf8(out).unwrap();
+        assert_eq!(xes.matches("<trace>").count(), 1);
+        assert_eq!(xes.matches("<event>").count(), 3);
+        assert!(xes.contains(r#"<string key="concept:name" value="49848561"/>"#));
+        assert!(xes.contains(r#"<string key="name" value="a&lt;b &amp; &quot;c&quot;"/>"#));
+        assert!(
+            xes.contains(r#"<date key="time:timestamp" value="2025-01-14T09:24:05.000+00:00"/>"#)
+        );
+        assert!(xes.contains(r#"<int key="exit_code" value="0"/>"#));
+        assert!(xes.trim_end().ends_with("</log>"));
+    }
+}
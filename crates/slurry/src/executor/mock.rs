@@ -0,0 +1,235 @@
+use std::{path::Path, sync::Mutex};
+
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+
+use super::{CommandExecutor, CommandOutput};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// A command together with its output, e.g., as stored in fixture files
+pub struct RecordedCommand {
+    /// The executed command
+    pub command: String,
+    /// The output of the command
+    pub output: CommandOutput,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum CommandPattern {
+    Exact(String),
+    Prefix(String),
+}
+
+impl CommandPattern {
+    fn matches(&self, command: &str) -> bool {
+        match self {
+            CommandPattern::Exact(c) => c == command,
+            CommandPattern::Prefix(p) => command.starts_with(p.as_str()),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+/// Executor returning canned outputs instead of executing commands, e.g., for testing without a live cluster
+///
+/// Each response is returned once for a matching command, in the order they were added.
+/// Once all matching responses were returned, the last one is repeated.
+/// This allows replaying recorded sequences (e.g., of `squeue` outputs, see [`RecordingExecutor`]),
+/// as well as returning the same output for every call.
+/// Commands without matching response fail.
+///
+/// File transfers always succeed without transferring anything.
+/// All executed commands and transfers are logged (see [`MockExecutor::calls`]).
+pub struct MockExecutor {
+    responses: Vec<(CommandPattern, CommandOutput)>,
+    used: Mutex<Vec<bool>>,
+    calls: Mutex<Vec<String>>,
+}
+
+impl MockExecutor {
+    /// Create a mock executor without any responses
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Replay the given recorded commands (matching the exact commands)
+    pub fn from_recorded(recorded: impl IntoIterator<Item = RecordedCommand>) -> Self {
+        recorded.into_iter().fold(Self::new(), |mock, r| {
+            mock.with_exact_response(r.command, r.output)
+        })
+    }
+
+    /// Replay the recorded commands of a fixture file (see [`RecordingExecutor::save_fixture`])
+    pub fn from_fixture(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let recorded: Vec<RecordedCommand> = serde_json::from_reader(std::fs::File::open(path)?)?;
+        Ok(Self::from_recorded(recorded))
+    }
+
+    /// Return `stdout` (with exit status `0`) for commands starting with `command_prefix` (e.g., `squeue`)
+    pub fn with_output(self, command_prefix: impl Into<String>, stdout: impl Into<String>) -> Self {
+        self.with_response(
+            command_prefix,
+            CommandOutput {
+                stdout: stdout.into(),
+                ..Default::default()
+            },
+        )
+    }
+
+    /// Return `output` for commands starting with `command_prefix`
+    pub fn with_response(self, command_prefix: impl Into<String>, output: CommandOutput) -> Self {
+        self.push(CommandPattern::Prefix(command_prefix.into()), output)
+    }
+
+    /// Return `output` for exactly the given `command`
+    pub fn with_exact_response(self, command: impl Into<String>, output: CommandOutput) -> Self {
+        self.push(CommandPattern::Exact(command.into()), output)
+    }
+
+    fn push(mut self, pattern: CommandPattern, output: CommandOutput) -> Self {
+        self.responses.push((pattern, output));
+        self.used.get_mut().unwrap().push(false);
+        self
+    }
+
+    /// All commands executed so far (and transfers, as `upload <local path> <remote path>` or `download <remote path> <local path>`)
+    pub fn calls(&self) -> Vec<String> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    fn respond(&self, command: &str) -> Result<CommandOutput, Error> {
+        self.calls.lock().unwrap().push(command.to_string());
+        let mut used = self.used.lock().unwrap();
+        let matching: Vec<usize> = (0..self.responses.len())
+            .filter(|&i| self.responses[i].0.matches(command))
+            .collect();
+        let index = matching
+            .iter()
+            .find(|&&i| !used[i])
+            .or(matching.last())
+            .copied()
+            .ok_or_else(|| Error::msg(format!("No mock output for command {command}.")))?;
+        used[index] = true;
+        Ok(self.responses[index].1.clone())
+    }
+}
+
+impl CommandExecutor for MockExecutor {
+    async fn execute(&self, command: &str) -> Result<CommandOutput, Error> {
+        self.respond(command)
+    }
+
+    async fn upload(&self, local_path: &Path, remote_path: &str) -> Result<(), Error> {
+        let call = format!("upload {} {remote_path}", local_path.display());
+        self.calls.lock().unwrap().push(call);
+        Ok(())
+    }
+
+    async fn download(&self, remote_path: &str, local_path: &Path) -> Result<(), Error> {
+        let call = format!("download {remote_path} {}", local_path.display());
+        self.calls.lock().unwrap().push(call);
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+/// Executor recording all commands executed using the wrapped executor (e.g., an SSH client), to create fixtures for [`MockExecutor`]
+pub struct RecordingExecutor<E> {
+    inner: E,
+    recorded: Mutex<Vec<RecordedCommand>>,
+}
+
+impl<E: CommandExecutor> RecordingExecutor<E> {
+    /// Record all commands executed using `inner`
+    pub fn new(inner: E) -> Self {
+        Self {
+            inner,
+            recorded: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// All commands recorded so far
+    pub fn recorded(&self) -> Vec<RecordedCommand> {
+        self.recorded.lock().unwrap().clone()
+    }
+
+    /// Store all commands recorded so far as JSON fixture file (see [`MockExecutor::from_fixture`])
+    pub fn save_fixture(&self, path: impl AsRef<Path>) -> Result<(), Error> {
+        let file = std::fs::File::create(path)?;
+        serde_json::to_writer_pretty(file, &self.recorded())?;
+        Ok(())
+    }
+
+    /// The wrapped executor
+    pub fn into_inner(self) -> E {
+        self.inner
+    }
+
+    fn record(&self, command: &str, output: &CommandOutput) {
+        self.recorded.lock().unwrap().push(RecordedCommand {
+            command: command.to_string(),
+            output: output.clone(),
+        });
+    }
+}
+
+impl<E: CommandExecutor> CommandExecutor for RecordingExecutor<E> {
+    async fn execute(&self, command: &str) -> Result<CommandOutput, Error> {
+        let output = self.inner.execute(command).await?;
+        self.record(command, &output);
+        Ok(output)
+    }
+
+    async fn execute_once(&self, command: &str) -> Result<CommandOutput, Error> {
+        let output = self.inner.execute_once(command).await?;
+        self.record(command, &output);
+        Ok(output)
+    }
+
+    async fn upload(&self, local_path: &Path, remote_path: &str) -> Result<(), Error> {
+        self.inner.upload(local_path, remote_path).await
+    }
+
+    async fn download(&self, remote_path: &str, local_path: &Path) -> Result<(), Error> {
+        self.inner.download(remote_path, local_path).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MockExecutor, RecordingExecutor};
+    use crate::executor::{CommandExecutor, LocalExecutor};
+
+    #[tokio::test]
+    async fn test_mock_executor() {
+        let mock = MockExecutor::new()
+            .with_output("sbatch", "4242\n")
+            .with_output("squeue", "first")
+            .with_output("squeue", "second");
+        assert_eq!(
+            mock.execute("sbatch --parsable start.sh")
+                .await
+                .unwrap()
+                .stdout,
+            "4242\n"
+        );
+        for expected in ["first", "second", "second"] {
+            assert_eq!(mock.execute("squeue -a").await.unwrap().stdout, expected);
+        }
+        assert!(mock.execute("scancel 4242").await.is_err());
+        assert_eq!(mock.calls().len(), 5);
+
+        // Record and replay outputs
+        let recorder = RecordingExecutor::new(LocalExecutor);
+        recorder.execute("echo 1").await.unwrap();
+        recorder.execute_once("echo 2 >&2; exit 1").await.unwrap();
+        let path = std::env::temp_dir().join("slurry_test_mock_executor.json");
+        recorder.save_fixture(&path).unwrap();
+        let replay = MockExecutor::from_fixture(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(replay.execute("echo 1").await.unwrap().stdout, "1\n");
+        let failed = replay.execute("echo 2 >&2; exit 1").await.unwrap();
+        assert_eq!((failed.stderr.as_str(), failed.exit_status), ("2\n", 1));
+        assert!(replay.execute("echo").await.is_err());
+    }
+}
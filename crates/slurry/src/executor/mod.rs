@@ -0,0 +1,310 @@
+use std::{future::Future, path::Path, process::Command, time::Duration};
+
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+
+/// Mock executors returning canned outputs, and recording real outputs as fixtures
+pub mod mock;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Error returned if an operation did not finish in time (e.g., a hung `squeue` command)
+///
+/// Reported as [`anyhow::Error`], and can be detected using `err.downcast_ref::<Timeout>()`.
+pub struct Timeout {
+    /// The operation which timed out (e.g., the executed command)
+    pub operation: String,
+    /// The timeout which was exceeded
+    pub after: Duration,
+}
+
+impl std::fmt::Display for Timeout {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Timed out after {:?}: {}", self.after, self.operation)
+    }
+}
+
+impl std::error::Error for Timeout {}
+
+/// Quote `s` as a single argument for POSIX shells (using single quotes)
+pub(crate) fn shell_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+#[cfg(feature = "stream")]
+/// Run `fut`, failing with a [`Timeout`] error if it does not finish within `timeout` (if set)
+pub async fn with_timeout<T>(
+    timeout: Option<Duration>,
+    operation: &str,
+    fut: impl Future<Output = Result<T, Error>>,
+) -> Result<T, Error> {
+    match timeout {
+        Some(after) => tokio::time::timeout(after, fut).await.map_err(|_| {
+            Error::new(Timeout {
+                operation: operation.to_string(),
+                after,
+            })
+        })?,
+        None => fut.await,
+    }
+}
+
+#[cfg(feature = "stream")]
+#[derive(Debug, Clone)]
+/// Executor failing commands of the wrapped executor (e.g., a SSH [`Client`](crate::Client)) with a [`Timeout`] error if they do not finish in time
+///
+/// File transfers are not subject to the timeout, as their duration depends on the size of the files.
+/// Functions requiring a `&Client` accept `&executor` (dereferencing to the wrapped client), but do not apply the timeout.
+pub struct TimeoutExecutor<E> {
+    inner: E,
+    timeout: Option<Duration>,
+}
+
+#[cfg(feature = "stream")]
+impl<E> TimeoutExecutor<E> {
+    /// Wrap `inner`, applying `timeout` to each command (no timeout if `None`)
+    pub fn new(inner: E, timeout: Option<Duration>) -> Self {
+        Self { inner, timeout }
+    }
+
+    /// The timeout applied to each command
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Unwrap the wrapped executor
+    pub fn into_inner(self) -> E {
+        self.inner
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<E> std::ops::Deref for TimeoutExecutor<E> {
+    type Target = E;
+
+    fn deref(&self) -> &Self::Target {
+        &self.inner
+    }
+}
+
+#[cfg(feature = "stream")]
+impl<E: CommandExecutor> CommandExecutor for TimeoutExecutor<E> {
+    async fn execute(&self, command: &str) -> Result<CommandOutput, Error> {
+        with_timeout(self.timeout, command, self.inner.execute(command)).await
+    }
+
+    async fn execute_once(&self, command: &str) -> Result<CommandOutput, Error> {
+        with_timeout(self.timeout, command, self.inner.execute_once(command)).await
+    }
+
+    async fn upload(&self, local_path: &Path, remote_path: &str) -> Result<(), Error> {
+        self.inner.upload(local_path, remote_path).await
+    }
+
+    async fn download(&self, remote_path: &str, local_path: &Path) -> Result<(), Error> {
+        self.inner.download(remote_path, local_path).await
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+/// Output of a command executed using a [`CommandExecutor`]
+pub struct CommandOutput {
+    /// Standard output of the command
+    pub stdout: String,
+    /// Standard error of the command
+    pub stderr: String,
+    /// Exit status of the command (`0` on success)
+    pub exit_status: u32,
+}
+
+/// Backend for executing shell commands on (and transferring files to and from) a SLURM system
+///
+/// Implemented by
+/// - [`LocalExecutor`], executing commands on the local machine,
+/// - the SSH [`Client`](crate::Client) (requires feature `ssh`),
+/// - closures mapping a command to its stdout (e.g., to provide fixed outputs in tests),
+/// - [`MockExecutor`](mock::MockExecutor), returning canned or recorded outputs (see [`RecordingExecutor`](mock::RecordingExecutor)).
+///
+/// All data extraction functions (e.g., [`get_squeue_res`](crate::data_extraction::get_squeue_res)) are generic over this trait,
+/// as well as all job management functions which do not require a dedicated SSH channel (e.g., `get_job_status` or `cancel_job`).
+pub trait CommandExecutor: Send + Sync {
+    /// Execute the given shell `command`
+    ///
+    /// A non-zero exit status is not treated as error, but reported in the [`CommandOutput`].
+    fn execute(&self, command: &str) -> impl Future<Output = Result<CommandOutput, Error>> + Send;
+
+    /// Execute the given shell `command` at most once, e.g., commands which must not be repeated (like `sbatch`)
+    ///
+    /// Executors retrying failed commands (e.g., a `ResilientClient`) do not retry these commands, all others execute them as usual.
+    fn execute_once(
+        &self,
+        command: &str,
+    ) -> impl Future<Output = Result<CommandOutput, Error>> + Send {
+        self.execute(command)
+    }
+
+    /// Upload the local file at `local_path` to `remote_path`, overwriting existing files
+    fn upload(
+        &self,
+        local_path: &Path,
+        remote_path: &str,
+    ) -> impl Future<Output = Result<(), Error>> + Send;
+
+    /// Download the file at `remote_path` to the local file at `local_path`, overwriting existing files
+    fn download(
+        &self,
+        remote_path: &str,
+        local_path: &Path,
+    ) -> impl Future<Output = Result<(), Error>> + Send;
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// Executes commands on the local machine (i.e., not via SSH) using `sh -c`
+///
+/// Uploads and downloads are simply copies between local paths.
+pub struct LocalExecutor;
+
+impl CommandExecutor for LocalExecutor {
+    async fn execute(&self, command: &str) -> Result<CommandOutput, Error> {
+        let out = Command::new("sh").arg("-c").arg(command).output()?;
+        Ok(CommandOutput {
+            stdout: String::from_utf8(out.stdout)?,
+            stderr: String::from_utf8_lossy(&out.stderr).to_string(),
+            // Commands terminated by a signal do not have an exit code
+            exit_status: out.status.code().map_or(u32::MAX, |code| code as u32),
+        })
+    }
+
+    async fn upload(&self, local_path: &Path, remote_path: &str) -> Result<(), Error> {
+        std::fs::copy(local_path, remote_path)?;
+        Ok(())
+    }
+
+    async fn download(&self, remote_path: &str, local_path: &Path) -> Result<(), Error> {
+        std::fs::copy(remote_path, local_path)?;
+        Ok(())
+    }
+}
+
+#[cfg(feature = "ssh")]
+impl CommandExecutor for async_ssh2_tokio::Client {
+    async fn execute(&self, command: &str) -> Result<CommandOutput, Error> {
+        let r = async_ssh2_tokio::Client::execute(self, command).await?;
+        Ok(CommandOutput {
+            stdout: r.stdout,
+            stderr: r.stderr,
+            exit_status: r.exit_status,
+        })
+    }
+
+    async fn upload(&self, local_path: &Path, remote_path: &str) -> Result<(), Error> {
+        crate::job_management::transfer::upload_file_with_progress(
+            self,
+            local_path,
+            remote_path,
+            |_| {},
+        )
+        .await
+    }
+
+    async fn download(&self, remote_path: &str, local_path: &Path) -> Result<(), Error> {
+        crate::job_management::transfer::download_file_with_progress(
+            self,
+            remote_path,
+            local_path,
+            |_| {},
+        )
+        .await
+    }
+}
+
+/// Closures returning the stdout of the given command, e.g., `|cmd| async move { Ok(client.execute(&cmd).await?.stdout) }`
+///
+/// The exit status is always reported as `0` and file transfers are not supported.
+impl<F, Fut> CommandExecutor for F
+where
+    F: Fn(String) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<String, Error>> + Send,
+{
+    fn execute(&self, command: &str) -> impl Future<Output = Result<CommandOutput, Error>> + Send {
+        let stdout = self(command.to_string());
+        async move {
+            Ok(CommandOutput {
+                stdout: stdout.await?,
+                ..Default::default()
+            })
+        }
+    }
+
+    async fn upload(&self, _local_path: &Path, _remote_path: &str) -> Result<(), Error> {
+        Err(Error::msg(
+            "Uploading files is not supported by this executor.",
+        ))
+    }
+
+    async fn download(&self, _remote_path: &str, _local_path: &Path) -> Result<(), Error> {
+        Err(Error::msg(
+            "Downloading files is not supported by this executor.",
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    #[cfg(feature = "stream")]
+    use super::{with_timeout, Timeout, TimeoutExecutor};
+    use super::{CommandExecutor, LocalExecutor};
+
+    #[tokio::test]
+    async fn test_local_executor() {
+        let out = LocalExecutor
+            .execute("echo 'out' && echo 'err' >&2 && exit 3")
+            .await
+            .unwrap();
+        assert_eq!(out.stdout, "out\n");
+        assert_eq!(out.stderr, "err\n");
+        assert_eq!(out.exit_status, 3);
+        let fixed = |cmd: String| async move { Ok(format!("ran {cmd}")) };
+        let out = fixed.execute("squeue").await.unwrap();
+        assert_eq!((out.stdout.as_str(), out.exit_status), ("ran squeue", 0));
+        assert!(fixed.upload(std::path::Path::new("a"), "b").await.is_err());
+    }
+
+    #[cfg(feature = "stream")]
+    #[tokio::test]
+    async fn test_with_timeout() {
+        let after = std::time::Duration::from_millis(20);
+        let err = with_timeout(Some(after), "squeue", async {
+            tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+            Ok(())
+        })
+        .await
+        .unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<Timeout>(),
+            Some(&Timeout {
+                operation: "squeue".to_string(),
+                after
+            })
+        );
+        let out = with_timeout(Some(after), "date", LocalExecutor.execute("echo 1")).await;
+        assert_eq!(out.unwrap().stdout, "1\n");
+        let slow = |cmd: String| async move {
+            if cmd == "squeue" {
+                tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+            }
+            Ok(cmd)
+        };
+        let executor = TimeoutExecutor::new(slow, Some(after));
+        let err = executor.execute("squeue").await.unwrap_err();
+        assert_eq!(err.downcast_ref::<Timeout>().unwrap().operation, "squeue");
+        assert_eq!(executor.execute("date").await.unwrap().stdout, "date");
+        assert_eq!(executor.execute_once("date").await.unwrap().stdout, "date");
+        assert!(with_timeout(None, "fail", async {
+            Err::<(), _>(anyhow::Error::msg("fail"))
+        })
+        .await
+        .unwrap_err()
+        .downcast_ref::<Timeout>()
+        .is_none());
+    }
+}
@@ -0,0 +1,132 @@
+use std::collections::BTreeMap;
+#[cfg(feature = "ssh")]
+use std::path::Path;
+
+use anyhow::Error;
+#[cfg(feature = "ssh")]
+use async_ssh2_tokio::Client;
+
+use crate::executor::CommandExecutor;
+
+use super::sbatch::shell_quote;
+#[cfg(feature = "ssh")]
+use super::{transfer::upload_file_with_progress, TransferProgress};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Container in which the command of a job is executed (using `apptainer exec`), see [`JobOptions::container`](super::JobOptions::container)
+pub struct ContainerSpec {
+    /// Path of the container image on the cluster (e.g., a `.sif` file), relative to the job folder or absolute
+    pub image: String,
+    /// Paths to bind into the container (`--bind`), e.g., `/hpcwork` or `/data:/mnt/data:ro`
+    pub binds: Vec<String>,
+    /// Environment variables to set inside the container (`--env`)
+    pub env: BTreeMap<String, String>,
+}
+
+impl ContainerSpec {
+    /// Create a new container spec for the given image
+    pub fn new(image: impl Into<String>) -> Self {
+        Self {
+            image: image.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Bind the given path into the container (`--bind`)
+    pub fn with_bind(mut self, bind: impl Into<String>) -> Self {
+        self.binds.push(bind.into());
+        self
+    }
+
+    /// Set the environment variable `key` inside the container (`--env`)
+    pub fn with_env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.insert(key.into(), value.into());
+        self
+    }
+
+    /// Wrap the given shell `command`, such that it is executed inside the container
+    pub fn wrap_command(&self, command: &str) -> String {
+        let mut args = vec![String::from("apptainer exec")];
+        if !self.binds.is_empty() {
+            args.push(format!("--bind {}", shell_quote(&self.binds.join(","))));
+        }
+        for (key, value) in &self.env {
+            args.push(format!("--env {}", shell_quote(&format!("{key}={value}"))));
+        }
+        args.push(shell_quote(&self.image));
+        args.push(format!("sh -c {}", shell_quote(command)));
+        args.join(" ")
+    }
+}
+
+/// Pull a container image from a registry (e.g., `docker://python:3.12`) to `remote_path` on the cluster (using `apptainer pull`)
+///
+/// Existing images at `remote_path` are overwritten.
+pub async fn pull_container_image(
+    client: &impl CommandExecutor,
+    source: &str,
+    remote_path: &str,
+) -> Result<(), Error> {
+    let r = client
+        .execute(&format!(
+            "apptainer pull --force {} {}",
+            shell_quote(remote_path),
+            shell_quote(source)
+        ))
+        .await?;
+    if r.exit_status != 0 {
+        return Err(Error::msg(format!(
+            "Could not pull container image {source}: {}",
+            r.stderr.trim()
+        )));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "ssh")]
+/// Upload a local container image to `remote_path` on the cluster, reporting progress to `on_progress`
+pub async fn upload_container_image(
+    client: &Client,
+    local_path: &Path,
+    remote_path: &str,
+    mut on_progress: impl FnMut(&TransferProgress),
+) -> Result<(), Error> {
+    let mut progress = TransferProgress {
+        file: remote_path.to_string(),
+        total_files: 1,
+        ..Default::default()
+    };
+    progress.file_total_bytes = tokio::fs::metadata(local_path).await?.len();
+    progress.total_bytes = progress.file_total_bytes;
+    upload_file_with_progress(client, local_path, remote_path, |bytes| {
+        progress.file_transferred_bytes += bytes;
+        progress.transferred_bytes += bytes;
+        on_progress(&progress);
+    })
+    .await?;
+    progress.transferred_files = 1;
+    on_progress(&progress);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ContainerSpec;
+
+    #[test]
+    fn test_wrap_command() {
+        let container = ContainerSpec::new("images/python.sif");
+        assert_eq!(
+            container.wrap_command("python main.py"),
+            "apptainer exec 'images/python.sif' sh -c 'python main.py'"
+        );
+        let container = container
+            .with_bind("/hpcwork")
+            .with_bind("/data:/mnt/data:ro")
+            .with_env("OMP_NUM_THREADS", "4");
+        assert_eq!(
+            container.wrap_command("echo 'done' > out.txt"),
+            r"apptainer exec --bind '/hpcwork,/data:/mnt/data:ro' --env 'OMP_NUM_THREADS=4' 'images/python.sif' sh -c 'echo '\''done'\'' > out.txt'"
+        );
+    }
+}
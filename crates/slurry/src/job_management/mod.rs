@@ -1,12 +1,57 @@
-use std::{collections::HashSet, path::PathBuf, sync::Arc, time::SystemTime};
+#[cfg(feature = "ssh")]
+use std::sync::{Arc, Mutex};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::{Duration, Instant, SystemTime},
+};
 
-use anyhow::{Error, Ok};
+use anyhow::Error;
+#[cfg(feature = "ssh")]
 use async_ssh2_tokio::Client;
-use chrono::{DateTime, NaiveDateTime, Utc};
+use chrono::{DateTime, FixedOffset, Utc};
 use serde::{Deserialize, Serialize};
-use tokio::task::JoinSet;
+#[cfg(feature = "ssh")]
+use tokio::{sync::Semaphore, task::JoinSet};
 
-use crate::JobState;
+#[cfg(feature = "ssh")]
+use crate::data_extraction::scontrol::{parse_scontrol_key_values, scontrol_value};
+use crate::{
+    data_extraction::{get_sstat_for_job, squeue::SqueueRow, SacctJobRow},
+    executor::CommandExecutor,
+    misc::polling::AdaptiveInterval,
+    recording::{JobProfile, JobProfileSample},
+    ArrayTaskRange, JobState, MemorySpec, SlurmDuration, TresRequest,
+};
+
+/// Builder for SLURM batch scripts
+pub mod sbatch;
+
+#[cfg(feature = "ssh")]
+/// Transferring files from and to SLURM clusters
+pub mod transfer;
+
+/// Executing jobs inside containers (using Apptainer/Singularity)
+pub mod container;
+
+/// Workflows of multiple dependent SLURM jobs
+pub mod workflow;
+
+/// Validating job options before submission
+pub mod validation;
+
+#[cfg(feature = "ssh")]
+pub use container::upload_container_image;
+pub use container::{pull_container_image, ContainerSpec};
+pub(crate) use sbatch::shell_quote;
+pub use sbatch::{sanitize_job_name, JobShell, MailType, SbatchScript, ScriptSetup};
+#[cfg(feature = "ssh")]
+pub use transfer::{download_job_results, TransferProgress};
+#[cfg(feature = "ssh")]
+use transfer::{upload_file_with_progress, write_remote_file};
+pub use validation::{ClusterLimits, JobOptionsViolation};
+pub use workflow::{Workflow, WorkflowRun, WorkflowState};
 
 type JobID = String;
 type FolderID = String;
@@ -16,16 +61,80 @@ type FolderID = String;
 pub struct JobOptions {
     /// The root directory (i.e., where the job should be started)
     pub root_dir: String,
+    /// Name of the job (`--job-name`), sanitized using [`sanitize_job_name`]
+    ///
+    /// If not set, the ID of the job folder is used.
+    pub name: Option<String>,
     /// Files to upload before starting the job (e.g., the binary that should be started or required data files)
     pub files_to_upload: HashSet<JobFilesToUpload>,
     /// How many CPUs to request per task (`--cpus-per-task`)
     pub num_cpus: usize,
     /// How long the job should be executed (`--time`)
-    pub time: String,
+    pub time: SlurmDuration,
+    /// How much memory to request (`--mem` or `--mem-per-cpu`), if not using the cluster default
+    pub memory: Option<MemorySpec>,
+    /// If the job may be requeued (`--requeue`), e.g., using [`requeue_job`] or by SLURM after a node failure
+    pub requeue: bool,
+    /// Partition(s) to submit to (`--partition`), if not using the cluster default
+    pub partition: Option<String>,
+    /// Account to charge (`--account`), if not using the user's default account
+    pub account: Option<String>,
+    /// Quality of service (`--qos`), if not using the default QOS
+    pub qos: Option<String>,
+    /// Generic resources to request per node (`--gres`), e.g., GPUs
+    pub gres: Vec<TresRequest>,
+    /// Required node features (`--constraint`)
+    pub constraint: Option<String>,
+    /// Additional `#SBATCH` directives not modeled by these options (e.g., `--switches=1` or options of site plugins), emitted verbatim
+    pub extra_directives: Vec<String>,
+    /// Events for which mail notifications should be sent (`--mail-type`), requires a [`JobOptions::mail_user`]
+    pub mail_types: Vec<MailType>,
+    /// Recipient of mail notifications (`--mail-user`)
+    pub mail_user: Option<String>,
+    /// Dependencies on other jobs (`--dependency`), all of which have to be satisfied before the job can start
+    pub dependencies: Vec<JobDependency>,
+    /// Path of the stdout file (`--output`), relative to the job folder (default: `stdout.txt`)
+    ///
+    /// Supports placeholders like `%j` (job ID) or `%x` (job name).
+    pub output: Option<String>,
+    /// Path of the stderr file (`--error`), relative to the job folder (default: same file as the stdout output)
+    pub error: Option<String>,
+    /// Environment variables to export before executing the command
+    ///
+    /// Keys have to be valid shell variable names (see [`SbatchScript::check_env`]), values are quoted.
+    pub env: HashMap<String, String>,
+    /// Container to execute the command in (using `apptainer exec`), if any
+    pub container: Option<ContainerSpec>,
+    /// Setup steps (e.g., loading modules or activating environments), executed before the command
+    pub setup_lines: Vec<ScriptSetup>,
+    /// Shell interpreting the job script
+    pub shell: JobShell,
     /// The bash command to execute
     pub command: String,
-    /// Port forwarding configuartion, if local port on HPC node executing the job should be forwarded
-    pub local_forwarding: Option<JobLocalForwarding>,
+}
+
+#[derive(Debug, Clone, Hash, PartialEq, Eq)]
+/// Dependency of a SLURM job on other jobs (see [`JobOptions::dependencies`])
+pub enum JobDependency {
+    /// Start after the given job completed successfully (`afterok`)
+    AFTEROK(JobID),
+    /// Start after the given job terminated, regardless of its exit code (`afterany`)
+    AFTERANY(JobID),
+    /// Start after the given job failed (`afternotok`), e.g., for cleanup jobs
+    AFTERNOTOK(JobID),
+    /// Start after all previous jobs with the same name and user terminated (`singleton`)
+    SINGLETON,
+}
+
+impl std::fmt::Display for JobDependency {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JobDependency::AFTEROK(job_id) => write!(f, "afterok:{job_id}"),
+            JobDependency::AFTERANY(job_id) => write!(f, "afterany:{job_id}"),
+            JobDependency::AFTERNOTOK(job_id) => write!(f, "afternotok:{job_id}"),
+            JobDependency::SINGLETON => write!(f, "singleton"),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
@@ -40,123 +149,640 @@ pub struct JobFilesToUpload {
 }
 
 #[derive(Debug, Clone, Hash, PartialEq, Eq)]
-/// Port forwarding options
+/// Port forwarding options (see [`PortForward::for_job`](crate::PortForward::for_job))
 ///
 /// Can be used to forward a port of the executing HPC cluster node to the user's local machine.
-///
-/// Forwarding is done over a relay node directly accessible over SSH (e.g., the login node of the SLURM system)
+/// Forwarding is tunneled through the SSH connection to the login node (see [`JobForwardingMode`]).
 pub struct JobLocalForwarding {
     /// The port where the forwarding should be available locally
     pub local_port: u16,
-    /// The port to use for the relay
-    pub relay_port: u16,
-    /// The address of the relay (e.g., hostname)
-    pub relay_addr: String,
+    /// The port of the service on the node executing the job
+    pub job_port: u16,
+    /// How the port of the job is reached from the login node
+    pub mode: JobForwardingMode,
+}
+
+#[derive(Debug, Clone, Default, Hash, PartialEq, Eq)]
+/// How the port of a job is reached from the login node (see [`JobLocalForwarding`])
+pub enum JobForwardingMode {
+    #[default]
+    /// Connect to the port on the node executing the job directly
+    ///
+    /// The service started by the job has to listen on an address reachable from the login node (e.g., `0.0.0.0`, not only `localhost`).
+    Direct,
+    /// Relay the port from within the job to `relay_port` on the login node `relay_host` (`ssh -N -f -R`), for services only listening on `localhost`
+    ///
+    /// The job script has to start the relay (see [`JobLocalForwarding::relay_setup`]), requiring SSH access from the compute nodes to the login node.
+    /// The relay host has to be the login node of the SSH connection used for the forwarding (e.g., as reported by `hostname`).
+    LoginNodeRelay {
+        /// Host name of the login node
+        relay_host: String,
+        /// Port on the login node (only bound on its `localhost`)
+        relay_port: u16,
+    },
+}
+
+impl JobLocalForwarding {
+    /// Setup step starting the relay to the login node (see [`JobForwardingMode::LoginNodeRelay`]), to add to the [`JobOptions::setup_lines`]
+    ///
+    /// Returns `None` for [`JobForwardingMode::Direct`], which does not require any setup.
+    pub fn relay_setup(&self) -> Option<ScriptSetup> {
+        match &self.mode {
+            JobForwardingMode::Direct => None,
+            JobForwardingMode::LoginNodeRelay {
+                relay_host,
+                relay_port,
+            } => Some(ScriptSetup::Raw(format!(
+                "ssh -N -f -R {relay_port}:localhost:{} {}",
+                self.job_port,
+                shell_quote(relay_host)
+            ))),
+        }
+    }
+}
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A job to submit using [`submit_job`], consisting of a batch script and the files required by it
+///
+/// Created from [`JobOptions`] or from a custom [`SbatchScript`] (see [`JobSubmission::new`]).
+pub struct JobSubmission {
+    /// The root directory (i.e., where the job folder is created)
+    pub root_dir: String,
+    /// Files to upload into the job folder before submitting the job
+    pub files_to_upload: HashSet<JobFilesToUpload>,
+    /// The batch script to submit
+    ///
+    /// If no job name is set, the ID of the job folder is used.
+    pub script: SbatchScript,
+    /// Maximum number of files uploaded concurrently (default: [`DEFAULT_UPLOAD_CONCURRENCY`])
+    ///
+    /// Each upload uses a separate SSH channel, so a limit of `1` uploads all files sequentially.
+    pub upload_concurrency: usize,
+}
+
+/// Default maximum number of concurrently uploaded files (see [`JobSubmission::upload_concurrency`])
+pub const DEFAULT_UPLOAD_CONCURRENCY: usize = 8;
+
+impl JobSubmission {
+    /// Create a new submission of the given `script`, executed in a new job folder in `root_dir`
+    pub fn new(root_dir: impl Into<String>, script: SbatchScript) -> Self {
+        Self {
+            root_dir: root_dir.into(),
+            files_to_upload: HashSet::new(),
+            script,
+            upload_concurrency: DEFAULT_UPLOAD_CONCURRENCY,
+        }
+    }
+
+    /// Upload the given files into the job folder before submitting the job
+    pub fn with_files_to_upload(mut self, files_to_upload: HashSet<JobFilesToUpload>) -> Self {
+        self.files_to_upload = files_to_upload;
+        self
+    }
+
+    /// Upload at most `upload_concurrency` files concurrently (see [`JobSubmission::upload_concurrency`])
+    pub fn with_upload_concurrency(mut self, upload_concurrency: usize) -> Self {
+        self.upload_concurrency = upload_concurrency;
+        self
+    }
+}
+
+impl From<JobOptions> for JobSubmission {
+    fn from(job_options: JobOptions) -> Self {
+        let mut script = SbatchScript::new()
+            .with_shell(job_options.shell)
+            .with_ntasks(1)
+            .with_cpus_per_task(job_options.num_cpus)
+            .with_time(job_options.time)
+            .with_output(
+                job_options
+                    .output
+                    .unwrap_or_else(|| String::from("stdout.txt")),
+            )
+            .with_requeue(job_options.requeue);
+        script.job_name = job_options
+            .name
+            .map(|name| sanitize_job_name(&name))
+            .filter(|name| !name.is_empty());
+        script.error = job_options.error;
+        script.env = job_options.env.into_iter().collect();
+        script.memory = job_options.memory;
+        script.partition = job_options.partition;
+        script.account = job_options.account;
+        script.qos = job_options.qos;
+        script.gres = job_options.gres;
+        script.constraint = job_options.constraint;
+        script.extra_directives = job_options.extra_directives;
+        if !job_options.dependencies.is_empty() {
+            script.dependency = Some(
+                job_options
+                    .dependencies
+                    .iter()
+                    .map(|d| d.to_string())
+                    .collect::<Vec<_>>()
+                    .join(","),
+            );
+        }
+        if let Some(mail_user) = job_options.mail_user {
+            script = script.with_mail(job_options.mail_types, mail_user);
+        }
+        for setup in job_options.setup_lines {
+            script = script.with_setup(setup);
+        }
+        script = script.with_command(match &job_options.container {
+            Some(container) => container.wrap_command(&job_options.command),
+            None => job_options.command,
+        });
+        Self::new(job_options.root_dir, script).with_files_to_upload(job_options.files_to_upload)
+    }
+}
+
+impl From<(String, SbatchScript)> for JobSubmission {
+    fn from((root_dir, script): (String, SbatchScript)) -> Self {
+        Self::new(root_dir, script)
+    }
 }
+
+#[cfg(feature = "ssh")]
 /// Submit a job to SLURM over SSH
+///
+/// The job can either be described using [`JobOptions`], or as a [`JobSubmission`] of a custom [`SbatchScript`]
+/// (also accepting a tuple of the root directory and the script).
 pub async fn submit_job(
     client: Arc<Client>,
-    job_options: JobOptions,
-) -> Result<(FolderID, JobID), Error> {
-    // Create job folder
+    job: impl Into<JobSubmission>,
+) -> Result<(FolderID, SubmittedJob), Error> {
+    submit_job_with_progress(client, job, |_| {}).await
+}
+
+#[cfg(feature = "ssh")]
+/// Submit a job to SLURM over SSH (see [`submit_job`]), reporting the progress of uploading files to `on_progress`
+///
+/// Files are uploaded in parallel (see [`JobSubmission::upload_concurrency`]), so the reported [`TransferProgress::file`] can alternate between files.
+/// Uploads which failed (e.g., because the server limits the number of concurrent channels) are retried sequentially.
+pub async fn submit_job_with_progress(
+    client: Arc<Client>,
+    job: impl Into<JobSubmission>,
+    on_progress: impl Fn(&TransferProgress) + Send + Sync + 'static,
+) -> Result<(FolderID, SubmittedJob), Error> {
     let folder_id = DateTime::<Utc>::from(SystemTime::now()).to_rfc3339();
-    client
-        .execute(&format!(
-            "mkdir -p '{}/{}'",
-            job_options.root_dir, folder_id
+    submit_job_in_folder(client, folder_id, job.into(), Arc::new(on_progress)).await
+}
+
+/// Submit a job to SLURM using any [`CommandExecutor`] (see [`submit_job`]), e.g., a [`ResilientClient`](crate::ResilientClient)
+///
+/// In contrast to [`submit_job`], files are uploaded sequentially using [`CommandExecutor::upload`], without reporting progress.
+/// The script is uploaded from a temporary local file.
+/// `sbatch` is executed using [`CommandExecutor::execute_once`], so that the job is not submitted twice if the connection drops.
+pub async fn submit_job_with(
+    client: &impl CommandExecutor,
+    job: impl Into<JobSubmission>,
+) -> Result<(FolderID, SubmittedJob), Error> {
+    let JobSubmission {
+        root_dir,
+        files_to_upload,
+        mut script,
+        ..
+    } = job.into();
+    script.check_env()?;
+    let folder_id = DateTime::<Utc>::from(SystemTime::now()).to_rfc3339();
+    let job_dir = format!("{root_dir}/{folder_id}");
+    let mut dirs: Vec<String> = files_to_upload
+        .iter()
+        .map(|f| shell_quote(&format!("{job_dir}/{}", f.remote_subpath)))
+        .collect();
+    dirs.push(shell_quote(&job_dir));
+    let r = client
+        .execute(&format!("mkdir -p {}", dirs.join(" ")))
+        .await?;
+    if r.exit_status != 0 {
+        return Err(Error::msg(format!(
+            "Could not create job folder {job_dir}: {}",
+            r.stderr.trim()
+        )));
+    }
+    for file_to_upload in &files_to_upload {
+        let file = Path::new(&file_to_upload.remote_subpath)
+            .join(&file_to_upload.remote_file_name)
+            .to_string_lossy()
+            .to_string();
+        client
+            .upload(&file_to_upload.local_path, &format!("{job_dir}/{file}"))
+            .await?;
+    }
+
+    script.job_name.get_or_insert_with(|| folder_id.clone());
+    let local_script = std::env::temp_dir().join(format!(
+        "slurry-{}-{}.sh",
+        std::process::id(),
+        sanitize_job_name(&folder_id)
+    ));
+    std::fs::write(&local_script, script.to_string())?;
+    let script_path = format!("{job_dir}/start.sh");
+    let res = client.upload(&local_script, &script_path).await;
+    let _ = std::fs::remove_file(&local_script);
+    res?;
+
+    let job_dir = shell_quote(&job_dir);
+    let sbatch_out = client
+        .execute_once(&format!(
+            "chmod +x {job_dir}/start.sh && cd {job_dir} && sbatch --parsable start.sh"
         ))
         .await?;
+    if sbatch_out.exit_status != 0 {
+        return Err(Error::msg(format!(
+            "sbatch failed: {}",
+            sbatch_out.stderr.trim()
+        )));
+    }
+    Ok((folder_id, sbatch_out.stdout.parse()?))
+}
+
+/// Maximum number of jobs submitted concurrently by [`submit_jobs`]
+pub const DEFAULT_SUBMIT_CONCURRENCY: usize = 8;
 
+#[cfg(feature = "ssh")]
+/// Submit many jobs to SLURM over SSH at once (e.g., the jobs of a parameter sweep)
+///
+/// Compared to calling [`submit_job`] for every job, this
+/// - creates the job folders of up to [`DEFAULT_SUBMIT_CONCURRENCY`] jobs concurrently,
+/// - uploads files which are required by multiple jobs (i.e., with the same local path and root directory) only once,
+///   and hard-links them into the job folders (falling back to copying them on the cluster).
+///
+/// As jobs are submitted concurrently, the files of each job are uploaded sequentially (ignoring [`JobSubmission::upload_concurrency`]).
+/// Returns the result of every job, in the order of `jobs`, or an error if uploading the shared files failed (in which case no job is submitted).
+pub async fn submit_jobs(
+    client: Arc<Client>,
+    jobs: Vec<impl Into<JobSubmission>>,
+) -> Result<Vec<Result<(FolderID, SubmittedJob), Error>>, Error> {
+    let jobs: Vec<JobSubmission> = jobs.into_iter().map(Into::into).collect();
+    let batch_id = DateTime::<Utc>::from(SystemTime::now()).to_rfc3339();
+    let shared_files = find_shared_files(&jobs);
+
+    // Upload shared files once per root directory
+    let mut shared_dirs: BTreeMap<&str, String> = BTreeMap::new();
+    for (root_dir, _) in &shared_files {
+        shared_dirs
+            .entry(root_dir)
+            .or_insert_with(|| format!("{root_dir}/{batch_id}-shared"));
+    }
+    for (root_dir, shared_dir) in &shared_dirs {
+        let files = shared_files
+            .iter()
+            .enumerate()
+            .filter(|(_, (r, _))| r == root_dir)
+            .map(|(i, (_, local_path))| JobFilesToUpload {
+                local_path: local_path.clone(),
+                remote_subpath: String::new(),
+                remote_file_name: i.to_string(),
+            })
+            .collect();
+        upload_job_files(
+            &client,
+            shared_dir,
+            files,
+            DEFAULT_UPLOAD_CONCURRENCY,
+            Arc::new(|_: &TransferProgress| {}),
+        )
+        .await?;
+    }
+
+    let semaphore = Arc::new(Semaphore::new(DEFAULT_SUBMIT_CONCURRENCY));
     let mut set = JoinSet::new();
-    let root_dir = job_options.root_dir.clone();
+    let width = jobs.len().saturating_sub(1).to_string().len();
+    for (i, mut job) in jobs.into_iter().enumerate() {
+        let folder_id = format!("{batch_id}-{i:0width$}");
+        let job_dir = format!("{}/{folder_id}", job.root_dir);
+        // Link shared files into the job folder instead of uploading them
+        let mut link_commands = vec![format!("mkdir -p {}", shell_quote(&job_dir))];
+        job.files_to_upload.retain(|file| {
+            let Some(index) = shared_files
+                .iter()
+                .position(|(r, p)| r == &job.root_dir && p == &file.local_path)
+            else {
+                return true;
+            };
+            let source = shell_quote(&format!("{}/{index}", shared_dirs[job.root_dir.as_str()]));
+            let target_dir = format!("{job_dir}/{}", file.remote_subpath);
+            let target = shell_quote(&format!("{target_dir}/{}", file.remote_file_name));
+            link_commands.push(format!(
+                "mkdir -p {} && {{ ln -f {source} {target} || cp -f {source} {target}; }}",
+                shell_quote(&target_dir)
+            ));
+            false
+        });
+        job.upload_concurrency = 1;
+        let client = Arc::clone(&client);
+        let semaphore = Arc::clone(&semaphore);
+        set.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            let res = async {
+                let r =
+                    CommandExecutor::execute(client.as_ref(), &link_commands.join(" && ")).await?;
+                if r.exit_status != 0 {
+                    return Err(Error::msg(format!(
+                        "Could not link shared files: {}",
+                        r.stderr.trim()
+                    )));
+                }
+                submit_job_in_folder(client, folder_id, job, Arc::new(|_: &TransferProgress| {}))
+                    .await
+            }
+            .await;
+            (i, res)
+        });
+    }
+    let mut results = set.join_all().await;
+    results.sort_by_key(|(i, _)| *i);
+
+    // Shared files are only removed after all jobs are submitted, as they are still linked into the job folders
+    for shared_dir in shared_dirs.values() {
+        CommandExecutor::execute(
+            client.as_ref(),
+            &format!("rm -rf {}", shell_quote(shared_dir)),
+        )
+        .await?;
+    }
+    Ok(results.into_iter().map(|(_, res)| res).collect())
+}
 
-    // Upload all files
-    job_options
-        .files_to_upload
+#[cfg(feature = "ssh")]
+/// Find all local files which are uploaded more than once into the same root directory by the given `jobs`
+///
+/// Returns the root directory and local path of these files, sorted and without duplicates.
+fn find_shared_files(jobs: &[JobSubmission]) -> Vec<(String, PathBuf)> {
+    let mut counts: BTreeMap<(String, PathBuf), usize> = BTreeMap::new();
+    for job in jobs {
+        for file in &job.files_to_upload {
+            *counts
+                .entry((job.root_dir.clone(), file.local_path.clone()))
+                .or_default() += 1;
+        }
+    }
+    counts
         .into_iter()
-        .for_each(|file_to_upload| {
-            let root_dir = root_dir.clone();
-            let client_arc = Arc::clone(&client);
-            let file_to_upload = file_to_upload.clone();
-            let folder_id = folder_id.clone();
-            set.spawn(async move {
-                client_arc
-                    .execute(&format!(
-                        "mkdir -p '{}/{}/{}'",
-                        root_dir, folder_id, file_to_upload.remote_subpath
-                    ))
-                    .await
-                    .unwrap_or_else(|_| {
-                        panic!(
-                            "Could not create directory for file {}",
-                            file_to_upload.remote_subpath
-                        )
-                    });
-                client_arc
-                    .upload_file(
-                        &file_to_upload.local_path,
-                        format!(
-                            "{}/{}/{}/{}",
-                            root_dir,
-                            folder_id,
-                            file_to_upload.remote_subpath,
-                            file_to_upload.remote_file_name
-                        ),
-                    )
-                    .await
-            });
+        .filter(|(_, count)| *count > 1)
+        .map(|(file, _)| file)
+        .collect()
+}
+
+#[cfg(feature = "ssh")]
+/// Submit a job in the job folder `folder_id` (which is created if it does not exist yet)
+async fn submit_job_in_folder<F: Fn(&TransferProgress) + Send + Sync + 'static>(
+    client: Arc<Client>,
+    folder_id: FolderID,
+    job: JobSubmission,
+    on_progress: Arc<F>,
+) -> Result<(FolderID, SubmittedJob), Error> {
+    let JobSubmission {
+        root_dir,
+        files_to_upload,
+        mut script,
+        upload_concurrency,
+    } = job;
+    script.check_env()?;
+    // Create job folder
+    let job_dir = format!("{root_dir}/{folder_id}");
+    let r = CommandExecutor::execute(
+        client.as_ref(),
+        &format!("mkdir -p {}", shell_quote(&job_dir)),
+    )
+    .await?;
+    if r.exit_status != 0 {
+        return Err(Error::msg(format!(
+            "Could not create job folder {job_dir}: {}",
+            r.stderr.trim()
+        )));
+    }
+    upload_job_files(
+        &client,
+        &job_dir,
+        files_to_upload,
+        upload_concurrency,
+        on_progress,
+    )
+    .await?;
+
+    // Create script on system
+    script.job_name.get_or_insert_with(|| folder_id.clone());
+    let script_path = format!("{job_dir}/start.sh");
+    write_remote_file(&client, &script_path, script.to_string().as_bytes()).await?;
+    let r = CommandExecutor::execute(
+        client.as_ref(),
+        &format!("chmod +x {}", shell_quote(&script_path)),
+    )
+    .await?;
+    if r.exit_status != 0 {
+        return Err(Error::msg(format!(
+            "Could not make {script_path} executable: {}",
+            r.stderr.trim()
+        )));
+    }
+
+    // Schedule job & get job id
+    let sbatch_out = CommandExecutor::execute(
+        client.as_ref(),
+        &format!("cd {} && sbatch --parsable start.sh", shell_quote(&job_dir)),
+    )
+    .await?;
+    if sbatch_out.exit_status != 0 {
+        return Err(Error::msg(format!(
+            "sbatch failed: {}",
+            sbatch_out.stderr.trim()
+        )));
+    }
+    Ok((folder_id, sbatch_out.stdout.parse()?))
+}
+
+#[cfg(feature = "ssh")]
+/// Upload all files into the job folder `job_dir`, using at most `upload_concurrency` channels at once
+///
+/// Uploads which failed are retried sequentially.
+async fn upload_job_files<F: Fn(&TransferProgress) + Send + Sync + 'static>(
+    client: &Arc<Client>,
+    job_dir: &str,
+    files_to_upload: HashSet<JobFilesToUpload>,
+    upload_concurrency: usize,
+    on_progress: Arc<F>,
+) -> Result<(), Error> {
+    let mut total_bytes = 0;
+    for file_to_upload in &files_to_upload {
+        total_bytes += tokio::fs::metadata(&file_to_upload.local_path).await?.len();
+    }
+    let progress = Arc::new(Mutex::new(TransferProgress {
+        total_files: files_to_upload.len(),
+        total_bytes,
+        ..Default::default()
+    }));
+
+    let semaphore = Arc::new(Semaphore::new(upload_concurrency.max(1)));
+    let mut set = JoinSet::new();
+    for file_to_upload in files_to_upload {
+        let client = Arc::clone(client);
+        let semaphore = Arc::clone(&semaphore);
+        let job_dir = job_dir.to_string();
+        let progress = Arc::clone(&progress);
+        let on_progress = Arc::clone(&on_progress);
+        set.spawn(async move {
+            let _permit = semaphore.acquire_owned().await;
+            let res = upload_job_file(
+                &client,
+                &job_dir,
+                &file_to_upload,
+                &progress,
+                on_progress.as_ref(),
+            )
+            .await;
+            (file_to_upload, res)
         });
-    set.join_all()
+    }
+    let failed: Vec<JobFilesToUpload> = set
+        .join_all()
         .await
         .into_iter()
-        .collect::<Result<(), _>>()?;
-
-    // Create Job Script
+        .filter_map(|(file_to_upload, res)| {
+            let err = res.err()?;
+            eprintln!(
+                "Failed to upload {:?}, retrying: {err:?}",
+                file_to_upload.local_path
+            );
+            Some(file_to_upload)
+        })
+        .collect();
+    // Fall back to uploading failed files sequentially
+    for file_to_upload in failed {
+        upload_job_file(
+            client,
+            job_dir,
+            &file_to_upload,
+            &progress,
+            on_progress.as_ref(),
+        )
+        .await?;
+    }
+    Ok(())
+}
 
-    // Add local port forwarding (if necessary)
-    let forwaring_str = match job_options.local_forwarding {
-        Some(forwarding_options) => format!(
-            "ssh -N -f -R {}:localhost:{} {}",
-            forwarding_options.relay_port,
-            forwarding_options.local_port,
-            forwarding_options.relay_addr
+#[cfg(feature = "ssh")]
+/// Upload a single file into the job folder `job_dir`, reporting the progress to `on_progress`
+///
+/// If the upload fails, the bytes transferred so far are subtracted from the overall progress again, so that it can be retried.
+async fn upload_job_file(
+    client: &Client,
+    job_dir: &str,
+    file_to_upload: &JobFilesToUpload,
+    progress: &Mutex<TransferProgress>,
+    on_progress: &(impl Fn(&TransferProgress) + Send + Sync),
+) -> Result<(), Error> {
+    CommandExecutor::execute(
+        client,
+        &format!(
+            "mkdir -p {}",
+            shell_quote(&format!("{job_dir}/{}", file_to_upload.remote_subpath))
         ),
-        None => String::default(),
+    )
+    .await?;
+    let file = Path::new(&file_to_upload.remote_subpath)
+        .join(&file_to_upload.remote_file_name)
+        .to_string_lossy()
+        .to_string();
+    let file_total_bytes = tokio::fs::metadata(&file_to_upload.local_path).await?.len();
+    let mut file_transferred_bytes = 0;
+    let mut on_chunk = |bytes| {
+        file_transferred_bytes += bytes;
+        let mut progress = progress.lock().unwrap();
+        progress.file = file.clone();
+        progress.file_transferred_bytes = file_transferred_bytes;
+        progress.file_total_bytes = file_total_bytes;
+        progress.transferred_bytes += bytes;
+        on_progress(&progress);
     };
-    // Create script on system
-    client
-        .execute(&format!(
-            "cd {}/{} &&
-    echo '#!/usr/bin/zsh
-### Job Parameters
-#SBATCH --ntasks=1
-#SBATCH --cpus-per-task={}
-#SBATCH --time={}
-#SBATCH --job-name={}  # Sets the job name
-#SBATCH --output=stdout.txt     # redirects stdout and stderr to stdout.txt
-
-### Program Code
-{}
-{}' > start.sh && chmod +x start.sh",
-            root_dir,
-            folder_id,
-            job_options.num_cpus,
-            job_options.time,
-            folder_id,
-            forwaring_str,
-            job_options.command
-        ))
-        .await?;
+    let res = upload_file_with_progress(
+        client,
+        &file_to_upload.local_path,
+        &format!("{job_dir}/{file}"),
+        &mut on_chunk,
+    )
+    .await;
+    let mut progress = progress.lock().unwrap();
+    match res {
+        Ok(()) => {
+            progress.transferred_files += 1;
+            on_progress(&progress);
+            Ok(())
+        }
+        Err(err) => {
+            progress.transferred_bytes -= file_transferred_bytes;
+            Err(err)
+        }
+    }
+}
 
-    // Schedule job & get job id
+/// Submit a job to SLURM over SSH without creating a job folder or script (using `sbatch --wrap`)
+///
+/// All options are passed as command line arguments to `sbatch`, and the job is started in the `root_dir` of the [`JobOptions`].
+/// This is useful for quick one-off jobs, which do not require uploading any files.
+pub async fn submit_command(
+    client: &impl CommandExecutor,
+    job_options: JobOptions,
+) -> Result<SubmittedJob, Error> {
+    if !job_options.files_to_upload.is_empty() {
+        return Err(Error::msg(
+            "Uploading files is not supported for jobs without a job folder, use submit_job instead.",
+        ));
+    }
+    let JobSubmission {
+        root_dir, script, ..
+    } = job_options.into();
+    script.check_env()?;
+    let root_dir = shell_quote(&root_dir);
     let sbatch_out = client
-        .execute(&format!("cd {root_dir}/{folder_id} && sbatch start.sh"))
+        .execute_once(&format!(
+            "mkdir -p {root_dir} && cd {root_dir} && sbatch --parsable {} --wrap={}",
+            script.to_sbatch_args(),
+            shell_quote(&script.body_lines().join("\n"))
+        ))
         .await?;
-    let job_id = sbatch_out.stdout.split(" ").last();
-    if let Some(job_id) = job_id {
-        Ok((folder_id.clone(), job_id.to_string()))
-    } else {
-        Err(Error::msg("No JOB ID returned by sbatch."))
+    if sbatch_out.exit_status != 0 {
+        return Err(Error::msg(format!(
+            "sbatch failed: {}",
+            sbatch_out.stderr.trim()
+        )));
+    }
+    sbatch_out.stdout.parse()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// A successfully submitted job, as reported by `sbatch --parsable` (i.e., `<job id>[;<cluster>]`)
+pub struct SubmittedJob {
+    /// The ID of the job
+    pub job_id: JobID,
+    /// The cluster the job was submitted to (only reported in multi-cluster setups)
+    pub cluster: Option<String>,
+}
+
+impl FromStr for SubmittedJob {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Warnings might be printed before the actual output
+        let line = s
+            .lines()
+            .map(str::trim)
+            .rfind(|l| !l.is_empty())
+            .ok_or_else(|| Error::msg("No job ID returned by sbatch."))?;
+        let (job_id, cluster) = match line.split_once(";") {
+            Some((job_id, cluster)) => (job_id, Some(cluster.to_string())),
+            None => (line, None),
+        };
+        if job_id.is_empty() || !job_id.chars().all(|c| c.is_ascii_digit()) {
+            return Err(Error::msg(format!(
+                "Invalid job ID returned by sbatch: {line}"
+            )));
+        }
+        Ok(Self {
+            job_id: job_id.to_string(),
+            cluster,
+        })
     }
 }
 
@@ -167,44 +793,1309 @@ pub enum JobStatus {
     /// Job is pending
     PENDING {
         /// Estimated start time of job (if available)
-        start_time: Option<NaiveDateTime>,
+        start_time: Option<DateTime<Utc>>,
+        /// Reason why the job is pending (if available), e.g., `Priority` or `Resources`
+        reason: Option<String>,
+        /// Time limit of job (if available)
+        time_limit: Option<Duration>,
     },
     /// Job is running
     RUNNING {
         /// Start time of job (if available)
-        start_time: Option<NaiveDateTime>,
+        start_time: Option<DateTime<Utc>>,
         /// (Estimated) end time of job (if available)
-        end_time: Option<NaiveDateTime>,
+        end_time: Option<DateTime<Utc>>,
+        /// Node executing the job script (if available)
+        exec_host: Option<String>,
+        /// Allocated nodes (if available), e.g., `n23m[0001-0002]`
+        #[serde(default)]
+        node_list: Option<String>,
+        /// Number of allocated nodes
+        nodes: usize,
+        /// Time the job has been running so far (if available)
+        elapsed: Option<Duration>,
+        /// Time limit of job (if available)
+        time_limit: Option<Duration>,
     },
     /// Job has ended
     ENDED {
         /// End state of Job
         state: JobState,
+        /// Exit code of the job script (if available)
+        exit_code: Option<i32>,
+        /// Time the job was running (if available)
+        elapsed: Option<Duration>,
+        /// End time of job (if available)
+        end_time: Option<DateTime<Utc>>,
     },
-    /// Job was not found
+    /// Job was not found (neither by `squeue` nor by `sacct`)
     NotFound,
 }
 
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+/// Options for retrieving the status of SLURM jobs (see [`get_job_status_with_options`])
+pub struct JobStatusOptions {
+    /// For pending jobs, query the start time estimated by the scheduler (using `squeue --start`)
+    ///
+    /// This requires an additional command execution, but the start time reported by `squeue` is usually missing otherwise.
+    pub estimate_start_time: bool,
+    /// Timezone of the cluster (see [`ClusterInfo::detect_timezone`](crate::data_extraction::ClusterInfo::detect_timezone)), detected with each call if not set
+    ///
+    /// Set it when polling job statuses, to avoid an additional command execution per poll.
+    #[serde(skip)]
+    pub timezone: Option<FixedOffset>,
+}
+
+impl JobStatusOptions {
+    /// The configured timezone, or the timezone detected on the cluster
+    async fn timezone_with(&self, client: &impl CommandExecutor) -> Result<FixedOffset, Error> {
+        match self.timezone {
+            Some(tz) => Ok(tz),
+            None => crate::data_extraction::ClusterInfo::detect_timezone_with(client).await,
+        }
+    }
+
+    /// Options with the timezone detected on the cluster, e.g., to poll job statuses
+    pub async fn detect_timezone_with(client: &impl CommandExecutor) -> Result<Self, Error> {
+        Ok(Self {
+            timezone: Some(
+                crate::data_extraction::ClusterInfo::detect_timezone_with(client).await?,
+            ),
+            ..Default::default()
+        })
+    }
+}
+
 /// Get the status of a SLURM job, given its ID and a SSH client
-pub async fn get_job_status(client: &Client, job_id: &str) -> Result<JobStatus, Error> {
-    let (_time, res) = crate::data_extraction::get_squeue_res_ssh(
-        client,
+///
+/// Jobs which are no longer listed by `squeue` are looked up using `sacct`,
+/// so that ended jobs are reported as [`JobStatus::ENDED`] with their final state and exit code.
+pub async fn get_job_status(
+    client: &impl CommandExecutor,
+    job_id: &str,
+) -> Result<JobStatus, Error> {
+    get_job_status_with_options(client, job_id, &JobStatusOptions::default()).await
+}
+
+/// Get the status of a SLURM job, given its ID, a SSH client and additional [`JobStatusOptions`]
+pub async fn get_job_status_with_options(
+    client: &impl CommandExecutor,
+    job_id: &str,
+    options: &JobStatusOptions,
+) -> Result<JobStatus, Error> {
+    let tz = options.timezone_with(client).await?;
+    let (_time, res) = crate::data_extraction::get_squeue_res(
         &crate::data_extraction::SqueueMode::JOBIDS(vec![job_id.to_string()]),
+        &tz,
+        client,
     )
     .await?;
     if res.is_empty() {
-        return Ok(JobStatus::NotFound);
-        // return Err(Error::msg("Could not find job."))
+        // The job might have aged out of squeue, so fall back to the accounting data
+        let rows =
+            crate::data_extraction::get_sacct_res(&[job_id.to_string()], &tz, client).await?;
+        return Ok(rows
+            .iter()
+            .find(|row| row.job_id == job_id)
+            .map_or(JobStatus::NotFound, job_status_of_sacct_row));
     }
     let j = &res[0];
-    Ok(match &j.state {
+    let mut status = job_status_of_row(j);
+    if let JobStatus::PENDING { start_time, .. } = &mut status {
+        if options.estimate_start_time {
+            let estimates = crate::data_extraction::get_estimated_start_times(
+                std::slice::from_ref(&j.job_id),
+                &tz,
+                client,
+            )
+            .await?;
+            *start_time = estimates.get(&j.job_id).copied().or(j.start_time);
+        }
+    }
+    Ok(status)
+}
+
+/// Get the status of multiple SLURM jobs at once (see [`get_job_status`]), using a single `squeue` (and `sacct`) call
+///
+/// Returns the status of each job by its ID.
+pub async fn get_job_statuses(
+    client: &impl CommandExecutor,
+    job_ids: &[JobID],
+) -> Result<HashMap<JobID, JobStatus>, Error> {
+    get_job_statuses_with_options(client, job_ids, &JobStatusOptions::default()).await
+}
+
+/// Get the status of multiple SLURM jobs at once, with additional [`JobStatusOptions`] (see [`get_job_statuses`])
+///
+/// Start times are not estimated for pending jobs (see [`JobStatusOptions::estimate_start_time`]).
+pub async fn get_job_statuses_with_options(
+    client: &impl CommandExecutor,
+    job_ids: &[JobID],
+    options: &JobStatusOptions,
+) -> Result<HashMap<JobID, JobStatus>, Error> {
+    if job_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let tz = options.timezone_with(client).await?;
+    let (_time, rows) = crate::data_extraction::get_squeue_res(
+        &crate::data_extraction::SqueueMode::JOBIDS(job_ids.to_vec()),
+        &tz,
+        client,
+    )
+    .await?;
+    let mut statuses: HashMap<JobID, JobStatus> = rows
+        .iter()
+        .filter(|row| job_ids.contains(&row.job_id))
+        .map(|row| (row.job_id.clone(), job_status_of_row(row)))
+        .collect();
+    let missing: Vec<JobID> = job_ids
+        .iter()
+        .filter(|job_id| !statuses.contains_key(*job_id))
+        .cloned()
+        .collect();
+    if !missing.is_empty() {
+        // The jobs might have aged out of squeue, so fall back to the accounting data
+        let rows = crate::data_extraction::get_sacct_res(&missing, &tz, client).await?;
+        for job_id in missing {
+            let status = rows
+                .iter()
+                .find(|row| row.job_id == job_id)
+                .map_or(JobStatus::NotFound, job_status_of_sacct_row);
+            statuses.insert(job_id, status);
+        }
+    }
+    Ok(statuses)
+}
+
+pub(crate) fn job_status_of_row(row: &SqueueRow) -> JobStatus {
+    match &row.state {
         JobState::PENDING => JobStatus::PENDING {
-            start_time: j.start_time,
+            start_time: row.start_time,
+            reason: Some(row.reason.clone()).filter(|r| !r.is_empty() && r != "None"),
+            time_limit: row.time_limit,
         },
         JobState::RUNNING => JobStatus::RUNNING {
-            start_time: j.start_time,
-            end_time: j.end_time,
+            start_time: row.start_time,
+            end_time: row.end_time,
+            exec_host: row.exec_host.clone(),
+            node_list: row.node_list.clone(),
+            nodes: row.nodes,
+            elapsed: row.time,
+            time_limit: row.time_limit,
+        },
+        c => JobStatus::ENDED {
+            state: c.clone(),
+            exit_code: row.exit_code,
+            elapsed: row
+                .start_time
+                .zip(row.end_time)
+                .and_then(|(start, end)| (end - start).to_std().ok()),
+            end_time: row.end_time,
         },
-        c => JobStatus::ENDED { state: c.clone() },
-    })
+    }
+}
+
+fn job_status_of_sacct_row(row: &SacctJobRow) -> JobStatus {
+    match &row.state {
+        JobState::PENDING => JobStatus::PENDING {
+            start_time: None,
+            reason: None,
+            time_limit: None,
+        },
+        JobState::RUNNING => JobStatus::RUNNING {
+            start_time: None,
+            end_time: None,
+            exec_host: None,
+            node_list: None,
+            nodes: 0,
+            elapsed: row.elapsed,
+            time_limit: None,
+        },
+        c => JobStatus::ENDED {
+            state: c.clone(),
+            exit_code: Some(row.exit_code),
+            elapsed: row.elapsed,
+            end_time: row.end_time,
+        },
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Array task IDs of an array job (see [`submit_array_job`]), e.g., `0-99%10`
+pub struct ArraySpec {
+    /// Range of array task IDs
+    pub range: ArrayTaskRange,
+    /// Maximum number of simultaneously running tasks (if any)
+    pub throttle: Option<u32>,
+}
+
+impl std::fmt::Display for ArraySpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.range)?;
+        if let Some(throttle) = self.throttle {
+            write!(f, "%{throttle}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "ssh")]
+/// Submit an array job to SLURM over SSH (`--array`), returning the folder ID and the ID of the array job
+///
+/// Each task can access its task ID using the `SLURM_ARRAY_TASK_ID` environment variable
+/// and (if no [`JobOptions::output`] is set) writes its output to a separate file (`stdout-<task id>.txt`).
+pub async fn submit_array_job(
+    client: Arc<Client>,
+    job_options: JobOptions,
+    array: ArraySpec,
+) -> Result<(FolderID, SubmittedJob), Error> {
+    let separate_output = job_options.output.is_none();
+    let mut job = JobSubmission::from(job_options);
+    job.script.array = Some(array.to_string());
+    if separate_output {
+        job.script.output = Some(String::from("stdout-%a.txt"));
+    }
+    submit_job(client, job).await
+}
+
+/// Get the status of all tasks of an array job, given the ID of the array job and a SSH client
+///
+/// Tasks are only included as long as they are listed by `squeue` (i.e., tasks which finished a while ago are omitted).
+pub async fn get_array_task_statuses(
+    client: &impl CommandExecutor,
+    array_job_id: &str,
+) -> Result<BTreeMap<u32, JobStatus>, Error> {
+    let tz = crate::data_extraction::ClusterInfo::detect_timezone_with(client).await?;
+    let (_time, res) = crate::data_extraction::get_squeue_res(
+        &crate::data_extraction::SqueueMode::JOBIDS(vec![array_job_id.to_string()]),
+        &tz,
+        client,
+    )
+    .await?;
+    Ok(array_task_statuses(&res))
+}
+
+fn array_task_statuses(rows: &[SqueueRow]) -> BTreeMap<u32, JobStatus> {
+    let mut statuses = BTreeMap::new();
+    for row in rows {
+        // Pending tasks are usually grouped into a single row (e.g., `49616001_[3-10%1]`)
+        for task_id in row.step_job_id.task_ids() {
+            statuses.insert(task_id, job_status_of_row(row));
+        }
+    }
+    statuses
+}
+
+/// Get the batch script of a job folder exactly as it was submitted by [`submit_job`] (i.e., its `start.sh`)
+pub async fn get_submitted_script(
+    client: &impl CommandExecutor,
+    root_dir: &str,
+    folder_id: &str,
+) -> Result<String, Error> {
+    let r = client
+        .execute(&format!(
+            "cat {}",
+            shell_quote(&format!("{root_dir}/{folder_id}/start.sh"))
+        ))
+        .await?;
+    if r.exit_status != 0 {
+        return Err(Error::msg(format!(
+            "Could not read script of job folder {folder_id}: {}",
+            r.stderr.trim()
+        )));
+    }
+    Ok(r.stdout)
+}
+
+/// Get the batch script of a job as stored by SLURM (using `scontrol write batch_script`)
+///
+/// In contrast to [`get_submitted_script`], this also works for jobs not submitted using this crate,
+/// but only as long as SLURM still knows the job (i.e., shortly after it ended).
+pub async fn get_job_batch_script(
+    client: &impl CommandExecutor,
+    job_id: &str,
+) -> Result<String, Error> {
+    let r = client
+        .execute(&format!(
+            "scontrol write batch_script {} -",
+            shell_quote(job_id)
+        ))
+        .await?;
+    if r.exit_status != 0 {
+        return Err(Error::msg(format!(
+            "Could not get batch script of job {job_id}: {}",
+            r.stderr.trim()
+        )));
+    }
+    Ok(r.stdout)
+}
+
+/// Fetch the output of a submitted job, given the root directory and folder ID returned by [`submit_job`]
+///
+/// The output files are determined from the `--output`/`--error` directives of the submitted script,
+/// matching placeholders (e.g., `%j`) against all files in the job folder.
+/// The contents of all matching files are concatenated (e.g., for array jobs or separate stderr files).
+pub async fn fetch_job_output(
+    client: &impl CommandExecutor,
+    root_dir: &str,
+    folder_id: &str,
+) -> Result<String, Error> {
+    let folder = shell_quote(&format!("{root_dir}/{folder_id}"));
+    let script = get_submitted_script(client, root_dir, folder_id).await?;
+    let globs = output_file_globs(&script)
+        .iter()
+        .map(|pattern| {
+            pattern
+                .split('*')
+                .map(shell_quote)
+                .collect::<Vec<_>>()
+                .join("*")
+        })
+        .collect::<Vec<_>>()
+        .join(" ");
+    let r = client
+        .execute(&format!(
+            "cd {folder} && for f in {globs}; do [ -f \"$f\" ] && cat \"$f\"; done; true"
+        ))
+        .await?;
+    Ok(r.stdout)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// Source of the output to stream using [`stream_job_output`]
+pub enum JobOutputSource {
+    /// Follow the stdout file of a job folder (i.e., as returned by [`submit_job`])
+    ///
+    /// If the configured output path contains placeholders (e.g., `%j`), the file has to exist already.
+    Folder {
+        /// The root directory containing the job folder
+        root_dir: String,
+        /// The ID of the job folder
+        folder_id: FolderID,
+    },
+    /// Follow the stdout file of the job with the given ID (as reported by `scontrol show job`)
+    ///
+    /// The stream ends once the job is no longer listed by `squeue`.
+    Job(JobID),
+}
+
+#[cfg(feature = "ssh")]
+/// Stream the output of a job line by line, as it is written (using `tail -F` over SSH)
+///
+/// The output is streamed from the beginning of the file.
+/// Streaming can be cancelled by dropping the returned stream, which closes the SSH channel (and thereby ends the remote `tail`).
+pub async fn stream_job_output(
+    client: &Client,
+    source: &JobOutputSource,
+) -> Result<impl futures::Stream<Item = Result<String, Error>> + use<>, Error> {
+    use tokio::io::AsyncBufReadExt;
+    let command = match source {
+        JobOutputSource::Folder {
+            root_dir,
+            folder_id,
+        } => {
+            let folder = shell_quote(&format!("{root_dir}/{folder_id}"));
+            let script = get_submitted_script(client, root_dir, folder_id).await?;
+            let glob = output_file_globs(&script).remove(0);
+            let glob = glob
+                .split('*')
+                .map(shell_quote)
+                .collect::<Vec<_>>()
+                .join("*");
+            format!("cd {folder} && tail -F -n +1 {glob}")
+        }
+        JobOutputSource::Job(job_id) => {
+            let quoted_id = shell_quote(job_id);
+            let job =
+                CommandExecutor::execute(client, &format!("scontrol show job -o {quoted_id}"))
+                    .await?;
+            let path = parse_scontrol_field(&job.stdout, "StdOut")
+                .ok_or_else(|| Error::msg(format!("No output file found for job {job_id}.")))?;
+            // Stop following the file shortly after the job left the queue
+            format!(
+                "tail -F -n +1 {} & TAIL_PID=$!; while squeue -h -j {quoted_id} 2>/dev/null | grep -q .; do sleep 5; done; sleep 2; kill $TAIL_PID",
+                shell_quote(&path)
+            )
+        }
+    };
+    let channel = client.get_channel().await?;
+    channel.exec(true, command).await?;
+    let lines = tokio::io::BufReader::new(channel.into_stream()).lines();
+    Ok(futures::stream::unfold(Some(lines), |lines| async move {
+        let mut lines = lines?;
+        let line = lines.next_line().await;
+        match line {
+            Ok(Some(line)) => Some((Ok(line), Some(lines))),
+            Ok(None) => None,
+            // End the stream after reporting I/O errors
+            Err(err) => Some((Err(err.into()), None)),
+        }
+    }))
+}
+
+#[cfg(feature = "ssh")]
+/// Get the value of a `<field>=<value>` pair in the (first line of the) output of `scontrol show -o`
+///
+/// Values containing spaces (e.g., paths) are kept intact (see [`parse_scontrol_key_values`]).
+fn parse_scontrol_field(output: &str, field: &str) -> Option<String> {
+    let line = output.lines().find(|line| !line.trim().is_empty())?;
+    scontrol_value(&parse_scontrol_key_values(line), field).map(|value| value.to_string())
+}
+
+/// Get glob patterns (using `*` as wildcard) for the output files of the given batch script
+///
+/// If no output file is specified, the SLURM default `slurm-%j.out` is used.
+fn output_file_globs(script: &str) -> Vec<String> {
+    let mut output = None;
+    let mut error = None;
+    for line in script.lines() {
+        let Some(directive) = line.trim().strip_prefix("#SBATCH") else {
+            continue;
+        };
+        let directive = directive.trim();
+        let (target, value) = if let Some(value) = directive
+            .strip_prefix("--output=")
+            .or_else(|| directive.strip_prefix("-o "))
+        {
+            (&mut output, value)
+        } else if let Some(value) = directive
+            .strip_prefix("--error=")
+            .or_else(|| directive.strip_prefix("-e "))
+        {
+            (&mut error, value)
+        } else {
+            continue;
+        };
+        let value = value.trim();
+        let value = match value.strip_prefix('"').and_then(|v| v.strip_suffix('"')) {
+            Some(quoted) => quoted.replace("\\\"", "\"").replace("\\\\", "\\"),
+            None => value.to_string(),
+        };
+        *target = Some(value);
+    }
+    let mut patterns = vec![output.unwrap_or_else(|| String::from("slurm-%j.out"))];
+    patterns.extend(error);
+    patterns.dedup();
+    patterns
+        .into_iter()
+        .map(|pattern| {
+            // Replace placeholders (e.g., `%j` or `%4a`) by wildcards, keeping escaped percent signs (`%%`)
+            let mut glob = String::new();
+            let mut chars = pattern.chars();
+            while let Some(c) = chars.next() {
+                if c != '%' {
+                    glob.push(c);
+                    continue;
+                }
+                match chars.by_ref().find(|c| !c.is_ascii_digit()) {
+                    Some('%') => glob.push('%'),
+                    _ => glob.push('*'),
+                }
+            }
+            glob
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Polling behavior of [`wait_for_job`]
+pub struct PollPolicy {
+    /// Initial interval between status checks (default: 5 seconds)
+    pub min_interval: Duration,
+    /// Maximal interval between status checks (default: 60 seconds)
+    ///
+    /// The interval is doubled after every check, up to this maximum.
+    pub max_interval: Duration,
+    /// Give up waiting after this duration (default: no timeout)
+    pub timeout: Option<Duration>,
+}
+
+impl Default for PollPolicy {
+    fn default() -> Self {
+        Self {
+            min_interval: Duration::from_secs(5),
+            max_interval: Duration::from_secs(60),
+            timeout: None,
+        }
+    }
+}
+
+/// Wait until a SLURM job reached a terminal state, returning its final status
+///
+/// The job status is polled using [`get_job_status`], backing off as configured by the [`PollPolicy`].
+/// Returns [`JobStatus::ENDED`], or [`JobStatus::NotFound`] if the job is neither listed by `squeue` nor by `sacct`.
+/// Fails if the timeout of the [`PollPolicy`] is exceeded.
+pub async fn wait_for_job(
+    client: &impl CommandExecutor,
+    job_id: &str,
+    policy: &PollPolicy,
+) -> Result<JobStatus, Error> {
+    let start = Instant::now();
+    let mut interval = AdaptiveInterval::new(policy.min_interval, policy.max_interval);
+    let options = JobStatusOptions::detect_timezone_with(client).await?;
+    loop {
+        let status = get_job_status_with_options(client, job_id, &options).await?;
+        if matches!(status, JobStatus::ENDED { .. } | JobStatus::NotFound) {
+            return Ok(status);
+        }
+        let mut wait = interval.current();
+        if let Some(timeout) = policy.timeout {
+            let remaining = timeout.saturating_sub(start.elapsed());
+            if remaining.is_zero() {
+                return Err(Error::msg(format!(
+                    "Timed out waiting for job {job_id} (last status: {status:?})."
+                )));
+            }
+            wait = wait.min(remaining);
+        }
+        tokio::time::sleep(wait).await;
+        interval.update(0);
+    }
+}
+
+/// Profile the resource usage (CPU, RSS and I/O per job step) of a job by sampling `sstat` every `interval` while it is running
+///
+/// Waits while the job is pending and returns once the job ended (or is no longer listed by `squeue`).
+/// If `recording_dir` is given, the profile is additionally saved into this JSON recording directory after every sample
+/// (see [`JobProfile::save_to_dir`]), so that it can be used when extracting event data.
+pub async fn profile_job(
+    client: &impl CommandExecutor,
+    job_id: &str,
+    interval: Duration,
+    recording_dir: Option<&Path>,
+) -> Result<JobProfile, Error> {
+    let mut profile = JobProfile::new(job_id);
+    let mut ticker = tokio::time::interval(interval);
+    let options = JobStatusOptions::detect_timezone_with(client).await?;
+    loop {
+        ticker.tick().await;
+        match get_job_status_with_options(client, job_id, &options).await? {
+            JobStatus::PENDING { .. } => continue,
+            JobStatus::RUNNING { .. } => {}
+            JobStatus::ENDED { .. } | JobStatus::NotFound => return Ok(profile),
+        }
+        let time = Utc::now();
+        let steps = get_sstat_for_job(job_id, client).await?;
+        if steps.is_empty() {
+            continue;
+        }
+        profile.samples.push(JobProfileSample { time, steps });
+        if let Some(dir) = recording_dir {
+            profile.save_to_dir(dir)?;
+        }
+    }
+}
+
+/// Hold a pending SLURM job (using `scontrol hold`), preventing it from being started until it is released again
+///
+/// Returns the updated status of the job.
+pub async fn hold_job(client: &impl CommandExecutor, job_id: &str) -> Result<JobStatus, Error> {
+    execute_scontrol(client, "hold", job_id).await?;
+    get_job_status(client, job_id).await
+}
+
+/// Release a held SLURM job (using `scontrol release`), see [`hold_job`]
+///
+/// Returns the updated status of the job.
+pub async fn release_job(client: &impl CommandExecutor, job_id: &str) -> Result<JobStatus, Error> {
+    execute_scontrol(client, "release", job_id).await?;
+    get_job_status(client, job_id).await
+}
+
+/// Requeue a SLURM job (using `scontrol requeue`), e.g., to restart a job which failed because of a transient error
+///
+/// If requeueing is disabled by default on the cluster (`JobRequeue=0`), only jobs submitted as requeueable (see [`JobOptions::requeue`]) can be requeued.
+/// Returns the updated status of the job.
+pub async fn requeue_job(client: &impl CommandExecutor, job_id: &str) -> Result<JobStatus, Error> {
+    execute_scontrol(client, "requeue", job_id).await?;
+    get_job_status(client, job_id).await
+}
+
+/// Execute `scontrol <command> <job_id>`, failing if `scontrol` reports an error
+async fn execute_scontrol(
+    client: &impl CommandExecutor,
+    command: &str,
+    job_id: &str,
+) -> Result<(), Error> {
+    let r = client
+        .execute(&format!("scontrol {command} {}", shell_quote(job_id)))
+        .await?;
+    if r.exit_status != 0 {
+        return Err(Error::msg(format!(
+            "scontrol {command} failed for job {job_id}: {}",
+            r.stderr.trim()
+        )));
+    }
+    Ok(())
+}
+
+#[derive(Debug)]
+/// Reason why a SLURM job could not be cancelled (see [`cancel_job`])
+pub enum CancelJobError {
+    /// No job with the given ID exists (anymore)
+    NotFound,
+    /// The user is not allowed to cancel the job (e.g., because it belongs to another user)
+    PermissionDenied,
+    /// The job has already finished (or is currently completing)
+    AlreadyFinished,
+    /// Any other error (e.g., the command could not be executed)
+    Other(Error),
+}
+
+impl std::fmt::Display for CancelJobError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CancelJobError::NotFound => write!(f, "Job not found"),
+            CancelJobError::PermissionDenied => write!(f, "Permission denied"),
+            CancelJobError::AlreadyFinished => write!(f, "Job already finished"),
+            CancelJobError::Other(error) => write!(f, "{error}"),
+        }
+    }
+}
+
+impl std::error::Error for CancelJobError {}
+
+/// Cancel a SLURM job (using `scancel`), given its ID and a SSH client
+pub async fn cancel_job(client: &impl CommandExecutor, job_id: &str) -> Result<(), CancelJobError> {
+    cancel_jobs(client, &[job_id.to_string()])
+        .await
+        .map_err(CancelJobError::Other)?
+        .remove(job_id)
+        .unwrap_or(Ok(()))
+}
+
+/// Cancel multiple SLURM jobs (using a single `scancel` call), given their IDs and a SSH client
+///
+/// Returns the result of cancelling each job by its ID.
+/// The outer result fails if `scancel` could not be executed at all,
+/// or if it failed without reporting an error for any of the jobs.
+pub async fn cancel_jobs(
+    client: &impl CommandExecutor,
+    job_ids: &[String],
+) -> Result<HashMap<JobID, Result<(), CancelJobError>>, Error> {
+    if job_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+    let quoted: Vec<String> = job_ids.iter().map(|id| shell_quote(id)).collect();
+    let r = client
+        .execute(&format!("scancel {}", quoted.join(" ")))
+        .await?;
+    let res = parse_scancel_output(job_ids, &r.stderr);
+    if r.exit_status != 0 && res.values().all(|r| r.is_ok()) {
+        return Err(Error::msg(format!("scancel failed: {}", r.stderr.trim())));
+    }
+    Ok(res)
+}
+
+/// Send a signal to a running SLURM job (using `scancel -s`), e.g., `USR1` to trigger a graceful checkpoint before the time limit
+///
+/// The `signal` can be given by name (e.g., `USR1` or `SIGUSR1`) or number.
+/// If `batch_only` is set, only the batch shell (i.e., the job script) is signalled, instead of all job steps.
+/// Failures are reported in the same way as for [`cancel_job`].
+pub async fn signal_job(
+    client: &impl CommandExecutor,
+    job_id: &str,
+    signal: &str,
+    batch_only: bool,
+) -> Result<(), CancelJobError> {
+    let r = client
+        .execute(&signal_command(job_id, signal, batch_only))
+        .await
+        .map_err(CancelJobError::Other)?;
+    match parse_scancel_output(&[job_id.to_string()], &r.stderr).remove(job_id) {
+        Some(Err(e)) => Err(e),
+        _ if r.exit_status != 0 => Err(CancelJobError::Other(Error::msg(format!(
+            "scancel failed: {}",
+            r.stderr.trim()
+        )))),
+        _ => Ok(()),
+    }
+}
+
+/// The `scancel` command sending `signal` to the job `job_id` (see [`signal_job`])
+fn signal_command(job_id: &str, signal: &str, batch_only: bool) -> String {
+    let batch = if batch_only { " --batch" } else { "" };
+    format!(
+        "scancel -s {}{batch} {}",
+        shell_quote(signal),
+        shell_quote(job_id)
+    )
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Filter for selecting jobs of the current user (see [`cancel_jobs_matching`])
+///
+/// All given criteria have to match.
+pub struct JobFilter {
+    /// Name of the jobs (`-n`), which may contain `*` wildcards (e.g., `sweep-*`)
+    pub name: Option<String>,
+    /// States of the jobs (`-t`), e.g., only [`JobState::PENDING`]
+    pub states: Vec<JobState>,
+    /// Partition of the jobs (`-p`)
+    pub partition: Option<String>,
+    /// Account of the jobs (`-A`)
+    pub account: Option<String>,
+}
+
+impl JobFilter {
+    /// Options selecting the matching jobs, as accepted by both `squeue` and `scancel`
+    ///
+    /// Name patterns with wildcards are not supported by SLURM, and instead matched by [`JobFilter::matches_name`].
+    fn args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        if let Some(name) = self.name.as_ref().filter(|n| !n.contains('*')) {
+            args.push(format!("-n {}", shell_quote(name)));
+        }
+        if !self.states.is_empty() {
+            let states: Vec<String> = self.states.iter().map(|s| s.to_string()).collect();
+            args.push(format!("-t {}", shell_quote(&states.join(","))));
+        }
+        if let Some(partition) = &self.partition {
+            args.push(format!("-p {}", shell_quote(partition)));
+        }
+        if let Some(account) = &self.account {
+            args.push(format!("-A {}", shell_quote(account)));
+        }
+        args
+    }
+
+    /// If the job name `name` matches the name (pattern) of this filter
+    fn matches_name(&self, name: &str) -> bool {
+        self.name
+            .as_ref()
+            .is_none_or(|pattern| match glob::Pattern::new(pattern) {
+                Ok(pattern) => pattern.matches(name),
+                Err(_) => pattern == name,
+            })
+    }
+
+    /// Parse the output of `squeue -o '%i|%j'`, returning the IDs of all jobs matching this filter
+    fn matching_job_ids(&self, output: &str) -> Vec<JobID> {
+        output
+            .lines()
+            .filter_map(|line| line.trim().split_once('|'))
+            .filter(|(_, name)| self.matches_name(name))
+            .map(|(job_id, _)| job_id.to_string())
+            .collect()
+    }
+}
+
+/// Cancel all jobs of the current user matching the given `filter` (e.g., all tasks of a parameter sweep)
+///
+/// Matching jobs are first listed using `squeue` (with the same `-n`/`-t`/`-p`/`-A` options as `scancel`) and then cancelled by their ID,
+/// so that only the reported jobs are cancelled. An empty filter matches all jobs of the current user.
+///
+/// Returns the IDs of all successfully cancelled jobs.
+pub async fn cancel_jobs_matching(
+    client: &impl CommandExecutor,
+    filter: &JobFilter,
+) -> Result<Vec<JobID>, Error> {
+    let r = client
+        .execute(&format!(
+            "squeue --me -h -o '%i|%j' {}",
+            filter.args().join(" ")
+        ))
+        .await?;
+    if r.exit_status != 0 {
+        return Err(Error::msg(format!(
+            "Could not list jobs to cancel: {}",
+            r.stderr.trim()
+        )));
+    }
+    let job_ids = filter.matching_job_ids(&r.stdout);
+    let res = cancel_jobs(client, &job_ids).await?;
+    Ok(job_ids
+        .into_iter()
+        .filter(|id| matches!(res.get(id), Some(Ok(()))))
+        .collect())
+}
+
+/// Parse the (error) output of `scancel` for the given `job_ids`
+///
+/// Errors are reported by `scancel` as, e.g., `scancel: error: Kill job error on job id 123: Invalid job id specified`.
+/// Jobs without a reported error were cancelled successfully.
+fn parse_scancel_output(
+    job_ids: &[String],
+    stderr: &str,
+) -> HashMap<JobID, Result<(), CancelJobError>> {
+    let mut res: HashMap<JobID, Result<(), CancelJobError>> =
+        job_ids.iter().map(|id| (id.clone(), Ok(()))).collect();
+    for line in stderr.lines() {
+        let Some((_, error)) = line.split_once("job id ") else {
+            continue;
+        };
+        let Some((job_id, message)) = error.split_once(": ") else {
+            continue;
+        };
+        let error = if message.contains("Invalid job id") {
+            CancelJobError::NotFound
+        } else if message.contains("permission denied") || message.contains("Access") {
+            CancelJobError::PermissionDenied
+        } else if message.contains("already completing or completed") {
+            CancelJobError::AlreadyFinished
+        } else {
+            CancelJobError::Other(Error::msg(message.trim().to_string()))
+        };
+        res.insert(job_id.trim().to_string(), Err(error));
+    }
+    res
+}
+
+#[cfg(test)]
+/// Minimal job options for tests
+pub(crate) fn test_job_options() -> JobOptions {
+    JobOptions {
+        root_dir: "hpc_experiments".to_string(),
+        name: None,
+        files_to_upload: HashSet::new(),
+        num_cpus: 12,
+        time: "0-00:10:00".parse().unwrap(),
+        memory: None,
+        requeue: false,
+        partition: None,
+        account: None,
+        qos: None,
+        gres: Vec::new(),
+        constraint: None,
+        extra_directives: Vec::new(),
+        mail_types: Vec::new(),
+        mail_user: None,
+        dependencies: Vec::new(),
+        output: None,
+        error: None,
+        env: HashMap::new(),
+        container: None,
+        setup_lines: Vec::new(),
+        shell: JobShell::default(),
+        command: "./run.sh".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::{FixedOffset, TimeZone, Utc};
+
+    use super::{
+        array_task_statuses, get_job_status, get_job_status_with_options, job_status_of_row,
+        job_status_of_sacct_row, output_file_globs, parse_scancel_output, signal_command,
+        test_job_options, ArraySpec, CancelJobError, ContainerSpec, JobDependency,
+        JobFilesToUpload, JobFilter, JobForwardingMode, JobLocalForwarding, JobOptions, JobStatus,
+        JobStatusOptions, JobSubmission, MailType, SacctJobRow, ScriptSetup, SubmittedJob,
+    };
+    use crate::{
+        data_extraction::squeue::test_row,
+        executor::{mock::MockExecutor, CommandOutput},
+        JobState,
+    };
+
+    #[test]
+    fn test_job_options_script() {
+        let script = JobSubmission::from(JobOptions {
+            name: Some("param sweep #1".to_string()),
+            partition: Some("c23g".to_string()),
+            account: Some("rwth1234".to_string()),
+            qos: Some("normal".to_string()),
+            memory: Some("4G".parse().unwrap()),
+            gres: vec!["gpu:2".parse().unwrap()],
+            constraint: Some("hpcwork".to_string()),
+            extra_directives: vec!["--switches=1".to_string()],
+            mail_types: vec![MailType::FAIL],
+            mail_user: Some("user@example.com".to_string()),
+            setup_lines: vec![
+                ScriptSetup::ModuleLoad("GCC/12.3.0".to_string()),
+                ScriptSetup::VenvActivate("~/venvs/my env".to_string()),
+            ],
+            dependencies: vec![
+                JobDependency::AFTEROK("123".to_string()),
+                JobDependency::AFTERNOTOK("124".to_string()),
+                JobDependency::SINGLETON,
+            ],
+            ..test_job_options()
+        })
+        .script
+        .to_string();
+        for directive in [
+            "--job-name=param_sweep__1",
+            "--partition=c23g",
+            "--account=rwth1234",
+            "--qos=normal",
+            "--mem=4G",
+            "--gres=gpu:2",
+            "--constraint=hpcwork",
+            "--mail-type=FAIL",
+            "--mail-user=user@example.com",
+            "--dependency=afterok:123,afternotok:124,singleton",
+            "--switches=1",
+        ] {
+            assert!(script.contains(&format!("#SBATCH {directive}\n")));
+        }
+        assert!(script.ends_with(
+            "module load 'GCC/12.3.0'\nsource ~/'venvs/my env/bin/activate'\n./run.sh\n"
+        ));
+        let container_script = JobSubmission::from(JobOptions {
+            container: Some(ContainerSpec::new("python.sif")),
+            ..test_job_options()
+        })
+        .script
+        .to_string();
+        assert!(container_script.ends_with("apptainer exec 'python.sif' sh -c './run.sh'\n"));
+        let default_script = JobSubmission::from(test_job_options()).script.to_string();
+        assert!(!default_script.contains("--partition"));
+        assert!(!default_script.contains("--job-name"));
+        assert!(!default_script.contains("--mail"));
+    }
+
+    #[cfg(feature = "ssh")]
+    #[test]
+    fn test_find_shared_files() {
+        use super::find_shared_files;
+
+        let file = |local_path: &str, remote_file_name: &str| JobFilesToUpload {
+            local_path: local_path.into(),
+            remote_subpath: String::new(),
+            remote_file_name: remote_file_name.to_string(),
+        };
+        let job = |root_dir: &str, files: Vec<JobFilesToUpload>| {
+            let mut job = JobSubmission::from(test_job_options());
+            job.root_dir = root_dir.to_string();
+            job.files_to_upload = files.into_iter().collect();
+            job
+        };
+        let jobs = vec![
+            job(
+                "sweep",
+                vec![file("bin/main", "main"), file("data/0.csv", "data.csv")],
+            ),
+            job(
+                "sweep",
+                vec![file("bin/main", "main"), file("data/1.csv", "data.csv")],
+            ),
+            job("other", vec![file("bin/main", "main")]),
+        ];
+        assert_eq!(
+            find_shared_files(&jobs),
+            vec![("sweep".to_string(), "bin/main".into())]
+        );
+        assert!(find_shared_files(&jobs[1..]).is_empty());
+    }
+
+    #[test]
+    fn test_parse_scancel_output() {
+        let job_ids: Vec<String> = ["1", "2", "3", "4", "5"]
+            .into_iter()
+            .map(String::from)
+            .collect();
+        let stderr = "scancel: error: Kill job error on job id 2: Invalid job id specified
+scancel: error: Kill job error on job id 3: Access/permission denied
+scancel: error: Kill job error on job id 4: Job/step already completing or completed
+scancel: error: Kill job error on job id 5: Unexpected message received";
+        let res = parse_scancel_output(&job_ids, stderr);
+        assert_eq!(res.len(), 5);
+        assert!(res["1"].is_ok());
+        assert!(matches!(res["2"], Err(CancelJobError::NotFound)));
+        assert!(matches!(res["3"], Err(CancelJobError::PermissionDenied)));
+        assert!(matches!(res["4"], Err(CancelJobError::AlreadyFinished)));
+        assert!(matches!(res["5"], Err(CancelJobError::Other(_))));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_jobs() {
+        let job_ids = vec!["49848561".to_string(), "49869434_2".to_string()];
+        let mock = MockExecutor::new().with_response(
+            "scancel",
+            CommandOutput {
+                stderr:
+                    "scancel: error: Kill job error on job id 49848561: Invalid job id specified"
+                        .to_string(),
+                exit_status: 1,
+                ..Default::default()
+            },
+        );
+        let res = super::cancel_jobs(&mock, &job_ids).await.unwrap();
+        assert_eq!(mock.calls()[0], "scancel '49848561' '49869434_2'");
+        assert!(matches!(res["49848561"], Err(CancelJobError::NotFound)));
+        assert!(res["49869434_2"].is_ok());
+
+        let mock = MockExecutor::new().with_response(
+            "scancel",
+            CommandOutput {
+                stderr: "scancel: error: Unable to contact slurm controller".to_string(),
+                exit_status: 1,
+                ..Default::default()
+            },
+        );
+        assert!(super::cancel_jobs(&mock, &job_ids).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_execute_scontrol() {
+        let mock = MockExecutor::new().with_output("scontrol", "");
+        super::execute_scontrol(&mock, "hold", "49848561; rm -rf ~")
+            .await
+            .unwrap();
+        assert_eq!(mock.calls()[0], "scontrol hold '49848561; rm -rf ~'");
+    }
+
+    #[test]
+    fn test_signal_command() {
+        assert_eq!(
+            signal_command("49848561", "USR1", false),
+            "scancel -s 'USR1' '49848561'"
+        );
+        assert_eq!(
+            signal_command("49848561", "SIGTERM", true),
+            "scancel -s 'SIGTERM' --batch '49848561'"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_signal_job_failure() {
+        let mock = MockExecutor::new().with_response(
+            "scancel",
+            CommandOutput {
+                stderr: "scancel: error: Invalid signal: FOO".to_string(),
+                exit_status: 1,
+                ..Default::default()
+            },
+        );
+        assert!(matches!(
+            super::signal_job(&mock, "49848561", "FOO", false).await,
+            Err(CancelJobError::Other(_))
+        ));
+        let mock = MockExecutor::new().with_output("scancel", "");
+        assert!(super::signal_job(&mock, "49848561", "USR1", false)
+            .await
+            .is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_job_status_timezone() {
+        let mock = MockExecutor::new()
+            .with_output("squeue", "")
+            .with_output("sacct", "");
+        let options = JobStatusOptions {
+            timezone: Some(FixedOffset::east_opt(3600).unwrap()),
+            ..Default::default()
+        };
+        let status = get_job_status_with_options(&mock, "49848561", &options)
+            .await
+            .unwrap();
+        assert!(matches!(status, JobStatus::NotFound));
+        assert!(!mock.calls().iter().any(|c| c.starts_with("date")));
+        // Job IDs are quoted for both squeue and sacct
+        let status = get_job_status_with_options(&mock, "1; rm -rf ~", &options)
+            .await
+            .unwrap();
+        assert!(matches!(status, JobStatus::NotFound));
+        assert_eq!(mock.calls().len(), 4);
+        assert!(mock.calls()[2..]
+            .iter()
+            .all(|c| c.contains("-j '1; rm -rf ~'")));
+        // Without a configured timezone, it is detected (which fails, as no `date` output is mocked)
+        assert!(get_job_status(&mock, "49848561").await.is_err());
+    }
+
+    #[test]
+    fn test_relay_setup() {
+        let mut forwarding = JobLocalForwarding {
+            local_port: 8080,
+            job_port: 8888,
+            mode: JobForwardingMode::Direct,
+        };
+        assert_eq!(forwarding.relay_setup(), None);
+        forwarding.mode = JobForwardingMode::LoginNodeRelay {
+            relay_host: "login23-1".to_string(),
+            relay_port: 18888,
+        };
+        assert_eq!(
+            forwarding.relay_setup().unwrap().to_string(),
+            "ssh -N -f -R 18888:localhost:8888 'login23-1'"
+        );
+    }
+
+    #[test]
+    fn test_job_filter() {
+        let filter = JobFilter {
+            name: Some("sweep-*".to_string()),
+            states: vec![JobState::PENDING, JobState::RUNNING],
+            partition: Some("c23ms".to_string()),
+            account: None,
+        };
+        assert_eq!(filter.args(), vec!["-t 'PENDING,RUNNING'", "-p 'c23ms'"]);
+        assert_eq!(
+            filter
+                .matching_job_ids("49848561_0|sweep-1\n49848561_[1-499]|sweep-1\n49848562|train\n"),
+            vec!["49848561_0", "49848561_[1-499]"]
+        );
+        let filter = JobFilter {
+            name: Some("train".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(filter.args(), vec!["-n 'train'"]);
+        assert_eq!(
+            filter.matching_job_ids("49848561_0|sweep-1\n49848562|train\n"),
+            vec!["49848562"]
+        );
+    }
+
+    #[test]
+    fn test_array_jobs() {
+        let spec = ArraySpec {
+            range: "0-9:3".parse().unwrap(),
+            throttle: Some(2),
+        };
+        assert_eq!(spec.to_string(), "0-9:3%2");
+
+        let time = Utc.with_ymd_and_hms(2025, 1, 14, 9, 23, 45).unwrap();
+        let mut running = test_row(time);
+        running.step_job_id = "49616001_0".parse().unwrap();
+        running.state = JobState::RUNNING;
+        let mut pending = test_row(time);
+        pending.step_job_id = "49616001_[3-9:3%2]".parse().unwrap();
+        let statuses = array_task_statuses(&[running, pending]);
+        assert_eq!(
+            statuses.keys().copied().collect::<Vec<_>>(),
+            vec![0, 3, 6, 9]
+        );
+        assert!(matches!(statuses[&0], JobStatus::RUNNING { .. }));
+        assert!(matches!(statuses[&9], JobStatus::PENDING { .. }));
+    }
+
+    #[test]
+    fn test_output_file_globs() {
+        let script = JobSubmission::from(test_job_options()).script.to_string();
+        assert_eq!(output_file_globs(&script), vec!["stdout.txt"]);
+        let script = JobSubmission::from(JobOptions {
+            output: Some("logs/%x-%j.out".to_string()),
+            error: Some("logs/%x-%4a.err".to_string()),
+            ..test_job_options()
+        })
+        .script
+        .to_string();
+        assert_eq!(
+            output_file_globs(&script),
+            vec!["logs/*-*.out", "logs/*-*.err"]
+        );
+        assert_eq!(
+            output_file_globs("#!/bin/bash\n#SBATCH -o \"my output %%.txt\"\nsrun hostname"),
+            vec!["my output %.txt"]
+        );
+        assert_eq!(output_file_globs("srun hostname"), vec!["slurm-*.out"]);
+    }
+
+    #[cfg(feature = "ssh")]
+    #[test]
+    fn test_parse_scontrol_field() {
+        use super::parse_scontrol_field;
+
+        let output = "JobId=49848561 JobName=test UserId=ab123456(12345) GroupId=ab123456(12345) MCS_label=N/A StdErr=/home/ab123456/my experiments/stdout.txt StdIn=/dev/null StdOut=/home/ab123456/my experiments/stdout.txt\n";
+        assert_eq!(
+            parse_scontrol_field(output, "StdOut").as_deref(),
+            Some("/home/ab123456/my experiments/stdout.txt")
+        );
+        assert_eq!(
+            parse_scontrol_field(output, "JobId").as_deref(),
+            Some("49848561")
+        );
+        assert_eq!(parse_scontrol_field(output, "Std"), None);
+    }
+
+    #[test]
+    fn test_parse_submitted_job() {
+        let job: SubmittedJob = "49848561\n".parse().unwrap();
+        assert_eq!(job.job_id, "49848561");
+        assert_eq!(job.cluster, None);
+        let job: SubmittedJob = "sbatch: Warning: ...\n49848562;c23\n".parse().unwrap();
+        assert_eq!(job.job_id, "49848562");
+        assert_eq!(job.cluster.as_deref(), Some("c23"));
+        assert!("Submitted batch job 49848561"
+            .parse::<SubmittedJob>()
+            .is_err());
+        assert!("".parse::<SubmittedJob>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_submit_command() {
+        let mock = MockExecutor::new().with_output("mkdir -p", "49848561;c23\n");
+        let job = super::submit_command(&mock, test_job_options())
+            .await
+            .unwrap();
+        assert_eq!(job.job_id, "49848561");
+        let calls = mock.calls();
+        assert!(calls[0].contains("sbatch --parsable") && calls[0].contains("--wrap='./run.sh'"));
+        let with_files = JobOptions {
+            files_to_upload: [JobFilesToUpload {
+                local_path: "run.sh".into(),
+                remote_subpath: String::new(),
+                remote_file_name: "run.sh".to_string(),
+            }]
+            .into(),
+            ..test_job_options()
+        };
+        assert!(super::submit_command(&mock, with_files).await.is_err());
+        assert_eq!(mock.calls().len(), 1);
+    }
+
+    #[test]
+    fn test_job_status_of_row() {
+        let time = Utc.with_ymd_and_hms(2025, 1, 14, 9, 23, 45).unwrap();
+        let mut row = test_row(time);
+        row.time_limit = Some(Duration::from_secs(3600));
+        assert!(matches!(
+            job_status_of_row(&row),
+            JobStatus::PENDING {
+                reason: Some(reason),
+                time_limit: Some(_),
+                ..
+            } if reason == "Priority"
+        ));
+        row.state = JobState::RUNNING;
+        row.reason = "None".to_string();
+        row.exec_host = Some("n23m0001".to_string());
+        row.node_list = Some("n23m0001".to_string());
+        row.time = Some(Duration::from_secs(60));
+        assert!(matches!(
+            job_status_of_row(&row),
+            JobStatus::RUNNING {
+                exec_host: Some(host),
+                node_list: Some(node_list),
+                nodes: 1,
+                elapsed: Some(_),
+                ..
+            } if host == "n23m0001" && node_list == "n23m0001"
+        ));
+        row.state = JobState::FAILED;
+        row.start_time = Some(time);
+        row.end_time = Some(time + chrono::Duration::seconds(90));
+        row.exit_code = Some(1);
+        assert!(matches!(
+            job_status_of_row(&row),
+            JobStatus::ENDED {
+                state: JobState::FAILED,
+                exit_code: Some(1),
+                elapsed: Some(elapsed),
+                ..
+            } if elapsed == Duration::from_secs(90)
+        ));
+
+        let sacct_row = SacctJobRow {
+            job_id: "49848561".to_string(),
+            state: JobState::TIMEOUT,
+            exit_code: 0,
+            signal: 15,
+            end_time: Some(time),
+            elapsed: Some(Duration::from_secs(3600)),
+        };
+        assert!(matches!(
+            job_status_of_sacct_row(&sacct_row),
+            JobStatus::ENDED {
+                state: JobState::TIMEOUT,
+                exit_code: Some(0),
+                end_time: Some(end),
+                ..
+            } if end == time
+        ));
+    }
 }
@@ -0,0 +1,557 @@
+use std::collections::BTreeMap;
+
+use anyhow::Error;
+
+pub(crate) use crate::executor::shell_quote;
+use crate::{MemorySpec, SlurmDuration, TresRequest};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Events for which SLURM sends mail notifications (`--mail-type`)
+pub enum MailType {
+    /// No notifications
+    NONE,
+    /// Job started
+    BEGIN,
+    /// Job ended
+    END,
+    /// Job failed
+    FAIL,
+    /// Job was requeued
+    REQUEUE,
+    /// All of `BEGIN`, `END`, `FAIL`, `REQUEUE` (and some more)
+    ALL,
+    /// Job reached 90% of its time limit
+    #[allow(non_camel_case_types)]
+    TIME_LIMIT_90,
+    /// Job reached its time limit
+    #[allow(non_camel_case_types)]
+    TIME_LIMIT,
+    /// Send notifications for each array task (instead of only for the whole array job)
+    #[allow(non_camel_case_types)]
+    ARRAY_TASKS,
+}
+
+impl std::fmt::Display for MailType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{self:?}")
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+/// Shell interpreting a job script (i.e., used in the shebang line)
+pub enum JobShell {
+    /// Bash (`/bin/bash`)
+    Bash,
+    /// Z shell (`/usr/bin/zsh`)
+    #[default]
+    Zsh,
+    /// POSIX shell (`/bin/sh`)
+    Sh,
+}
+
+impl JobShell {
+    /// Path of the shell executable
+    pub fn path(&self) -> &'static str {
+        match self {
+            JobShell::Bash => "/bin/bash",
+            JobShell::Zsh => "/usr/bin/zsh",
+            JobShell::Sh => "/bin/sh",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// Setup step of a job script, executed before the actual command (e.g., loading modules)
+pub enum ScriptSetup {
+    /// Load an environment module (`module load <module>`), e.g., `gcc/12`
+    ModuleLoad(String),
+    /// Activate a conda environment (`conda activate <env>`)
+    ///
+    /// Requires conda to be initialized for the shell (e.g., by a previous [`ScriptSetup::ModuleLoad`] or [`ScriptSetup::SourceFile`]).
+    CondaActivate(String),
+    /// Activate a Python virtual environment at the given path (`source <path>/bin/activate`)
+    VenvActivate(String),
+    /// Source the given file (`source <path>`)
+    SourceFile(String),
+    /// A raw line, inserted into the script as is
+    Raw(String),
+}
+
+impl std::fmt::Display for ScriptSetup {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ScriptSetup::ModuleLoad(module) => write!(f, "module load {}", shell_quote(module)),
+            ScriptSetup::CondaActivate(env) => write!(f, "conda activate {}", shell_quote(env)),
+            ScriptSetup::VenvActivate(path) => {
+                write!(f, "source {}", quote_path(&format!("{path}/bin/activate")))
+            }
+            ScriptSetup::SourceFile(path) => write!(f, "source {}", quote_path(path)),
+            ScriptSetup::Raw(line) => write!(f, "{line}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Builder for SLURM batch scripts, as submitted using `sbatch`
+///
+/// All set options are rendered as `#SBATCH` directives (quoted if necessary), followed by the commands of the script.
+/// Use [`ToString::to_string`] to get the final script.
+///
+/// ```
+/// use slurry::job_management::SbatchScript;
+///
+/// let script = SbatchScript::new()
+///     .with_partition("c23ms")
+///     .with_ntasks(4)
+///     .with_command("srun ./simulate");
+/// assert!(script.to_string().contains("#SBATCH --partition=c23ms\n"));
+/// ```
+pub struct SbatchScript {
+    /// Shell interpreting the script (default: [`JobShell::Zsh`])
+    pub shell: JobShell,
+    /// Name of the job (`--job-name`)
+    pub job_name: Option<String>,
+    /// Number of tasks (`--ntasks`)
+    pub ntasks: Option<usize>,
+    /// Number of nodes (`--nodes`), e.g., `2` or `1-4`
+    pub nodes: Option<String>,
+    /// Number of CPUs per task (`--cpus-per-task`)
+    pub cpus_per_task: Option<usize>,
+    /// Time limit (`--time`)
+    pub time: Option<SlurmDuration>,
+    /// Memory requirement (`--mem` or `--mem-per-cpu`)
+    pub memory: Option<MemorySpec>,
+    /// Partition(s) to submit to (`--partition`)
+    pub partition: Option<String>,
+    /// Account to charge (`--account`)
+    pub account: Option<String>,
+    /// Quality of service (`--qos`)
+    pub qos: Option<String>,
+    /// Generic resources per node (`--gres`), e.g., GPUs
+    pub gres: Vec<TresRequest>,
+    /// Required node features (`--constraint`), e.g., `hpcwork` or `skylake|cascadelake`
+    pub constraint: Option<String>,
+    /// Events for which mail notifications are sent (`--mail-type`)
+    pub mail_types: Vec<MailType>,
+    /// Recipient of mail notifications (`--mail-user`)
+    pub mail_user: Option<String>,
+    /// Array task IDs (`--array`), e.g., `0-9%2`
+    pub array: Option<String>,
+    /// Dependencies on other jobs (`--dependency`), e.g., `afterok:123`
+    pub dependency: Option<String>,
+    /// Path of the stdout file (`--output`), supporting placeholders like `%j`
+    pub output: Option<String>,
+    /// Path of the stderr file (`--error`), supporting placeholders like `%j`
+    pub error: Option<String>,
+    /// Request exclusive node allocation (`--exclusive`)
+    pub exclusive: bool,
+    /// Allow the job to be requeued (`--requeue`)
+    pub requeue: bool,
+    /// Additional directives not modeled by this builder (e.g., `--switches=1`), emitted verbatim after all other directives
+    pub extra_directives: Vec<String>,
+    /// Environment variables to export before executing the commands
+    ///
+    /// Keys have to be valid shell variable names (checked on submission, see [`SbatchScript::check_env`]), values are quoted.
+    pub env: BTreeMap<String, String>,
+    /// Commands of the script (one per line)
+    pub commands: Vec<String>,
+}
+
+impl SbatchScript {
+    /// Create a new script without any directives or commands
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the shell interpreting the script
+    pub fn with_shell(mut self, shell: JobShell) -> Self {
+        self.shell = shell;
+        self
+    }
+
+    /// Set the job name (`--job-name`)
+    pub fn with_job_name(mut self, job_name: impl Into<String>) -> Self {
+        self.job_name = Some(job_name.into());
+        self
+    }
+
+    /// Set the number of tasks (`--ntasks`)
+    pub fn with_ntasks(mut self, ntasks: usize) -> Self {
+        self.ntasks = Some(ntasks);
+        self
+    }
+
+    /// Set the number of nodes (`--nodes`), e.g., `2` or `1-4`
+    pub fn with_nodes(mut self, nodes: impl Into<String>) -> Self {
+        self.nodes = Some(nodes.into());
+        self
+    }
+
+    /// Set the number of CPUs per task (`--cpus-per-task`)
+    pub fn with_cpus_per_task(mut self, cpus_per_task: usize) -> Self {
+        self.cpus_per_task = Some(cpus_per_task);
+        self
+    }
+
+    /// Set the time limit (`--time`)
+    pub fn with_time(mut self, time: SlurmDuration) -> Self {
+        self.time = Some(time);
+        self
+    }
+
+    /// Set the memory requirement (`--mem` or `--mem-per-cpu`)
+    pub fn with_memory(mut self, memory: MemorySpec) -> Self {
+        self.memory = Some(memory);
+        self
+    }
+
+    /// Set the partition(s) (`--partition`)
+    pub fn with_partition(mut self, partition: impl Into<String>) -> Self {
+        self.partition = Some(partition.into());
+        self
+    }
+
+    /// Set the account (`--account`)
+    pub fn with_account(mut self, account: impl Into<String>) -> Self {
+        self.account = Some(account.into());
+        self
+    }
+
+    /// Set the quality of service (`--qos`)
+    pub fn with_qos(mut self, qos: impl Into<String>) -> Self {
+        self.qos = Some(qos.into());
+        self
+    }
+
+    /// Add a generic resource request (`--gres`)
+    pub fn with_gres(mut self, gres: TresRequest) -> Self {
+        self.gres.push(gres);
+        self
+    }
+
+    /// Set the required node features (`--constraint`)
+    pub fn with_constraint(mut self, constraint: impl Into<String>) -> Self {
+        self.constraint = Some(constraint.into());
+        self
+    }
+
+    /// Send mail notifications for the given events to `mail_user` (`--mail-type` and `--mail-user`)
+    pub fn with_mail(mut self, mail_types: Vec<MailType>, mail_user: impl Into<String>) -> Self {
+        self.mail_types = mail_types;
+        self.mail_user = Some(mail_user.into());
+        self
+    }
+
+    /// Set the array task IDs (`--array`), e.g., `0-9%2`
+    pub fn with_array(mut self, array: impl Into<String>) -> Self {
+        self.array = Some(array.into());
+        self
+    }
+
+    /// Set the dependencies (`--dependency`), e.g., `afterok:123`
+    pub fn with_dependency(mut self, dependency: impl Into<String>) -> Self {
+        self.dependency = Some(dependency.into());
+        self
+    }
+
+    /// Set the path of the stdout file (`--output`)
+    pub fn with_output(mut self, output: impl Into<String>) -> Self {
+        self.output = Some(output.into());
+        self
+    }
+
+    /// Set the path of the stderr file (`--error`)
+    pub fn with_error(mut self, error: impl Into<String>) -> Self {
+        self.error = Some(error.into());
+        self
+    }
+
+    /// Request exclusive node allocation (`--exclusive`)
+    pub fn with_exclusive(mut self, exclusive: bool) -> Self {
+        self.exclusive = exclusive;
+        self
+    }
+
+    /// Allow the job to be requeued (`--requeue`)
+    pub fn with_requeue(mut self, requeue: bool) -> Self {
+        self.requeue = requeue;
+        self
+    }
+
+    /// Add a directive not modeled by this builder (e.g., `--switches=1`), which is emitted verbatim
+    pub fn with_extra_directive(mut self, directive: impl Into<String>) -> Self {
+        self.extra_directives.push(directive.into());
+        self
+    }
+
+    /// Export the environment variable `key` with the given `value` before executing the commands
+    pub fn with_env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.insert(key.into(), value.into());
+        self
+    }
+
+    /// Append a setup step (e.g., loading a module) to the script
+    pub fn with_setup(mut self, setup: ScriptSetup) -> Self {
+        self.commands.push(setup.to_string());
+        self
+    }
+
+    /// Append a command to the script
+    pub fn with_command(mut self, command: impl Into<String>) -> Self {
+        self.commands.push(command.into());
+        self
+    }
+
+    /// Check that all keys of [`SbatchScript::env`] are valid shell variable names (i.e., match `[A-Za-z_][A-Za-z0-9_]*`)
+    ///
+    /// Other keys would be executed as shell code when exporting them in the script.
+    pub fn check_env(&self) -> Result<(), Error> {
+        match self.env.keys().find(|key| !is_shell_variable_name(key)) {
+            Some(key) => Err(Error::msg(format!(
+                "Invalid environment variable name: {key:?}"
+            ))),
+            None => Ok(()),
+        }
+    }
+
+    /// All lines of the script body (i.e., exported environment variables and commands)
+    pub fn body_lines(&self) -> Vec<String> {
+        self.env
+            .iter()
+            .map(|(key, value)| format!("export {key}={}", shell_quote(value)))
+            .chain(self.commands.iter().cloned())
+            .collect()
+    }
+
+    /// All set directives as `sbatch` options (e.g., `--partition=c23ms`), in the order they are rendered
+    pub fn directives(&self) -> Vec<String> {
+        self.options()
+            .into_iter()
+            .map(|(flag, value)| match value {
+                Some(value) => format!("{flag}={}", quote_directive_value(&value)),
+                None => flag,
+            })
+            .chain(self.extra_directives())
+            .collect()
+    }
+
+    /// All set directives as shell-quoted command line arguments for `sbatch`
+    pub fn to_sbatch_args(&self) -> String {
+        self.options()
+            .into_iter()
+            .map(|(flag, value)| match value {
+                Some(value) => shell_quote(&format!("{flag}={value}")),
+                None => flag,
+            })
+            .chain(self.extra_directives().map(|d| shell_quote(&d)))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    // Extra directives, without line breaks (which would end the directive)
+    fn extra_directives(&self) -> impl Iterator<Item = String> + '_ {
+        self.extra_directives
+            .iter()
+            .map(|d| d.replace(['\n', '\r'], " ").trim().to_string())
+            .filter(|d| !d.is_empty())
+    }
+
+    // All set options as flag (e.g., `--partition`) and (unquoted) value, if the option has one
+    fn options(&self) -> Vec<(String, Option<String>)> {
+        let mut options = Vec::new();
+        let mut add = |flag: &str, value: Option<String>| {
+            if let Some(value) = value {
+                options.push((format!("--{flag}"), Some(value)));
+            }
+        };
+        add("job-name", self.job_name.clone());
+        add("ntasks", self.ntasks.map(|n| n.to_string()));
+        add("nodes", self.nodes.clone());
+        add("cpus-per-task", self.cpus_per_task.map(|n| n.to_string()));
+        add("time", self.time.as_ref().map(|t| t.to_string()));
+        add("partition", self.partition.clone());
+        add("account", self.account.clone());
+        add("qos", self.qos.clone());
+        add(
+            "gres",
+            (!self.gres.is_empty()).then(|| {
+                self.gres
+                    .iter()
+                    .map(|g| {
+                        TresRequest {
+                            category: None,
+                            ..g.clone()
+                        }
+                        .to_string()
+                    })
+                    .collect::<Vec<_>>()
+                    .join(",")
+            }),
+        );
+        add("constraint", self.constraint.clone());
+        add(
+            "mail-type",
+            (!self.mail_types.is_empty()).then(|| {
+                self.mail_types
+                    .iter()
+                    .map(|t| t.to_string())
+                    .collect::<Vec<_>>()
+                    .join(",")
+            }),
+        );
+        add("mail-user", self.mail_user.clone());
+        add("array", self.array.clone());
+        add("dependency", self.dependency.clone());
+        add("output", self.output.clone());
+        add("error", self.error.clone());
+        if let Some(memory) = &self.memory {
+            let arg = memory.to_sbatch_arg();
+            if let Some((flag, value)) = arg.split_once("=") {
+                options.push((flag.to_string(), Some(value.to_string())));
+            }
+        }
+        if self.exclusive {
+            options.push((String::from("--exclusive"), None));
+        }
+        if self.requeue {
+            options.push((String::from("--requeue"), None));
+        }
+        options
+    }
+}
+
+impl std::fmt::Display for SbatchScript {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "#!{}", self.shell.path())?;
+        for directive in self.directives() {
+            writeln!(f, "#SBATCH {directive}")?;
+        }
+        writeln!(f)?;
+        for line in self.body_lines() {
+            writeln!(f, "{line}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Quote a value of an `#SBATCH` directive, if it contains whitespace or quotes
+///
+/// Line breaks are replaced by spaces, as they would end the directive.
+fn quote_directive_value(value: &str) -> String {
+    let value = value.replace(['\n', '\r'], " ");
+    if value.contains(|c: char| c.is_whitespace() || c == '"' || c == '\'') {
+        format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        value
+    }
+}
+
+/// Sanitize a job name for use with `sbatch` (`--job-name`)
+///
+/// All characters except ASCII letters, digits, `-`, `_` and `.` are replaced by `_`,
+/// so that the name stays readable in `squeue` output and can safely be used in file names (i.e., using the `%x` placeholder).
+pub fn sanitize_job_name(name: &str) -> String {
+    name.trim()
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+/// Check if `name` is a valid shell variable name (e.g., `OMP_NUM_THREADS`)
+fn is_shell_variable_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Quote a path for POSIX shells (see [`shell_quote`]), keeping a leading `~/` unquoted so that it is still expanded
+fn quote_path(path: &str) -> String {
+    match path.strip_prefix("~/") {
+        Some(rest) => format!("~/{}", shell_quote(rest)),
+        None => shell_quote(path),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{shell_quote, JobShell, MailType, SbatchScript};
+    use crate::MemorySpec;
+
+    #[test]
+    fn test_sbatch_script() {
+        let script = SbatchScript::new()
+            .with_job_name("param sweep")
+            .with_ntasks(1)
+            .with_cpus_per_task(12)
+            .with_time("0-01:00:00".parse().unwrap())
+            .with_memory(MemorySpec::per_cpu_mb(3900))
+            .with_partition("c23g")
+            .with_account("rwth1234")
+            .with_gres("gres/gpu:h100:2".parse().unwrap())
+            .with_mail(vec![MailType::END, MailType::FAIL], "user@example.com")
+            .with_array("0-9%2")
+            .with_output("out-%j.txt")
+            .with_exclusive(true)
+            .with_extra_directive("--switches=1@10:00")
+            .with_env("GREETING", "Hello 'World'")
+            .with_command("echo $GREETING");
+        assert_eq!(
+            script.to_string(),
+            r#"#!/usr/bin/zsh
+#SBATCH --job-name="param sweep"
+#SBATCH --ntasks=1
+#SBATCH --cpus-per-task=12
+#SBATCH --time=01:00:00
+#SBATCH --partition=c23g
+#SBATCH --account=rwth1234
+#SBATCH --gres=gpu:h100:2
+#SBATCH --mail-type=END,FAIL
+#SBATCH --mail-user=user@example.com
+#SBATCH --array=0-9%2
+#SBATCH --output=out-%j.txt
+#SBATCH --mem-per-cpu=3900M
+#SBATCH --exclusive
+#SBATCH --switches=1@10:00
+
+export GREETING='Hello '\''World'\'''
+echo $GREETING
+"#
+        );
+        assert_eq!(
+            SbatchScript::new()
+                .with_job_name("param sweep")
+                .with_memory(MemorySpec::per_node_mb(4096))
+                .with_exclusive(true)
+                .with_extra_directive("--comment=my sweep")
+                .to_sbatch_args(),
+            "'--job-name=param sweep' '--mem=4G' --exclusive '--comment=my sweep'"
+        );
+        assert_eq!(shell_quote("echo 'a'"), r"'echo '\''a'\'''");
+        assert_eq!(
+            SbatchScript::new()
+                .with_shell(JobShell::Bash)
+                .with_command(r#"echo "it's $HOME" | sed 's/a/b/'"#)
+                .to_string(),
+            "#!/bin/bash\n\necho \"it's $HOME\" | sed 's/a/b/'\n"
+        );
+    }
+
+    #[test]
+    fn test_check_env() {
+        let script = SbatchScript::new()
+            .with_env("OMP_NUM_THREADS", "12")
+            .with_env("_private2", "1");
+        assert!(script.check_env().is_ok());
+        for key in ["A=1; rm -rf ~ #", "2FA", "", "MY-VAR"] {
+            assert!(script.clone().with_env(key, "1").check_env().is_err());
+        }
+    }
+}
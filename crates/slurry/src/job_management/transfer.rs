@@ -0,0 +1,357 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::Error;
+use async_ssh2_tokio::Client;
+use russh_sftp::client::SftpSession;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+const CHUNK_SIZE: usize = 64 * 1024;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Progress of a running upload or download
+///
+/// Reported by [`download_job_results`] and [`submit_job_with_progress`](super::submit_job_with_progress).
+pub struct TransferProgress {
+    /// The file currently being transferred (relative to the remote directory)
+    pub file: String,
+    /// Number of transferred bytes of the current file
+    pub file_transferred_bytes: u64,
+    /// Size of the current file in bytes
+    pub file_total_bytes: u64,
+    /// Number of completely transferred files
+    pub transferred_files: usize,
+    /// Total number of files to transfer
+    pub total_files: usize,
+    /// Number of transferred bytes (over all files)
+    pub transferred_bytes: u64,
+    /// Total number of bytes to transfer (over all files)
+    pub total_bytes: u64,
+}
+
+/// Open a SFTP session over a new SSH channel
+pub(crate) async fn open_sftp(client: &Client) -> Result<SftpSession, Error> {
+    let channel = client.get_channel().await?;
+    channel.request_subsystem(true, "sftp").await?;
+    Ok(SftpSession::new(channel.into_stream()).await?)
+}
+
+/// The path to use for SFTP requests, which are resolved relative to the home directory but do not expand `~`
+pub(crate) fn sftp_path(path: &str) -> &str {
+    path.strip_prefix("~/").unwrap_or(path)
+}
+
+/// Upload the file at `local_path` to `remote_path` over SFTP, calling `on_chunk` with the number of bytes of each sent chunk
+///
+/// Fails if the remote file cannot be created or written (e.g., if the directory does not exist or the quota is exceeded).
+pub(crate) async fn upload_file_with_progress(
+    client: &Client,
+    local_path: &Path,
+    remote_path: &str,
+    mut on_chunk: impl FnMut(u64),
+) -> Result<(), Error> {
+    let mut local = tokio::fs::File::open(local_path).await?;
+    let sftp = open_sftp(client).await?;
+    let mut remote = sftp
+        .create(sftp_path(remote_path))
+        .await
+        .map_err(|e| Error::new(e).context(format!("Could not create {remote_path}.")))?;
+    let mut buf = vec![0; CHUNK_SIZE];
+    loop {
+        let n = local.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        remote
+            .write_all(&buf[..n])
+            .await
+            .map_err(|e| Error::new(e).context(format!("Could not write {remote_path}.")))?;
+        on_chunk(n as u64);
+    }
+    remote.shutdown().await?;
+    sftp.close().await?;
+    Ok(())
+}
+
+/// Download the file at `remote_path` to `local_path` over SFTP, calling `on_chunk` with the number of bytes of each received chunk
+///
+/// Fails if the remote file cannot be opened or read (e.g., if it does not exist), removing the partially written local file.
+pub(crate) async fn download_file_with_progress(
+    client: &Client,
+    remote_path: &str,
+    local_path: &Path,
+    on_chunk: impl FnMut(u64),
+) -> Result<(), Error> {
+    let sftp = open_sftp(client).await?;
+    let res = download_sftp_file(&sftp, remote_path, local_path, on_chunk).await;
+    sftp.close().await?;
+    res
+}
+
+/// Download the file at `remote_path` to `local_path` using an open SFTP session (see [`download_file_with_progress`])
+async fn download_sftp_file(
+    sftp: &SftpSession,
+    remote_path: &str,
+    local_path: &Path,
+    mut on_chunk: impl FnMut(u64),
+) -> Result<(), Error> {
+    let mut remote = sftp
+        .open(sftp_path(remote_path))
+        .await
+        .map_err(|e| Error::new(e).context(format!("Could not open {remote_path}.")))?;
+    let mut local = tokio::fs::File::create(local_path).await?;
+    let mut buf = vec![0; CHUNK_SIZE];
+    let res: Result<(), Error> = async {
+        loop {
+            let n = remote.read(&mut buf).await?;
+            if n == 0 {
+                break;
+            }
+            local.write_all(&buf[..n]).await?;
+            on_chunk(n as u64);
+        }
+        local.flush().await?;
+        Ok(())
+    }
+    .await;
+    if let Err(e) = res {
+        drop(local);
+        let _ = tokio::fs::remove_file(local_path).await;
+        return Err(e.context(format!("Could not download {remote_path}.")));
+    }
+    Ok(())
+}
+
+/// Write `contents` to the file at `remote_path` over SFTP
+///
+/// In contrast to passing the contents as command line argument, this works for arbitrary contents without any quoting or length limits.
+pub(crate) async fn write_remote_file(
+    client: &Client,
+    remote_path: &str,
+    contents: &[u8],
+) -> Result<(), Error> {
+    let sftp = open_sftp(client).await?;
+    let mut remote = sftp
+        .create(sftp_path(remote_path))
+        .await
+        .map_err(|e| Error::new(e).context(format!("Could not create {remote_path}.")))?;
+    remote
+        .write_all(contents)
+        .await
+        .map_err(|e| Error::new(e).context(format!("Could not write {remote_path}.")))?;
+    remote.shutdown().await?;
+    sftp.close().await?;
+    Ok(())
+}
+
+/// Remote directories from which files can be downloaded (i.e., over SFTP, see [`download_job_results`])
+trait RemoteFiles {
+    /// List all regular files below `dir` (recursively), by their size and path relative to `dir` (using `/` as separator)
+    async fn list_files(&self, dir: &str) -> Result<Vec<(u64, String)>, Error>;
+
+    /// Download the file at `remote_path` to `local_path`, calling `on_chunk` with the number of bytes of each received chunk
+    async fn download(
+        &self,
+        remote_path: &str,
+        local_path: &Path,
+        on_chunk: impl FnMut(u64),
+    ) -> Result<(), Error>;
+}
+
+impl RemoteFiles for SftpSession {
+    async fn list_files(&self, dir: &str) -> Result<Vec<(u64, String)>, Error> {
+        let mut files = Vec::new();
+        let mut dirs = vec![String::new()];
+        while let Some(sub_dir) = dirs.pop() {
+            let path = join_remote_path(dir, &sub_dir);
+            let entries = self
+                .read_dir(sftp_path(&path))
+                .await
+                .map_err(|e| Error::new(e).context(format!("Could not list files in {path}.")))?;
+            for entry in entries {
+                let name = entry.file_name();
+                // Skip `.` and `..`, as well as invalid names which could escape the local directory
+                if matches!(name.as_str(), "" | "." | "..") || name.contains(['/', '\\']) {
+                    continue;
+                }
+                let file = join_remote_path(&sub_dir, &name);
+                let file_type = entry.file_type();
+                if file_type.is_dir() {
+                    dirs.push(file);
+                } else if file_type.is_file() {
+                    files.push((entry.metadata().len(), file));
+                }
+            }
+        }
+        files.sort_by(|(_, a), (_, b)| a.cmp(b));
+        Ok(files)
+    }
+
+    async fn download(
+        &self,
+        remote_path: &str,
+        local_path: &Path,
+        on_chunk: impl FnMut(u64),
+    ) -> Result<(), Error> {
+        download_sftp_file(self, remote_path, local_path, on_chunk).await
+    }
+}
+
+/// Join a remote directory and a (relative) path inside it
+///
+/// An empty directory refers to the home directory, so the path is returned as is.
+fn join_remote_path(dir: &str, path: &str) -> String {
+    match dir.trim_end_matches('/') {
+        "" if dir.is_empty() => path.to_string(),
+        dir => format!("{dir}/{path}"),
+    }
+}
+
+/// Download all files produced by a job from `remote_dir` (recursively) into `local_dir`
+///
+/// If `filters` are given, only files whose path (relative to `remote_dir`) matches at least one of the glob patterns are downloaded
+/// (e.g., `results/*.csv` or `**/*.json`).
+/// Files are listed and transferred over a single SFTP session, reporting progress to `on_progress` after every received chunk.
+/// Symbolic links are not followed.
+///
+/// Fails on the first file which could not be downloaded, removing its partially written local copy (but keeping all previously downloaded files).
+/// Returns the local paths of all downloaded files.
+pub async fn download_job_results(
+    client: &Client,
+    remote_dir: &str,
+    local_dir: &Path,
+    filters: &[String],
+    on_progress: impl FnMut(&TransferProgress),
+) -> Result<Vec<PathBuf>, Error> {
+    let sftp = open_sftp(client).await?;
+    let res = download_files(&sftp, remote_dir, local_dir, filters, on_progress).await;
+    sftp.close().await?;
+    res
+}
+
+/// Download all (matching) files from `remote_dir` into `local_dir` (see [`download_job_results`])
+async fn download_files(
+    remote: &impl RemoteFiles,
+    remote_dir: &str,
+    local_dir: &Path,
+    filters: &[String],
+    mut on_progress: impl FnMut(&TransferProgress),
+) -> Result<Vec<PathBuf>, Error> {
+    let patterns = filters
+        .iter()
+        .map(|f| glob::Pattern::new(f))
+        .collect::<Result<Vec<_>, _>>()?;
+    let files: Vec<(u64, String)> = remote
+        .list_files(remote_dir)
+        .await?
+        .into_iter()
+        .filter(|(_, file)| patterns.is_empty() || patterns.iter().any(|p| p.matches(file)))
+        .collect();
+    let mut progress = TransferProgress {
+        total_files: files.len(),
+        total_bytes: files.iter().map(|(size, _)| size).sum(),
+        ..Default::default()
+    };
+    let mut downloaded = Vec::with_capacity(files.len());
+    for (size, file) in files {
+        let local_path = local_dir.join(&file);
+        if let Some(parent) = local_path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        let remote_path = join_remote_path(remote_dir, &file);
+        progress.file = file;
+        progress.file_transferred_bytes = 0;
+        progress.file_total_bytes = size;
+        remote
+            .download(&remote_path, &local_path, |n| {
+                progress.file_transferred_bytes += n;
+                progress.transferred_bytes += n;
+                on_progress(&progress);
+            })
+            .await?;
+        progress.transferred_files += 1;
+        on_progress(&progress);
+        downloaded.push(local_path);
+    }
+    Ok(downloaded)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::Path, sync::Mutex};
+
+    use anyhow::Error;
+
+    use super::{download_files, join_remote_path, RemoteFiles};
+
+    /// Remote directory with a readable `stdout.txt` and an unreadable `results/run 1.csv`
+    #[derive(Default)]
+    struct FakeRemote {
+        downloaded: Mutex<Vec<String>>,
+    }
+
+    impl RemoteFiles for FakeRemote {
+        async fn list_files(&self, _dir: &str) -> Result<Vec<(u64, String)>, Error> {
+            Ok(vec![
+                (52, "results/run 1.csv".to_string()),
+                (6, "stdout.txt".to_string()),
+            ])
+        }
+
+        async fn download(
+            &self,
+            remote_path: &str,
+            local_path: &Path,
+            mut on_chunk: impl FnMut(u64),
+        ) -> Result<(), Error> {
+            self.downloaded
+                .lock()
+                .unwrap()
+                .push(remote_path.to_string());
+            if remote_path.ends_with(".csv") {
+                return Err(Error::msg(format!("Could not open {remote_path}.")));
+            }
+            tokio::fs::write(local_path, "output").await?;
+            on_chunk(6);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_download_files() {
+        assert_eq!(join_remote_path("", "stdout.txt"), "stdout.txt");
+        assert_eq!(join_remote_path("/", "stdout.txt"), "/stdout.txt");
+        assert_eq!(
+            join_remote_path("jobs/1//", "stdout.txt"),
+            "jobs/1/stdout.txt"
+        );
+
+        let local_dir =
+            std::env::temp_dir().join(format!("slurry-download-{}", std::process::id()));
+        let remote = FakeRemote::default();
+        let mut progress = Vec::new();
+        let downloaded = download_files(
+            &remote,
+            "jobs/1/",
+            &local_dir,
+            &["*.txt".to_string()],
+            |p| progress.push(p.clone()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(downloaded, vec![local_dir.join("stdout.txt")]);
+        assert_eq!(progress.last().unwrap().transferred_files, 1);
+        assert_eq!(progress.last().unwrap().total_bytes, 6);
+
+        // Failed downloads are reported by their (normalized) remote path
+        let err = download_files(&remote, "jobs/1/", &local_dir, &[], |_| {})
+            .await
+            .unwrap_err();
+        assert_eq!(err.to_string(), "Could not open jobs/1/results/run 1.csv.");
+        assert_eq!(
+            *remote.downloaded.lock().unwrap(),
+            ["jobs/1/stdout.txt", "jobs/1/results/run 1.csv"]
+        );
+        assert!(local_dir.join("stdout.txt").exists());
+        std::fs::remove_dir_all(&local_dir).unwrap();
+    }
+}
@@ -0,0 +1,290 @@
+use std::{path::PathBuf, time::Duration};
+
+use crate::executor::CommandExecutor;
+use anyhow::Error;
+
+use super::JobOptions;
+use crate::{
+    data_extraction::{get_qos_list, get_sinfo_partitions, QosRow, SinfoPartitionRow},
+    parse_slurm_size, SlurmDuration,
+};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// Limits of a cluster which submitted jobs have to respect (see [`JobOptions::validate`])
+///
+/// The default value does not impose any limits.
+pub struct ClusterLimits {
+    /// Maximum time limit of a job
+    pub max_time: Option<Duration>,
+    /// Maximum number of CPUs of a job
+    pub max_cpus: Option<usize>,
+    /// Maximum memory of a job (per node) in bytes
+    pub max_memory: Option<u64>,
+    /// Names of all available partitions (if known)
+    pub partitions: Vec<String>,
+}
+
+impl ClusterLimits {
+    /// Limits of the given partition (as reported by `sinfo`)
+    pub fn from_partition(partition: &SinfoPartitionRow) -> Self {
+        Self {
+            max_time: partition.time_limit,
+            max_cpus: None,
+            max_memory: Some((partition.memory as u64) << 20).filter(|m| *m > 0),
+            partitions: vec![partition.partition.clone()],
+        }
+    }
+
+    /// Additionally respect the per-job limits of the given QOS (as reported by `sacctmgr`)
+    pub fn with_qos(mut self, qos: &QosRow) -> Self {
+        self.max_time = min_limit(self.max_time, qos.max_wall);
+        let cpus = qos.max_tres_per_job.get("cpu").and_then(|c| c.parse().ok());
+        self.max_cpus = min_limit(self.max_cpus, cpus);
+        let memory = qos.max_tres_per_job.get("mem").and_then(|m| {
+            // TRES amounts without unit are given in megabytes
+            match m.parse::<u64>() {
+                Ok(mb) => Some(mb << 20),
+                Err(_) => parse_slurm_size(m).ok(),
+            }
+        });
+        self.max_memory = min_limit(self.max_memory, memory);
+        self
+    }
+
+    /// Fetch the limits of the given (or default) partition and QOS from the cluster
+    ///
+    /// All available partitions are included in [`ClusterLimits::partitions`].
+    pub async fn fetch(
+        client: &impl CommandExecutor,
+        partition: Option<&str>,
+        qos: Option<&str>,
+    ) -> Result<Self, Error> {
+        let (_time, partitions) = get_sinfo_partitions(client).await?;
+        let mut limits = partitions
+            .iter()
+            .find(|p| match partition {
+                Some(name) => p.partition == name,
+                None => p.is_default,
+            })
+            .map(Self::from_partition)
+            .unwrap_or_default();
+        limits.partitions = partitions.into_iter().map(|p| p.partition).collect();
+        if let Some(qos) = qos {
+            let qos_list = get_qos_list(client).await?;
+            if let Some(qos) = qos_list.iter().find(|q| q.name == qos) {
+                limits = limits.with_qos(qos);
+            }
+        }
+        Ok(limits)
+    }
+}
+
+/// The stricter of two optional limits
+fn min_limit<T: Ord>(a: Option<T>, b: Option<T>) -> Option<T> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (a, b) => a.or(b),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Problem of [`JobOptions`] detected before submitting a job (see [`JobOptions::validate`])
+pub enum JobOptionsViolation {
+    /// No (finite) time limit is set
+    MissingTime,
+    /// The time limit exceeds the limit of the cluster
+    TimeExceedsLimit {
+        /// Requested time limit
+        requested: Duration,
+        /// Maximum time limit
+        limit: Duration,
+    },
+    /// No CPUs are requested
+    NoCpus,
+    /// The number of CPUs exceeds the limit of the cluster
+    CpusExceedLimit {
+        /// Requested number of CPUs
+        requested: usize,
+        /// Maximum number of CPUs
+        limit: usize,
+    },
+    /// No memory is requested
+    NoMemory,
+    /// The (total) memory exceeds the limit of the cluster
+    MemoryExceedsLimit {
+        /// Requested memory in bytes
+        requested: u64,
+        /// Maximum memory in bytes
+        limit: u64,
+    },
+    /// A local file to upload does not exist
+    MissingFile(PathBuf),
+    /// The partition is not available on the cluster
+    UnknownPartition(String),
+    /// Mail notifications are requested without a recipient
+    MissingMailUser,
+    /// No command to execute is given
+    EmptyCommand,
+}
+
+impl std::fmt::Display for JobOptionsViolation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JobOptionsViolation::MissingTime => write!(f, "No time limit set"),
+            JobOptionsViolation::TimeExceedsLimit { requested, limit } => write!(
+                f,
+                "Time limit {} exceeds maximum of {}",
+                SlurmDuration::from(*requested),
+                SlurmDuration::from(*limit)
+            ),
+            JobOptionsViolation::NoCpus => write!(f, "No CPUs requested"),
+            JobOptionsViolation::CpusExceedLimit { requested, limit } => {
+                write!(f, "{requested} CPUs exceed maximum of {limit}")
+            }
+            JobOptionsViolation::NoMemory => write!(f, "No memory requested"),
+            JobOptionsViolation::MemoryExceedsLimit { requested, limit } => write!(
+                f,
+                "Memory of {}M exceeds maximum of {}M",
+                requested >> 20,
+                limit >> 20
+            ),
+            JobOptionsViolation::MissingFile(path) => {
+                write!(f, "File to upload {path:?} does not exist")
+            }
+            JobOptionsViolation::UnknownPartition(partition) => {
+                write!(f, "Unknown partition {partition}")
+            }
+            JobOptionsViolation::MissingMailUser => {
+                write!(f, "Mail notifications require a mail user")
+            }
+            JobOptionsViolation::EmptyCommand => write!(f, "No command given"),
+        }
+    }
+}
+
+impl JobOptions {
+    /// Check these options for problems before submitting a job, respecting the given cluster `limits`
+    ///
+    /// This does not access the cluster (see [`ClusterLimits::fetch`] for obtaining the limits beforehand).
+    /// Returns all detected violations, i.e., an empty list if the options are valid.
+    pub fn validate(&self, limits: &ClusterLimits) -> Vec<JobOptionsViolation> {
+        let mut violations = Vec::new();
+        match self.time.as_duration() {
+            Some(time) if !time.is_zero() => {
+                if let Some(limit) = limits.max_time.filter(|limit| time > *limit) {
+                    violations.push(JobOptionsViolation::TimeExceedsLimit {
+                        requested: time,
+                        limit,
+                    });
+                }
+            }
+            _ => violations.push(JobOptionsViolation::MissingTime),
+        }
+        if self.num_cpus == 0 {
+            violations.push(JobOptionsViolation::NoCpus);
+        } else if let Some(limit) = limits.max_cpus.filter(|limit| self.num_cpus > *limit) {
+            violations.push(JobOptionsViolation::CpusExceedLimit {
+                requested: self.num_cpus,
+                limit,
+            });
+        }
+        if let Some(memory) = &self.memory {
+            let requested = if memory.per_cpu {
+                memory.bytes * self.num_cpus as u64
+            } else {
+                memory.bytes
+            };
+            if requested == 0 {
+                violations.push(JobOptionsViolation::NoMemory);
+            } else if let Some(limit) = limits.max_memory.filter(|limit| requested > *limit) {
+                violations.push(JobOptionsViolation::MemoryExceedsLimit { requested, limit });
+            }
+        }
+        let mut files: Vec<&PathBuf> = self
+            .files_to_upload
+            .iter()
+            .map(|f| &f.local_path)
+            .filter(|path| !path.is_file())
+            .collect();
+        files.sort();
+        violations.extend(
+            files
+                .into_iter()
+                .map(|path| JobOptionsViolation::MissingFile(path.clone())),
+        );
+        if let Some(partition) = &self.partition {
+            // Multiple partitions can be given as comma-separated list
+            let unknown = partition.split(',').filter(|p| {
+                !limits.partitions.is_empty() && !limits.partitions.iter().any(|k| k == p)
+            });
+            violations
+                .extend(unknown.map(|p| JobOptionsViolation::UnknownPartition(p.to_string())));
+        }
+        if !self.mail_types.is_empty() && self.mail_user.is_none() {
+            violations.push(JobOptionsViolation::MissingMailUser);
+        }
+        if self.command.trim().is_empty() {
+            violations.push(JobOptionsViolation::EmptyCommand);
+        }
+        violations
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, time::Duration};
+
+    use super::{ClusterLimits, JobOptionsViolation};
+    use crate::{
+        job_management::{test_job_options, JobFilesToUpload, MailType},
+        MemorySpec, SlurmDuration,
+    };
+
+    #[test]
+    fn test_validate_job_options() {
+        let options = test_job_options();
+        assert_eq!(options.validate(&ClusterLimits::default()), vec![]);
+
+        let limits = ClusterLimits {
+            max_time: Some(Duration::from_secs(60 * 60)),
+            max_cpus: Some(96),
+            max_memory: Some(4 << 30),
+            partitions: vec!["c23ms".to_string(), "c23g".to_string()],
+        };
+        let mut options = test_job_options();
+        options.time = SlurmDuration::Finite(Duration::from_secs(2 * 60 * 60));
+        options.num_cpus = 8;
+        options.memory = Some("1Gc".parse::<MemorySpec>().unwrap());
+        options.partition = Some("c23ms,c18m".to_string());
+        options.mail_types = vec![MailType::END];
+        options.files_to_upload.insert(JobFilesToUpload {
+            local_path: PathBuf::from("/does/not/exist"),
+            remote_subpath: String::new(),
+            remote_file_name: "exist".to_string(),
+        });
+        assert_eq!(
+            options.validate(&limits),
+            vec![
+                JobOptionsViolation::TimeExceedsLimit {
+                    requested: Duration::from_secs(2 * 60 * 60),
+                    limit: Duration::from_secs(60 * 60)
+                },
+                JobOptionsViolation::MemoryExceedsLimit {
+                    requested: 8 << 30,
+                    limit: 4 << 30
+                },
+                JobOptionsViolation::MissingFile(PathBuf::from("/does/not/exist")),
+                JobOptionsViolation::UnknownPartition("c18m".to_string()),
+                JobOptionsViolation::MissingMailUser,
+            ]
+        );
+
+        options.time = SlurmDuration::Unlimited;
+        options.num_cpus = 0;
+        options.command = String::new();
+        let violations = options.validate(&ClusterLimits::default());
+        assert!(violations.contains(&JobOptionsViolation::MissingTime));
+        assert!(violations.contains(&JobOptionsViolation::NoCpus));
+        assert!(violations.contains(&JobOptionsViolation::EmptyCommand));
+    }
+}
@@ -0,0 +1,351 @@
+use std::collections::{BTreeMap, HashMap};
+
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+
+use super::{
+    cancel_jobs, get_job_statuses, submit_job_with, FolderID, JobDependency, JobOptions, JobStatus,
+    SubmittedJob,
+};
+use crate::{executor::CommandExecutor, JobState};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// A single job of a [`Workflow`]
+pub struct WorkflowNode {
+    /// Unique name of the node in the workflow
+    pub name: String,
+    /// Options of the job
+    pub options: JobOptions,
+    /// Names of the nodes which have to complete successfully before this job can start
+    pub depends_on: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+/// A workflow of SLURM jobs, forming a directed acyclic graph (DAG) of dependencies
+///
+/// All jobs are submitted at once using [`Workflow::submit`], with dependencies enforced by SLURM (`--dependency=afterok:...`).
+pub struct Workflow {
+    /// All nodes of the workflow
+    pub nodes: Vec<WorkflowNode>,
+}
+
+impl Workflow {
+    /// Create a new empty workflow
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a job to the workflow, which starts after all nodes named in `depends_on` completed successfully
+    pub fn with_job(
+        mut self,
+        name: impl Into<String>,
+        options: JobOptions,
+        depends_on: Vec<String>,
+    ) -> Self {
+        self.nodes.push(WorkflowNode {
+            name: name.into(),
+            options,
+            depends_on,
+        });
+        self
+    }
+
+    /// Order the nodes such that all dependencies of a node come before it
+    ///
+    /// Fails if node names are not unique, dependencies refer to unknown nodes, or the dependencies contain a cycle.
+    pub fn topological_order(&self) -> Result<Vec<&WorkflowNode>, Error> {
+        let mut remaining_deps: HashMap<&str, usize> = HashMap::new();
+        let mut dependents: HashMap<&str, Vec<&str>> = HashMap::new();
+        for node in &self.nodes {
+            if remaining_deps
+                .insert(&node.name, node.depends_on.len())
+                .is_some()
+            {
+                return Err(Error::msg(format!(
+                    "Duplicate workflow node {}.",
+                    node.name
+                )));
+            }
+            for dep in &node.depends_on {
+                dependents.entry(dep).or_default().push(&node.name);
+            }
+        }
+        if let Some((dep, _)) = dependents
+            .iter()
+            .find(|(dep, _)| !remaining_deps.contains_key(*dep))
+        {
+            return Err(Error::msg(format!("Unknown workflow node {dep}.")));
+        }
+        let nodes: HashMap<&str, &WorkflowNode> =
+            self.nodes.iter().map(|n| (n.name.as_str(), n)).collect();
+        // Kahn's algorithm, keeping the insertion order for independent nodes
+        let mut ready: Vec<&str> = self
+            .nodes
+            .iter()
+            .filter(|n| n.depends_on.is_empty())
+            .map(|n| n.name.as_str())
+            .rev()
+            .collect();
+        let mut order = Vec::with_capacity(self.nodes.len());
+        while let Some(name) = ready.pop() {
+            order.push(nodes[name]);
+            for dependent in dependents.get(name).into_iter().flatten() {
+                let deps = remaining_deps.get_mut(dependent).unwrap();
+                *deps -= 1;
+                if *deps == 0 {
+                    ready.insert(0, dependent);
+                }
+            }
+        }
+        if order.len() != self.nodes.len() {
+            return Err(Error::msg("Workflow dependencies contain a cycle."));
+        }
+        Ok(order)
+    }
+
+    /// Submit all jobs of the workflow, in topological order (see [`Workflow::topological_order`] and [`submit_job_with`])
+    ///
+    /// If submitting a job fails, all previously submitted jobs of the workflow are cancelled and the submission error is returned.
+    pub async fn submit(self, client: &impl CommandExecutor) -> Result<WorkflowRun, Error> {
+        let order: Vec<WorkflowNode> = self.topological_order()?.into_iter().cloned().collect();
+        let mut run = WorkflowRun::default();
+        for node in order {
+            let mut options = node.options;
+            for dep in &node.depends_on {
+                options
+                    .dependencies
+                    .push(JobDependency::AFTEROK(run.jobs[dep].1.job_id.clone()));
+            }
+            match submit_job_with(client, options).await {
+                Ok(job) => {
+                    run.jobs.insert(node.name, job);
+                }
+                Err(err) => {
+                    if let Err(cancel_err) = run.cancel(client).await {
+                        eprintln!("Failed to cancel the submitted workflow jobs: {cancel_err:?}");
+                    }
+                    return Err(
+                        err.context(format!("Failed to submit workflow node {}", node.name))
+                    );
+                }
+            }
+        }
+        Ok(run)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+/// Overall state of a submitted workflow (see [`WorkflowRun::state`])
+pub enum WorkflowState {
+    /// Some jobs are still pending or running
+    RUNNING,
+    /// All jobs completed successfully
+    COMPLETED,
+    /// At least one job failed (or was cancelled)
+    FAILED,
+    /// No job failed or is still running, but some jobs were not found (e.g., on clusters without accounting)
+    UNKNOWN,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+/// A submitted [`Workflow`]
+pub struct WorkflowRun {
+    /// Job folder and submitted job, by the name of the workflow node
+    pub jobs: BTreeMap<String, (FolderID, SubmittedJob)>,
+}
+
+impl WorkflowRun {
+    /// Get the status of all jobs of the workflow, by the name of the workflow node
+    ///
+    /// Jobs which are no longer listed by `squeue` are looked up using `sacct` (see [`get_job_statuses`]).
+    pub async fn status(
+        &self,
+        client: &impl CommandExecutor,
+    ) -> Result<BTreeMap<String, JobStatus>, Error> {
+        let job_ids: Vec<String> = self.jobs.values().map(|(_, j)| j.job_id.clone()).collect();
+        let mut statuses = get_job_statuses(client, &job_ids).await?;
+        Ok(self
+            .jobs
+            .iter()
+            .map(|(name, (_, job))| {
+                let status = statuses.remove(&job.job_id).unwrap_or(JobStatus::NotFound);
+                (name.clone(), status)
+            })
+            .collect())
+    }
+
+    /// Compute the overall state of the workflow from the status of all its jobs (see [`WorkflowRun::status`])
+    ///
+    /// Failed jobs take precedence over pending or running ones, which take precedence over jobs which were not found.
+    pub fn state(statuses: &BTreeMap<String, JobStatus>) -> WorkflowState {
+        let mut state = WorkflowState::COMPLETED;
+        for status in statuses.values() {
+            match status {
+                JobStatus::ENDED {
+                    state: JobState::COMPLETED,
+                    ..
+                } => {}
+                JobStatus::ENDED { .. } => return WorkflowState::FAILED,
+                JobStatus::PENDING { .. } | JobStatus::RUNNING { .. } => {
+                    state = WorkflowState::RUNNING
+                }
+                JobStatus::NotFound => {
+                    if state == WorkflowState::COMPLETED {
+                        state = WorkflowState::UNKNOWN
+                    }
+                }
+            }
+        }
+        state
+    }
+
+    /// Cancel all jobs of the workflow
+    pub async fn cancel(&self, client: &impl CommandExecutor) -> Result<(), Error> {
+        let job_ids: Vec<String> = self.jobs.values().map(|(_, j)| j.job_id.clone()).collect();
+        cancel_jobs(client, &job_ids).await?;
+        Ok(())
+    }
+
+    /// If any job of the workflow failed, cancel all remaining (i.e., pending or running) jobs
+    ///
+    /// Otherwise, dependent jobs of failed jobs would stay pending forever.
+    /// Returns the names of the cancelled workflow nodes.
+    pub async fn cancel_remaining_on_failure(
+        &self,
+        client: &impl CommandExecutor,
+    ) -> Result<Vec<String>, Error> {
+        let statuses = self.status(client).await?;
+        if Self::state(&statuses) != WorkflowState::FAILED {
+            return Ok(Vec::new());
+        }
+        let remaining: Vec<String> = statuses
+            .into_iter()
+            .filter(|(_, s)| matches!(s, JobStatus::PENDING { .. } | JobStatus::RUNNING { .. }))
+            .map(|(name, _)| name)
+            .collect();
+        let job_ids: Vec<String> = remaining
+            .iter()
+            .map(|name| self.jobs[name].1.job_id.clone())
+            .collect();
+        cancel_jobs(client, &job_ids).await?;
+        Ok(remaining)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::{Workflow, WorkflowRun, WorkflowState};
+    use crate::{
+        executor::{mock::MockExecutor, CommandOutput},
+        job_management::{test_job_options, JobStatus},
+        JobState,
+    };
+
+    #[test]
+    fn test_workflow() {
+        let workflow = Workflow::new()
+            .with_job("evaluate", test_job_options(), vec!["train".to_string()])
+            .with_job("preprocess", test_job_options(), vec![])
+            .with_job("train", test_job_options(), vec!["preprocess".to_string()])
+            .with_job(
+                "report",
+                test_job_options(),
+                vec!["evaluate".to_string(), "preprocess".to_string()],
+            );
+        let order: Vec<&str> = workflow
+            .topological_order()
+            .unwrap()
+            .iter()
+            .map(|n| n.name.as_str())
+            .collect();
+        assert_eq!(order, vec!["preprocess", "train", "evaluate", "report"]);
+
+        let cyclic = workflow
+            .clone()
+            .with_job("a", test_job_options(), vec!["b".to_string()])
+            .with_job("b", test_job_options(), vec!["a".to_string()]);
+        assert!(cyclic.topological_order().is_err());
+        let unknown = Workflow::new().with_job("a", test_job_options(), vec!["b".to_string()]);
+        assert!(unknown.topological_order().is_err());
+
+        let mut statuses = BTreeMap::from([
+            ("preprocess".to_string(), JobStatus::NotFound),
+            (
+                "train".to_string(),
+                JobStatus::RUNNING {
+                    start_time: None,
+                    end_time: None,
+                    exec_host: None,
+                    node_list: None,
+                    nodes: 1,
+                    elapsed: None,
+                    time_limit: None,
+                },
+            ),
+        ]);
+        assert_eq!(WorkflowRun::state(&statuses), WorkflowState::RUNNING);
+        statuses.insert(
+            "train".to_string(),
+            JobStatus::ENDED {
+                state: JobState::COMPLETED,
+                exit_code: None,
+                elapsed: None,
+                end_time: None,
+            },
+        );
+        assert_eq!(WorkflowRun::state(&statuses), WorkflowState::UNKNOWN);
+        statuses.insert(
+            "preprocess".to_string(),
+            JobStatus::ENDED {
+                state: JobState::COMPLETED,
+                exit_code: Some(0),
+                elapsed: None,
+                end_time: None,
+            },
+        );
+        assert_eq!(WorkflowRun::state(&statuses), WorkflowState::COMPLETED);
+        statuses.insert(
+            "evaluate".to_string(),
+            JobStatus::ENDED {
+                state: JobState::FAILED,
+                exit_code: None,
+                elapsed: None,
+                end_time: None,
+            },
+        );
+        assert_eq!(WorkflowRun::state(&statuses), WorkflowState::FAILED);
+    }
+
+    #[tokio::test]
+    async fn test_workflow_submit_failure() {
+        let mock = MockExecutor::new()
+            .with_output("mkdir -p", "")
+            .with_output("chmod +x", "49848561\n")
+            .with_response(
+                "chmod +x",
+                CommandOutput {
+                    stderr: "sbatch: error: Batch job submission failed".to_string(),
+                    exit_status: 1,
+                    ..Default::default()
+                },
+            )
+            .with_response(
+                "scancel",
+                CommandOutput {
+                    stderr: "scancel: error: Unable to contact slurm controller".to_string(),
+                    exit_status: 1,
+                    ..Default::default()
+                },
+            );
+        let workflow = Workflow::new()
+            .with_job("preprocess", test_job_options(), vec![])
+            .with_job("train", test_job_options(), vec!["preprocess".to_string()]);
+        let err = workflow.submit(&mock).await.unwrap_err();
+        // The submission error is returned, even though cancelling failed as well
+        assert_eq!(err.to_string(), "Failed to submit workflow node train");
+        assert!(err.root_cause().to_string().starts_with("sbatch failed"));
+        assert!(mock.calls().contains(&"scancel '49848561'".to_string()));
+    }
+}
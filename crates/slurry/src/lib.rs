@@ -13,32 +13,79 @@ use anyhow::Error;
 use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "ssh")]
-use async_ssh2_tokio::client::{AuthKeyboardInteractive, AuthMethod, ServerCheckMethod};
-#[cfg(feature = "ssh")]
-const SERVER_CHECK_METHOD: ServerCheckMethod = ServerCheckMethod::NoCheck;
+use async_ssh2_tokio::client::{AuthKeyboardInteractive, AuthMethod};
 #[cfg(feature = "ssh")]
 pub use async_ssh2_tokio::Client;
 
-#[cfg(feature = "ssh")]
+#[cfg(feature = "stream")]
 /// Module for managing (e.g., creating or cancelling) SLURM jobs
 pub mod job_management;
 
+/// Module for executing commands on SLURM systems
+/// e.g., locally or over SSH
+pub mod executor;
+
 /// Module for extracting data from SLURM systems
 /// e.g., about currently running jobs
 pub mod data_extraction;
 
+/// Module for storing recorded SLURM data
+/// e.g., `squeue` snapshots and job changes
+pub mod recording;
+
+/// Module for extracting event data from recorded SLURM data
+/// e.g., job lifecycles as XES event logs or CSV tables
+pub mod event_data_extraction;
+
+/// Module for computing metrics from extracted SLURM data
+/// e.g., the utilization of partitions
+pub mod metrics;
+
+#[cfg(feature = "slurmrestd")]
+/// Module for accessing SLURM clusters using the Slurm REST API (`slurmrestd`)
+/// e.g., if executing commands over SSH is not allowed
+pub mod slurmrestd;
+
+#[cfg(feature = "credentials")]
+/// Module for storing credentials in the OS keychain
+/// e.g., passwords or TOTP secrets referenced by connection configs
+pub mod credentials;
+
 /// Module for miscellaneous features
 ///
-/// e.g., SSH port forwarding
+/// e.g., SSH port forwarding or adaptive polling intervals
 pub mod misc;
 
 #[cfg(feature = "ssh")]
 #[doc(inline)]
-pub use misc::port_forwarding::ssh_port_forwarding;
+pub use misc::port_forwarding::{ssh_port_forwarding, ForwardDirection, PortForward};
+
+#[cfg(feature = "ssh")]
+#[doc(inline)]
+pub use misc::client_pool::ClientPool;
+
+#[cfg(feature = "ssh")]
+#[doc(inline)]
+pub use misc::resilient_client::ResilientClient;
+
+#[cfg(feature = "ssh")]
+#[doc(inline)]
+pub use misc::host_verification::HostVerification;
 
 #[cfg(feature = "ssh")]
 #[doc(inline)]
-pub use job_management::submit_job;
+pub use misc::auth_prompt::{PromptHandler, PromptResponse, SharedPromptHandler};
+
+#[cfg(feature = "ssh")]
+#[doc(inline)]
+pub use job_management::{cancel_job, submit_job};
+
+#[doc(inline)]
+pub use executor::{CommandExecutor, LocalExecutor, Timeout};
+
+#[cfg(feature = "stream")]
+#[doc(inline)]
+pub use executor::TimeoutExecutor;
 
 #[doc(inline)]
 pub use data_extraction::get_squeue_res_locally;
@@ -50,46 +97,150 @@ pub use data_extraction::get_squeue_res_ssh;
 #[doc(inline)]
 pub use data_extraction::squeue_diff;
 
-// days-hours:minutes:seconds
-fn parse_slurm_duration(s: &str) -> Result<Duration, Error> {
-    let mut dur = Duration::default();
-
-    let v: Vec<_> = s.split("-").collect();
-    let mut hms_part = v[0];
-    let has_days_part: bool = v.len() > 1;
-    if has_days_part {
-        // days part exists
-        let days: u64 = v[0].parse()?;
-        dur += Duration::from_secs(days * 60 * 60 * 24);
-        hms_part = v[1];
-    }
-    let hms = hms_part.split(":").collect::<Vec<_>>();
-
-    if hms.len() == 3 {
-        let hours: u64 = hms[0].parse()?;
-        let mins: u64 = hms[1].parse()?;
-        let secs: u64 = hms[1].parse()?;
-        dur += Duration::from_secs(secs + 60 * mins + 60 * 60 * hours);
-    } else if hms.len() == 2 {
-        let mins: u64 = hms[0].parse()?;
-        let secs: u64 = hms[1].parse()?;
-        dur += Duration::from_secs(secs + 60 * mins);
-    } else if hms.len() == 1 {
-        if has_days_part {
-            // then: hours
-            let hours: u64 = hms[0].parse()?;
-            dur += Duration::from_secs(60 * 60 * hours);
-        } else {
-            // otherwise: minutes
-            let mins: u64 = hms[0].parse()?;
-            dur += Duration::from_secs(60 * mins);
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// A time value as used by SLURM, e.g., for time limits (`--time`) or elapsed times
+///
+/// Supports all formats documented for `sbatch --time`, i.e., `minutes`, `minutes:seconds`,
+/// `hours:minutes:seconds`, `days-hours`, `days-hours:minutes` and `days-hours:minutes:seconds`,
+/// optionally with fractional seconds (e.g., `01:23.456`, as reported by `sacct`/`sstat`).
+///
+/// Formatted as `[days-]hours:minutes:seconds[.fraction]`, which can be parsed again.
+pub enum SlurmDuration {
+    /// A finite duration
+    Finite(Duration),
+    /// No time limit (`UNLIMITED` or `INFINITE`)
+    Unlimited,
+    /// Value is not set (e.g., `NOT_SET`, `N/A` or `NONE`)
+    NotSet,
+}
+
+impl SlurmDuration {
+    /// The finite duration, if there is one
+    pub fn as_duration(&self) -> Option<Duration> {
+        match self {
+            SlurmDuration::Finite(dur) => Some(*dur),
+            _ => None,
         }
-    } else {
-        println!("Parse Error: Got {} splits for duration {}.", hms.len(), s);
-        return Err(Error::msg("Invalid duration format."));
     }
+}
 
-    Ok(dur)
+impl From<Duration> for SlurmDuration {
+    fn from(dur: Duration) -> Self {
+        SlurmDuration::Finite(dur)
+    }
+}
+
+impl FromStr for SlurmDuration {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match s.to_uppercase().as_str() {
+            "UNLIMITED" | "INFINITE" => return Ok(SlurmDuration::Unlimited),
+            "NOT_SET" | "N/A" | "NONE" => return Ok(SlurmDuration::NotSet),
+            _ => {}
+        }
+        let component = |c: &str| -> Result<f64, Error> {
+            let v: f64 = c.parse()?;
+            if !v.is_finite() || v < 0.0 || c.starts_with('+') {
+                return Err(Error::msg(format!(
+                    "Invalid duration component {c} in {s}."
+                )));
+            }
+            Ok(v)
+        };
+        let (days, hms_part) = match s.split_once("-") {
+            Some((days, rest)) => (Some(component(days)?), rest),
+            None => (None, s),
+        };
+        let hms = hms_part
+            .split(":")
+            .map(component)
+            .collect::<Result<Vec<_>, _>>()?;
+        let secs = match (days, hms.as_slice()) {
+            // minutes
+            (None, [m]) => 60.0 * m,
+            // minutes:seconds
+            (None, [m, s]) => 60.0 * m + s,
+            // hours:minutes:seconds
+            (None, [h, m, s]) => 60.0 * 60.0 * h + 60.0 * m + s,
+            // days-hours
+            (Some(d), [h]) => 24.0 * 60.0 * 60.0 * d + 60.0 * 60.0 * h,
+            // days-hours:minutes
+            (Some(d), [h, m]) => 24.0 * 60.0 * 60.0 * d + 60.0 * 60.0 * h + 60.0 * m,
+            // days-hours:minutes:seconds
+            (Some(d), [h, m, s]) => 24.0 * 60.0 * 60.0 * d + 60.0 * 60.0 * h + 60.0 * m + s,
+            _ => return Err(Error::msg(format!("Invalid duration format {s}."))),
+        };
+        Ok(SlurmDuration::Finite(Duration::from_secs_f64(secs)))
+    }
+}
+
+impl std::fmt::Display for SlurmDuration {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let dur = match self {
+            SlurmDuration::Finite(dur) => dur,
+            SlurmDuration::Unlimited => return write!(f, "UNLIMITED"),
+            SlurmDuration::NotSet => return write!(f, "NOT_SET"),
+        };
+        let secs = dur.as_secs();
+        let (days, hours, mins, secs) = (
+            secs / (24 * 60 * 60),
+            (secs / (60 * 60)) % 24,
+            (secs / 60) % 60,
+            secs % 60,
+        );
+        if days > 0 {
+            write!(f, "{days}-")?;
+        }
+        write!(f, "{hours:02}:{mins:02}:{secs:02}")?;
+        if dur.subsec_nanos() > 0 {
+            let fraction = format!("{:09}", dur.subsec_nanos());
+            write!(f, ".{}", fraction.trim_end_matches('0'))?;
+        }
+        Ok(())
+    }
+}
+
+// Serialized as string (e.g., `1-12:00:00`), in the same format as passed to SLURM
+impl Serialize for SlurmDuration {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for SlurmDuration {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+// days-hours:minutes:seconds, only accepting finite durations
+fn parse_slurm_duration(s: &str) -> Result<Duration, Error> {
+    s.parse::<SlurmDuration>()?
+        .as_duration()
+        .ok_or_else(|| Error::msg(format!("Duration {s} is not finite.")))
+}
+
+// e.g., 1234K, 2.50G, or 512 (i.e., bytes without unit suffix)
+pub(crate) fn parse_slurm_size(s: &str) -> Result<u64, Error> {
+    let s = s.trim();
+    let (num, unit) = match s.find(|c: char| c.is_ascii_alphabetic()) {
+        Some(i) => s.split_at(i),
+        None => (s, ""),
+    };
+    let factor: u64 = match unit.to_uppercase().as_str() {
+        "" => 1,
+        "K" => 1 << 10,
+        "M" => 1 << 20,
+        "G" => 1 << 30,
+        "T" => 1 << 40,
+        "P" => 1 << 50,
+        u => return Err(Error::msg(format!("Invalid size unit {u} in {s}."))),
+    };
+    let num: f64 = num.parse()?;
+    Ok((num * factor as f64).round() as u64)
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
@@ -142,6 +293,413 @@ impl FromStr for JobState {
     }
 }
 
+impl std::fmt::Display for JobState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            JobState::OTHER(s) => write!(f, "{s}"),
+            s => write!(f, "{s:?}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+/// A request for a trackable resource (TRES) or generic resource (GRES), e.g., GPUs
+///
+/// Parsed from (and formatted as) strings like `gres/gpu:a100:2`, `gpu:2`, or `gres/shard=1`.
+pub struct TresRequest {
+    /// Category of the resource (e.g., `gres` or `license`), if specified
+    pub category: Option<String>,
+    /// Name of the resource (e.g., `gpu`)
+    pub name: String,
+    /// Type of the resource (e.g., the GPU model `a100`), if specified
+    pub resource_type: Option<String>,
+    /// Number of requested resources
+    pub count: u64,
+}
+
+impl TresRequest {
+    /// Parse a comma-separated list of TRES/GRES requests (e.g., `gres/gpu:a100:2,gres/shard:1`)
+    ///
+    /// Placeholders for unset values (`N/A`, `(null)`) result in an empty list.
+    pub fn parse_list(s: &str) -> Result<Vec<Self>, Error> {
+        match s.trim() {
+            "" | "N/A" | "(null)" | "None" => Ok(Vec::new()),
+            s => s.split(",").map(|r| r.parse()).collect(),
+        }
+    }
+}
+
+impl FromStr for TresRequest {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        // Strip allocation index information, e.g., `gpu:2(IDX:0-1)`
+        let s = s.split("(").next().unwrap_or_default().trim();
+        let (category, s) = match s.split_once("/") {
+            Some((category, rest)) => (Some(category.to_string()), rest),
+            None => match s.strip_prefix("gres:") {
+                Some(rest) => (Some(String::from("gres")), rest),
+                None => (None, s),
+            },
+        };
+        let (s, count) = match s.split_once("=") {
+            Some((s, count)) => (s, Some(count.parse::<u64>()?)),
+            None => (s, None),
+        };
+        let mut parts: Vec<&str> = s.split(":").collect();
+        let count = match count {
+            Some(count) => count,
+            None if parts.len() > 1 => match parts.last().unwrap().parse::<u64>() {
+                Ok(count) => {
+                    parts.pop();
+                    count
+                }
+                Err(_) => 1,
+            },
+            None => 1,
+        };
+        if parts.is_empty() || parts[0].is_empty() || parts.len() > 2 {
+            return Err(Error::msg(format!("Invalid TRES request: {s}")));
+        }
+        Ok(Self {
+            category,
+            name: parts[0].to_string(),
+            resource_type: parts.get(1).map(|t| t.to_string()),
+            count,
+        })
+    }
+}
+
+impl std::fmt::Display for TresRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(category) = &self.category {
+            write!(f, "{category}/")?;
+        }
+        write!(f, "{}", self.name)?;
+        if let Some(resource_type) = &self.resource_type {
+            write!(f, ":{resource_type}")?;
+        }
+        write!(f, ":{}", self.count)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq, Hash)]
+/// A request for a license, as specified using `--licenses` (e.g., `ansys:2` or `comsol@db`)
+pub struct LicenseRequest {
+    /// Name of the license, including the license server for remote licenses (e.g., `comsol@db`)
+    pub name: String,
+    /// Number of requested licenses
+    pub count: u32,
+}
+
+impl LicenseRequest {
+    /// Parse a comma-separated list of license requests (e.g., `ansys:2,comsol@db`)
+    ///
+    /// Placeholders for unset values (`N/A`, `(null)`) result in an empty list.
+    pub fn parse_list(s: &str) -> Result<Vec<Self>, Error> {
+        match s.trim() {
+            "" | "N/A" | "(null)" | "None" => Ok(Vec::new()),
+            s => s.split(",").map(|r| r.parse()).collect(),
+        }
+    }
+}
+
+impl FromStr for LicenseRequest {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (name, count) = match s.rsplit_once(":") {
+            Some((name, count)) => (name, count.parse()?),
+            None => (s, 1),
+        };
+        if name.is_empty() {
+            return Err(Error::msg(format!("Invalid license request: {s}")));
+        }
+        Ok(Self {
+            name: name.to_string(),
+            count,
+        })
+    }
+}
+
+impl std::fmt::Display for LicenseRequest {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.name, self.count)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+/// A memory requirement of a SLURM job, e.g., as requested using `--mem` or `--mem-per-cpu`
+///
+/// Parsed from (and formatted as) strings like `3900M`, `2G` or `3900Mc`.
+/// Values without unit suffix are interpreted as megabytes (as done by SLURM).
+/// A trailing `c` (or `n`) marks the memory as being per CPU (or per node).
+pub struct MemorySpec {
+    /// Amount of memory in bytes
+    pub bytes: u64,
+    /// If the memory is requested per allocated CPU (instead of per node)
+    pub per_cpu: bool,
+}
+
+impl MemorySpec {
+    /// Create a new per-node memory requirement of the given size in megabytes
+    pub fn per_node_mb(mb: u64) -> Self {
+        Self {
+            bytes: mb << 20,
+            per_cpu: false,
+        }
+    }
+
+    /// Create a new per-CPU memory requirement of the given size in megabytes
+    pub fn per_cpu_mb(mb: u64) -> Self {
+        Self {
+            bytes: mb << 20,
+            per_cpu: true,
+        }
+    }
+
+    /// Total amount of memory in bytes for a job allocation with `cpus` CPUs on `nodes` nodes
+    pub fn total_bytes(&self, cpus: usize, nodes: usize) -> u64 {
+        if self.per_cpu {
+            self.bytes * cpus as u64
+        } else {
+            self.bytes * nodes as u64
+        }
+    }
+
+    /// The corresponding `sbatch` option, i.e., `--mem=<size>` or `--mem-per-cpu=<size>`
+    pub fn to_sbatch_arg(&self) -> String {
+        let flag = if self.per_cpu {
+            "--mem-per-cpu"
+        } else {
+            "--mem"
+        };
+        format!("{flag}={}", self.size_str())
+    }
+
+    // Formats the size using the largest unit which represents it exactly (rounding up to whole kilobytes)
+    fn size_str(&self) -> String {
+        let kb = self.bytes.div_ceil(1 << 10);
+        if kb == 0 {
+            return String::from("0");
+        }
+        let (value, unit) = [("T", 1 << 30), ("G", 1 << 20), ("M", 1 << 10)]
+            .into_iter()
+            .find(|(_, factor)| kb.is_multiple_of(*factor))
+            .map(|(unit, factor)| (kb / factor, unit))
+            .unwrap_or((kb, "K"));
+        format!("{value}{unit}")
+    }
+}
+
+impl FromStr for MemorySpec {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let (s, per_cpu) = match s.strip_suffix(['c', 'C']) {
+            Some(s) => (s, true),
+            None => (s.strip_suffix(['n', 'N']).unwrap_or(s), false),
+        };
+        let bytes = if s.ends_with(|c: char| c.is_ascii_digit()) {
+            // No unit suffix: megabytes
+            parse_slurm_size(&format!("{s}M"))?
+        } else {
+            parse_slurm_size(s)?
+        };
+        Ok(Self { bytes, per_cpu })
+    }
+}
+
+impl std::fmt::Display for MemorySpec {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.size_str())?;
+        if self.per_cpu {
+            write!(f, "c")?;
+        }
+        Ok(())
+    }
+}
+
+// Serialized as string (e.g., `3900Mc`), which also allows reading recordings where memory was stored as raw string
+impl Serialize for MemorySpec {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for MemorySpec {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// A range of array task IDs, e.g., `3-10` or `1-9:2` (including the `end` ID)
+pub struct ArrayTaskRange {
+    /// First task ID
+    pub start: u32,
+    /// Last task ID (inclusive)
+    pub end: u32,
+    /// Step between task IDs
+    pub step: u32,
+}
+
+impl FromStr for ArrayTaskRange {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (range, step) = match s.split_once(":") {
+            Some((range, step)) => (range, step.parse()?),
+            None => (s, 1),
+        };
+        let (start, end) = match range.split_once("-") {
+            Some((start, end)) => (start.parse()?, end.parse()?),
+            None => {
+                let id = range.parse()?;
+                (id, id)
+            }
+        };
+        if step == 0 || start > end {
+            return Err(Error::msg(format!("Invalid array task range {s}.")));
+        }
+        Ok(Self { start, end, step })
+    }
+}
+
+impl std::fmt::Display for ArrayTaskRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.start)?;
+        if self.end != self.start {
+            write!(f, "-{}", self.end)?;
+        }
+        if self.step != 1 {
+            write!(f, ":{}", self.step)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+/// ID of a (possibly array) SLURM job, as reported by `squeue` (i.e., `%i`)
+///
+/// e.g., `49848561` (regular job), `49869434_2` (single array task), or `49616001_[3-10%1]` (pending array tasks)
+pub struct ArrayJobId {
+    /// The job ID (for array jobs, the ID of the whole array job)
+    pub base_id: String,
+    /// The array task ranges (empty for regular jobs)
+    pub tasks: Vec<ArrayTaskRange>,
+    /// Maximum number of simultaneously running array tasks (i.e., `%<throttle>`)
+    pub throttle: Option<u32>,
+}
+
+impl ArrayJobId {
+    /// If this is the ID of (one or multiple tasks of) an array job
+    pub fn is_array(&self) -> bool {
+        !self.tasks.is_empty()
+    }
+
+    /// The task ID, if this refers to a single array task (e.g., `49869434_2`)
+    pub fn single_task(&self) -> Option<u32> {
+        match self.tasks.as_slice() {
+            [range] if range.start == range.end && self.throttle.is_none() => Some(range.start),
+            _ => None,
+        }
+    }
+
+    /// Expand all task ranges to the individual array task IDs
+    pub fn task_ids(&self) -> impl Iterator<Item = u32> + '_ {
+        self.tasks
+            .iter()
+            .flat_map(|r| (r.start..=r.end).step_by(r.step as usize))
+    }
+
+    /// The array task part of the ID (e.g., `2` or `[3-10%1]`), `None` for regular jobs
+    pub fn task_string(&self) -> Option<String> {
+        if let Some(task) = self.single_task() {
+            return Some(task.to_string());
+        }
+        if !self.is_array() {
+            return None;
+        }
+        let ranges = self
+            .tasks
+            .iter()
+            .map(|r| r.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        Some(match self.throttle {
+            Some(throttle) => format!("[{ranges}%{throttle}]"),
+            None => format!("[{ranges}]"),
+        })
+    }
+}
+
+impl FromStr for ArrayJobId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let Some((base_id, tasks)) = s.split_once("_") else {
+            return Ok(Self {
+                base_id: s.to_string(),
+                tasks: Vec::new(),
+                throttle: None,
+            });
+        };
+        let tasks = tasks
+            .strip_prefix("[")
+            .and_then(|t| t.strip_suffix("]"))
+            .unwrap_or(tasks);
+        let (tasks, throttle) = match tasks.split_once("%") {
+            Some((tasks, throttle)) => (tasks, Some(throttle.parse()?)),
+            None => (tasks, None),
+        };
+        Ok(Self {
+            base_id: base_id.to_string(),
+            tasks: tasks
+                .split(",")
+                .map(|t| t.parse())
+                .collect::<Result<_, _>>()?,
+            throttle,
+        })
+    }
+}
+
+impl std::fmt::Display for ArrayJobId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.task_string() {
+            Some(tasks) => write!(f, "{}_{}", self.base_id, tasks),
+            None => write!(f, "{}", self.base_id),
+        }
+    }
+}
+
+impl Serialize for ArrayJobId {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for ArrayJobId {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        // Older recordings contain the job ID and (optional) task part as a tuple
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Str(String),
+            Tuple(String, Option<String>),
+        }
+        let s = match Repr::deserialize(deserializer)? {
+            Repr::Str(s) => s,
+            Repr::Tuple(base_id, Some(tasks)) => format!("{base_id}_{tasks}"),
+            Repr::Tuple(base_id, None) => base_id,
+        };
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
 #[cfg(feature = "ssh")]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 /// A connection config for logging in using SSH
@@ -152,6 +710,39 @@ pub struct ConnectionConfig {
     pub username: String,
     /// The authentication configuration
     pub auth: ConnectionAuth,
+    /// Interval after which a keepalive message is sent if the connection is idle (`None` disables keepalives)
+    ///
+    /// Prevents idle connections (e.g., between polls of a recording loop) from being dropped by firewalls.
+    #[serde(default)]
+    pub keepalive_interval: Option<Duration>,
+    /// Number of unanswered keepalive messages after which the connection is considered broken
+    #[serde(default = "default_keepalive_max")]
+    pub keepalive_max: usize,
+    /// How the host key of the server is verified (no verification by default)
+    #[serde(default)]
+    pub host_verification: HostVerification,
+    /// Timeout for establishing the network connection (including authentication, but not waiting for prompt handlers)
+    #[serde(default)]
+    pub connect_timeout: Option<Duration>,
+    /// Timeout for each command executed over the connection (e.g., `squeue`)
+    ///
+    /// Applied by the executors returned by [`login_with_timeouts`] and by [`ResilientClient`].
+    /// Other executors (e.g., a [`ClientPool`]) can be wrapped in a [`TimeoutExecutor`].
+    /// Exceeding a timeout results in a [`Timeout`] error.
+    #[serde(default)]
+    pub command_timeout: Option<Duration>,
+    #[cfg(feature = "credentials")]
+    /// ID of credentials in the OS keychain, filling in secrets missing in `auth` on login (see [`CredentialStore::fill_auth`](credentials::CredentialStore::fill_auth))
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub credential_id: Option<String>,
+    /// Jump host to connect through (e.g., a bastion host in front of the login nodes), as `ProxyJump` in OpenSSH
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proxy_jump: Option<Box<ConnectionConfig>>,
+}
+
+#[cfg(feature = "ssh")]
+fn default_keepalive_max() -> usize {
+    3
 }
 
 #[cfg(feature = "ssh")]
@@ -163,7 +754,16 @@ impl Default for ConnectionConfig {
             auth: ConnectionAuth::PasswordMFA {
                 password: String::new(),
                 mfa_code: String::new(),
+                mfa_handler: None,
             },
+            keepalive_interval: None,
+            keepalive_max: default_keepalive_max(),
+            host_verification: HostVerification::default(),
+            connect_timeout: None,
+            command_timeout: None,
+            #[cfg(feature = "credentials")]
+            credential_id: None,
+            proxy_jump: None,
         }
     }
 }
@@ -175,6 +775,7 @@ impl ConnectionConfig {
             host,
             username,
             auth,
+            ..Default::default()
         }
     }
     /// Assign the passed authentication settings to the connection config
@@ -194,6 +795,50 @@ impl ConnectionConfig {
         self.host = host;
         self
     }
+
+    /// Send keepalive messages every `interval` while idle, considering the connection broken after `max` unanswered ones
+    pub fn with_keepalive(mut self, interval: Duration, max: usize) -> Self {
+        self.keepalive_interval = Some(interval);
+        self.keepalive_max = max;
+        self
+    }
+
+    /// Fail with a [`Timeout`] error if connecting takes longer than `connect_timeout`, or any command longer than `command_timeout`
+    pub fn with_timeouts(
+        mut self,
+        connect_timeout: Option<Duration>,
+        command_timeout: Option<Duration>,
+    ) -> Self {
+        self.connect_timeout = connect_timeout;
+        self.command_timeout = command_timeout;
+        self
+    }
+
+    /// Connect through the given jump host
+    pub fn with_proxy_jump(mut self, jump: ConnectionConfig) -> Self {
+        self.proxy_jump = Some(Box::new(jump));
+        self
+    }
+
+    /// Create a connection config for a host alias of the user's OpenSSH config (`~/.ssh/config`)
+    ///
+    /// Resolves `HostName`, `Port`, `User`, `IdentityFile` and `ProxyJump` (recursively for the jump hosts),
+    /// defaulting to the alias as host name, port `22`, the local user name and the default identity files (e.g., `~/.ssh/id_ed25519`).
+    /// Host keys are verified following `StrictHostKeyChecking` and `UserKnownHostsFile` (see [`HostVerification`]),
+    /// by default against `~/.ssh/known_hosts`.
+    /// Passphrases of identity files can be set using [`ConnectionConfig::with_auth`].
+    pub fn from_ssh_config(host_alias: &str) -> Result<Self, Error> {
+        let content = misc::ssh_config::home_dir()
+            .and_then(|home| std::fs::read_to_string(home.join(".ssh").join("config")).ok())
+            .unwrap_or_default();
+        misc::ssh_config::connection_config_from_ssh_config(&content, host_alias, 0)
+    }
+
+    /// Verify the host key of the server as specified
+    pub fn with_host_verification(mut self, host_verification: HostVerification) -> Self {
+        self.host_verification = host_verification;
+        self
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -209,6 +854,18 @@ pub enum ConnectionAuth {
         #[serde(rename = "mfaCode")]
         /// Multi-Factor-Authentication (MFA) token
         mfa_code: String,
+        #[serde(skip)]
+        /// Handler providing the MFA token instead of `mfa_code` (e.g., by asking the user), also answering other prompts except the password prompt
+        mfa_handler: Option<SharedPromptHandler>,
+    },
+    #[serde(rename = "interactive")]
+    /// Login via keyboard-interactive authentication with arbitrary prompts (e.g., for OTP or push approval dialogs)
+    Interactive {
+        /// Expected prompts with their responses (which can also span multiple rounds)
+        prompts: Vec<PromptResponse>,
+        #[serde(skip)]
+        /// Handler answering all prompts sent by the server without fixed response, including prompts not listed in `prompts`
+        handler: Option<SharedPromptHandler>,
     },
     #[serde(rename = "ssh-key")]
     /// Login via an SSH key
@@ -221,33 +878,96 @@ pub enum ConnectionAuth {
 }
 
 #[cfg(feature = "ssh")]
-impl From<ConnectionAuth> for AuthMethod {
-    fn from(val: ConnectionAuth) -> Self {
-        match val {
-            ConnectionAuth::PasswordMFA { password, mfa_code } => {
-                AuthMethod::with_keyboard_interactive(
-                    AuthKeyboardInteractive::new()
-                        .with_response("Password", password)
-                        .with_response("Two-factor code", mfa_code),
-                )
+/// Prompt of the MFA token for [`ConnectionAuth::PasswordMFA`]
+const MFA_PROMPT: &str = "Two-factor code";
+
+#[cfg(feature = "ssh")]
+impl ConnectionAuth {
+    /// Answer a `prompt` sent by the server (without configured response) using the prompt handler, adding the response
+    ///
+    /// For [`ConnectionAuth::PasswordMFA`], the MFA handler answers all prompts except the password prompt
+    /// (converting the settings into [`ConnectionAuth::Interactive`] if it is not the MFA prompt).
+    /// Fails if there is no handler.
+    pub async fn answer_prompt(&mut self, prompt: &str) -> Result<(), Error> {
+        let handler = match self {
+            ConnectionAuth::PasswordMFA {
+                mfa_handler: Some(handler),
+                ..
             }
-            ConnectionAuth::SSHKey { path, passphrase } => {
-                AuthMethod::with_key_file(path, passphrase.as_deref())
+            | ConnectionAuth::Interactive {
+                handler: Some(handler),
+                ..
+            } => handler.clone(),
+            _ => {
+                return Err(Error::msg(format!(
+                    "No response or prompt handler for prompt {prompt:?}."
+                )))
+            }
+        };
+        let response = handler.respond(prompt).await?;
+        match self {
+            ConnectionAuth::PasswordMFA {
+                mfa_code,
+                mfa_handler,
+                ..
+            } if prompt.trim_end_matches([' ', ':']) == MFA_PROMPT => {
+                *mfa_code = response;
+                *mfa_handler = None;
             }
+            ConnectionAuth::PasswordMFA { password, .. } => {
+                *self = ConnectionAuth::Interactive {
+                    prompts: vec![
+                        PromptResponse::fixed("Password", password.clone()),
+                        PromptResponse::exact(prompt, response),
+                    ],
+                    handler: Some(handler),
+                }
+            }
+            ConnectionAuth::Interactive { prompts, .. } => {
+                prompts.retain(|p| p.response.is_some() || !p.matches(prompt));
+                prompts.push(PromptResponse::exact(prompt, response));
+            }
+            ConnectionAuth::SSHKey { .. } => unreachable!(),
         }
+        Ok(())
+    }
+}
+
+#[cfg(feature = "ssh")]
+impl From<ConnectionAuth> for AuthMethod {
+    fn from(val: ConnectionAuth) -> Self {
+        (&val).into()
     }
 }
 
 #[cfg(feature = "ssh")]
+/// Prompts without fixed response (e.g., the MFA prompt if there is an MFA handler) are not answered (see [`ConnectionAuth::answer_prompt`])
 impl From<&ConnectionAuth> for AuthMethod {
     fn from(val: &ConnectionAuth) -> Self {
         match val {
-            ConnectionAuth::PasswordMFA { password, mfa_code } => {
-                AuthMethod::with_keyboard_interactive(
-                    AuthKeyboardInteractive::new()
-                        .with_response("Password", password.clone())
-                        .with_response("Two-factor code", mfa_code.clone()),
-                )
+            ConnectionAuth::PasswordMFA {
+                password,
+                mfa_code,
+                mfa_handler,
+            } => {
+                let auth =
+                    AuthKeyboardInteractive::new().with_response("Password", password.clone());
+                AuthMethod::with_keyboard_interactive(match mfa_handler {
+                    Some(_) => auth,
+                    None => auth.with_response(MFA_PROMPT, mfa_code.clone()),
+                })
+            }
+            ConnectionAuth::Interactive { prompts, .. } => {
+                let auth = prompts
+                    .iter()
+                    .fold(AuthKeyboardInteractive::new(), |auth, p| {
+                        match (&p.response, p.exact) {
+                            (Some(response), false) => auth.with_response(&p.prompt, response),
+                            (Some(response), true) => auth.with_response_exact(&p.prompt, response),
+                            (None, _) => auth,
+                        }
+                    });
+                AuthMethod::with_keyboard_interactive(auth)
             }
             ConnectionAuth::SSHKey { path, passphrase } => {
                 AuthMethod::with_key_file(path, passphrase.as_deref())
@@ -258,17 +978,370 @@ impl From<&ConnectionAuth> for AuthMethod {
 
 #[cfg(feature = "ssh")]
 /// Login via SSH using the specified configuration
+///
+/// Fails with a [`Timeout`] error if connecting takes longer than the [connect timeout](ConnectionConfig::connect_timeout).
+/// The [command timeout](ConnectionConfig::command_timeout) is not applied to the returned client (see [`login_with_timeouts`]).
+/// When connecting through a jump host (see [`ConnectionConfig::proxy_jump`]), the host keys of the target are obtained on the jump host
+/// (see [`HostVerification`], hashed host names of `known_hosts` files are not supported for the target).
+///
+/// Prompts of keyboard-interactive authentication without configured response are answered by the prompt handler (if any) once the server sends them.
+/// As the SSH library only accepts responses up front, the connection is then established again (sending all previous responses again),
+/// so the connect timeout applies to each attempt and does not include the time taken by the handler.
 pub async fn login_with_cfg(cfg: &ConnectionConfig) -> Result<Client, Error> {
-    let auth_method = (&cfg.auth).into();
-    let client = Client::connect_with_config(
-        cfg.host.clone(),
-        &cfg.username,
-        auth_method,
-        SERVER_CHECK_METHOD,
-        async_ssh2_tokio::Config {
-            ..Default::default()
-        },
-    )
-    .await?;
-    Ok(client)
+    #[cfg(feature = "credentials")]
+    let mut auth = match &cfg.credential_id {
+        Some(id) => credentials::CredentialStore::default().fill_auth(id, &cfg.auth)?,
+        None => cfg.auth.clone(),
+    };
+    #[cfg(not(feature = "credentials"))]
+    let mut auth = cfg.auth.clone();
+    let ssh_config = async_ssh2_tokio::Config {
+        keepalive_interval: cfg.keepalive_interval,
+        keepalive_max: cfg.keepalive_max,
+        ..Default::default()
+    };
+    let jump_client = match &cfg.proxy_jump {
+        None => None,
+        Some(jump) => {
+            // The jump host is connected using its own timeout
+            Some(std::sync::Arc::new(Box::pin(login_with_cfg(jump)).await?))
+        }
+    };
+    let mut answered_prompts: Vec<String> = Vec::new();
+    loop {
+        let auth_method: AuthMethod = (&auth).into();
+        let res = executor::with_timeout(
+            cfg.connect_timeout,
+            &format!("Connecting to {}", cfg.host.0),
+            async {
+                Ok(match &jump_client {
+                    None => {
+                        let server_check =
+                            cfg.host_verification.server_check_method(&cfg.host).await?;
+                        Client::connect_with_config(
+                            cfg.host.clone(),
+                            &cfg.username,
+                            auth_method,
+                            server_check,
+                            ssh_config.clone(),
+                        )
+                        .await?
+                    }
+                    Some(jump_client) => {
+                        let tunnel_addr = misc::port_forwarding::jump_tunnel(
+                            jump_client.clone(),
+                            cfg.host.clone(),
+                        )
+                        .await?;
+                        let (server_check, known_hosts) = cfg
+                            .host_verification
+                            .server_check_method_via_jump(&cfg.host, jump_client, tunnel_addr)
+                            .await?;
+                        let client = Client::connect_with_config(
+                            tunnel_addr,
+                            &cfg.username,
+                            auth_method,
+                            server_check,
+                            ssh_config.clone(),
+                        )
+                        .await;
+                        // Remove the temporary `known_hosts` file once connected
+                        drop(known_hosts);
+                        client?
+                    }
+                })
+            },
+        )
+        .await;
+        let prompt = match &res {
+            Err(e) => match e.downcast_ref::<async_ssh2_tokio::Error>() {
+                Some(async_ssh2_tokio::Error::KeyboardInteractiveNoResponseForPrompt(prompt)) => {
+                    prompt.clone()
+                }
+                _ => return res,
+            },
+            Ok(_) => return res,
+        };
+        // A prompt sent again although it was answered does not match the configured prompts
+        if answered_prompts.contains(&prompt) {
+            return res;
+        }
+        auth.answer_prompt(&prompt).await?;
+        answered_prompts.push(prompt);
+    }
+}
+
+#[cfg(feature = "ssh")]
+/// Login via SSH using the specified configuration (see [`login_with_cfg`]),
+/// applying its [command timeout](ConnectionConfig::command_timeout) to all commands executed using the returned executor
+pub async fn login_with_timeouts(cfg: &ConnectionConfig) -> Result<TimeoutExecutor<Client>, Error> {
+    let client = login_with_cfg(cfg).await?;
+    Ok(TimeoutExecutor::new(client, cfg.command_timeout))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{
+        parse_slurm_duration, ArrayJobId, LicenseRequest, MemorySpec, SlurmDuration, TresRequest,
+    };
+
+    #[test]
+    fn test_parse_slurm_duration() {
+        let secs = |s: &str| parse_slurm_duration(s).unwrap().as_secs_f64();
+        assert_eq!(secs("30"), 30.0 * 60.0);
+        assert_eq!(secs("05:30"), 5.0 * 60.0 + 30.0);
+        assert_eq!(secs("01:02:03"), 3600.0 + 2.0 * 60.0 + 3.0);
+        assert_eq!(secs("2-12"), 2.0 * 86400.0 + 12.0 * 3600.0);
+        assert_eq!(secs("2-12:30"), 2.0 * 86400.0 + 12.0 * 3600.0 + 30.0 * 60.0);
+        assert_eq!(secs("1-00:00:01"), 86400.0 + 1.0);
+        assert_eq!(secs("0-00:01:00"), 60.0);
+        assert_eq!(secs("01:23.456"), 83.456);
+        assert_eq!(secs("00:00:00"), 0.0);
+
+        assert_eq!(
+            "UNLIMITED".parse::<SlurmDuration>().unwrap(),
+            SlurmDuration::Unlimited
+        );
+        assert_eq!(
+            "infinite".parse::<SlurmDuration>().unwrap(),
+            SlurmDuration::Unlimited
+        );
+        assert_eq!(
+            "NOT_SET".parse::<SlurmDuration>().unwrap(),
+            SlurmDuration::NotSet
+        );
+        assert!(parse_slurm_duration("UNLIMITED").is_err());
+
+        for invalid in [
+            "",
+            "1:2:3:4",
+            "1-2:3:4:5",
+            "a:b",
+            "-5",
+            "1--2",
+            "1:-2",
+            "NaN",
+        ] {
+            assert!(
+                invalid.parse::<SlurmDuration>().is_err(),
+                "{invalid:?} should not parse"
+            );
+        }
+    }
+
+    #[test]
+    fn test_format_slurm_duration() {
+        let dur = |secs: u64| SlurmDuration::from(Duration::from_secs(secs));
+        assert_eq!(dur(60).to_string(), "00:01:00");
+        assert_eq!(dur(3723).to_string(), "01:02:03");
+        assert_eq!(dur(2 * 86400 + 3600).to_string(), "2-01:00:00");
+        assert_eq!(
+            SlurmDuration::from(Duration::from_millis(83_456)).to_string(),
+            "00:01:23.456"
+        );
+        assert_eq!(SlurmDuration::Unlimited.to_string(), "UNLIMITED");
+
+        for s in [
+            "00:01:00",
+            "2-01:00:00",
+            "00:01:23.456",
+            "UNLIMITED",
+            "NOT_SET",
+        ] {
+            let parsed: SlurmDuration = s.parse().unwrap();
+            assert_eq!(parsed.to_string(), s);
+        }
+        let json = serde_json::to_string(&dur(90)).unwrap();
+        assert_eq!(json, "\"00:01:30\"");
+        assert_eq!(
+            serde_json::from_str::<SlurmDuration>(&json).unwrap(),
+            dur(90)
+        );
+    }
+
+    #[test]
+    fn test_parse_tres_requests() {
+        let tres = TresRequest::parse_list("gres/gpu:a100:2,gres/shard=1").unwrap();
+        assert_eq!(
+            tres[0],
+            TresRequest {
+                category: Some("gres".to_string()),
+                name: "gpu".to_string(),
+                resource_type: Some("a100".to_string()),
+                count: 2,
+            }
+        );
+        assert_eq!(tres[1].name, "shard");
+        assert_eq!(tres[1].count, 1);
+        assert_eq!(tres[0].to_string(), "gres/gpu:a100:2");
+
+        let gpu: TresRequest = "gpu:4(IDX:0-3)".parse().unwrap();
+        assert_eq!(gpu.category, None);
+        assert_eq!(gpu.count, 4);
+        assert!(TresRequest::parse_list("N/A").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_parse_license_request() {
+        let licenses = LicenseRequest::parse_list("ansys:2,comsol@db").unwrap();
+        assert_eq!(
+            licenses,
+            vec![
+                LicenseRequest {
+                    name: "ansys".to_string(),
+                    count: 2
+                },
+                LicenseRequest {
+                    name: "comsol@db".to_string(),
+                    count: 1
+                }
+            ]
+        );
+        assert_eq!(licenses[1].to_string(), "comsol@db:1");
+        assert!(LicenseRequest::parse_list("(null)").unwrap().is_empty());
+        assert!("ansys:x".parse::<LicenseRequest>().is_err());
+    }
+
+    #[test]
+    fn test_parse_memory_spec() {
+        let mem: MemorySpec = "3900M".parse().unwrap();
+        assert_eq!(mem, MemorySpec::per_node_mb(3900));
+        assert_eq!(mem.to_string(), "3900M");
+        assert_eq!(mem.to_sbatch_arg(), "--mem=3900M");
+
+        let mem: MemorySpec = "2Gc".parse().unwrap();
+        assert_eq!(mem.bytes, 2 << 30);
+        assert!(mem.per_cpu);
+        assert_eq!(mem.to_string(), "2Gc");
+        assert_eq!(mem.to_sbatch_arg(), "--mem-per-cpu=2G");
+        assert_eq!(mem.total_bytes(4, 1), 8 << 30);
+
+        assert_eq!("1024".parse::<MemorySpec>().unwrap().to_string(), "1G");
+        assert_eq!("0".parse::<MemorySpec>().unwrap().bytes, 0);
+        assert_eq!("1.5G".parse::<MemorySpec>().unwrap().to_string(), "1536M");
+        assert!("lots".parse::<MemorySpec>().is_err());
+
+        let json = serde_json::to_string(&mem).unwrap();
+        assert_eq!(json, "\"2Gc\"");
+        assert_eq!(serde_json::from_str::<MemorySpec>(&json).unwrap(), mem);
+    }
+
+    #[test]
+    fn test_parse_array_job_id() {
+        let regular: ArrayJobId = "49848561".parse().unwrap();
+        assert!(!regular.is_array());
+        assert_eq!(regular.to_string(), "49848561");
+
+        let task: ArrayJobId = "49869434_2".parse().unwrap();
+        assert_eq!(task.base_id, "49869434");
+        assert_eq!(task.single_task(), Some(2));
+        assert_eq!(task.to_string(), "49869434_2");
+
+        let pending: ArrayJobId = "49616001_[3-10%1]".parse().unwrap();
+        assert_eq!(pending.throttle, Some(1));
+        assert_eq!(pending.single_task(), None);
+        assert_eq!(pending.task_ids().count(), 8);
+        assert_eq!(pending.to_string(), "49616001_[3-10%1]");
+
+        let stepped: ArrayJobId = "123_[1,4-9:2]".parse().unwrap();
+        assert_eq!(stepped.task_ids().collect::<Vec<_>>(), vec![1, 4, 6, 8]);
+        assert!("123_[5-1]".parse::<ArrayJobId>().is_err());
+
+        // Older recordings contain tuples
+        let legacy: ArrayJobId = serde_json::from_str(r#"["49616001", "[3-10%1]"]"#).unwrap();
+        assert_eq!(legacy, pending);
+        let legacy: ArrayJobId = serde_json::from_str(r#"["49848561", null]"#).unwrap();
+        assert_eq!(legacy, regular);
+        assert_eq!(
+            serde_json::from_str::<ArrayJobId>(&serde_json::to_string(&pending).unwrap()).unwrap(),
+            pending
+        );
+    }
+
+    #[cfg(feature = "ssh")]
+    #[test]
+    fn test_connection_config_keepalive() {
+        use crate::ConnectionConfig;
+
+        // Configs without keepalive settings (e.g., from the frontend) are still accepted
+        let cfg: ConnectionConfig = serde_json::from_str(
+            r#"{"host": ["login23-1.hpc.itc.rwth-aachen.de", 22], "username": "ab123456", "auth": {"mode": "ssh-key", "path": "~/.ssh/id_ed25519", "passphrase": null}}"#,
+        )
+        .unwrap();
+        assert_eq!((cfg.keepalive_interval, cfg.keepalive_max), (None, 3));
+        let cfg = cfg.with_keepalive(Duration::from_secs(30), 5);
+        assert_eq!(cfg.keepalive_interval, Some(Duration::from_secs(30)));
+        assert_eq!(cfg.keepalive_max, 5);
+    }
+
+    #[cfg(feature = "ssh")]
+    #[tokio::test]
+    async fn test_answer_auth_prompts() {
+        use crate::{ConnectionAuth, PromptResponse, SharedPromptHandler};
+
+        let mut auth: ConnectionAuth = serde_json::from_str(
+            r#"{"mode": "interactive", "prompts": [{"prompt": "Password", "response": "secret"}, {"prompt": "Passcode or option (1-2): ", "exact": true}]}"#,
+        )
+        .unwrap();
+        assert!(auth
+            .answer_prompt("Passcode or option (1-2): ")
+            .await
+            .is_err());
+        let handler = SharedPromptHandler::new(|prompt: String| async move {
+            Ok(if prompt.starts_with("Passcode") {
+                "1"
+            } else {
+                "123456"
+            }
+            .to_string())
+        });
+        let ConnectionAuth::Interactive { prompts, .. } = auth else {
+            unreachable!()
+        };
+        let mut auth = ConnectionAuth::Interactive {
+            prompts,
+            handler: Some(handler.clone()),
+        };
+        // Prompts are answered as sent by the server, including prompts of later rounds which were not configured
+        auth.answer_prompt("Passcode or option (1-2): ")
+            .await
+            .unwrap();
+        auth.answer_prompt("Verification code: ").await.unwrap();
+        let ConnectionAuth::Interactive { prompts, .. } = &auth else {
+            unreachable!()
+        };
+        assert_eq!(
+            prompts,
+            &vec![
+                PromptResponse::fixed("Password", "secret"),
+                PromptResponse::exact("Passcode or option (1-2): ", "1"),
+                PromptResponse::exact("Verification code: ", "123456"),
+            ]
+        );
+
+        let mut auth = ConnectionAuth::PasswordMFA {
+            password: "secret".to_string(),
+            mfa_code: String::new(),
+            mfa_handler: Some(handler.clone()),
+        };
+        auth.answer_prompt("Two-factor code: ").await.unwrap();
+        let ConnectionAuth::PasswordMFA { mfa_code, .. } = &auth else {
+            unreachable!()
+        };
+        assert_eq!(mfa_code, "123456");
+
+        let mut auth = ConnectionAuth::PasswordMFA {
+            password: "secret".to_string(),
+            mfa_code: String::new(),
+            mfa_handler: Some(handler),
+        };
+        auth.answer_prompt("OTP").await.unwrap();
+        let ConnectionAuth::Interactive { prompts, .. } = &auth else {
+            panic!("Unknown prompts should switch to interactive authentication");
+        };
+        assert_eq!(prompts[1], PromptResponse::exact("OTP", "123456"));
+        assert!(prompts[0].matches("Password: "));
+    }
 }
@@ -0,0 +1,175 @@
+use std::collections::BTreeMap;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    data_extraction::{
+        sinfo::{NodeState, SinfoNodeRow},
+        squeue::SqueueRow,
+    },
+    JobState,
+};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
+/// Utilization of a single partition, as computed by [`partition_utilization`]
+pub struct PartitionUtilization {
+    /// Name of the partition
+    pub partition: String,
+    /// Number of running jobs
+    pub running_jobs: usize,
+    /// Number of pending jobs
+    ///
+    /// Jobs pending in multiple partitions (e.g., submitted using `-p a,b`) are counted for each of them.
+    pub pending_jobs: usize,
+    /// Number of CPUs requested by pending jobs
+    pub pending_cpus: usize,
+    /// Number of allocated CPUs
+    pub cpus_allocated: usize,
+    /// Total number of CPUs
+    pub cpus_total: usize,
+    /// Number of (partially) allocated nodes
+    pub nodes_allocated: usize,
+    /// Number of nodes which are unavailable (e.g., drained or down)
+    pub nodes_unavailable: usize,
+    /// Total number of nodes
+    pub nodes_total: usize,
+}
+
+impl PartitionUtilization {
+    /// Fraction of allocated CPUs (between `0.0` and `1.0`)
+    pub fn cpu_utilization(&self) -> f64 {
+        if self.cpus_total == 0 {
+            return 0.0;
+        }
+        self.cpus_allocated as f64 / self.cpus_total as f64
+    }
+
+    /// Fraction of (partially) allocated nodes (between `0.0` and `1.0`)
+    pub fn node_utilization(&self) -> f64 {
+        if self.nodes_total == 0 {
+            return 0.0;
+        }
+        self.nodes_allocated as f64 / self.nodes_total as f64
+    }
+}
+
+/// Compute the utilization of all partitions from a `squeue` snapshot (`jobs`) and the `sinfo` output for all `nodes`
+///
+/// The `nodes` are expected to be listed once per partition (as returned by [`get_sinfo_nodes`](crate::data_extraction::get_sinfo_nodes)).
+/// The result is sorted by partition name.
+pub fn partition_utilization(
+    jobs: &[SqueueRow],
+    nodes: &[SinfoNodeRow],
+) -> Vec<PartitionUtilization> {
+    fn get<'a, 'b>(
+        partitions: &'a mut BTreeMap<&'b str, PartitionUtilization>,
+        name: &'b str,
+    ) -> &'a mut PartitionUtilization {
+        partitions
+            .entry(name)
+            .or_insert_with(|| PartitionUtilization {
+                partition: name.to_string(),
+                ..Default::default()
+            })
+    }
+    let mut partitions: BTreeMap<&str, PartitionUtilization> = BTreeMap::new();
+    for node in nodes {
+        let p = get(&mut partitions, &node.partition);
+        p.nodes_total += 1;
+        p.cpus_total += node.cpus;
+        p.cpus_allocated += node.cpus_allocated;
+        match node.state {
+            NodeState::ALLOCATED | NodeState::MIXED | NodeState::COMPLETING => {
+                p.nodes_allocated += 1
+            }
+            NodeState::DRAINED
+            | NodeState::DOWN
+            | NodeState::FAIL
+            | NodeState::MAINTENANCE
+            | NodeState::POWERED_DOWN => p.nodes_unavailable += 1,
+            _ => {}
+        }
+    }
+    for job in jobs {
+        match job.state {
+            JobState::RUNNING => get(&mut partitions, &job.partition).running_jobs += 1,
+            JobState::PENDING => {
+                for partition in job.partition.split(",") {
+                    let p = get(&mut partitions, partition);
+                    p.pending_jobs += 1;
+                    p.pending_cpus += job.cpus;
+                }
+            }
+            _ => {}
+        }
+    }
+    partitions.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+
+    use super::partition_utilization;
+    use crate::{
+        data_extraction::{
+            sinfo::{NodeState, SinfoNodeRow},
+            squeue::{test_row, SqueueRow},
+        },
+        JobState,
+    };
+
+    fn node(name: &str, partition: &str, state: NodeState, allocated: usize) -> SinfoNodeRow {
+        SinfoNodeRow {
+            node_name: name.to_string(),
+            partition: partition.to_string(),
+            state,
+            cpus: 96,
+            cpus_allocated: allocated,
+            cpus_idle: 96 - allocated,
+            memory: 256000,
+            free_memory: None,
+            cpu_load: None,
+            reason: None,
+        }
+    }
+
+    fn job(job_id: &str, partition: &str, state: JobState, cpus: usize) -> SqueueRow {
+        let mut row = test_row(Utc.with_ymd_and_hms(2025, 1, 14, 9, 23, 45).unwrap());
+        row.job_id = job_id.to_string();
+        row.partition = partition.to_string();
+        row.state = state;
+        row.cpus = cpus;
+        row
+    }
+
+    #[test]
+    fn test_partition_utilization() {
+        let nodes = vec![
+            node("n1", "c23ms", NodeState::MIXED, 48),
+            node("n2", "c23ms", NodeState::IDLE, 0),
+            node("n3", "c23ms", NodeState::DRAINED, 0),
+            node("n1", "c23test", NodeState::MIXED, 48),
+        ];
+        let jobs = vec![
+            job("1", "c23ms", JobState::RUNNING, 48),
+            job("2", "c23ms", JobState::PENDING, 4),
+            job("3", "c23ms,c23test", JobState::PENDING, 8),
+            job("4", "c23ms", JobState::COMPLETED, 8),
+        ];
+        let utilization = partition_utilization(&jobs, &nodes);
+        assert_eq!(utilization.len(), 2);
+        let c23ms = &utilization[0];
+        assert_eq!(c23ms.partition, "c23ms");
+        assert_eq!(c23ms.running_jobs, 1);
+        assert_eq!(c23ms.pending_jobs, 2);
+        assert_eq!(c23ms.pending_cpus, 12);
+        assert_eq!(c23ms.nodes_total, 3);
+        assert_eq!(c23ms.nodes_allocated, 1);
+        assert_eq!(c23ms.nodes_unavailable, 1);
+        assert_eq!(c23ms.cpus_total, 288);
+        assert!((c23ms.cpu_utilization() - 48.0 / 288.0).abs() < 1e-9);
+        assert_eq!(utilization[1].pending_jobs, 1);
+        assert_eq!(utilization[1].running_jobs, 0);
+    }
+}
@@ -0,0 +1,100 @@
+use std::{future::Future, pin::Pin, sync::Arc};
+
+use anyhow::Error;
+use serde::{Deserialize, Serialize};
+
+/// Handler answering prompts of keyboard-interactive SSH authentication (e.g., by asking the user in a dialog)
+///
+/// Implemented for closures mapping the prompt text to the response, e.g., `|prompt| async move { Ok(ask_user(&prompt).await) }`.
+pub trait PromptHandler: Send + Sync {
+    /// Answer the given prompt
+    fn respond<'a>(
+        &'a self,
+        prompt: &'a str,
+    ) -> Pin<Box<dyn Future<Output = Result<String, Error>> + Send + 'a>>;
+}
+
+impl<F, Fut> PromptHandler for F
+where
+    F: Fn(String) -> Fut + Send + Sync,
+    Fut: Future<Output = Result<String, Error>> + Send + 'static,
+{
+    fn respond<'a>(
+        &'a self,
+        prompt: &'a str,
+    ) -> Pin<Box<dyn Future<Output = Result<String, Error>> + Send + 'a>> {
+        Box::pin(self(prompt.to_string()))
+    }
+}
+
+#[derive(Clone)]
+/// Shared [`PromptHandler`], as stored in [`ConnectionAuth`](crate::ConnectionAuth) (not serialized)
+pub struct SharedPromptHandler(pub Arc<dyn PromptHandler>);
+
+impl SharedPromptHandler {
+    /// Wrap the given handler
+    pub fn new(handler: impl PromptHandler + 'static) -> Self {
+        Self(Arc::new(handler))
+    }
+
+    /// Answer the given prompt using the wrapped handler
+    pub async fn respond(&self, prompt: &str) -> Result<String, Error> {
+        self.0.respond(prompt).await
+    }
+}
+
+impl std::fmt::Debug for SharedPromptHandler {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SharedPromptHandler")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+/// An expected prompt of keyboard-interactive authentication and its response
+pub struct PromptResponse {
+    /// Text of the prompt (e.g., `Password`, also matching `Password: ` unless `exact` is set)
+    pub prompt: String,
+    /// Fixed response, or `None` to ask the prompt handler (once the server sends the prompt)
+    #[serde(default)]
+    pub response: Option<String>,
+    /// Only match prompts equal to `prompt`
+    #[serde(default)]
+    pub exact: bool,
+}
+
+impl PromptResponse {
+    /// Answer the prompt with a fixed response
+    pub fn fixed(prompt: impl Into<String>, response: impl Into<String>) -> Self {
+        Self {
+            prompt: prompt.into(),
+            response: Some(response.into()),
+            exact: false,
+        }
+    }
+
+    /// Answer the prompt with a fixed response, only matching prompts equal to `prompt`
+    pub fn exact(prompt: impl Into<String>, response: impl Into<String>) -> Self {
+        Self {
+            exact: true,
+            ..Self::fixed(prompt, response)
+        }
+    }
+
+    /// Check if the `prompt` sent by the server matches this prompt
+    pub fn matches(&self, prompt: &str) -> bool {
+        if self.exact {
+            self.prompt == prompt
+        } else {
+            self.prompt.trim_end_matches([' ', ':']) == prompt.trim_end_matches([' ', ':'])
+        }
+    }
+
+    /// Answer the prompt using the prompt handler
+    pub fn ask(prompt: impl Into<String>) -> Self {
+        Self {
+            prompt: prompt.into(),
+            response: None,
+            exact: false,
+        }
+    }
+}
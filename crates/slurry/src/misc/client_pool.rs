@@ -0,0 +1,165 @@
+use std::{
+    ops::Deref,
+    path::Path,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use anyhow::Error;
+use async_ssh2_tokio::Client;
+use futures::future::try_join_all;
+
+use crate::{
+    executor::{CommandExecutor, CommandOutput},
+    login_with_cfg, ConnectionConfig,
+};
+
+/// Pool of multiple SSH sessions to the same host, e.g., to run many commands or transfers in parallel
+///
+/// Each call to [`ClientPool::get`] hands out the session with the fewest operations currently running through the pool.
+/// The pool implements [`CommandExecutor`], so it can be passed to all functions generic over it (e.g., [`get_squeue_res`](crate::data_extraction::get_squeue_res)).
+/// Functions requiring a `&Client` accept `&pool.get()` instead.
+pub struct ClientPool {
+    clients: Vec<Arc<Client>>,
+    in_use: Vec<AtomicUsize>,
+    next: AtomicUsize,
+}
+
+impl std::fmt::Debug for ClientPool {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ClientPool")
+            .field("size", &self.clients.len())
+            .field("in_use", &self.in_use)
+            .finish()
+    }
+}
+
+impl ClientPool {
+    /// Create a pool from already connected clients
+    ///
+    /// Useful if every login requires user input (e.g., a new MFA code for [`ConnectionAuth::PasswordMFA`](crate::ConnectionAuth::PasswordMFA)).
+    pub fn new(clients: Vec<Client>) -> Result<Self, Error> {
+        if clients.is_empty() {
+            return Err(Error::msg("A client pool requires at least one client."));
+        }
+        Ok(Self {
+            in_use: clients.iter().map(|_| AtomicUsize::new(0)).collect(),
+            clients: clients.into_iter().map(Arc::new).collect(),
+            next: AtomicUsize::new(0),
+        })
+    }
+
+    /// Open `size` sessions using the given configuration (see [`login_with_cfg`])
+    ///
+    /// All sessions are opened concurrently, so this is only suitable for authentication methods which can be reused (e.g., SSH keys).
+    pub async fn connect(cfg: &ConnectionConfig, size: usize) -> Result<Self, Error> {
+        let clients = try_join_all((0..size.max(1)).map(|_| login_with_cfg(cfg))).await?;
+        Self::new(clients)
+    }
+
+    /// Number of sessions in the pool
+    pub fn len(&self) -> usize {
+        self.clients.len()
+    }
+
+    /// Always `false`, as a pool contains at least one session
+    pub fn is_empty(&self) -> bool {
+        self.clients.is_empty()
+    }
+
+    /// Hand out the least busy session
+    ///
+    /// The session counts as busy until the returned [`PooledClient`] is dropped.
+    pub fn get(&self) -> PooledClient<'_> {
+        let start = self.next.fetch_add(1, Ordering::Relaxed);
+        let index = least_busy(&self.in_use, start);
+        self.in_use[index].fetch_add(1, Ordering::Relaxed);
+        PooledClient {
+            client: &self.clients[index],
+            in_use: &self.in_use[index],
+        }
+    }
+
+    /// Disconnect all sessions of the pool
+    pub async fn disconnect(&self) -> Result<(), Error> {
+        try_join_all(self.clients.iter().map(|c| c.disconnect())).await?;
+        Ok(())
+    }
+}
+
+/// Index of the entry with the fewest operations in use, preferring entries in order starting from `start` (i.e., round-robin for ties)
+fn least_busy(in_use: &[AtomicUsize], start: usize) -> usize {
+    let n = in_use.len();
+    (0..n)
+        .map(|i| (start + i) % n)
+        .min_by_key(|&i| in_use[i].load(Ordering::Relaxed))
+        .unwrap_or(0)
+}
+
+#[derive(Debug)]
+/// A session handed out by a [`ClientPool`], dereferencing to the [`Client`]
+pub struct PooledClient<'a> {
+    client: &'a Arc<Client>,
+    in_use: &'a AtomicUsize,
+}
+
+impl PooledClient<'_> {
+    /// A shared handle to the session, e.g., for [`submit_job`](crate::job_management::submit_job)
+    ///
+    /// Operations using the returned handle are not tracked by the pool.
+    pub fn arc(&self) -> Arc<Client> {
+        Arc::clone(self.client)
+    }
+}
+
+impl Deref for PooledClient<'_> {
+    type Target = Client;
+
+    fn deref(&self) -> &Self::Target {
+        self.client
+    }
+}
+
+impl Drop for PooledClient<'_> {
+    fn drop(&mut self) {
+        self.in_use.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl CommandExecutor for ClientPool {
+    async fn execute(&self, command: &str) -> Result<CommandOutput, Error> {
+        let client = self.get();
+        let res = CommandExecutor::execute(&*client, command).await;
+        res
+    }
+
+    async fn upload(&self, local_path: &Path, remote_path: &str) -> Result<(), Error> {
+        let client = self.get();
+        let res = client.upload(local_path, remote_path).await;
+        res
+    }
+
+    async fn download(&self, remote_path: &str, local_path: &Path) -> Result<(), Error> {
+        let client = self.get();
+        let res = client.download(remote_path, local_path).await;
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::AtomicUsize;
+
+    use super::least_busy;
+
+    #[test]
+    fn test_least_busy() {
+        let in_use: Vec<_> = [2, 0, 1, 0].into_iter().map(AtomicUsize::new).collect();
+        assert_eq!(least_busy(&in_use, 0), 1);
+        assert_eq!(least_busy(&in_use, 2), 3);
+        assert_eq!(least_busy(&in_use, 5), 1);
+        assert_eq!(least_busy(&in_use[..1], 7), 0);
+    }
+}
@@ -0,0 +1,334 @@
+use std::{
+    fs::OpenOptions,
+    io::Write,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Error;
+use async_ssh2_tokio::client::{Client, ServerCheckMethod};
+use base64::{engine::general_purpose, Engine};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{executor::CommandExecutor, job_management::shell_quote, misc::ssh_config::home_dir};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "mode")]
+/// How the host key of the server is verified when connecting (see [`ConnectionConfig::host_verification`](crate::ConnectionConfig::host_verification))
+///
+/// Pinning fingerprints and trust-on-first-use require `ssh-keyscan` (part of OpenSSH) to be installed locally
+/// (or on the jump host, for hosts behind a jump host).
+pub enum HostVerification {
+    #[default]
+    #[serde(rename = "none")]
+    /// Accept any host key (not recommended, vulnerable to man-in-the-middle attacks)
+    NoCheck,
+    #[serde(rename = "known-hosts")]
+    /// Require the host key to be listed in a `known_hosts` file
+    KnownHosts {
+        /// Path of the `known_hosts` file (`~/.ssh/known_hosts` if not set)
+        path: Option<String>,
+    },
+    #[serde(rename = "fingerprint")]
+    /// Require the host key to have the given fingerprint
+    Fingerprint {
+        /// SHA256 fingerprint of the host key as printed by `ssh-keygen -l` (e.g., `SHA256:EgaP5lQ+OEmc+qhPvweWp1886rqQzmmQzA/VcDpTNlc`)
+        fingerprint: String,
+    },
+    #[serde(rename = "tofu")]
+    /// Trust the host keys on the first connection, and require the same keys afterwards
+    TrustOnFirstUse {
+        /// Path of the local store for trusted host keys (in `known_hosts` format)
+        store: String,
+    },
+}
+
+impl HostVerification {
+    /// The check to use when connecting to `host`
+    ///
+    /// For [`HostVerification::TrustOnFirstUse`], the host keys are added to the store if the host is not contained yet.
+    pub async fn server_check_method(
+        &self,
+        host: &(String, u16),
+    ) -> Result<ServerCheckMethod, Error> {
+        match self {
+            HostVerification::NoCheck => Ok(ServerCheckMethod::NoCheck),
+            HostVerification::KnownHosts { path: None } => {
+                Ok(ServerCheckMethod::DefaultKnownHostsFile)
+            }
+            HostVerification::KnownHosts { path: Some(path) } => {
+                Ok(ServerCheckMethod::KnownHostsFile(path.clone()))
+            }
+            HostVerification::Fingerprint { fingerprint } => {
+                let lines = scan_host_keys(host).await?;
+                let (_, key) = key_with_fingerprint(&lines, fingerprint, host)?;
+                Ok(ServerCheckMethod::PublicKey(key.to_string()))
+            }
+            HostVerification::TrustOnFirstUse { store } => {
+                trusted_host_keys(store, host, scan_host_keys(host)).await?;
+                Ok(ServerCheckMethod::KnownHostsFile(store.clone()))
+            }
+        }
+    }
+
+    /// The check to use when connecting to `host` through a local tunnel of the `jump` host, listening on `tunnel_addr`
+    ///
+    /// The host keys of `host` are looked up as configured, scanning them on the jump host (for fingerprints and trust-on-first-use).
+    /// As the connection is made to the tunnel address, the keys are accepted for it using a temporary `known_hosts` file,
+    /// which is removed once the returned [`TunnelKnownHosts`] is dropped.
+    /// Hashed host names of `known_hosts` files are not supported.
+    pub(crate) async fn server_check_method_via_jump(
+        &self,
+        host: &(String, u16),
+        jump: &Client,
+        tunnel_addr: SocketAddr,
+    ) -> Result<(ServerCheckMethod, Option<TunnelKnownHosts>), Error> {
+        let keys = match self {
+            HostVerification::NoCheck => return Ok((ServerCheckMethod::NoCheck, None)),
+            HostVerification::KnownHosts { path } => {
+                let path = match path {
+                    Some(path) => PathBuf::from(path),
+                    None => home_dir()
+                        .ok_or(Error::msg("Could not find the home directory."))?
+                        .join(".ssh")
+                        .join("known_hosts"),
+                };
+                let content = std::fs::read_to_string(&path).unwrap_or_default();
+                let keys = known_host_keys(&content, &host.0, host.1);
+                if keys.is_empty() {
+                    return Err(Error::msg(format!(
+                        "No host key of {} found in {} (hashed host names are not supported for hosts behind a jump host).",
+                        host.0,
+                        path.display()
+                    )));
+                }
+                keys
+            }
+            HostVerification::Fingerprint { fingerprint } => {
+                let lines = scan_host_keys_on(host, jump).await?;
+                let (key_type, key) = key_with_fingerprint(&lines, fingerprint, host)?;
+                vec![format!("{key_type} {key}")]
+            }
+            HostVerification::TrustOnFirstUse { store } => {
+                trusted_host_keys(store, host, scan_host_keys_on(host, jump)).await?
+            }
+        };
+        let known_hosts = TunnelKnownHosts::create(&keys, tunnel_addr)?;
+        let path = known_hosts.0.to_string_lossy().to_string();
+        Ok((ServerCheckMethod::KnownHostsFile(path), Some(known_hosts)))
+    }
+}
+
+/// Temporary `known_hosts` file accepting host keys for the local address of a jump host tunnel, removed when dropped
+pub(crate) struct TunnelKnownHosts(PathBuf);
+
+impl TunnelKnownHosts {
+    /// Write the `keys` (as `<type> <base64 key>`) for `tunnel_addr` into a new temporary file
+    ///
+    /// The file must not exist yet (so that no existing file or symlink is followed), and is only readable by the current user.
+    fn create(keys: &[String], tunnel_addr: SocketAddr) -> Result<Self, Error> {
+        let nanos = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap_or_default()
+            .subsec_nanos();
+        let path = std::env::temp_dir().join(format!(
+            "slurry_known_hosts_{}_{}_{nanos}",
+            std::process::id(),
+            tunnel_addr.port()
+        ));
+        let pattern = known_hosts_pattern(&tunnel_addr.ip().to_string(), tunnel_addr.port());
+        let content: String = keys
+            .iter()
+            .map(|key| format!("{pattern} {key}\n"))
+            .collect();
+        let mut options = OpenOptions::new();
+        options.write(true).create_new(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(&path)?;
+        let res = Self(path);
+        file.write_all(content.as_bytes())?;
+        Ok(res)
+    }
+}
+
+impl Drop for TunnelKnownHosts {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Find the key with the given `fingerprint` among the scanned `known_hosts` lines of `host`, as key type and base64 key
+fn key_with_fingerprint<'a>(
+    lines: &'a [String],
+    fingerprint: &str,
+    host: &(String, u16),
+) -> Result<(&'a str, &'a str), Error> {
+    let expected = fingerprint.trim().trim_end_matches('=');
+    for line in lines {
+        if let Some((_, key_type, key)) = parse_known_hosts_line(line) {
+            if key_fingerprint(key)? == expected {
+                return Ok((key_type, key));
+            }
+        }
+    }
+    Err(Error::msg(format!(
+        "No host key of {} matches the fingerprint {expected}.",
+        host.0
+    )))
+}
+
+/// The keys of `host` in the trust-on-first-use `store`, adding the keys obtained by `scan` if the host is not contained yet
+async fn trusted_host_keys(
+    store: &str,
+    host: &(String, u16),
+    scan: impl std::future::Future<Output = Result<Vec<String>, Error>>,
+) -> Result<Vec<String>, Error> {
+    let content = std::fs::read_to_string(store).unwrap_or_default();
+    let keys = known_host_keys(&content, &host.0, host.1);
+    if !keys.is_empty() {
+        return Ok(keys);
+    }
+    let lines = scan.await?;
+    if let Some(parent) = Path::new(store).parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(store)?;
+    for line in &lines {
+        writeln!(file, "{line}")?;
+    }
+    Ok(lines
+        .iter()
+        .filter_map(|line| parse_known_hosts_line(line))
+        .map(|(_, key_type, key)| format!("{key_type} {key}"))
+        .collect())
+}
+
+/// SHA256 fingerprint of a public key given in base64 (as in `known_hosts` files), in the format of `ssh-keygen -l`
+pub fn key_fingerprint(key_base64: &str) -> Result<String, Error> {
+    let blob = general_purpose::STANDARD.decode(key_base64.trim())?;
+    let hash = Sha256::digest(blob);
+    Ok(format!(
+        "SHA256:{}",
+        general_purpose::STANDARD_NO_PAD.encode(hash)
+    ))
+}
+
+/// Get the public host keys of `host` as `known_hosts` lines using `ssh-keyscan`
+async fn scan_host_keys(host: &(String, u16)) -> Result<Vec<String>, Error> {
+    let out = tokio::process::Command::new("ssh-keyscan")
+        .arg("-p")
+        .arg(host.1.to_string())
+        .arg(&host.0)
+        .output()
+        .await
+        .map_err(|e| Error::new(e).context("Could not run ssh-keyscan."))?;
+    parse_keyscan_output(&String::from_utf8(out.stdout)?, host)
+}
+
+/// Get the public host keys of `host` as `known_hosts` lines using `ssh-keyscan` on the `jump` host
+async fn scan_host_keys_on(host: &(String, u16), jump: &Client) -> Result<Vec<String>, Error> {
+    let out = CommandExecutor::execute(
+        jump,
+        &format!("ssh-keyscan -p {} {}", host.1, shell_quote(&host.0)),
+    )
+    .await?;
+    parse_keyscan_output(&out.stdout, host)
+}
+
+/// Collect the `known_hosts` lines of `ssh-keyscan` output, failing if there are none
+fn parse_keyscan_output(output: &str, host: &(String, u16)) -> Result<Vec<String>, Error> {
+    let lines: Vec<String> = output
+        .lines()
+        .filter(|line| parse_known_hosts_line(line).is_some())
+        .map(|line| line.trim().to_string())
+        .collect();
+    if lines.is_empty() {
+        return Err(Error::msg(format!(
+            "Could not get host keys of {}.",
+            host.0
+        )));
+    }
+    Ok(lines)
+}
+
+/// Split a `known_hosts` line into its host patterns, the key type and the base64 key, skipping comments and markers (e.g., `@revoked`)
+fn parse_known_hosts_line(line: &str) -> Option<(&str, &str, &str)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') || line.starts_with('@') {
+        return None;
+    }
+    let mut parts = line.split_whitespace();
+    let hosts = parts.next()?;
+    let key_type = parts.next()?;
+    let key = parts.next()?;
+    Some((hosts, key_type, key))
+}
+
+/// The host pattern of `host` in `known_hosts` files (e.g., `[localhost]:2222`)
+fn known_hosts_pattern(host: &str, port: u16) -> String {
+    if port == 22 {
+        host.to_string()
+    } else {
+        format!("[{host}]:{port}")
+    }
+}
+
+/// The keys (as `<type> <base64 key>`) listed for `host` in a `known_hosts` file (not supporting hashed host names or wildcards)
+fn known_host_keys(content: &str, host: &str, port: u16) -> Vec<String> {
+    let pattern = known_hosts_pattern(host, port);
+    content
+        .lines()
+        .filter_map(parse_known_hosts_line)
+        .filter(|(hosts, _, _)| hosts.split(',').any(|h| h == pattern))
+        .map(|(_, key_type, key)| format!("{key_type} {key}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{key_fingerprint, known_host_keys, HostVerification, TunnelKnownHosts};
+
+    #[test]
+    fn test_host_key_fingerprint() {
+        assert_eq!(
+            key_fingerprint("AAAAC3NzaC1lZDI1NTE5AAAAIGXLMv63+OnubMlC28E8tiBAY/anBzZs652W75G4x1Ie")
+                .unwrap(),
+            "SHA256:EgaP5lQ+OEmc+qhPvweWp1886rqQzmmQzA/VcDpTNlc"
+        );
+        let store = "# trusted hosts\nlogin23-1.hpc.itc.rwth-aachen.de,134.61.193.120 ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIGXLMv63+OnubMlC28E8tiBAY/anBzZs652W75G4x1Ie\n[localhost]:2222 ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIGXLMv63+OnubMlC28E8tiBAY/anBzZs652W75G4x1Ie\n";
+        let keys = known_host_keys(store, "login23-1.hpc.itc.rwth-aachen.de", 22);
+        assert_eq!(
+            keys,
+            ["ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIGXLMv63+OnubMlC28E8tiBAY/anBzZs652W75G4x1Ie"]
+        );
+        assert_eq!(known_host_keys(store, "localhost", 2222).len(), 1);
+        assert!(known_host_keys(store, "localhost", 22).is_empty());
+        assert!(known_host_keys(store, "login23-2.hpc.itc.rwth-aachen.de", 22).is_empty());
+
+        // Keys of hosts behind a jump host are accepted for the tunnel address
+        let tunnel = TunnelKnownHosts::create(&keys, "127.0.0.1:40022".parse().unwrap()).unwrap();
+        let path = tunnel.0.clone();
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(known_host_keys(&content, "127.0.0.1", 40022), keys);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+            assert_eq!(mode & 0o777, 0o600);
+        }
+        drop(tunnel);
+        assert!(!path.exists());
+
+        let tofu: HostVerification =
+            serde_json::from_str(r#"{"mode": "tofu", "store": "/tmp/slurry_known_hosts"}"#)
+                .unwrap();
+        assert_eq!(
+            tofu,
+            HostVerification::TrustOnFirstUse {
+                store: "/tmp/slurry_known_hosts".to_string()
+            }
+        );
+    }
+}
@@ -1,11 +1,34 @@
-#[cfg(test)]
+#[cfg(all(test, feature = "ssh"))]
 use crate::ConnectionConfig;
 
 #[cfg(feature = "ssh")]
 /// SSH Port Forwarding
 pub mod port_forwarding;
 
-#[cfg(test)]
+#[cfg(feature = "ssh")]
+/// Pools of SSH sessions for parallel operations
+pub mod client_pool;
+
+#[cfg(feature = "ssh")]
+/// SSH clients reconnecting automatically
+pub mod resilient_client;
+
+#[cfg(feature = "ssh")]
+/// Verification of SSH host keys
+pub mod host_verification;
+
+#[cfg(feature = "ssh")]
+/// Reading connection settings from OpenSSH client configs
+pub(crate) mod ssh_config;
+
+#[cfg(feature = "ssh")]
+/// Handlers for keyboard-interactive authentication prompts
+pub mod auth_prompt;
+
+/// Adaptive polling intervals for recording loops
+pub mod polling;
+
+#[cfg(all(test, feature = "ssh"))]
 pub(crate) fn get_config_from_env() -> ConnectionConfig {
     use std::env;
 
@@ -0,0 +1,80 @@
+use std::time::Duration;
+
+#[derive(Debug, Clone)]
+/// Adaptive polling interval for recording loops
+///
+/// The interval is halved whenever many changes were recorded (see [`AdaptiveInterval::with_busy_threshold`])
+/// and doubled whenever nothing changed, always staying within the configured bounds.
+/// This keeps the load on login nodes low while the queue is quiet, without missing short-lived state changes when it is busy.
+pub struct AdaptiveInterval {
+    min: Duration,
+    max: Duration,
+    current: Duration,
+    busy_threshold: usize,
+}
+
+impl AdaptiveInterval {
+    /// Create a new adaptive interval between `min` and `max`, starting at `min`
+    pub fn new(min: Duration, max: Duration) -> Self {
+        let max = max.max(min);
+        Self {
+            min,
+            max,
+            current: min,
+            busy_threshold: 10,
+        }
+    }
+
+    /// Start at the given `interval` (clamped to the configured bounds) instead of the minimum
+    pub fn starting_at(mut self, interval: Duration) -> Self {
+        self.current = interval.clamp(self.min, self.max);
+        self
+    }
+
+    /// Number of changes per iteration, from which on the interval is shortened (default: `10`)
+    pub fn with_busy_threshold(mut self, busy_threshold: usize) -> Self {
+        self.busy_threshold = busy_threshold.max(1);
+        self
+    }
+
+    /// The current interval
+    pub fn current(&self) -> Duration {
+        self.current
+    }
+
+    /// Update the interval based on the number of changes recorded in the last iteration, returning the next interval
+    pub fn update(&mut self, num_changes: usize) -> Duration {
+        if num_changes >= self.busy_threshold {
+            self.current = (self.current / 2).max(self.min);
+        } else if num_changes == 0 {
+            self.current = (self.current * 2).min(self.max);
+        }
+        self.current
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::AdaptiveInterval;
+
+    #[test]
+    fn test_adaptive_interval() {
+        let mut interval = AdaptiveInterval::new(Duration::from_secs(2), Duration::from_secs(30))
+            .starting_at(Duration::from_secs(5));
+        // Quiet queue: Back off exponentially up to the maximum
+        assert_eq!(interval.update(0), Duration::from_secs(10));
+        assert_eq!(interval.update(0), Duration::from_secs(20));
+        assert_eq!(interval.update(0), Duration::from_secs(30));
+        assert_eq!(interval.update(0), Duration::from_secs(30));
+        // Few changes: Keep the interval
+        assert_eq!(interval.update(3), Duration::from_secs(30));
+        // Many changes: Shorten down to the minimum
+        assert_eq!(interval.update(10), Duration::from_secs(15));
+        assert_eq!(interval.update(50), Duration::from_millis(7500));
+        assert_eq!(interval.update(50), Duration::from_millis(3750));
+        assert_eq!(interval.update(50), Duration::from_secs(2));
+        assert_eq!(interval.current(), Duration::from_secs(2));
+    }
+}
@@ -1,73 +1,707 @@
 use std::{
+    collections::HashMap,
     net::{IpAddr, Ipv4Addr, SocketAddr},
-    sync::Arc,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::Instant,
 };
 
 use anyhow::Error;
+use async_ssh2_tokio::Client;
 use tokio::{
-    net::TcpListener,
-    task::{self, JoinHandle},
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+    sync::mpsc,
+    task::{self, JoinHandle, JoinSet},
 };
 
+use crate::{
+    executor::CommandExecutor,
+    job_management::{
+        get_job_status_with_options, sbatch::shell_quote, JobForwardingMode, JobLocalForwarding,
+        JobStatus, JobStatusOptions, PollPolicy,
+    },
+    misc::polling::AdaptiveInterval,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Direction of a [`PortForward`]
+pub enum ForwardDirection {
+    /// Connections to a local address are forwarded to an address reachable from the SSH server (`ssh -L`)
+    Local,
+    /// Connections to an address on the SSH server are forwarded to a local address (`ssh -R`)
+    Remote,
+}
+
+#[derive(Debug)]
+/// Handle of a running port forwarding over SSH (see [`PortForward::local`], [`PortForward::remote`] and [`PortForward::for_job`])
+///
+/// The forwarding runs until [`PortForward::shutdown`] is called or the handle is dropped,
+/// which closes the listener and all active connections.
+pub struct PortForward {
+    direction: ForwardDirection,
+    local_addr: SocketAddr,
+    remote_addr: SocketAddr,
+    active: Arc<AtomicUsize>,
+    errors: Arc<Mutex<Vec<String>>>,
+    task: Option<JoinHandle<()>>,
+}
+
+impl PortForward {
+    /// Forward `local_addr` to `target` (host name and port), as reachable from the SSH server (`ssh -L`)
+    ///
+    /// Host names are resolved on the SSH server, so that cluster-internal host names (e.g., of compute nodes) can be used.
+    /// Use port `0` to bind an arbitrary free local port (see [`PortForward::local_addr`]).
+    pub async fn local(
+        client: Arc<Client>,
+        local_addr: SocketAddr,
+        target: (String, u16),
+    ) -> Result<Self, Error> {
+        let ip = match target.0.parse::<IpAddr>() {
+            Ok(ip) => ip,
+            Err(_) => resolve_remote_host(client.as_ref(), &target.0).await?,
+        };
+        let target = SocketAddr::new(ip, target.1);
+        let listener = TcpListener::bind(local_addr)
+            .await
+            .map_err(|e| Error::new(e).context(format!("Could not bind {local_addr}.")))?;
+        let local_addr = listener.local_addr()?;
+        let active = Arc::new(AtomicUsize::new(0));
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let task = task::spawn(accept_loop(
+            listener,
+            client,
+            target,
+            active.clone(),
+            errors.clone(),
+        ));
+        Ok(Self {
+            direction: ForwardDirection::Local,
+            local_addr,
+            remote_addr: target,
+            active,
+            errors,
+            task: Some(task),
+        })
+    }
+
+    /// Forward `remote_addr` on the SSH server to `local_target` (`ssh -R`), e.g., to expose a local service to jobs
+    ///
+    /// The SSH library does not support remote forwarding requests, so connections are accepted by a relay started on the SSH server
+    /// (requiring `python3` there), and transferred over a single SSH channel.
+    /// Use port `0` to bind an arbitrary free port on the SSH server (see [`PortForward::remote_addr`]).
+    pub async fn remote(
+        client: Arc<Client>,
+        remote_addr: SocketAddr,
+        local_target: SocketAddr,
+    ) -> Result<Self, Error> {
+        let channel = client.get_channel().await?;
+        channel
+            .exec(
+                true,
+                format!(
+                    "python3 -c {} {} {}",
+                    shell_quote(REMOTE_RELAY),
+                    shell_quote(&remote_addr.ip().to_string()),
+                    remote_addr.port()
+                ),
+            )
+            .await?;
+        let mut stream = channel.into_stream();
+        let mut buf = Vec::new();
+        let port = match read_frame(&mut stream, &mut buf).await {
+            Ok(Some(frame)) if frame.kind == FRAME_LISTENING => frame.id,
+            res => {
+                return Err(Error::msg(format!(
+                "Could not listen on {remote_addr} on the SSH server (requires python3): {res:?}"
+            )))
+            }
+        };
+        let remote_addr = SocketAddr::new(remote_addr.ip(), port as u16);
+        let active = Arc::new(AtomicUsize::new(0));
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let task = task::spawn(remote_accept_loop(
+            stream,
+            buf,
+            local_target,
+            active.clone(),
+            errors.clone(),
+        ));
+        Ok(Self {
+            direction: ForwardDirection::Remote,
+            local_addr: local_target,
+            remote_addr,
+            active,
+            errors,
+            task: Some(task),
+        })
+    }
+
+    /// Forward a port of the node executing a SLURM job to the local machine, replacing `ssh -R` in the job script
+    ///
+    /// Waits until the job is running (polling as configured by the [`PollPolicy`]),
+    /// then forwards `127.0.0.1:<local_port>` to `<job_port>` on the node executing the job, tunneled through the login node.
+    /// With [`JobForwardingMode::LoginNodeRelay`], the relay port on the login node is forwarded instead.
+    /// Fails if the job ends before it started running, or if the timeout of the [`PollPolicy`] is exceeded.
+    pub async fn for_job(
+        client: Arc<Client>,
+        job_id: &str,
+        forwarding: &JobLocalForwarding,
+        policy: &PollPolicy,
+    ) -> Result<Self, Error> {
+        let exec_host = wait_for_exec_host(client.as_ref(), job_id, policy).await?;
+        let target = match &forwarding.mode {
+            JobForwardingMode::Direct => (exec_host, forwarding.job_port),
+            JobForwardingMode::LoginNodeRelay { relay_port, .. } => {
+                (Ipv4Addr::LOCALHOST.to_string(), *relay_port)
+            }
+        };
+        Self::local(
+            client,
+            SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), forwarding.local_port),
+            target,
+        )
+        .await
+    }
+
+    /// Direction of the forwarding
+    pub fn direction(&self) -> ForwardDirection {
+        self.direction
+    }
+
+    /// Local address accepting connections ([`ForwardDirection::Local`]), or connections are forwarded to ([`ForwardDirection::Remote`])
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Address on the SSH server connections are forwarded to ([`ForwardDirection::Local`], as resolved there), or accepting connections ([`ForwardDirection::Remote`])
+    pub fn remote_addr(&self) -> SocketAddr {
+        self.remote_addr
+    }
+
+    /// Number of currently forwarded connections
+    pub fn active_connections(&self) -> usize {
+        self.active.load(Ordering::SeqCst)
+    }
+
+    /// Errors of failed connections so far (e.g., if the target refused the connection)
+    pub fn errors(&self) -> Vec<String> {
+        self.errors.lock().unwrap().clone()
+    }
+
+    /// Check if the forwarding is still accepting connections
+    pub fn is_running(&self) -> bool {
+        self.task.as_ref().is_some_and(|t| !t.is_finished())
+    }
+
+    /// Stop the forwarding, closing the listener and all active connections
+    pub async fn shutdown(mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+            let _ = task.await;
+        }
+    }
+}
+
+impl Drop for PortForward {
+    fn drop(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.abort();
+        }
+    }
+}
+
+/// Decrements the active connection count when a connection task finishes (or is aborted)
+struct ActiveGuard(Arc<AtomicUsize>);
+
+impl Drop for ActiveGuard {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+async fn accept_loop(
+    listener: TcpListener,
+    client: Arc<Client>,
+    target: SocketAddr,
+    active: Arc<AtomicUsize>,
+    errors: Arc<Mutex<Vec<String>>>,
+) {
+    // Connection tasks are aborted together with this loop
+    let mut connections = JoinSet::new();
+    loop {
+        tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok((socket, peer)) => {
+                    active.fetch_add(1, Ordering::SeqCst);
+                    let guard = ActiveGuard(active.clone());
+                    let client = client.clone();
+                    let errors = errors.clone();
+                    connections.spawn(async move {
+                        let _guard = guard;
+                        let res = forward_connection(&client, socket, peer, target).await;
+                        if let Err(e) = res {
+                            errors.lock().unwrap().push(format!("{peer}: {e:?}"));
+                        }
+                    });
+                }
+                Err(e) => errors
+                    .lock()
+                    .unwrap()
+                    .push(format!("Could not accept connection: {e:?}")),
+            },
+            // Clean up finished connections
+            Some(_) = connections.join_next(), if !connections.is_empty() => {}
+        }
+    }
+}
+
+async fn forward_connection(
+    client: &Client,
+    mut socket: TcpStream,
+    peer: SocketAddr,
+    target: SocketAddr,
+) -> Result<(), Error> {
+    let channel = client.open_direct_tcpip_channel(target, peer).await?;
+    let mut ssh_stream = channel.into_stream();
+    tokio::io::copy_bidirectional(&mut socket, &mut ssh_stream).await?;
+    Ok(())
+}
+
+/// Relay for [`PortForward::remote`], executed on the SSH server (`python3 -c <relay> <host> <port>`)
+///
+/// Listens on the given address and multiplexes all accepted connections over stdin/stdout (see [`Frame`]).
+const REMOTE_RELAY: &str = r#"import os, selectors, socket, struct, sys
+out = sys.stdout.buffer
+def send(kind, i, data=b""):
+    out.write(struct.pack(">cII", kind, i, len(data)) + data)
+    out.flush()
+host, port = sys.argv[1], int(sys.argv[2])
+srv = socket.socket(socket.AF_INET6 if ":" in host else socket.AF_INET)
+srv.setsockopt(socket.SOL_SOCKET, socket.SO_REUSEADDR, 1)
+srv.bind((host, port))
+srv.listen(16)
+send(b"L", srv.getsockname()[1])
+sel = selectors.DefaultSelector()
+sel.register(srv, selectors.EVENT_READ)
+sel.register(0, selectors.EVENT_READ)
+conns, n, buf = {}, 0, b""
+def close(i):
+    sel.unregister(conns[i])
+    conns.pop(i).close()
+while True:
+    for key, _ in sel.select():
+        if key.fileobj is srv:
+            c, _ = srv.accept()
+            n += 1
+            conns[n] = c
+            sel.register(c, selectors.EVENT_READ, n)
+            send(b"O", n)
+        elif key.fileobj == 0:
+            data = os.read(0, 65536)
+            if not data:
+                sys.exit()
+            buf += data
+            while len(buf) >= 9:
+                kind, i, length = struct.unpack(">cII", buf[:9])
+                if len(buf) < 9 + length:
+                    break
+                payload, buf = buf[9:9 + length], buf[9 + length:]
+                if i not in conns:
+                    continue
+                if kind == b"D":
+                    conns[i].sendall(payload)
+                else:
+                    close(i)
+        else:
+            i = key.data
+            try:
+                data = key.fileobj.recv(65536)
+            except OSError:
+                data = b""
+            if data:
+                send(b"D", i, data)
+            else:
+                close(i)
+                send(b"C", i)
+"#;
+
+/// The relay is listening (the ID is the bound port)
+const FRAME_LISTENING: u8 = b'L';
+/// A connection was accepted by the relay
+const FRAME_OPEN: u8 = b'O';
+/// Data of a connection
+const FRAME_DATA: u8 = b'D';
+/// A connection was closed
+const FRAME_CLOSE: u8 = b'C';
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Message exchanged with the relay of [`PortForward::remote`]
+///
+/// Encoded as kind (1 byte), connection ID (4 bytes) and payload length (4 bytes, both big-endian), followed by the payload.
+struct Frame {
+    kind: u8,
+    id: u32,
+    data: Vec<u8>,
+}
+
+impl Frame {
+    fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(9 + self.data.len());
+        bytes.push(self.kind);
+        bytes.extend_from_slice(&self.id.to_be_bytes());
+        bytes.extend_from_slice(&(self.data.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&self.data);
+        bytes
+    }
+
+    /// Remove the first complete frame from `buf`, if any
+    fn take(buf: &mut Vec<u8>) -> Option<Self> {
+        if buf.len() < 9 {
+            return None;
+        }
+        let len = u32::from_be_bytes(buf[5..9].try_into().unwrap()) as usize;
+        if buf.len() < 9 + len {
+            return None;
+        }
+        let frame = Frame {
+            kind: buf[0],
+            id: u32::from_be_bytes(buf[1..5].try_into().unwrap()),
+            data: buf[9..9 + len].to_vec(),
+        };
+        buf.drain(..9 + len);
+        Some(frame)
+    }
+}
+
+/// Read the next frame, buffering partially received frames in `buf`
+///
+/// Returns `None` once the stream is closed. Cancel-safe, as long as the same `buf` is used.
+async fn read_frame(
+    reader: &mut (impl AsyncRead + Unpin),
+    buf: &mut Vec<u8>,
+) -> Result<Option<Frame>, Error> {
+    let mut chunk = [0; 32 * 1024];
+    loop {
+        if let Some(frame) = Frame::take(buf) {
+            return Ok(Some(frame));
+        }
+        let n = reader.read(&mut chunk).await?;
+        if n == 0 {
+            return Ok(None);
+        }
+        buf.extend_from_slice(&chunk[..n]);
+    }
+}
+
+/// Forward the connections accepted by the relay on `stream` to `local_target`
+async fn remote_accept_loop(
+    stream: impl AsyncRead + AsyncWrite + Send + 'static,
+    mut buf: Vec<u8>,
+    local_target: SocketAddr,
+    active: Arc<AtomicUsize>,
+    errors: Arc<Mutex<Vec<String>>>,
+) {
+    let (mut reader, mut writer) = tokio::io::split(stream);
+    let (outgoing, mut outgoing_rx) = mpsc::channel::<Frame>(64);
+    let mut incoming: HashMap<u32, mpsc::UnboundedSender<Vec<u8>>> = HashMap::new();
+    // Connection tasks are aborted together with this loop
+    let mut connections = JoinSet::new();
+    let res: Result<(), Error> = async {
+        loop {
+            tokio::select! {
+                frame = read_frame(&mut reader, &mut buf) => {
+                    let Some(frame) = frame? else {
+                        return Err(Error::msg("Relay on the SSH server exited."));
+                    };
+                    match frame.kind {
+                        FRAME_OPEN => {
+                            let (tx, rx) = mpsc::unbounded_channel();
+                            incoming.insert(frame.id, tx);
+                            active.fetch_add(1, Ordering::SeqCst);
+                            let guard = ActiveGuard(active.clone());
+                            let outgoing = outgoing.clone();
+                            let errors = errors.clone();
+                            connections.spawn(async move {
+                                let _guard = guard;
+                                let res = relay_connection(frame.id, local_target, rx, &outgoing).await;
+                                if let Err(e) = res {
+                                    errors.lock().unwrap().push(format!("Connection {}: {e:?}", frame.id));
+                                }
+                                let _ = outgoing.send(Frame { kind: FRAME_CLOSE, id: frame.id, data: Vec::new() }).await;
+                            });
+                        }
+                        FRAME_DATA => {
+                            if let Some(tx) = incoming.get(&frame.id) {
+                                let _ = tx.send(frame.data);
+                            }
+                        }
+                        FRAME_CLOSE => {
+                            // Dropping the sender closes the local connection
+                            incoming.remove(&frame.id);
+                        }
+                        kind => return Err(Error::msg(format!("Unexpected frame from relay: {kind}"))),
+                    }
+                }
+                Some(frame) = outgoing_rx.recv() => {
+                    if frame.kind == FRAME_CLOSE {
+                        incoming.remove(&frame.id);
+                    }
+                    writer.write_all(&frame.encode()).await?;
+                    writer.flush().await?;
+                }
+                // Clean up finished connections
+                Some(_) = connections.join_next(), if !connections.is_empty() => {}
+            }
+        }
+    }
+    .await;
+    if let Err(e) = res {
+        errors.lock().unwrap().push(format!("{e:?}"));
+    }
+}
+
+/// Transfer a single connection accepted by the relay to `local_target`, until either side closes it
+async fn relay_connection(
+    id: u32,
+    local_target: SocketAddr,
+    mut incoming: mpsc::UnboundedReceiver<Vec<u8>>,
+    outgoing: &mpsc::Sender<Frame>,
+) -> Result<(), Error> {
+    let socket = TcpStream::connect(local_target)
+        .await
+        .map_err(|e| Error::new(e).context(format!("Could not connect to {local_target}.")))?;
+    let (mut read, mut write) = socket.into_split();
+    let mut chunk = vec![0; 32 * 1024];
+    loop {
+        tokio::select! {
+            data = incoming.recv() => match data {
+                Some(data) => write.write_all(&data).await?,
+                None => return Ok(()),
+            },
+            n = read.read(&mut chunk) => {
+                let n = n?;
+                if n == 0 {
+                    return Ok(());
+                }
+                let frame = Frame { kind: FRAME_DATA, id, data: chunk[..n].to_vec() };
+                if outgoing.send(frame).await.is_err() {
+                    return Ok(());
+                }
+            }
+        }
+    }
+}
+
+/// Resolve `host` on the SSH server (using `getent hosts`)
+async fn resolve_remote_host(client: &Client, host: &str) -> Result<IpAddr, Error> {
+    let out =
+        CommandExecutor::execute(client, &format!("getent hosts {}", shell_quote(host))).await?;
+    parse_getent_hosts(&out.stdout)
+        .ok_or_else(|| Error::msg(format!("Could not resolve {host} on the SSH server.")))
+}
+
+/// Parse the first address of the output of `getent hosts`
+fn parse_getent_hosts(stdout: &str) -> Option<IpAddr> {
+    stdout
+        .lines()
+        .find_map(|line| line.split_whitespace().next()?.parse().ok())
+}
+
+/// Wait until a job is running, returning the node executing it
+async fn wait_for_exec_host(
+    client: &Client,
+    job_id: &str,
+    policy: &PollPolicy,
+) -> Result<String, Error> {
+    let start = Instant::now();
+    let mut interval = AdaptiveInterval::new(policy.min_interval, policy.max_interval);
+    let options = JobStatusOptions::detect_timezone_with(client).await?;
+    loop {
+        match get_job_status_with_options(client, job_id, &options).await? {
+            JobStatus::RUNNING {
+                exec_host: Some(exec_host),
+                ..
+            } => return Ok(exec_host),
+            status @ (JobStatus::ENDED { .. } | JobStatus::NotFound) => {
+                return Err(Error::msg(format!(
+                    "Job {job_id} is not running (status: {status:?})."
+                )))
+            }
+            _ => {}
+        }
+        let mut wait = interval.current();
+        if let Some(timeout) = policy.timeout {
+            let remaining = timeout.saturating_sub(start.elapsed());
+            if remaining.is_zero() {
+                return Err(Error::msg(format!(
+                    "Timed out waiting for job {job_id} to start."
+                )));
+            }
+            wait = wait.min(remaining);
+        }
+        tokio::time::sleep(wait).await;
+        interval.update(0);
+    }
+}
+
 /// Perform port forwarding over SSH
 ///
-/// Using the given client, the local port on the SSH machine will be forwarded to the remote port (e.g., the user's machine)
+/// Using the given client, the local address is forwarded to the remote address (`host:port`, as reachable from the SSH server).
+/// See [`PortForward::local`].
 pub async fn ssh_port_forwarding<S: AsRef<str>>(
-    client: Arc<async_ssh2_tokio::Client>,
+    client: Arc<Client>,
     local_addr: S,
     remote_addr: S,
-) -> Result<JoinHandle<()>, Error> {
-    println!("Got client!");
-    let l_addr: SocketAddr = local_addr.as_ref().parse().unwrap();
-    let local_listener = TcpListener::bind(l_addr)
-        .await
-        .expect("Cannot bind local port");
-    let arc = std::sync::Arc::new(client);
-    let r_addr: SocketAddr = remote_addr.as_ref().parse().unwrap();
-    let f = task::spawn(async move {
+) -> Result<PortForward, Error> {
+    let local_addr: SocketAddr = local_addr.as_ref().parse()?;
+    let (host, port) = remote_addr
+        .as_ref()
+        .rsplit_once(':')
+        .ok_or_else(|| Error::msg("Expected remote address of the form host:port."))?;
+    PortForward::local(client, local_addr, (host.to_string(), port.parse()?)).await
+}
+
+/// Open a local tunnel to `target` through the `jump` host, returning the local address to connect to
+///
+/// Each accepted connection is forwarded over a separate channel.
+/// The tunnel stops accepting connections once all connections were closed,
+/// disconnecting the jump host unless it is still used elsewhere (e.g., by another tunnel).
+pub(crate) async fn jump_tunnel(
+    jump: Arc<async_ssh2_tokio::Client>,
+    target: (String, u16),
+) -> Result<SocketAddr, Error> {
+    let listener = TcpListener::bind(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0)).await?;
+    let addr = listener.local_addr()?;
+    task::spawn(async move {
+        let mut connections = JoinSet::new();
         loop {
-            let (mut socket, _) = local_listener
-                .accept()
-                .await
-                .expect("Cannot process local client");
-
-            println!("Client connected");
-            let a = arc.clone();
-            tokio::spawn(async move {
-                let c = a
-                    .open_direct_tcpip_channel(
-                        r_addr,
-                        SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 0),
-                    )
-                    .await;
-                match c {
-                    Ok(channel) => {
-                        let mut ssh_stream = channel.into_stream();
-
-                        let copy_bidirectional =
-                            tokio::io::copy_bidirectional(&mut socket, &mut ssh_stream).await;
-                        match copy_bidirectional {
-                            Ok((bytes_to_remote, bytes_to_local)) => {
-                                println!(
-                            "Connection closed. Sent {bytes_to_remote} bytes to remote, received {bytes_to_local} bytes from remote"
-                        );
+            tokio::select! {
+                accepted = listener.accept() => match accepted {
+                    Ok((mut socket, _)) => {
+                        let jump = jump.clone();
+                        let target = target.clone();
+                        connections.spawn(async move {
+                            let res: Result<(), Error> = async {
+                                let channel = jump.open_direct_tcpip_channel(target, None).await?;
+                                let mut ssh_stream = channel.into_stream();
+                                tokio::io::copy_bidirectional(&mut socket, &mut ssh_stream).await?;
+                                Ok(())
                             }
-                            Err(e) => eprintln!("Error forwarding traffic: {e:?}"),
-                        }
+                            .await;
+                            if let Err(e) = res {
+                                eprintln!("Error in jump host tunnel: {e:?}");
+                            }
+                        });
+                    }
+                    Err(e) => eprintln!("Could not accept jump host tunnel connection: {e:?}"),
+                },
+                Some(_) = connections.join_next(), if !connections.is_empty() => {
+                    if connections.is_empty() {
+                        break;
                     }
-                    Err(e) => eprintln!("Could not open channel: {e:?}"),
                 }
-            });
+            }
+        }
+        if let Some(jump) = Arc::into_inner(jump) {
+            let _ = jump.disconnect().await;
         }
     });
-    Ok(f)
+    Ok(addr)
 }
 
 #[cfg(test)]
 mod test {
-    use std::sync::Arc;
+    use std::{
+        net::{IpAddr, Ipv4Addr, SocketAddr},
+        sync::{atomic::AtomicUsize, Arc, Mutex},
+    };
+
+    use tokio::{io::AsyncWriteExt, net::TcpListener};
+
+    use crate::misc::port_forwarding::{
+        parse_getent_hosts, read_frame, remote_accept_loop, ssh_port_forwarding, Frame, FRAME_DATA,
+        FRAME_OPEN,
+    };
+
+    #[test]
+    fn test_parse_getent_hosts() {
+        assert_eq!(
+            parse_getent_hosts("10.10.4.23      n23m0104.hpc.itc.rwth-aachen.de n23m0104\n"),
+            Some("10.10.4.23".parse::<IpAddr>().unwrap())
+        );
+        assert_eq!(
+            parse_getent_hosts("fe80::1 n23m0104\n"),
+            Some("fe80::1".parse::<IpAddr>().unwrap())
+        );
+        assert_eq!(parse_getent_hosts(""), None);
+    }
+
+    #[tokio::test]
+    async fn test_remote_accept_loop() {
+        // Local echo server as forwarding target
+        let listener = TcpListener::bind(SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0))
+            .await
+            .unwrap();
+        let local_target = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let (mut read, mut write) = socket.split();
+            tokio::io::copy(&mut read, &mut write).await.unwrap();
+        });
+        // Simulate the relay on the SSH server
+        let (relay, stream) = tokio::io::duplex(1024);
+        let active = Arc::new(AtomicUsize::new(0));
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        tokio::spawn(remote_accept_loop(
+            stream,
+            Vec::new(),
+            local_target,
+            active.clone(),
+            errors.clone(),
+        ));
+        let (mut relay_read, mut relay_write) = tokio::io::split(relay);
+        for frame in [
+            Frame {
+                kind: FRAME_OPEN,
+                id: 7,
+                data: Vec::new(),
+            },
+            Frame {
+                kind: FRAME_DATA,
+                id: 7,
+                data: b"hello".to_vec(),
+            },
+        ] {
+            relay_write.write_all(&frame.encode()).await.unwrap();
+        }
+        let mut buf = Vec::new();
+        let mut echoed = Vec::new();
+        while echoed.len() < 5 {
+            let frame = read_frame(&mut relay_read, &mut buf)
+                .await
+                .unwrap()
+                .unwrap();
+            assert_eq!((frame.kind, frame.id), (FRAME_DATA, 7));
+            echoed.extend(frame.data);
+        }
+        assert_eq!(echoed, b"hello");
+        assert_eq!(active.load(std::sync::atomic::Ordering::SeqCst), 1);
+        assert!(errors.lock().unwrap().is_empty());
 
-    use crate::misc::port_forwarding::ssh_port_forwarding;
+        // Partial frames are buffered until complete
+        let mut partial = Frame {
+            kind: FRAME_DATA,
+            id: 1,
+            data: b"abc".to_vec(),
+        }
+        .encode();
+        partial.truncate(10);
+        assert_eq!(Frame::take(&mut partial), None);
+        assert_eq!(partial.len(), 10);
+    }
 
     #[tokio::test]
     async fn test_port_forwarding() {
@@ -76,8 +710,11 @@ mod test {
         let login_cfg = crate::misc::get_config_from_env();
         let client = login_with_cfg(&login_cfg).await.unwrap();
         let arc = Arc::new(client);
-        ssh_port_forwarding(arc, "127.0.0.1:3000", "127.0.0.1:3000")
+        let forward = ssh_port_forwarding(arc, "127.0.0.1:3000", "127.0.0.1:3000")
             .await
             .unwrap();
+        assert!(forward.is_running());
+        assert_eq!(forward.active_connections(), 0);
+        forward.shutdown().await;
     }
 }
@@ -0,0 +1,268 @@
+use std::{future::Future, io::ErrorKind, path::Path, pin::Pin, sync::Arc, time::Duration};
+
+use anyhow::Error;
+use async_ssh2_tokio::Client;
+use tokio::sync::Mutex;
+
+use crate::{
+    executor::{with_timeout, CommandExecutor, CommandOutput, Timeout},
+    login_with_cfg, ConnectionAuth, ConnectionConfig,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// Exponential backoff between retries of a failed operation
+pub struct RetryPolicy {
+    /// Delay before the first retry
+    pub initial_delay: Duration,
+    /// Upper bound for the delay between retries
+    pub max_delay: Duration,
+    /// Maximum number of retries (i.e., not counting the first attempt)
+    pub max_retries: usize,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+            max_retries: 8,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// The delay before the given retry (starting at `0`), doubling after every retry
+    pub fn delay(&self, retry: usize) -> Duration {
+        let factor = 1u32.checked_shl(retry as u32).unwrap_or(u32::MAX);
+        self.initial_delay
+            .saturating_mul(factor)
+            .min(self.max_delay)
+    }
+}
+
+type MfaPrompt =
+    Arc<dyn Fn() -> Pin<Box<dyn Future<Output = Result<String, Error>> + Send>> + Send + Sync>;
+
+/// SSH client which reconnects automatically when the connection drops (e.g., overnight during a recording loop)
+///
+/// Operations failing due to the connection (see [`is_connection_error`]) are retried with exponential backoff (see [`RetryPolicy`]),
+/// reconnecting before each retry using the stored [`ConnectionConfig`].
+/// Other failures (e.g., a non-zero exit status or a missing file) are returned immediately.
+/// Commands executed using [`CommandExecutor::execute_once`] (e.g., `sbatch`) are never retried,
+/// as they might have been executed before the connection dropped.
+/// Commands are subject to the [command timeout](ConnectionConfig::command_timeout) of the configuration.
+///
+/// For [`ConnectionAuth::PasswordMFA`], a new MFA code is required for every login,
+/// which is requested using the callback passed to [`ResilientClient::with_mfa_prompt`].
+pub struct ResilientClient {
+    cfg: ConnectionConfig,
+    client: Mutex<Arc<Client>>,
+    retry_policy: RetryPolicy,
+    mfa_prompt: Option<MfaPrompt>,
+}
+
+impl std::fmt::Debug for ResilientClient {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ResilientClient")
+            .field("host", &self.cfg.host)
+            .field("username", &self.cfg.username)
+            .field("retry_policy", &self.retry_policy)
+            .field("mfa_prompt", &self.mfa_prompt.is_some())
+            .finish()
+    }
+}
+
+impl ResilientClient {
+    /// Login using the given configuration (see [`login_with_cfg`])
+    pub async fn connect(cfg: ConnectionConfig) -> Result<Self, Error> {
+        let client = login_with_cfg(&cfg).await?;
+        Ok(Self::from_client(cfg, client))
+    }
+
+    /// Wrap an already connected client, using `cfg` for reconnecting
+    pub fn from_client(cfg: ConnectionConfig, client: Client) -> Self {
+        Self {
+            cfg,
+            client: Mutex::new(Arc::new(client)),
+            retry_policy: RetryPolicy::default(),
+            mfa_prompt: None,
+        }
+    }
+
+    /// Use the given retry policy instead of [`RetryPolicy::default`]
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Request a new MFA code using `prompt` whenever reconnecting with [`ConnectionAuth::PasswordMFA`]
+    ///
+    /// Without a prompt, the MFA code of the stored configuration is reused.
+    pub fn with_mfa_prompt<F, Fut>(mut self, prompt: F) -> Self
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<String, Error>> + Send + 'static,
+    {
+        self.mfa_prompt = Some(Arc::new(move || Box::pin(prompt())));
+        self
+    }
+
+    /// The currently connected client
+    ///
+    /// Useful for functions requiring a `&Client` (which are not retried automatically).
+    pub async fn client(&self) -> Arc<Client> {
+        Arc::clone(&*self.client.lock().await)
+    }
+
+    /// Replace the connection, unless it was already replaced since `failed` was handed out
+    async fn reconnect(&self, failed: &Arc<Client>) -> Result<(), Error> {
+        let mut client = self.client.lock().await;
+        if !Arc::ptr_eq(&client, failed) {
+            return Ok(());
+        }
+        let mut cfg = self.cfg.clone();
+        if let (ConnectionAuth::PasswordMFA { mfa_code, .. }, Some(prompt)) =
+            (&mut cfg.auth, &self.mfa_prompt)
+        {
+            *mfa_code = prompt().await?;
+        }
+        *client = Arc::new(login_with_cfg(&cfg).await?);
+        // The previous connection is broken anyway
+        let _ = failed.disconnect().await;
+        Ok(())
+    }
+
+    /// Run `op` with the current client, reconnecting and retrying on connection errors (see [`is_connection_error`]) according to the retry policy
+    pub async fn retry<T, F, Fut>(&self, op: F) -> Result<T, Error>
+    where
+        F: Fn(Arc<Client>) -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        let mut retry = 0;
+        loop {
+            let client = self.client().await;
+            match op(Arc::clone(&client)).await {
+                Ok(res) => return Ok(res),
+                Err(err) if !is_connection_error(&err) => return Err(err),
+                Err(err) if retry >= self.retry_policy.max_retries => {
+                    return Err(err.context(format!("Giving up after {retry} retries.")));
+                }
+                Err(err) => eprintln!("Operation failed, reconnecting: {err:?}"),
+            }
+            tokio::time::sleep(self.retry_policy.delay(retry)).await;
+            retry += 1;
+            // A failed reconnect is retried in the next iteration
+            if let Err(e) = self.reconnect(&client).await {
+                eprintln!("Reconnecting failed: {e:?}");
+            }
+        }
+    }
+}
+
+/// If `err` (or any of its causes) indicates a broken SSH connection, instead of a failure of the operation itself
+///
+/// This includes errors of the SSH session or channels, I/O errors of the connection (e.g., a reset connection)
+/// and [timeouts](Timeout), as a dropped connection often only shows up as a hanging command.
+pub fn is_connection_error(err: &Error) -> bool {
+    err.chain().any(|cause| {
+        if let Some(err) = cause.downcast_ref::<async_ssh2_tokio::Error>() {
+            matches!(
+                err,
+                async_ssh2_tokio::Error::SshError(_)
+                    | async_ssh2_tokio::Error::IoError(_)
+                    | async_ssh2_tokio::Error::CommandDidntExit
+            )
+        } else if let Some(err) = cause.downcast_ref::<russh_sftp::client::error::Error>() {
+            matches!(
+                err,
+                russh_sftp::client::error::Error::IO(_) | russh_sftp::client::error::Error::Timeout
+            )
+        } else if let Some(err) = cause.downcast_ref::<std::io::Error>() {
+            matches!(
+                err.kind(),
+                ErrorKind::ConnectionReset
+                    | ErrorKind::ConnectionAborted
+                    | ErrorKind::NotConnected
+                    | ErrorKind::BrokenPipe
+                    | ErrorKind::UnexpectedEof
+                    | ErrorKind::TimedOut
+            )
+        } else {
+            cause.is::<Timeout>()
+        }
+    })
+}
+
+impl CommandExecutor for ResilientClient {
+    async fn execute(&self, command: &str) -> Result<CommandOutput, Error> {
+        let timeout = self.cfg.command_timeout;
+        self.retry(|client| async move {
+            with_timeout(
+                timeout,
+                command,
+                CommandExecutor::execute(&*client, command),
+            )
+            .await
+        })
+        .await
+    }
+
+    async fn execute_once(&self, command: &str) -> Result<CommandOutput, Error> {
+        let client = self.client().await;
+        with_timeout(
+            self.cfg.command_timeout,
+            command,
+            CommandExecutor::execute(&*client, command),
+        )
+        .await
+    }
+
+    async fn upload(&self, local_path: &Path, remote_path: &str) -> Result<(), Error> {
+        self.retry(|client| async move { client.upload(local_path, remote_path).await })
+            .await
+    }
+
+    async fn download(&self, remote_path: &str, local_path: &Path) -> Result<(), Error> {
+        self.retry(|client| async move { client.download(remote_path, local_path).await })
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use anyhow::Error;
+
+    use super::{is_connection_error, RetryPolicy};
+    use crate::Timeout;
+
+    #[test]
+    fn test_retry_delay() {
+        let policy = RetryPolicy {
+            initial_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(5),
+            max_retries: 10,
+        };
+        let delays: Vec<_> = (0..6).map(|r| policy.delay(r).as_millis()).collect();
+        assert_eq!(delays, vec![500, 1000, 2000, 4000, 5000, 5000]);
+        assert_eq!(policy.delay(100), Duration::from_secs(5));
+    }
+
+    #[test]
+    fn test_is_connection_error() {
+        let reset = Error::new(std::io::Error::from(std::io::ErrorKind::ConnectionReset));
+        assert!(is_connection_error(&reset.context("Could not list jobs.")));
+        let timeout = Error::new(Timeout {
+            operation: "squeue".to_string(),
+            after: Duration::from_secs(30),
+        });
+        assert!(is_connection_error(&timeout));
+        assert!(!is_connection_error(&Error::new(
+            async_ssh2_tokio::Error::PasswordWrong
+        )));
+        let missing = Error::new(std::io::Error::from(std::io::ErrorKind::NotFound));
+        assert!(!is_connection_error(&missing));
+        assert!(!is_connection_error(&Error::msg("scontrol hold failed")));
+    }
+}
@@ -0,0 +1,303 @@
+use std::path::PathBuf;
+
+use anyhow::Error;
+
+use crate::{ConnectionAuth, ConnectionConfig, HostVerification};
+
+/// Settings of a host as resolved from an OpenSSH client config (e.g., `~/.ssh/config`)
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct SshConfigHost {
+    pub host_name: Option<String>,
+    pub port: Option<u16>,
+    pub user: Option<String>,
+    pub identity_files: Vec<String>,
+    pub proxy_jump: Option<String>,
+    pub strict_host_key_checking: Option<String>,
+    pub user_known_hosts_file: Option<String>,
+}
+
+/// Home directory of the current user
+pub(crate) fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+}
+
+/// Resolve the settings for `alias` from the content of an OpenSSH client config
+///
+/// As in OpenSSH, the first value obtained for each setting is used.
+/// `Match` blocks and `Include` directives are not supported (and ignored).
+pub(crate) fn resolve_ssh_config(content: &str, alias: &str) -> SshConfigHost {
+    let mut res = SshConfigHost::default();
+    // Settings before the first `Host` line apply to all hosts
+    let mut active = true;
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (key, value) = match line.split_once(|c: char| c.is_whitespace() || c == '=') {
+            Some((key, value)) => (
+                key.to_lowercase(),
+                value
+                    .trim_start_matches(|c: char| c.is_whitespace() || c == '=')
+                    .trim(),
+            ),
+            None => continue,
+        };
+        let value = value.trim_matches('"');
+        match key.as_str() {
+            "host" => active = host_matches(value, alias),
+            "match" => active = false,
+            _ if !active => {}
+            "hostname" => {
+                res.host_name.get_or_insert_with(|| value.to_string());
+            }
+            "port" if res.port.is_none() => res.port = value.parse().ok(),
+            "user" => {
+                res.user.get_or_insert_with(|| value.to_string());
+            }
+            "identityfile" => res.identity_files.push(value.to_string()),
+            "proxyjump" => {
+                res.proxy_jump.get_or_insert_with(|| value.to_string());
+            }
+            "stricthostkeychecking" => {
+                res.strict_host_key_checking
+                    .get_or_insert_with(|| value.to_lowercase());
+            }
+            "userknownhostsfile" => {
+                res.user_known_hosts_file
+                    .get_or_insert_with(|| value.to_string());
+            }
+            _ => {}
+        }
+    }
+    res
+}
+
+/// Check if any of the whitespace-separated `Host` patterns matches `alias` (and no negated pattern does)
+fn host_matches(patterns: &str, alias: &str) -> bool {
+    let mut matched = false;
+    for pattern in patterns.split_whitespace() {
+        match pattern.strip_prefix('!') {
+            Some(negated) if glob_matches(negated, alias) => return false,
+            Some(_) => {}
+            None => matched |= glob_matches(pattern, alias),
+        }
+    }
+    matched
+}
+
+/// Match `s` against a pattern with `*` (any sequence) and `?` (any character) wildcards
+fn glob_matches(pattern: &str, s: &str) -> bool {
+    glob::Pattern::new(pattern).is_ok_and(|p| p.matches(s))
+}
+
+/// Expand `~` and the tokens `%d` (home directory), `%h` (host name), `%r` (user) and `%%` in a path
+pub(crate) fn expand_path(path: &str, host_name: &str, user: &str) -> String {
+    let home = home_dir()
+        .map(|h| h.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let path = match path.strip_prefix('~') {
+        Some(rest) => format!("{home}{rest}"),
+        None => path.to_string(),
+    };
+    let mut res = String::new();
+    let mut chars = path.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            res.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('d') => res.push_str(&home),
+            Some('h') => res.push_str(host_name),
+            Some('r') => res.push_str(user),
+            Some(other) => res.push(other),
+            None => res.push('%'),
+        }
+    }
+    res
+}
+
+/// Split a `ProxyJump` hop of the form `[user@]host[:port]`
+pub(crate) fn parse_jump_hop(hop: &str) -> (Option<&str>, &str, Option<u16>) {
+    let hop = hop.trim().trim_start_matches("ssh://");
+    let (user, rest) = match hop.rsplit_once('@') {
+        Some((user, rest)) => (Some(user), rest),
+        None => (None, hop),
+    };
+    match rest.rsplit_once(':') {
+        Some((host, port)) if port.parse::<u16>().is_ok() => (user, host, port.parse().ok()),
+        _ => (user, rest, None),
+    }
+}
+
+/// Build a connection config for `alias` from the content of an OpenSSH client config (see [`ConnectionConfig::from_ssh_config`])
+pub(crate) fn connection_config_from_ssh_config(
+    content: &str,
+    alias: &str,
+    depth: usize,
+) -> Result<ConnectionConfig, Error> {
+    if depth > 8 {
+        return Err(Error::msg(format!("Too many ProxyJump hops for {alias}.")));
+    }
+    let host = resolve_ssh_config(content, alias);
+    let host_name = host
+        .host_name
+        .map_or_else(|| alias.to_string(), |h| h.replace("%h", alias));
+    let username = host
+        .user
+        .or_else(|| std::env::var("USER").ok())
+        .or_else(|| std::env::var("USERNAME").ok())
+        .ok_or_else(|| Error::msg(format!("No user configured for {alias}.")))?;
+    let path = match host.identity_files.first() {
+        Some(path) => expand_path(path, &host_name, &username),
+        None => default_identity_file()
+            .ok_or_else(|| Error::msg(format!("No identity file configured for {alias}.")))?,
+    };
+    let proxy_jump = match host.proxy_jump.as_deref() {
+        None | Some("none") => None,
+        Some(jumps) => Some(Box::new(jump_from_spec(content, jumps, depth + 1)?)),
+    };
+    // Only the first of multiple known hosts files is used
+    let known_hosts = host
+        .user_known_hosts_file
+        .as_deref()
+        .and_then(|files| files.split_whitespace().next())
+        .map(|file| expand_path(file, &host_name, &username));
+    let host_verification = match host.strict_host_key_checking.as_deref() {
+        Some("no" | "off") => HostVerification::NoCheck,
+        Some("accept-new") => HostVerification::TrustOnFirstUse {
+            store: known_hosts
+                .unwrap_or_else(|| expand_path("~/.ssh/known_hosts", &host_name, &username)),
+        },
+        _ => HostVerification::KnownHosts { path: known_hosts },
+    };
+    Ok(ConnectionConfig {
+        host: (host_name, host.port.unwrap_or(22)),
+        username,
+        auth: ConnectionAuth::SSHKey {
+            path,
+            passphrase: None,
+        },
+        proxy_jump,
+        host_verification,
+        ..Default::default()
+    })
+}
+
+/// Build the connection config of the last hop of a `ProxyJump` specification (e.g., `gateway,admin@bastion:2222`), jumping over the previous hops
+fn jump_from_spec(content: &str, jumps: &str, depth: usize) -> Result<ConnectionConfig, Error> {
+    let (previous, last) = match jumps.rsplit_once(',') {
+        Some((previous, last)) => (Some(previous), last),
+        None => (None, jumps),
+    };
+    let (user, host, port) = parse_jump_hop(last);
+    let mut jump = connection_config_from_ssh_config(content, host, depth)?;
+    if let Some(user) = user {
+        jump.username = user.to_string();
+    }
+    if let Some(port) = port {
+        jump.host.1 = port;
+    }
+    if let Some(previous) = previous {
+        jump.proxy_jump = Some(Box::new(jump_from_spec(content, previous, depth + 1)?));
+    }
+    Ok(jump)
+}
+
+/// The first existing default identity file of OpenSSH (e.g., `~/.ssh/id_ed25519`)
+fn default_identity_file() -> Option<String> {
+    let ssh_dir = home_dir()?.join(".ssh");
+    ["id_ed25519", "id_ecdsa", "id_rsa"]
+        .iter()
+        .map(|name| ssh_dir.join(name))
+        .find(|path| path.exists())
+        .map(|path| path.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        connection_config_from_ssh_config, parse_jump_hop, resolve_ssh_config, SshConfigHost,
+    };
+    use crate::HostVerification;
+
+    #[test]
+    fn test_resolve_ssh_config() {
+        let content = r#"
+# Cluster login nodes
+Host hpc hpc-login
+    HostName login23-1.hpc.itc.rwth-aachen.de
+    User ab123456
+    IdentityFile ~/.ssh/id_hpc
+    ProxyJump gateway
+
+Host gateway
+    HostName=gw.example.com
+    Port 2222
+    User gw
+    IdentityFile ~/.ssh/id_gw
+    StrictHostKeyChecking accept-new
+    UserKnownHostsFile /etc/ssh/known_hosts_gw ~/.ssh/known_hosts2
+
+Host * !gateway
+    User fallback
+    Port 22
+    IdentityFile "~/.ssh/id_ed25519"
+"#;
+        assert_eq!(
+            resolve_ssh_config(content, "hpc"),
+            SshConfigHost {
+                host_name: Some("login23-1.hpc.itc.rwth-aachen.de".to_string()),
+                port: Some(22),
+                user: Some("ab123456".to_string()),
+                identity_files: vec!["~/.ssh/id_hpc".to_string(), "~/.ssh/id_ed25519".to_string()],
+                proxy_jump: Some("gateway".to_string()),
+                strict_host_key_checking: None,
+                user_known_hosts_file: None,
+            }
+        );
+        let gateway = resolve_ssh_config(content, "gateway");
+        assert_eq!(gateway.host_name.as_deref(), Some("gw.example.com"));
+        assert_eq!(
+            (gateway.port, gateway.user),
+            (Some(2222), Some("gw".to_string()))
+        );
+        assert_eq!(
+            parse_jump_hop("admin@gateway:2200"),
+            (Some("admin"), "gateway", Some(2200))
+        );
+        assert_eq!(parse_jump_hop("gateway"), (None, "gateway", None));
+
+        let cfg = connection_config_from_ssh_config(content, "hpc", 0).unwrap();
+        assert_eq!(
+            cfg.host,
+            ("login23-1.hpc.itc.rwth-aachen.de".to_string(), 22)
+        );
+        assert_eq!(
+            cfg.host_verification,
+            HostVerification::KnownHosts { path: None }
+        );
+        let jump = cfg.proxy_jump.unwrap();
+        assert_eq!(jump.host, ("gw.example.com".to_string(), 2222));
+        assert!(jump.proxy_jump.is_none());
+        assert_eq!(
+            jump.host_verification,
+            HostVerification::TrustOnFirstUse {
+                store: "/etc/ssh/known_hosts_gw".to_string()
+            }
+        );
+        let chained = content.replace("ProxyJump gateway", "ProxyJump admin@gateway:2200,bastion");
+        let cfg = connection_config_from_ssh_config(&chained, "hpc", 0).unwrap();
+        let bastion = cfg.proxy_jump.unwrap();
+        assert_eq!(
+            (bastion.host.0.as_str(), bastion.username.as_str()),
+            ("bastion", "fallback")
+        );
+        let gateway = bastion.proxy_jump.unwrap();
+        assert_eq!(gateway.host, ("gw.example.com".to_string(), 2200));
+        assert_eq!(gateway.username, "admin");
+    }
+}
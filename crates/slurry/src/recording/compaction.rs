@@ -0,0 +1,281 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Error;
+use chrono::{DateTime, Utc};
+use rayon::prelude::*;
+
+use super::{
+    integrity::{prune_manifests, MANIFEST_PREFIX},
+    latest_job_ids_file, list_recorded_job_ids, open_recording_file, parse_time_file_name,
+    profile::PROFILE_PREFIX,
+    recording_file_time, JobRecording, JsonDirStore,
+};
+
+/// Name of the consolidated file of a finished job (inside its job folder)
+pub const COMPACT_JOB_FILE: &str = "COMPACT.json";
+
+/// Name of the consolidated file of all merged job ID files, mapping each time to the IDs of all jobs present at that time
+pub const COMPACT_JOB_IDS_FILE: &str = "JOB_IDS.json";
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+/// Statistics of a [`compact_recording`] run
+pub struct CompactionStats {
+    /// Number of jobs which were compacted into a single file
+    pub compacted_jobs: usize,
+    /// Number of job ID files which were merged
+    pub merged_id_files: usize,
+    /// Number of removed files
+    pub removed_files: usize,
+}
+
+/// Compact the JSON recording directory at `path` (see [`JsonDirStore`])
+///
+/// All files of finished jobs (i.e., jobs not listed in the latest job ID file) are merged into a single [`COMPACT_JOB_FILE`]
+/// per job folder, containing the initial row and all ordered changes (see [`JobRecording`]).
+/// All job ID files except the latest one are merged into [`COMPACT_JOB_IDS_FILE`].
+/// Running jobs and the latest job ID file are kept as is, so that recordings can be resumed and compacted again later.
+///
+/// Compacted jobs can still be loaded using [`JobRecording::load_from_dir`].
+/// Compacted files are compressed if the original files were compressed.
+/// Removed files are also removed from all manifests (see [`verify_recording`](super::verify_recording)).
+pub fn compact_recording(path: &Path) -> Result<CompactionStats, Error> {
+    let latest_ids_file = latest_job_ids_file(path)?;
+    let active_ids: HashSet<String> = match &latest_ids_file {
+        Some((_, file)) => serde_json::from_reader(open_recording_file(file)?)?,
+        None => HashSet::new(),
+    };
+    let finished_ids: Vec<String> = list_recorded_job_ids(path)?
+        .into_iter()
+        .filter(|job_id| !active_ids.contains(job_id))
+        .collect();
+    let job_stats: Vec<Option<usize>> = finished_ids
+        .par_iter()
+        .map(|job_id| {
+            compact_job(path, job_id)
+                .inspect_err(|e| eprintln!("Failed to compact job {job_id}: {e:?}"))
+                .ok()
+                .flatten()
+        })
+        .collect();
+    let mut stats = CompactionStats::default();
+    for removed in job_stats.into_iter().flatten() {
+        stats.compacted_jobs += 1;
+        stats.removed_files += removed;
+    }
+
+    // Merge all job ID files, except the latest one
+    let mut id_files: Vec<(DateTime<Utc>, PathBuf)> = Vec::new();
+    for entry in std::fs::read_dir(path)? {
+        let file_path = entry?.path();
+        let Some(file_name) = file_path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        if !is_job_ids_file(&file_path, file_name) {
+            continue;
+        }
+        let time = parse_time_file_name(recording_file_time(file_name))?;
+        if latest_ids_file.as_ref().is_none_or(|(t, _)| *t != time) {
+            id_files.push((time, file_path));
+        }
+    }
+    if id_files.is_empty() {
+        prune_manifests(path)?;
+        return Ok(stats);
+    }
+    let mut job_ids_per_time = load_job_ids_per_time(path)?;
+    let mut compressed = false;
+    for (time, file_path) in &id_files {
+        let ids: HashSet<String> = serde_json::from_reader(open_recording_file(file_path)?)?;
+        job_ids_per_time.insert(*time, ids);
+        compressed |= is_compressed(file_path);
+    }
+    write_compact(path, COMPACT_JOB_IDS_FILE, &job_ids_per_time, compressed)?;
+    for (_, file_path) in &id_files {
+        std::fs::remove_file(file_path)?;
+    }
+    stats.merged_id_files = id_files.len();
+    stats.removed_files += id_files.len();
+    prune_manifests(path)?;
+    Ok(stats)
+}
+
+/// Load the merged job ID files of a compacted recording at `path` (see [`compact_recording`])
+///
+/// Returns an empty map if the recording was not compacted yet.
+pub fn load_job_ids_per_time(
+    path: &Path,
+) -> Result<BTreeMap<DateTime<Utc>, HashSet<String>>, Error> {
+    for file_name in [
+        COMPACT_JOB_IDS_FILE.to_string(),
+        format!("{COMPACT_JOB_IDS_FILE}.zst"),
+    ] {
+        let file_path = path.join(file_name);
+        if file_path.exists() {
+            return Ok(serde_json::from_reader(open_recording_file(&file_path)?)?);
+        }
+    }
+    Ok(BTreeMap::new())
+}
+
+/// If the file at `file_path` (named `file_name`) is a top-level job ID file (i.e., `<time>.json`)
+pub(crate) fn is_job_ids_file(file_path: &Path, file_name: &str) -> bool {
+    file_path.is_file()
+        && file_name.contains(".json")
+        && !file_name.starts_with("NODES-")
+        && !file_name.starts_with(MANIFEST_PREFIX)
+        && !file_name.starts_with(PROFILE_PREFIX)
+        && !file_name.starts_with(COMPACT_JOB_IDS_FILE)
+}
+
+/// Compact a single finished job, returning the number of removed files (or `None` if the job was already compacted)
+fn compact_job(path: &Path, job_id: &str) -> Result<Option<usize>, Error> {
+    let job_path = path.join(job_id);
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(&job_path)? {
+        let file_path = entry?.path();
+        let is_json = file_path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.contains(".json"));
+        if is_json {
+            files.push(file_path);
+        }
+    }
+    let is_compacted = |f: &PathBuf| {
+        f.file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.starts_with(COMPACT_JOB_FILE))
+    };
+    if files.iter().all(is_compacted) {
+        return Ok(None);
+    }
+    let job = JobRecording::load_from_dir(path, job_id)?;
+    let compressed = files.iter().any(|f| is_compressed(f));
+    write_compact(&job_path, COMPACT_JOB_FILE, &job, compressed)?;
+    let mut removed = 0;
+    for file in files.iter().filter(|f| !is_compacted(f)) {
+        std::fs::remove_file(file)?;
+        removed += 1;
+    }
+    // A previous compacted file might have been replaced by a (differently compressed) new one
+    let new_file = compact_file_path(&job_path, COMPACT_JOB_FILE, compressed);
+    for file in files.iter().filter(|f| is_compacted(f) && **f != new_file) {
+        std::fs::remove_file(file)?;
+    }
+    Ok(Some(removed))
+}
+
+fn is_compressed(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "zst")
+}
+
+fn compact_file_path(dir: &Path, file_name: &str, compressed: bool) -> PathBuf {
+    if compressed {
+        dir.join(format!("{file_name}.zst"))
+    } else {
+        dir.join(file_name)
+    }
+}
+
+/// Write a compacted file to `dir`, first writing to a temporary file so that an interrupted compaction never leaves a truncated file behind
+fn write_compact<T: serde::Serialize + ?Sized>(
+    dir: &Path,
+    file_name: &str,
+    value: &T,
+    compressed: bool,
+) -> Result<(), Error> {
+    let store = JsonDirStore::new(dir);
+    #[cfg(feature = "zstd")]
+    let store = if compressed {
+        store.with_zstd(0)
+    } else {
+        store
+    };
+    // Without zstd support, compressed recordings can not be read in the first place
+    #[cfg(not(feature = "zstd"))]
+    let _ = compressed;
+    let compressed = store.zstd_level.is_some();
+    // Not named `*.json*`, so that it is ignored when loading the recording
+    let tmp_name = file_name.replace(".json", ".partial");
+    store.write_json(dir.join(&tmp_name), value)?;
+    std::fs::rename(
+        compact_file_path(dir, &tmp_name, compressed),
+        compact_file_path(dir, file_name, compressed),
+    )?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use chrono::{TimeZone, Utc};
+    use structdiff::StructDiff;
+
+    use super::{compact_recording, load_job_ids_per_time, COMPACT_JOB_FILE};
+    use crate::{
+        data_extraction::squeue::test_row,
+        recording::{JobRecording, JsonDirStore, RecordingState, RecordingStore},
+        JobState,
+    };
+
+    #[test]
+    fn test_compact_recording() {
+        let dir = std::env::temp_dir().join(format!("slurry-compact-{}", std::process::id()));
+        let store = JsonDirStore::new(&dir);
+        let time = Utc.with_ymd_and_hms(2025, 1, 14, 9, 23, 45).unwrap();
+        let finished = test_row(time);
+        let mut running = test_row(time);
+        running.job_id = "49848562".to_string();
+        let mut completed = finished.clone();
+        completed.state = JobState::COMPLETED;
+
+        store
+            .record_job_ids(&time, &HashSet::from([finished.job_id.clone()]))
+            .unwrap();
+        store.record_new_job(&time, &finished).unwrap();
+        for i in 1..=3 {
+            let later = time + chrono::Duration::seconds(5 * i);
+            let mut changed = finished.clone();
+            changed.priority = i as f64;
+            store
+                .record_job_delta(&later, &finished.job_id, &finished.diff(&changed))
+                .unwrap();
+        }
+        let end = time + chrono::Duration::seconds(20);
+        store
+            .record_job_delta(&end, &finished.job_id, &finished.diff(&completed))
+            .unwrap();
+        store
+            .record_job_ids(&end, &HashSet::from([running.job_id.clone()]))
+            .unwrap();
+        store.record_new_job(&end, &running).unwrap();
+        let before = JobRecording::load_from_dir(&dir, &finished.job_id).unwrap();
+
+        let stats = compact_recording(&dir).unwrap();
+        assert_eq!(stats.compacted_jobs, 1);
+        assert_eq!(stats.merged_id_files, 1);
+        assert_eq!(stats.removed_files, 6);
+        let files: Vec<_> = std::fs::read_dir(dir.join(&finished.job_id))
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(files, vec![COMPACT_JOB_FILE]);
+
+        let after = JobRecording::load_from_dir(&dir, &finished.job_id).unwrap();
+        assert_eq!(after.initial_time, before.initial_time);
+        assert_eq!(after.deltas.len(), 4);
+        assert_eq!(after.latest_row().state, JobState::COMPLETED);
+        assert_eq!(load_job_ids_per_time(&dir).unwrap()[&time].len(), 1);
+
+        // Running jobs are kept, so that the recording can be resumed
+        let state = RecordingState::load_from(&dir).unwrap();
+        assert!(state.known_jobs.contains_key(&running.job_id));
+        assert_eq!(state.all_ids.len(), 2);
+        assert_eq!(compact_recording(&dir).unwrap().compacted_jobs, 0);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
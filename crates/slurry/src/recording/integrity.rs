@@ -0,0 +1,206 @@
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Error;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{open_recording_file, time_file_name};
+
+/// Prefix of manifest files (i.e., `MANIFEST-<time>.json`)
+pub const MANIFEST_PREFIX: &str = "MANIFEST-";
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+/// Checksums of all files written for a single snapshot of a JSON recording (see [`JsonDirStore::with_manifest`](super::JsonDirStore::with_manifest))
+pub struct RecordingManifest {
+    /// Time of the snapshot
+    pub time: Option<DateTime<Utc>>,
+    /// CRC-32 checksums (as hex strings) of all written files, by their path relative to the recording directory
+    pub files: BTreeMap<String, String>,
+}
+
+impl RecordingManifest {
+    /// File name of this manifest
+    pub fn file_name(&self) -> String {
+        let time = self.time.unwrap_or_default();
+        format!("{MANIFEST_PREFIX}{}.json", time_file_name(&time))
+    }
+}
+
+/// Compute the CRC-32 (IEEE) checksum of `bytes`, formatted as hex string
+pub(crate) fn checksum(bytes: &[u8]) -> String {
+    let mut crc: u32 = !0;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    format!("{:08x}", !crc)
+}
+
+/// Remove all files which no longer exist (e.g., after [`compact_recording`](super::compact_recording)) from the manifests in `path`
+///
+/// Manifests without any remaining files are removed.
+pub(crate) fn prune_manifests(path: &Path) -> Result<(), Error> {
+    for entry in std::fs::read_dir(path)? {
+        let manifest_path = entry?.path();
+        let is_manifest = manifest_path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.starts_with(MANIFEST_PREFIX));
+        if !is_manifest {
+            continue;
+        }
+        let Ok(mut manifest) =
+            serde_json::from_reader::<_, RecordingManifest>(open_recording_file(&manifest_path)?)
+        else {
+            // Corrupted manifests are reported by `verify_recording`
+            continue;
+        };
+        let len = manifest.files.len();
+        manifest.files.retain(|file, _| path.join(file).exists());
+        if manifest.files.is_empty() {
+            std::fs::remove_file(&manifest_path)?;
+        } else if manifest.files.len() != len {
+            std::fs::write(&manifest_path, serde_json::to_vec(&manifest)?)?;
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Default)]
+/// Result of verifying a JSON recording using [`verify_recording`]
+pub struct RecordingVerification {
+    /// Number of checked files
+    pub checked_files: usize,
+    /// Files which can not be parsed (e.g., because they were truncated), along with the parse error
+    pub corrupt_files: Vec<(PathBuf, String)>,
+    /// Files which are listed in a manifest, but do not exist
+    pub missing_files: Vec<PathBuf>,
+    /// Files whose checksum does not match the checksum listed in a manifest
+    pub checksum_mismatches: Vec<PathBuf>,
+}
+
+impl RecordingVerification {
+    /// If no issues were found
+    pub fn is_ok(&self) -> bool {
+        self.corrupt_files.is_empty()
+            && self.missing_files.is_empty()
+            && self.checksum_mismatches.is_empty()
+    }
+}
+
+/// Verify the integrity of the JSON recording directory at `path` (see [`JsonDirStore`](super::JsonDirStore))
+///
+/// All recording files are checked to contain valid JSON, detecting files truncated by an interrupted write (e.g., when a recording loop is killed).
+/// Additionally, if manifests were written, all listed files are checked to exist and match their checksum.
+pub fn verify_recording(path: &Path) -> Result<RecordingVerification, Error> {
+    let mut report = RecordingVerification::default();
+    let mut manifests = Vec::new();
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(path)? {
+        let entry_path = entry?.path();
+        if entry_path.is_dir() {
+            for job_entry in std::fs::read_dir(&entry_path)? {
+                files.push(job_entry?.path());
+            }
+        } else if entry_path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.starts_with(MANIFEST_PREFIX))
+        {
+            manifests.push(entry_path);
+        } else {
+            files.push(entry_path);
+        }
+    }
+    for file in files {
+        let is_json = file
+            .file_name()
+            .and_then(|n| n.to_str())
+            .is_some_and(|n| n.contains(".json"));
+        if !is_json {
+            continue;
+        }
+        report.checked_files += 1;
+        let parsed = open_recording_file(&file).and_then(|reader| {
+            serde_json::from_reader::<_, serde::de::IgnoredAny>(reader)?;
+            Ok(())
+        });
+        if let Err(err) = parsed {
+            report.corrupt_files.push((file, err.to_string()));
+        }
+    }
+    for manifest_path in manifests {
+        let manifest: RecordingManifest =
+            match serde_json::from_reader(open_recording_file(&manifest_path)?) {
+                Ok(manifest) => manifest,
+                Err(err) => {
+                    report.corrupt_files.push((manifest_path, err.to_string()));
+                    continue;
+                }
+            };
+        for (file_name, expected) in &manifest.files {
+            let file = path.join(file_name);
+            match std::fs::read(&file) {
+                Ok(bytes) if checksum(&bytes) == *expected => {}
+                Ok(_) => report.checksum_mismatches.push(file),
+                Err(_) => report.missing_files.push(file),
+            }
+        }
+    }
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use chrono::{TimeZone, Utc};
+
+    use super::{checksum, verify_recording};
+    use crate::{
+        data_extraction::squeue::test_row,
+        recording::{JsonDirStore, RecordingStore},
+    };
+
+    #[test]
+    fn test_verify_recording() {
+        assert_eq!(checksum(b"123456789"), "cbf43926");
+
+        let dir = std::env::temp_dir().join(format!("slurry-verify-{}", std::process::id()));
+        let store = JsonDirStore::new(&dir).with_manifest(true);
+        let time = Utc.with_ymd_and_hms(2025, 1, 14, 9, 23, 45).unwrap();
+        let row = test_row(time);
+        store
+            .record_job_ids(&time, &HashSet::from([row.job_id.clone()]))
+            .unwrap();
+        store.record_new_job(&time, &row).unwrap();
+        store.flush().unwrap();
+        let report = verify_recording(&dir).unwrap();
+        assert!(report.is_ok());
+        assert_eq!(report.checked_files, 2);
+
+        // Simulate a write interrupted by killing the recording loop
+        let job_dir = dir.join(&row.job_id);
+        let job_file = std::fs::read_dir(&job_dir)
+            .unwrap()
+            .next()
+            .unwrap()
+            .unwrap();
+        let content = std::fs::read(job_file.path()).unwrap();
+        std::fs::write(job_file.path(), &content[..content.len() / 2]).unwrap();
+        let report = verify_recording(&dir).unwrap();
+        assert!(!report.is_ok());
+        assert_eq!(report.corrupt_files.len(), 1);
+        assert_eq!(report.checksum_mismatches, vec![job_file.path()]);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
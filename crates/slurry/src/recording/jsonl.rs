@@ -0,0 +1,245 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use anyhow::Error;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::{
+    open_recording_file, recorded_row, JobRecording, RecordedNodeStates, RecordingStore,
+    SqueueRowDiff,
+};
+use crate::data_extraction::{sinfo::SinfoNodeRow, squeue::SqueueRow};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+/// A single line of a JSONL recording
+pub enum JsonlRecord {
+    /// Initial row of a newly discovered job
+    Initial {
+        /// Time at which the job was discovered
+        time: DateTime<Utc>,
+        /// ID of the job
+        job_id: String,
+        /// The initial `squeue` row of the job
+        payload: Box<SqueueRow>,
+    },
+    /// Changes of a known job
+    Delta {
+        /// Time at which the changes were detected
+        time: DateTime<Utc>,
+        /// ID of the job
+        job_id: String,
+        /// The changed fields of the job
+        payload: Vec<SqueueRowDiff>,
+    },
+    /// Nodes which are new or changed their state
+    Nodes {
+        /// Time at which the changes were detected
+        time: DateTime<Utc>,
+        /// The new or changed nodes
+        payload: Vec<SinfoNodeRow>,
+    },
+}
+
+impl JsonlRecord {
+    /// The ID of the job this record belongs to (if any)
+    pub fn job_id(&self) -> Option<&str> {
+        match self {
+            JsonlRecord::Initial { job_id, .. } | JsonlRecord::Delta { job_id, .. } => Some(job_id),
+            JsonlRecord::Nodes { .. } => None,
+        }
+    }
+
+    /// The time of this record
+    pub fn time(&self) -> &DateTime<Utc> {
+        match self {
+            JsonlRecord::Initial { time, .. }
+            | JsonlRecord::Delta { time, .. }
+            | JsonlRecord::Nodes { time, .. } => time,
+        }
+    }
+}
+
+#[derive(Debug)]
+/// Recording store appending all data to a single JSONL file
+///
+/// Each line is a [`JsonlRecord`], i.e., `{"kind": "initial" | "delta", "time": ..., "job_id": ..., "payload": ...}`
+/// (or `{"kind": "nodes", "time": ..., "payload": ...}` for node state changes).
+/// Compared to [`JsonDirStore`](super::JsonDirStore), this avoids creating many small files
+/// and allows to easily tail or replay a recording.
+/// The IDs of all present jobs per `squeue` execution are not recorded.
+pub struct JsonlStore {
+    path: PathBuf,
+    writer: Mutex<BufWriter<File>>,
+    raw_lines: bool,
+}
+
+impl JsonlStore {
+    /// Open the JSONL file at `path` for appending (creating it, if it does not exist yet)
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref().to_path_buf();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        Ok(Self {
+            path,
+            writer: Mutex::new(BufWriter::new(file)),
+            raw_lines: false,
+        })
+    }
+
+    /// Also save the original `squeue` output line in the initial record of each job (see [`SqueueRow::raw_line`])
+    pub fn with_raw_lines(mut self, raw_lines: bool) -> Self {
+        self.raw_lines = raw_lines;
+        self
+    }
+
+    /// The path of the JSONL file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn append(&self, record: &JsonlRecord) -> Result<(), Error> {
+        // Serialize first, so that concurrent writers never interleave partial lines
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+        self.writer.lock().unwrap().write_all(&line)?;
+        Ok(())
+    }
+}
+
+impl RecordingStore for JsonlStore {
+    fn record_job_ids(
+        &self,
+        _time: &DateTime<Utc>,
+        _job_ids: &HashSet<String>,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn record_new_job(&self, time: &DateTime<Utc>, row: &SqueueRow) -> Result<(), Error> {
+        self.append(&JsonlRecord::Initial {
+            time: *time,
+            job_id: row.job_id.clone(),
+            payload: Box::new(recorded_row(row, self.raw_lines).into_owned()),
+        })
+    }
+
+    fn record_job_delta(
+        &self,
+        time: &DateTime<Utc>,
+        job_id: &str,
+        diff: &[SqueueRowDiff],
+    ) -> Result<(), Error> {
+        self.append(&JsonlRecord::Delta {
+            time: *time,
+            job_id: job_id.to_string(),
+            payload: diff.to_vec(),
+        })
+    }
+
+    fn record_node_states(
+        &self,
+        time: &DateTime<Utc>,
+        nodes: &[SinfoNodeRow],
+    ) -> Result<(), Error> {
+        self.append(&JsonlRecord::Nodes {
+            time: *time,
+            payload: nodes.to_vec(),
+        })
+    }
+
+    fn flush(&self) -> Result<(), Error> {
+        self.writer.lock().unwrap().flush()?;
+        Ok(())
+    }
+
+    fn load_jobs(&self) -> Result<Vec<JobRecording>, Error> {
+        self.flush()?;
+        read_jsonl_recording(&self.path)
+    }
+
+    fn load_node_states(&self) -> Result<Vec<RecordedNodeStates>, Error> {
+        self.flush()?;
+        read_jsonl_node_states(&self.path)
+    }
+}
+
+/// Read all records of the JSONL recording at `path`
+///
+/// Lines which can not be parsed (e.g., a truncated last line) are skipped.
+pub fn read_jsonl_records(path: &Path) -> Result<impl Iterator<Item = JsonlRecord> + use<>, Error> {
+    let reader = BufReader::new(open_recording_file(path)?);
+    Ok(reader
+        .lines()
+        .map_while(Result::ok)
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str(&line) {
+            Ok(record) => Some(record),
+            Err(err) => {
+                println!("[!] {:?} for {:?}", err, &line);
+                None
+            }
+        }))
+}
+
+/// Read all recorded node states of the JSONL recording at `path`, ordered by time
+pub fn read_jsonl_node_states(path: &Path) -> Result<Vec<RecordedNodeStates>, Error> {
+    let mut node_states: Vec<_> = read_jsonl_records(path)?
+        .filter_map(|record| match record {
+            JsonlRecord::Nodes { time, payload } => Some((time, payload)),
+            _ => None,
+        })
+        .collect();
+    node_states.sort_by_key(|(time, _)| *time);
+    Ok(node_states)
+}
+
+/// Read the JSONL recording at `path`, grouping all records by job
+pub fn read_jsonl_recording(path: &Path) -> Result<Vec<JobRecording>, Error> {
+    let mut jobs: HashMap<String, JobRecording> = HashMap::new();
+    for record in read_jsonl_records(path)? {
+        match record {
+            JsonlRecord::Initial {
+                time,
+                job_id,
+                payload,
+            } => {
+                if jobs.contains_key(&job_id) {
+                    eprintln!("Job {job_id} was recorded as new multiple times!");
+                    continue;
+                }
+                jobs.insert(
+                    job_id.clone(),
+                    JobRecording {
+                        job_id,
+                        initial_time: time,
+                        initial: *payload,
+                        deltas: Vec::new(),
+                    },
+                );
+            }
+            JsonlRecord::Delta {
+                time,
+                job_id,
+                payload,
+            } => match jobs.get_mut(&job_id) {
+                Some(job) => job.deltas.push((time, payload)),
+                None => eprintln!("Delta for unknown job {job_id}!"),
+            },
+            JsonlRecord::Nodes { .. } => {}
+        }
+    }
+    let mut jobs: Vec<_> = jobs.into_values().collect();
+    for job in &mut jobs {
+        job.deltas.sort_by_key(|(time, _)| *time);
+    }
+    Ok(jobs)
+}
@@ -0,0 +1,159 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    mem::discriminant,
+};
+
+use chrono::{DateTime, Utc};
+use rayon::prelude::*;
+use structdiff::StructDiff;
+
+use super::{JobRecording, SqueueRowDiff};
+use crate::data_extraction::squeue::SqueueRow;
+
+/// Merge recordings of the same jobs (e.g., recorded on multiple login nodes, or by restarted recording loops), deduplicating jobs by their ID
+///
+/// The changes of all recordings of a job are replayed ordered by their time, starting from the earliest initial row.
+/// Changes already reflected in the merged job (i.e., also recorded by another recording) are dropped.
+/// A change of a field is only applied if the merged job agrees with the recording on the previous value of that field,
+/// so that a recording lagging behind does not revert changes which were already recorded by another one.
+/// Initial rows of later recordings (e.g., after a restart) are applied as a whole, as they reflect the complete state of the job at that time.
+///
+/// Returns the merged jobs, ordered by their ID.
+pub fn merge_job_recordings(jobs: impl IntoIterator<Item = JobRecording>) -> Vec<JobRecording> {
+    let mut by_id: BTreeMap<String, Vec<JobRecording>> = BTreeMap::new();
+    for job in jobs {
+        by_id.entry(job.job_id.clone()).or_default().push(job);
+    }
+    by_id
+        .into_par_iter()
+        .map(|(_, recordings)| merge_recordings_of_job(recordings))
+        .collect()
+}
+
+/// A recorded change of a job
+enum Change {
+    /// Initial row of a later recording
+    Initial(SqueueRow),
+    /// Changes of a recording, with the recorded row before the changes
+    Delta {
+        before: SqueueRow,
+        delta: Vec<SqueueRowDiff>,
+    },
+}
+
+/// Merge all `recordings` of a single job (see [`merge_job_recordings`])
+fn merge_recordings_of_job(mut recordings: Vec<JobRecording>) -> JobRecording {
+    if recordings.len() == 1 {
+        return recordings.remove(0);
+    }
+    recordings.sort_by_key(|r| r.initial_time);
+    let mut changes: Vec<(DateTime<Utc>, Change)> = Vec::new();
+    let mut add_deltas = |initial: &SqueueRow, deltas: Vec<(DateTime<Utc>, Vec<SqueueRowDiff>)>| {
+        let mut row = initial.clone();
+        for (time, delta) in deltas {
+            let before = row.clone();
+            row.apply_mut(delta.clone());
+            changes.push((time, Change::Delta { before, delta }));
+        }
+    };
+    let mut recordings = recordings.into_iter();
+    let mut merged = recordings.next().expect("At least two recordings");
+    add_deltas(&merged.initial, std::mem::take(&mut merged.deltas));
+    let mut initial_rows = Vec::new();
+    for recording in recordings {
+        add_deltas(&recording.initial, recording.deltas);
+        initial_rows.push((recording.initial_time, Change::Initial(recording.initial)));
+    }
+    changes.extend(initial_rows);
+    changes.sort_by_key(|(time, _)| *time);
+
+    let mut current = merged.initial.clone();
+    for (time, change) in changes {
+        let target = match change {
+            Change::Initial(row) => row,
+            Change::Delta { before, delta } => {
+                // Fields on which the recording disagrees with the merged job (e.g., as it is lagging behind)
+                let disagreeing: HashSet<_> =
+                    current.diff(&before).iter().map(discriminant).collect();
+                let mut target = current.clone();
+                target.apply_mut(
+                    delta
+                        .into_iter()
+                        .filter(|d| !disagreeing.contains(&discriminant(d)))
+                        .collect(),
+                );
+                target
+            }
+        };
+        let actual = current.diff(&target);
+        if !actual.is_empty() {
+            current.apply_mut(actual.clone());
+            merged.deltas.push((time, actual));
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone, Utc};
+    use structdiff::StructDiff;
+
+    use super::merge_job_recordings;
+    use crate::{
+        data_extraction::squeue::{test_row, SqueueRow},
+        recording::JobRecording,
+        JobState,
+    };
+
+    #[test]
+    fn test_merge_job_recordings() {
+        let time = Utc.with_ymd_and_hms(2025, 1, 14, 9, 23, 45).unwrap();
+        let pending = test_row(time);
+        let mut running = pending.clone();
+        running.state = JobState::RUNNING;
+        let mut completed = running.clone();
+        completed.state = JobState::COMPLETED;
+        let recording = |offset: i64, rows: &[&SqueueRow]| {
+            let at = |i: i64| time + Duration::seconds(offset + 10 * i);
+            JobRecording {
+                job_id: pending.job_id.clone(),
+                initial_time: at(0),
+                initial: rows[0].clone(),
+                deltas: rows
+                    .windows(2)
+                    .enumerate()
+                    .map(|(i, w)| (at(i as i64 + 1), w[0].diff(w[1])))
+                    .collect(),
+            }
+        };
+        let first = recording(0, &[&pending, &running, &completed]);
+        // The second recording detects all changes 11 seconds later
+        let mut second = recording(1, &[&pending, &running, &completed]);
+        for (time, _) in &mut second.deltas {
+            *time += Duration::seconds(10);
+        }
+        let mut other = first.clone();
+        other.job_id = "49848562".to_string();
+        let merged = merge_job_recordings([second, first.clone(), other]);
+        assert_eq!(merged.len(), 2);
+        assert_eq!(merged[0].initial_time, time);
+        let states: Vec<_> = merged[0]
+            .deltas
+            .iter()
+            .map(|(t, _)| (*t - time).num_seconds())
+            .collect();
+        assert_eq!(states, [10, 20]);
+        assert_eq!(merged[0].latest_row().state, JobState::COMPLETED);
+
+        // A restarted recording only observed the completion
+        let restarted = recording(60, &[&completed]);
+        let merged = merge_job_recordings([first.clone(), restarted]);
+        assert_eq!(merged[0].deltas.len(), 2);
+        let mut requeued = recording(60, &[&pending]);
+        requeued.initial.reason = "BeginTime".to_string();
+        let merged = merge_job_recordings([first, requeued]);
+        assert_eq!(merged[0].deltas.len(), 3);
+        assert_eq!(merged[0].latest_row().state, JobState::PENDING);
+    }
+}
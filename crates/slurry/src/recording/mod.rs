@@ -0,0 +1,697 @@
+use std::{
+    borrow::Cow,
+    collections::{HashMap, HashSet},
+    fs::{create_dir_all, File},
+    io::{BufReader, BufWriter, Read},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use anyhow::Error;
+use chrono::{DateTime, Utc};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use structdiff::StructDiff;
+
+use crate::data_extraction::{sinfo::SinfoNodeRow, squeue::SqueueRow};
+
+/// Module for recording `squeue` data into a single append-only JSONL file
+pub mod jsonl;
+
+#[cfg(feature = "parquet")]
+/// Module for recording `squeue` data as Parquet files
+pub mod parquet;
+
+/// Module for compacting JSON recording directories
+pub mod compaction;
+
+/// Module for verifying the integrity of JSON recording directories
+pub mod integrity;
+
+/// Module for storing resource usage profiles of jobs in JSON recording directories
+pub mod profile;
+
+/// Module for merging multiple recordings of the same jobs
+pub mod merge;
+
+pub use compaction::{compact_recording, CompactionStats};
+pub use integrity::{verify_recording, RecordingManifest, RecordingVerification};
+pub use jsonl::{read_jsonl_recording, JsonlRecord, JsonlStore};
+pub use merge::merge_job_recordings;
+pub use profile::{JobProfile, JobProfileSample};
+
+#[cfg(feature = "parquet")]
+pub use self::parquet::ParquetStore;
+
+/// A single changed field of a [`SqueueRow`], as computed between two `squeue` executions
+pub type SqueueRowDiff = <SqueueRow as StructDiff>::Diff;
+
+/// Nodes which are new or changed their state, as recorded at the given time
+pub type RecordedNodeStates = (DateTime<Utc>, Vec<SinfoNodeRow>);
+
+/// Storage backend for recorded `squeue` data (e.g., used by [`squeue_diff_with_store`](crate::data_extraction::squeue::squeue_diff_with_store))
+///
+/// A recording consists of the IDs of all jobs present at each point in time,
+/// the initial row of each newly discovered job, and the changes (deltas) of known jobs.
+/// Additionally, node state changes can be recorded (see [`sinfo_diff_with_store`](crate::data_extraction::sinfo::sinfo_diff_with_store)).
+///
+/// Stores which can also be read (e.g., for extracting event data using [`extract_ocel_from_store`](crate::event_data_extraction::extract_ocel_from_store))
+/// implement [`RecordingStore::load_jobs`] and [`RecordingStore::load_node_states`].
+pub trait RecordingStore: Send + Sync {
+    /// Record the IDs of all jobs present at `time`
+    fn record_job_ids(&self, time: &DateTime<Utc>, job_ids: &HashSet<String>) -> Result<(), Error>;
+
+    /// Record the initial row of a job, which was discovered at `time`
+    fn record_new_job(&self, time: &DateTime<Utc>, row: &SqueueRow) -> Result<(), Error>;
+
+    /// Record the changes of a known job, which were detected at `time`
+    fn record_job_delta(
+        &self,
+        time: &DateTime<Utc>,
+        job_id: &str,
+        diff: &[SqueueRowDiff],
+    ) -> Result<(), Error>;
+
+    /// Record the nodes which are new or changed their state at `time`
+    fn record_node_states(&self, time: &DateTime<Utc>, nodes: &[SinfoNodeRow])
+        -> Result<(), Error>;
+
+    /// Persist all buffered data
+    ///
+    /// Called after all data of a single `squeue` execution was recorded.
+    fn flush(&self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    /// Load all recorded jobs
+    ///
+    /// Returns an error for stores which do not support reading (the default).
+    fn load_jobs(&self) -> Result<Vec<JobRecording>, Error> {
+        Err(Error::msg("Reading jobs is not supported by this store."))
+    }
+
+    /// Load all recorded node states, ordered by time
+    ///
+    /// Returns an error for stores which do not support reading (the default).
+    fn load_node_states(&self) -> Result<Vec<RecordedNodeStates>, Error> {
+        Err(Error::msg(
+            "Reading node states is not supported by this store.",
+        ))
+    }
+}
+
+#[derive(Debug)]
+/// Recording store wrapper counting the number of recorded changes (i.e., new jobs and job deltas)
+///
+/// E.g., used to adapt the polling interval of recording loops (see [`AdaptiveInterval`](crate::misc::polling::AdaptiveInterval)).
+pub struct CountingStore<'a, S: RecordingStore + ?Sized> {
+    inner: &'a S,
+    changes: AtomicUsize,
+}
+
+impl<'a, S: RecordingStore + ?Sized> CountingStore<'a, S> {
+    /// Wrap the given `inner` store
+    pub fn new(inner: &'a S) -> Self {
+        Self {
+            inner,
+            changes: AtomicUsize::new(0),
+        }
+    }
+
+    /// Get the number of changes recorded since the last call, resetting the count
+    pub fn take_changes(&self) -> usize {
+        self.changes.swap(0, Ordering::Relaxed)
+    }
+}
+
+impl<S: RecordingStore + ?Sized> RecordingStore for CountingStore<'_, S> {
+    fn record_job_ids(&self, time: &DateTime<Utc>, job_ids: &HashSet<String>) -> Result<(), Error> {
+        self.inner.record_job_ids(time, job_ids)
+    }
+
+    fn record_new_job(&self, time: &DateTime<Utc>, row: &SqueueRow) -> Result<(), Error> {
+        self.changes.fetch_add(1, Ordering::Relaxed);
+        self.inner.record_new_job(time, row)
+    }
+
+    fn record_job_delta(
+        &self,
+        time: &DateTime<Utc>,
+        job_id: &str,
+        diff: &[SqueueRowDiff],
+    ) -> Result<(), Error> {
+        self.changes.fetch_add(1, Ordering::Relaxed);
+        self.inner.record_job_delta(time, job_id, diff)
+    }
+
+    fn record_node_states(
+        &self,
+        time: &DateTime<Utc>,
+        nodes: &[SinfoNodeRow],
+    ) -> Result<(), Error> {
+        self.inner.record_node_states(time, nodes)
+    }
+
+    fn flush(&self) -> Result<(), Error> {
+        self.inner.flush()
+    }
+
+    fn load_jobs(&self) -> Result<Vec<JobRecording>, Error> {
+        self.inner.load_jobs()
+    }
+
+    fn load_node_states(&self) -> Result<Vec<RecordedNodeStates>, Error> {
+        self.inner.load_node_states()
+    }
+}
+
+#[derive(Debug, Clone)]
+/// Recording store saving JSON files into a directory
+///
+/// This is the original recording format, consisting of
+/// - `<time>.json`: IDs of all jobs present at that time
+/// - `<job_id>/<time>.json`: Initial row of a job
+/// - `<job_id>/DELTA-<time>.json`: Changes of a job
+/// - `NODES-<time>.json`: Nodes which are new or changed their state
+/// - `PROFILE-<job_id>.json`: Sampled resource usage of a job (see [`JobProfile`])
+/// - `MANIFEST-<time>.json`: Checksums of all files written at that time (only if enabled, see [`JsonDirStore::with_manifest`])
+///
+/// If compression is enabled (see [`JsonDirStore::with_zstd`]), all files are additionally compressed using zstd
+/// and saved with an additional `.zst` extension. Use [`open_recording_file`] to read both variants.
+pub struct JsonDirStore {
+    path: PathBuf,
+    zstd_level: Option<i32>,
+    raw_lines: bool,
+    manifest: Option<Arc<Mutex<RecordingManifest>>>,
+}
+
+impl JsonDirStore {
+    /// Create a new JSON recording store in the directory at `path`
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            zstd_level: None,
+            raw_lines: false,
+            manifest: None,
+        }
+    }
+
+    /// Write a manifest with the checksums of all files written for each snapshot (on [`RecordingStore::flush`])
+    ///
+    /// This allows detecting corrupted or truncated files using [`verify_recording`].
+    pub fn with_manifest(mut self, manifest: bool) -> Self {
+        self.manifest = manifest.then(Default::default);
+        self
+    }
+
+    /// Also save the original `squeue` output line in the initial row of each job (see [`SqueueRow::raw_line`])
+    pub fn with_raw_lines(mut self, raw_lines: bool) -> Self {
+        self.raw_lines = raw_lines;
+        self
+    }
+
+    #[cfg(feature = "zstd")]
+    /// Compress all written files using zstd with the given compression `level` (`0` uses the default level)
+    pub fn with_zstd(mut self, level: i32) -> Self {
+        self.zstd_level = Some(level);
+        self
+    }
+
+    /// The directory of the recording
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Load all jobs with a folder in the recording directory, also returning the errors of all jobs failing to load
+    pub fn load_jobs_with_failures(&self) -> Result<(Vec<JobRecording>, Vec<String>), Error> {
+        let results: Vec<_> = list_recorded_job_ids(&self.path)?
+            .into_par_iter()
+            .map(|job_id| {
+                JobRecording::load_from_dir(&self.path, &job_id)
+                    .map_err(|e| format!("Failed to load job {job_id}: {e:?}"))
+            })
+            .collect();
+        let mut jobs = Vec::with_capacity(results.len());
+        let mut failures = Vec::new();
+        for res in results {
+            match res {
+                Ok(job) => jobs.push(job),
+                Err(failure) => failures.push(failure),
+            }
+        }
+        Ok((jobs, failures))
+    }
+
+    fn write_json<T: Serialize + ?Sized>(&self, path: PathBuf, value: &T) -> Result<(), Error> {
+        let json = serde_json::to_vec(value)?;
+        let (path, bytes) = match self.zstd_level {
+            #[cfg(feature = "zstd")]
+            Some(level) => {
+                let mut path = path.into_os_string();
+                path.push(".zst");
+                (
+                    PathBuf::from(path),
+                    zstd::encode_all(json.as_slice(), level)?,
+                )
+            }
+            _ => (path, json),
+        };
+        std::fs::write(&path, &bytes)?;
+        if let Some(manifest) = &self.manifest {
+            if let Ok(relative_path) = path.strip_prefix(&self.path) {
+                manifest.lock().unwrap().files.insert(
+                    relative_path.to_string_lossy().to_string(),
+                    integrity::checksum(&bytes),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Set the time of the current manifest (if enabled and not yet set)
+    fn start_manifest(&self, time: &DateTime<Utc>) {
+        if let Some(manifest) = &self.manifest {
+            manifest.lock().unwrap().time.get_or_insert(*time);
+        }
+    }
+}
+
+/// The `row` as it should be recorded, i.e., without its raw `squeue` line unless `raw_lines` is enabled
+pub(crate) fn recorded_row(row: &SqueueRow, raw_lines: bool) -> Cow<'_, SqueueRow> {
+    if raw_lines || row.raw_line.is_none() {
+        return Cow::Borrowed(row);
+    }
+    let mut row = row.clone();
+    row.raw_line = None;
+    Cow::Owned(row)
+}
+
+/// Open a file of a JSON recording for reading, transparently decompressing zstd-compressed (`.zst`) files
+pub fn open_recording_file(path: &Path) -> Result<Box<dyn Read>, Error> {
+    let file = File::open(path)?;
+    if path.extension().is_some_and(|ext| ext == "zst") {
+        #[cfg(feature = "zstd")]
+        return Ok(Box::new(zstd::Decoder::new(file)?));
+        #[cfg(not(feature = "zstd"))]
+        return Err(Error::msg(
+            "Reading compressed recordings requires the zstd feature.",
+        ));
+    }
+    Ok(Box::new(BufReader::new(file)))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// All recorded data of a single job: Its initial row and all subsequent changes
+pub struct JobRecording {
+    /// ID of the job
+    pub job_id: String,
+    /// Time at which the job was first recorded
+    pub initial_time: DateTime<Utc>,
+    /// Initial `squeue` row of the job
+    pub initial: SqueueRow,
+    /// Changes of the job, ordered by time
+    pub deltas: Vec<(DateTime<Utc>, Vec<SqueueRowDiff>)>,
+}
+
+impl JobRecording {
+    /// Load the recorded data of the job `job_id` from the JSON recording directory at `path` (see [`JsonDirStore`])
+    ///
+    /// Both compacted (see [`compact_recording`]) and regular job folders are supported.
+    pub fn load_from_dir(path: &Path, job_id: &str) -> Result<Self, Error> {
+        let mut initial = None;
+        let mut deltas = Vec::new();
+        for entry in std::fs::read_dir(path.join(job_id))? {
+            let file_path = entry?.path();
+            let Some(file_name) = file_path.file_name().and_then(|n| n.to_str()) else {
+                continue;
+            };
+            if !file_name.contains(".json") {
+                continue;
+            }
+            if file_name.starts_with(compaction::COMPACT_JOB_FILE) {
+                let job: JobRecording = serde_json::from_reader(open_recording_file(&file_path)?)?;
+                deltas.extend(job.deltas);
+                initial = match initial {
+                    Some((prev_time, prev_row)) if prev_time < job.initial_time => {
+                        Some((prev_time, prev_row))
+                    }
+                    _ => Some((job.initial_time, job.initial)),
+                };
+                continue;
+            }
+            let time = parse_time_file_name(recording_file_time(file_name))?;
+            if file_name.starts_with("DELTA-") {
+                let diff: Vec<SqueueRowDiff> =
+                    serde_json::from_reader(open_recording_file(&file_path)?)?;
+                deltas.push((time, diff));
+            } else {
+                let row: SqueueRow = serde_json::from_reader(open_recording_file(&file_path)?)?;
+                initial = match initial {
+                    // Keep the earliest row if a job was (unexpectedly) recorded as new multiple times
+                    Some((prev_time, prev_row)) if prev_time < time => Some((prev_time, prev_row)),
+                    _ => Some((time, row)),
+                };
+            }
+        }
+        let (initial_time, initial) = initial
+            .ok_or_else(|| Error::msg(format!("No initial data recorded for job {job_id}.")))?;
+        deltas.retain(|(time, _)| *time > initial_time);
+        deltas.sort_by_key(|(time, _)| *time);
+        Ok(Self {
+            job_id: job_id.to_string(),
+            initial_time,
+            initial,
+            deltas,
+        })
+    }
+
+    /// The latest known row of the job, i.e., the initial row with all changes applied
+    pub fn latest_row(&self) -> SqueueRow {
+        let mut row = self.initial.clone();
+        for (_, diff) in &self.deltas {
+            row.apply_mut(diff.clone());
+        }
+        row
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+/// State of a running recording, as needed by [`squeue_diff`](crate::data_extraction::squeue::squeue_diff)
+pub struct RecordingState {
+    /// Latest rows of all jobs present in the last `squeue` execution
+    pub known_jobs: HashMap<String, SqueueRow>,
+    /// IDs of all jobs recorded so far
+    pub all_ids: HashSet<String>,
+}
+
+impl RecordingState {
+    /// Reconstruct the state of the JSON recording directory at `path` (see [`JsonDirStore`]), e.g., to resume a recording after a restart
+    ///
+    /// The known jobs are the jobs listed in the latest job ID file, with all their recorded changes applied.
+    /// If `path` does not exist yet, an empty state is returned.
+    pub fn load_from(path: &Path) -> Result<Self, Error> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let latest_ids_file = latest_job_ids_file(path)?;
+        let all_ids = list_recorded_job_ids(path)?;
+        let Some((_, latest_ids_file)) = latest_ids_file else {
+            return Ok(Self {
+                known_jobs: HashMap::new(),
+                all_ids,
+            });
+        };
+        let latest_ids: HashSet<String> =
+            serde_json::from_reader(open_recording_file(&latest_ids_file)?)?;
+        let known_jobs = latest_ids
+            .into_par_iter()
+            .map(|job_id| {
+                let row = JobRecording::load_from_dir(path, &job_id)?.latest_row();
+                Ok((job_id, row))
+            })
+            .collect::<Result<_, Error>>()?;
+        Ok(Self {
+            known_jobs,
+            all_ids,
+        })
+    }
+}
+
+/// Find the latest job ID file (i.e., `<time>.json`) in the JSON recording directory at `path` (see [`JsonDirStore`])
+pub(crate) fn latest_job_ids_file(path: &Path) -> Result<Option<(DateTime<Utc>, PathBuf)>, Error> {
+    let mut latest_ids_file = None;
+    for entry in std::fs::read_dir(path)? {
+        let file_path = entry?.path();
+        let Some(file_name) = file_path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        if !compaction::is_job_ids_file(&file_path, file_name) {
+            continue;
+        }
+        let time = parse_time_file_name(recording_file_time(file_name))?;
+        if latest_ids_file.as_ref().is_none_or(|(t, _)| *t < time) {
+            latest_ids_file = Some((time, file_path));
+        }
+    }
+    Ok(latest_ids_file)
+}
+
+/// List the IDs of all jobs in the JSON recording directory at `path` (see [`JsonDirStore`])
+pub fn list_recorded_job_ids(path: &Path) -> Result<HashSet<String>, Error> {
+    let mut job_ids = HashSet::new();
+    for entry in std::fs::read_dir(path)? {
+        let entry = entry?;
+        if entry.file_type()?.is_dir() {
+            if let Some(job_id) = entry.file_name().to_str() {
+                job_ids.insert(job_id.to_string());
+            }
+        }
+    }
+    Ok(job_ids)
+}
+
+/// Parse a timestamp formatted for use in file names (see [`time_file_name`])
+pub(crate) fn parse_time_file_name(s: &str) -> Result<DateTime<Utc>, Error> {
+    Ok(DateTime::parse_from_rfc3339(&s.replace("_", ":"))?.to_utc())
+}
+
+/// Get the timestamp part of a recording file name (e.g., `DELTA-<time>.json.zst` or `<time>.json`)
+pub fn recording_file_time(file_name: &str) -> &str {
+    let name = file_name.strip_suffix(".zst").unwrap_or(file_name);
+    let name = name.strip_suffix(".json").unwrap_or(name);
+    name.strip_prefix("DELTA-").unwrap_or(name)
+}
+
+/// Format a timestamp for use in file names (i.e., RFC 3339 without colons)
+pub(crate) fn time_file_name(time: &DateTime<Utc>) -> String {
+    time.to_rfc3339().replace(":", "_")
+}
+
+impl RecordingStore for JsonDirStore {
+    fn record_job_ids(&self, time: &DateTime<Utc>, job_ids: &HashSet<String>) -> Result<(), Error> {
+        create_dir_all(&self.path)?;
+        self.start_manifest(time);
+        self.write_json(
+            self.path.join(format!("{}.json", time_file_name(time))),
+            job_ids,
+        )
+    }
+
+    fn record_new_job(&self, time: &DateTime<Utc>, row: &SqueueRow) -> Result<(), Error> {
+        let folder_path = self.path.join(&row.job_id);
+        create_dir_all(&folder_path)?;
+        self.start_manifest(time);
+        self.write_json(
+            folder_path.join(format!("{}.json", time_file_name(time))),
+            &recorded_row(row, self.raw_lines),
+        )
+    }
+
+    fn record_job_delta(
+        &self,
+        time: &DateTime<Utc>,
+        job_id: &str,
+        diff: &[SqueueRowDiff],
+    ) -> Result<(), Error> {
+        self.start_manifest(time);
+        self.write_json(
+            self.path
+                .join(job_id)
+                .join(format!("DELTA-{}.json", time_file_name(time))),
+            diff,
+        )
+    }
+
+    fn record_node_states(
+        &self,
+        time: &DateTime<Utc>,
+        nodes: &[SinfoNodeRow],
+    ) -> Result<(), Error> {
+        create_dir_all(&self.path)?;
+        self.start_manifest(time);
+        self.write_json(
+            self.path
+                .join(format!("NODES-{}.json", time_file_name(time))),
+            nodes,
+        )
+    }
+
+    fn flush(&self) -> Result<(), Error> {
+        let Some(manifest) = &self.manifest else {
+            return Ok(());
+        };
+        let manifest = std::mem::take(&mut *manifest.lock().unwrap());
+        if manifest.files.is_empty() {
+            return Ok(());
+        }
+        let file = File::create(self.path.join(manifest.file_name()))?;
+        serde_json::to_writer(BufWriter::new(file), &manifest)?;
+        Ok(())
+    }
+
+    /// Load all jobs with a folder in the recording directory
+    ///
+    /// Jobs failing to load are skipped (printing the error).
+    fn load_jobs(&self) -> Result<Vec<JobRecording>, Error> {
+        let (jobs, failures) = self.load_jobs_with_failures()?;
+        for failure in failures {
+            eprintln!("{failure}");
+        }
+        Ok(jobs)
+    }
+
+    fn load_node_states(&self) -> Result<Vec<RecordedNodeStates>, Error> {
+        let mut node_states = Vec::new();
+        for entry in std::fs::read_dir(&self.path)? {
+            let file_path = entry?.path();
+            let Some(name) = file_path
+                .file_name()
+                .and_then(|n| n.to_str())
+                .and_then(|n| n.strip_prefix("NODES-"))
+            else {
+                continue;
+            };
+            let time = parse_time_file_name(recording_file_time(name))?;
+            let nodes = serde_json::from_reader(open_recording_file(&file_path)?)?;
+            node_states.push((time, nodes));
+        }
+        node_states.sort_by_key(|(time, _)| *time);
+        Ok(node_states)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_time_file_name, recording_file_time, time_file_name};
+    use crate::data_extraction::squeue::test_row;
+
+    #[test]
+    fn test_recording_file_time() {
+        let time = "2025-01-14T09_23_45.123+00_00";
+        assert_eq!(recording_file_time(&format!("{time}.json")), time);
+        assert_eq!(recording_file_time(&format!("DELTA-{time}.json")), time);
+        assert_eq!(recording_file_time(&format!("DELTA-{time}.json.zst")), time);
+
+        let now = chrono::Utc::now();
+        assert_eq!(parse_time_file_name(&time_file_name(&now)).unwrap(), now);
+    }
+
+    #[test]
+    fn test_jsonl_store() {
+        use chrono::{TimeZone, Utc};
+        use structdiff::StructDiff;
+
+        use super::{read_jsonl_recording, JsonlStore, RecordingStore};
+        use crate::JobState;
+
+        let path = std::env::temp_dir().join(format!("slurry-jsonl-{}.jsonl", std::process::id()));
+        let time = Utc.with_ymd_and_hms(2025, 1, 14, 9, 23, 45).unwrap();
+        let row = test_row(time);
+        let mut running = row.clone();
+        running.state = JobState::RUNNING;
+        running.exec_host = Some("n23m0001".to_string());
+
+        let store = JsonlStore::open(&path).unwrap();
+        store.record_new_job(&time, &row).unwrap();
+        let later = time + chrono::Duration::seconds(5);
+        store
+            .record_job_delta(&later, &row.job_id, &row.diff(&running))
+            .unwrap();
+        store.flush().unwrap();
+
+        let jobs = read_jsonl_recording(&path).unwrap();
+        assert_eq!(jobs.len(), 1);
+        assert_eq!(jobs[0].initial_time, time);
+        assert_eq!(jobs[0].deltas.len(), 1);
+        assert_eq!(jobs[0].deltas[0].0, later);
+        let mut replayed = jobs[0].initial.clone();
+        replayed.apply_mut(jobs[0].deltas[0].1.clone());
+        assert_eq!(replayed.state, JobState::RUNNING);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn test_raw_lines() {
+        use chrono::{TimeZone, Utc};
+
+        use super::{JobRecording, JsonDirStore, RecordingStore};
+
+        let dir = std::env::temp_dir().join(format!("slurry-raw-{}", std::process::id()));
+        let time = Utc.with_ymd_and_hms(2025, 1, 14, 9, 23, 45).unwrap();
+        let mut row = test_row(time);
+        row.raw_line = Some("default|49848561|...".to_string());
+
+        JsonDirStore::new(dir.join("without"))
+            .record_new_job(&time, &row)
+            .unwrap();
+        JsonDirStore::new(dir.join("with"))
+            .with_raw_lines(true)
+            .record_new_job(&time, &row)
+            .unwrap();
+        let without = JobRecording::load_from_dir(&dir.join("without"), &row.job_id).unwrap();
+        assert_eq!(without.initial.raw_line, None);
+        let with = JobRecording::load_from_dir(&dir.join("with"), &row.job_id).unwrap();
+        assert_eq!(with.initial.raw_line, row.raw_line);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[cfg(feature = "zstd")]
+    #[test]
+    fn test_zstd_json_store() {
+        use std::collections::HashSet;
+
+        use super::{open_recording_file, JsonDirStore, RecordingStore};
+
+        let dir = std::env::temp_dir().join(format!("slurry-zstd-{}", std::process::id()));
+        let store = JsonDirStore::new(&dir).with_zstd(0);
+        let time = chrono::Utc::now();
+        let ids: HashSet<String> = ["1".to_string(), "2".to_string()].into_iter().collect();
+        store.record_job_ids(&time, &ids).unwrap();
+
+        let path = dir.join(format!("{}.json.zst", super::time_file_name(&time)));
+        let read_ids: HashSet<String> =
+            serde_json::from_reader(open_recording_file(&path).unwrap()).unwrap();
+        assert_eq!(read_ids, ids);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_load_recording_state() {
+        use chrono::{TimeZone, Utc};
+        use structdiff::StructDiff;
+
+        use super::{JsonDirStore, RecordingState, RecordingStore};
+        use crate::JobState;
+
+        let path = std::env::temp_dir().join(format!("slurry-state-{}", std::process::id()));
+        let store = JsonDirStore::new(&path);
+        let time = Utc.with_ymd_and_hms(2025, 1, 14, 9, 23, 45).unwrap();
+        let row = test_row(time);
+        let mut done = row.clone();
+        done.job_id = "49848562".to_string();
+        let mut running = row.clone();
+        running.state = JobState::RUNNING;
+
+        store
+            .record_job_ids(&time, &[row.job_id.clone(), done.job_id.clone()].into())
+            .unwrap();
+        store.record_new_job(&time, &row).unwrap();
+        store.record_new_job(&time, &done).unwrap();
+        let later = time + chrono::Duration::seconds(5);
+        store
+            .record_job_ids(&later, &[row.job_id.clone()].into())
+            .unwrap();
+        store
+            .record_job_delta(&later, &row.job_id, &row.diff(&running))
+            .unwrap();
+
+        let state = RecordingState::load_from(&path).unwrap();
+        assert_eq!(state.all_ids.len(), 2);
+        assert_eq!(state.known_jobs.len(), 1);
+        assert_eq!(state.known_jobs[&row.job_id].state, JobState::RUNNING);
+        std::fs::remove_dir_all(path).unwrap();
+    }
+}
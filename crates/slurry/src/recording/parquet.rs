@@ -0,0 +1,438 @@
+use std::{
+    collections::HashSet,
+    fs::{create_dir_all, File},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use ::parquet::{arrow::ArrowWriter, basic::Compression, file::properties::WriterProperties};
+use anyhow::Error;
+use arrow_array::{
+    ArrayRef, BooleanArray, Float64Array, Int32Array, RecordBatch, StringArray,
+    TimestampMillisecondArray, UInt64Array,
+};
+use arrow_schema::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use chrono::{DateTime, Utc};
+
+use super::{RecordingStore, SqueueRowDiff};
+use crate::data_extraction::{sinfo::SinfoNodeRow, squeue::SqueueRow};
+
+const JOB_IDS_FILE: &str = "job_ids.parquet";
+const JOBS_FILE: &str = "jobs.parquet";
+const DELTAS_FILE: &str = "deltas.parquet";
+const NODES_FILE: &str = "nodes.parquet";
+
+/// A single changed field of a job: (time, job id, field, JSON-encoded value)
+type DeltaRecord = (DateTime<Utc>, String, String, String);
+
+fn time_field(name: &str, nullable: bool) -> Field {
+    Field::new(
+        name,
+        DataType::Timestamp(TimeUnit::Millisecond, Some("UTC".into())),
+        nullable,
+    )
+}
+
+fn time_array(times: impl IntoIterator<Item = Option<DateTime<Utc>>>) -> ArrayRef {
+    Arc::new(
+        TimestampMillisecondArray::from(
+            times
+                .into_iter()
+                .map(|t| t.map(|t| t.timestamp_millis()))
+                .collect::<Vec<_>>(),
+        )
+        .with_timezone("UTC"),
+    )
+}
+
+fn string_array<'a>(vals: impl IntoIterator<Item = Option<&'a str>>) -> ArrayRef {
+    Arc::new(StringArray::from(vals.into_iter().collect::<Vec<_>>()))
+}
+
+fn u64_array(vals: impl IntoIterator<Item = Option<u64>>) -> ArrayRef {
+    Arc::new(UInt64Array::from(vals.into_iter().collect::<Vec<_>>()))
+}
+
+fn job_ids_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        time_field("time", false),
+        Field::new("job_id", DataType::Utf8, false),
+    ]))
+}
+
+fn jobs_schema() -> SchemaRef {
+    let string = |name: &str, nullable: bool| Field::new(name, DataType::Utf8, nullable);
+    let number = |name: &str, nullable: bool| Field::new(name, DataType::UInt64, nullable);
+    Arc::new(Schema::new(vec![
+        time_field("time", false),
+        string("job_id", false),
+        string("account", false),
+        string("exec_host", true),
+        number("min_cpus", false),
+        number("cpus", false),
+        number("nodes", false),
+        time_field("end_time", true),
+        string("dependency", true),
+        string("features", false),
+        string("array_job_id", false),
+        string("group", false),
+        string("array_task_id", true),
+        number("time_limit_secs", true),
+        number("time_left_secs", true),
+        string("name", false),
+        number("min_memory_bytes", false),
+        Field::new("min_memory_per_cpu", DataType::Boolean, false),
+        number("time_secs", true),
+        Field::new("priority", DataType::Float64, false),
+        string("partition", false),
+        string("state", false),
+        string("reason", false),
+        time_field("start_time", true),
+        time_field("submit_time", false),
+        string("work_dir", false),
+        string("command", false),
+        string("tres_per_node", false),
+        string("licenses", false),
+        string("user", true),
+        Field::new("exit_code", DataType::Int32, true),
+        string("cluster", true),
+    ]))
+}
+
+fn deltas_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        time_field("time", false),
+        Field::new("job_id", DataType::Utf8, false),
+        Field::new("field", DataType::Utf8, false),
+        Field::new("value", DataType::Utf8, false),
+    ]))
+}
+
+fn nodes_schema() -> SchemaRef {
+    Arc::new(Schema::new(vec![
+        time_field("time", false),
+        Field::new("node_name", DataType::Utf8, false),
+        Field::new("partition", DataType::Utf8, false),
+        Field::new("state", DataType::Utf8, false),
+        Field::new("reason", DataType::Utf8, true),
+    ]))
+}
+
+fn jobs_batch(
+    schema: SchemaRef,
+    jobs: &[(DateTime<Utc>, SqueueRow)],
+) -> Result<RecordBatch, Error> {
+    let rows = || jobs.iter().map(|(_, row)| row);
+    let secs = |d: Option<std::time::Duration>| d.map(|d| d.as_secs());
+    let tres = rows()
+        .map(|r| {
+            r.tres_per_node
+                .iter()
+                .map(|t| t.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        })
+        .collect::<Vec<_>>();
+    let licenses = rows()
+        .map(|r| {
+            r.licenses
+                .iter()
+                .map(|l| l.to_string())
+                .collect::<Vec<_>>()
+                .join(",")
+        })
+        .collect::<Vec<_>>();
+    let columns: Vec<ArrayRef> = vec![
+        time_array(jobs.iter().map(|(time, _)| Some(*time))),
+        string_array(rows().map(|r| Some(r.job_id.as_str()))),
+        string_array(rows().map(|r| Some(r.account.as_str()))),
+        string_array(rows().map(|r| r.exec_host.as_deref())),
+        u64_array(rows().map(|r| Some(r.min_cpus as u64))),
+        u64_array(rows().map(|r| Some(r.cpus as u64))),
+        u64_array(rows().map(|r| Some(r.nodes as u64))),
+        time_array(rows().map(|r| r.end_time)),
+        string_array(rows().map(|r| r.dependency.as_deref())),
+        string_array(rows().map(|r| Some(r.features.as_str()))),
+        string_array(rows().map(|r| Some(r.array_job_id.as_str()))),
+        string_array(rows().map(|r| Some(r.group.as_str()))),
+        Arc::new(StringArray::from(
+            rows()
+                .map(|r| r.step_job_id.task_string())
+                .collect::<Vec<_>>(),
+        )),
+        u64_array(rows().map(|r| secs(r.time_limit))),
+        u64_array(rows().map(|r| secs(r.time_left))),
+        string_array(rows().map(|r| Some(r.name.as_str()))),
+        u64_array(rows().map(|r| Some(r.min_memory.bytes))),
+        Arc::new(BooleanArray::from(
+            rows().map(|r| r.min_memory.per_cpu).collect::<Vec<_>>(),
+        )),
+        u64_array(rows().map(|r| secs(r.time))),
+        Arc::new(Float64Array::from(
+            rows().map(|r| r.priority).collect::<Vec<_>>(),
+        )),
+        string_array(rows().map(|r| Some(r.partition.as_str()))),
+        Arc::new(StringArray::from(
+            rows().map(|r| r.state.to_string()).collect::<Vec<_>>(),
+        )),
+        string_array(rows().map(|r| Some(r.reason.as_str()))),
+        time_array(rows().map(|r| r.start_time)),
+        time_array(rows().map(|r| Some(r.submit_time))),
+        Arc::new(StringArray::from(
+            rows()
+                .map(|r| r.work_dir.to_string_lossy().to_string())
+                .collect::<Vec<_>>(),
+        )),
+        string_array(rows().map(|r| Some(r.command.as_str()))),
+        string_array(tres.iter().map(|t| Some(t.as_str()))),
+        string_array(licenses.iter().map(|l| Some(l.as_str()))),
+        string_array(rows().map(|r| r.user.as_deref())),
+        Arc::new(Int32Array::from(
+            rows().map(|r| r.exit_code).collect::<Vec<_>>(),
+        )),
+        string_array(rows().map(|r| r.cluster.as_deref())),
+    ];
+    Ok(RecordBatch::try_new(schema, columns)?)
+}
+
+#[derive(Debug)]
+struct ParquetTable<T> {
+    schema: SchemaRef,
+    writer: Option<ArrowWriter<File>>,
+    buffer: Vec<T>,
+}
+
+impl<T> ParquetTable<T> {
+    fn create(path: &Path, schema: SchemaRef) -> Result<Self, Error> {
+        let props = WriterProperties::builder()
+            .set_compression(Compression::SNAPPY)
+            .build();
+        let writer = ArrowWriter::try_new(File::create(path)?, schema.clone(), Some(props))?;
+        Ok(Self {
+            schema,
+            writer: Some(writer),
+            buffer: Vec::new(),
+        })
+    }
+
+    /// Write all buffered values as a new row group
+    fn flush(
+        &mut self,
+        to_batch: impl FnOnce(SchemaRef, &[T]) -> Result<RecordBatch, Error>,
+    ) -> Result<(), Error> {
+        if self.buffer.is_empty() {
+            return Ok(());
+        }
+        let writer = self
+            .writer
+            .as_mut()
+            .ok_or(Error::msg("Parquet file was already closed."))?;
+        let batch = to_batch(self.schema.clone(), &self.buffer)?;
+        writer.write(&batch)?;
+        writer.flush()?;
+        self.buffer.clear();
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<(), Error> {
+        if let Some(writer) = self.writer.take() {
+            writer.close()?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+/// Recording store saving Parquet files into a directory
+///
+/// The recording consists of four tables, to which one row group is appended per `squeue` execution:
+/// - `job_ids.parquet`: IDs of all jobs present at each time (`time`, `job_id`)
+/// - `jobs.parquet`: Initial rows of all jobs, with one column per [`SqueueRow`] field
+/// - `deltas.parquet`: Changes of jobs (`time`, `job_id`, `field`, `value`), where `value` is JSON-encoded
+/// - `nodes.parquet`: Nodes which are new or changed their state (`time`, `node_name`, `partition`, `state`, `reason`)
+///
+/// The files can be read directly, e.g., using pandas or `DuckDB`.
+/// Note, that Parquet files are only readable after they were closed using [`ParquetStore::close`]
+/// (which also happens automatically once the store is dropped).
+pub struct ParquetStore {
+    path: PathBuf,
+    job_ids: Mutex<ParquetTable<(DateTime<Utc>, String)>>,
+    jobs: Mutex<ParquetTable<(DateTime<Utc>, SqueueRow)>>,
+    deltas: Mutex<ParquetTable<DeltaRecord>>,
+    nodes: Mutex<ParquetTable<(DateTime<Utc>, SinfoNodeRow)>>,
+}
+
+impl ParquetStore {
+    /// Create a new Parquet recording store in the directory at `path`
+    ///
+    /// Existing Parquet files of a previous recording in this directory are overwritten.
+    pub fn create(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref().to_path_buf();
+        create_dir_all(&path)?;
+        Ok(Self {
+            job_ids: Mutex::new(ParquetTable::create(
+                &path.join(JOB_IDS_FILE),
+                job_ids_schema(),
+            )?),
+            jobs: Mutex::new(ParquetTable::create(&path.join(JOBS_FILE), jobs_schema())?),
+            deltas: Mutex::new(ParquetTable::create(
+                &path.join(DELTAS_FILE),
+                deltas_schema(),
+            )?),
+            nodes: Mutex::new(ParquetTable::create(
+                &path.join(NODES_FILE),
+                nodes_schema(),
+            )?),
+            path,
+        })
+    }
+
+    /// The directory of the recording
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Write all buffered data and finalize the Parquet files
+    pub fn close(self) -> Result<(), Error> {
+        self.flush()?;
+        self.close_tables()
+    }
+
+    fn close_tables(&self) -> Result<(), Error> {
+        self.job_ids.lock().unwrap().close()?;
+        self.jobs.lock().unwrap().close()?;
+        self.deltas.lock().unwrap().close()?;
+        self.nodes.lock().unwrap().close()?;
+        Ok(())
+    }
+}
+
+impl Drop for ParquetStore {
+    fn drop(&mut self) {
+        if let Err(e) = self.flush().and_then(|_| self.close_tables()) {
+            eprintln!("Failed to close Parquet recording: {e:?}");
+        }
+    }
+}
+
+impl RecordingStore for ParquetStore {
+    fn record_job_ids(&self, time: &DateTime<Utc>, job_ids: &HashSet<String>) -> Result<(), Error> {
+        let mut table = self.job_ids.lock().unwrap();
+        table
+            .buffer
+            .extend(job_ids.iter().map(|id| (*time, id.clone())));
+        Ok(())
+    }
+
+    fn record_new_job(&self, time: &DateTime<Utc>, row: &SqueueRow) -> Result<(), Error> {
+        self.jobs.lock().unwrap().buffer.push((*time, row.clone()));
+        Ok(())
+    }
+
+    fn record_job_delta(
+        &self,
+        time: &DateTime<Utc>,
+        job_id: &str,
+        diff: &[SqueueRowDiff],
+    ) -> Result<(), Error> {
+        let mut values = Vec::with_capacity(diff.len());
+        for d in diff {
+            // Diffs are serialized as `{"<field>": <value>}`
+            let serde_json::Value::Object(map) = serde_json::to_value(d)? else {
+                return Err(Error::msg("Unexpected diff format."));
+            };
+            for (field, value) in map {
+                values.push((*time, job_id.to_string(), field, value.to_string()));
+            }
+        }
+        self.deltas.lock().unwrap().buffer.extend(values);
+        Ok(())
+    }
+
+    fn record_node_states(
+        &self,
+        time: &DateTime<Utc>,
+        nodes: &[SinfoNodeRow],
+    ) -> Result<(), Error> {
+        let mut table = self.nodes.lock().unwrap();
+        table
+            .buffer
+            .extend(nodes.iter().map(|node| (*time, node.clone())));
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<(), Error> {
+        self.job_ids.lock().unwrap().flush(|schema, ids| {
+            Ok(RecordBatch::try_new(
+                schema,
+                vec![
+                    time_array(ids.iter().map(|(time, _)| Some(*time))),
+                    string_array(ids.iter().map(|(_, id)| Some(id.as_str()))),
+                ],
+            )?)
+        })?;
+        self.jobs.lock().unwrap().flush(jobs_batch)?;
+        self.deltas.lock().unwrap().flush(|schema, deltas| {
+            Ok(RecordBatch::try_new(
+                schema,
+                vec![
+                    time_array(deltas.iter().map(|(time, ..)| Some(*time))),
+                    string_array(deltas.iter().map(|(_, id, ..)| Some(id.as_str()))),
+                    string_array(deltas.iter().map(|(_, _, field, _)| Some(field.as_str()))),
+                    string_array(deltas.iter().map(|(.., value)| Some(value.as_str()))),
+                ],
+            )?)
+        })?;
+        self.nodes.lock().unwrap().flush(|schema, nodes| {
+            Ok(RecordBatch::try_new(
+                schema,
+                vec![
+                    time_array(nodes.iter().map(|(time, _)| Some(*time))),
+                    string_array(nodes.iter().map(|(_, n)| Some(n.node_name.as_str()))),
+                    string_array(nodes.iter().map(|(_, n)| Some(n.partition.as_str()))),
+                    Arc::new(StringArray::from(
+                        nodes
+                            .iter()
+                            .map(|(_, n)| format!("{:?}", n.state))
+                            .collect::<Vec<_>>(),
+                    )),
+                    string_array(nodes.iter().map(|(_, n)| n.reason.as_deref())),
+                ],
+            )?)
+        })?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use ::parquet::file::reader::{FileReader, SerializedFileReader};
+    use chrono::{TimeZone, Utc};
+
+    use super::ParquetStore;
+    use crate::recording::RecordingStore;
+
+    #[test]
+    fn test_parquet_store() {
+        let dir = std::env::temp_dir().join(format!("slurry-parquet-{}", std::process::id()));
+        let store = ParquetStore::create(&dir).unwrap();
+        let time = Utc.with_ymd_and_hms(2025, 1, 14, 9, 23, 45).unwrap();
+        for i in 0..2 {
+            let ids: HashSet<String> = ["1".to_string(), "2".to_string()].into_iter().collect();
+            store
+                .record_job_ids(&(time + chrono::Duration::seconds(i)), &ids)
+                .unwrap();
+            store.flush().unwrap();
+        }
+        store.close().unwrap();
+
+        let reader =
+            SerializedFileReader::new(std::fs::File::open(dir.join("job_ids.parquet")).unwrap())
+                .unwrap();
+        assert_eq!(reader.metadata().num_row_groups(), 2);
+        assert_eq!(reader.metadata().file_metadata().num_rows(), 4);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -0,0 +1,139 @@
+use std::{
+    fs::File,
+    io::BufWriter,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Error;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::open_recording_file;
+use crate::data_extraction::sstat::SstatStepRow;
+
+/// Prefix of job profile files in JSON recording directories (i.e., `PROFILE-<job_id>.json`)
+pub const PROFILE_PREFIX: &str = "PROFILE-";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Resource usage of all steps of a running job at a single point in time (as reported by `sstat`)
+pub struct JobProfileSample {
+    /// Time at which `sstat` was executed
+    pub time: DateTime<Utc>,
+    /// Statistics of all running steps of the job
+    pub steps: Vec<SstatStepRow>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Time series of the resource usage (CPU, RSS and I/O) of a job, sampled periodically while it was running
+///
+/// Created by [`profile_job`](crate::job_management::profile_job).
+pub struct JobProfile {
+    /// ID of the profiled job
+    pub job_id: String,
+    /// All samples, ordered by time
+    pub samples: Vec<JobProfileSample>,
+}
+
+impl JobProfile {
+    /// Create a new empty profile for the given job
+    pub fn new(job_id: impl Into<String>) -> Self {
+        Self {
+            job_id: job_id.into(),
+            samples: Vec::new(),
+        }
+    }
+
+    /// Get the time series of a single job step (e.g., `49848561.batch` or `49848561.0`)
+    pub fn step_series(&self, job_step_id: &str) -> Vec<(DateTime<Utc>, &SstatStepRow)> {
+        self.samples
+            .iter()
+            .flat_map(|sample| {
+                sample
+                    .steps
+                    .iter()
+                    .filter(|step| step.job_step_id == job_step_id)
+                    .map(|step| (sample.time, step))
+            })
+            .collect()
+    }
+
+    /// Path of the profile file of `job_id` in the JSON recording directory at `path`
+    pub fn file_path(path: &Path, job_id: &str) -> PathBuf {
+        path.join(format!("{PROFILE_PREFIX}{job_id}.json"))
+    }
+
+    /// Save the profile into the JSON recording directory at `path` (see [`JsonDirStore`](super::JsonDirStore)),
+    /// overwriting any previously saved profile of the same job
+    ///
+    /// This allows including the sampled resource usage when extracting event data from the recording.
+    pub fn save_to_dir(&self, path: &Path) -> Result<(), Error> {
+        std::fs::create_dir_all(path)?;
+        let file = File::create(Self::file_path(path, &self.job_id))?;
+        serde_json::to_writer(BufWriter::new(file), self)?;
+        Ok(())
+    }
+
+    /// Load the profile of `job_id` from the JSON recording directory at `path`, if one was saved
+    pub fn load_from_dir(path: &Path, job_id: &str) -> Result<Option<Self>, Error> {
+        let file_path = Self::file_path(path, job_id);
+        if !file_path.exists() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_reader(open_recording_file(
+            &file_path,
+        )?)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use chrono::{TimeZone, Utc};
+
+    use super::{JobProfile, JobProfileSample};
+    use crate::{data_extraction::sstat::SstatStepRow, recording::latest_job_ids_file};
+
+    #[test]
+    fn test_job_profile() {
+        let step = |id: &str, max_rss: u64| SstatStepRow {
+            job_step_id: id.to_string(),
+            num_tasks: Some(1),
+            ave_cpu: Some(Duration::from_secs(12)),
+            min_cpu: Some(Duration::from_secs(12)),
+            ave_rss: Some(max_rss),
+            max_rss: Some(max_rss),
+            max_vm_size: None,
+            ave_disk_read: Some(1024),
+            ave_disk_write: Some(0),
+            max_disk_read: Some(1024),
+            max_disk_write: Some(0),
+        };
+        let time = Utc.with_ymd_and_hms(2025, 1, 14, 9, 23, 45).unwrap();
+        let mut profile = JobProfile::new("49848561");
+        profile.samples.push(JobProfileSample {
+            time,
+            steps: vec![step("49848561.batch", 1000)],
+        });
+        profile.samples.push(JobProfileSample {
+            time: time + chrono::Duration::seconds(30),
+            steps: vec![step("49848561.batch", 2000), step("49848561.0", 500)],
+        });
+        let series = profile.step_series("49848561.batch");
+        assert_eq!(series.len(), 2);
+        assert_eq!(series[1].1.max_rss, Some(2000));
+        assert_eq!(profile.step_series("49848561.0").len(), 1);
+
+        let dir = std::env::temp_dir().join(format!("slurry-profile-{}", std::process::id()));
+        profile.save_to_dir(&dir).unwrap();
+        // Profile files are not mistaken for job ID files
+        assert!(latest_job_ids_file(&dir).unwrap().is_none());
+        let loaded = JobProfile::load_from_dir(&dir, "49848561")
+            .unwrap()
+            .unwrap();
+        assert_eq!(loaded.samples.len(), 2);
+        assert_eq!(loaded.samples[1].time, profile.samples[1].time);
+        assert!(JobProfile::load_from_dir(&dir, "1").unwrap().is_none());
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
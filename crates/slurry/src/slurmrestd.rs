@@ -0,0 +1,618 @@
+use std::{path::Path, time::SystemTime};
+
+use anyhow::Error;
+use chrono::{DateTime, Utc};
+use reqwest::Method;
+use serde_json::{json, Map, Value};
+
+use crate::{
+    data_extraction::{
+        squeue::{parse_squeue_json_value, SqueueRow},
+        SqueueMode,
+    },
+    executor::{CommandExecutor, CommandOutput},
+    job_management::{job_status_of_row, JobStatus, JobSubmission, SbatchScript, SubmittedJob},
+    SlurmDuration,
+};
+
+/// Version of the Slurm REST API used by default (supported since SLURM 23.11)
+pub const DEFAULT_API_VERSION: &str = "v0.0.40";
+
+/// Environment of submitted jobs used by default (see [`SlurmRestClient::with_environment`])
+pub const DEFAULT_ENVIRONMENT: &[&str] = &["PATH=/usr/local/bin:/usr/bin:/bin"];
+
+/// Error number reported by the Slurm REST API for unknown job IDs (`ESLURM_INVALID_JOB_ID`)
+const INVALID_JOB_ID: i64 = 2017;
+
+#[derive(Debug, Clone)]
+/// Client for the Slurm REST API (`slurmrestd`), authenticating using a JWT token
+///
+/// This allows recording and submitting jobs on clusters which do not allow executing commands over SSH.
+/// Tokens can, e.g., be obtained using `scontrol token` (requires JWT authentication to be enabled for the cluster).
+///
+/// The client implements [`CommandExecutor`] for the commands which can be mapped to the REST API
+/// (`squeue --json` and `scancel`), so that, e.g., [`get_squeue_res_with_source`](crate::data_extraction::get_squeue_res_with_source)
+/// (with [`SqueueSource::Json`](crate::data_extraction::SqueueSource::Json)) or [`cancel_jobs`](crate::job_management::cancel_jobs) can be used with it.
+pub struct SlurmRestClient {
+    base_url: String,
+    user_name: String,
+    token: String,
+    api_version: String,
+    environment: Vec<String>,
+    home_dir: Option<String>,
+    http: reqwest::Client,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Errors reported in a response of the Slurm REST API
+pub struct SlurmRestError {
+    /// Reported errors, as error number (if any) and description
+    pub errors: Vec<(Option<i64>, String)>,
+}
+
+impl SlurmRestError {
+    /// Check if the request failed because of an unknown job ID
+    pub fn is_invalid_job_id(&self) -> bool {
+        self.errors
+            .iter()
+            .any(|(number, _)| *number == Some(INVALID_JOB_ID))
+    }
+}
+
+impl std::fmt::Display for SlurmRestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let messages: Vec<&str> = self.errors.iter().map(|(_, msg)| msg.as_str()).collect();
+        write!(f, "slurmrestd: {}", messages.join("; "))
+    }
+}
+
+impl std::error::Error for SlurmRestError {}
+
+/// Check if `err` is a [`SlurmRestError`] reporting an unknown job ID
+fn is_invalid_job_id(err: &Error) -> bool {
+    err.downcast_ref::<SlurmRestError>()
+        .is_some_and(SlurmRestError::is_invalid_job_id)
+}
+
+impl SlurmRestClient {
+    /// Create a new client for the `slurmrestd` instance at `base_url` (e.g., `https://slurm.example.com:6820`)
+    pub fn new(
+        base_url: impl Into<String>,
+        user_name: impl Into<String>,
+        token: impl Into<String>,
+    ) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            user_name: user_name.into(),
+            token: token.into(),
+            api_version: DEFAULT_API_VERSION.to_string(),
+            environment: DEFAULT_ENVIRONMENT.iter().map(|e| e.to_string()).collect(),
+            home_dir: None,
+            http: reqwest::Client::new(),
+        }
+    }
+
+    /// Use the given environment variables for submitted jobs, instead of the [`DEFAULT_ENVIRONMENT`]
+    ///
+    /// Jobs submitted using the REST API do not inherit the environment of a login shell,
+    /// so, e.g., `PATH` (and `HOME`) should be set as on the cluster.
+    pub fn with_environment<K: AsRef<str>, V: AsRef<str>>(
+        mut self,
+        environment: impl IntoIterator<Item = (K, V)>,
+    ) -> Self {
+        self.environment = environment
+            .into_iter()
+            .map(|(key, value)| format!("{}={}", key.as_ref(), value.as_ref()))
+            .collect();
+        self
+    }
+
+    /// Use `home_dir` (e.g., `/home/ab123456`) to resolve relative root directories (and `~/`) of submitted jobs
+    ///
+    /// The REST API requires absolute paths, so jobs with relative root directories can only be submitted if a home directory is set.
+    pub fn with_home_dir(mut self, home_dir: impl Into<String>) -> Self {
+        self.home_dir = Some(home_dir.into());
+        self
+    }
+
+    /// Resolve `path` to an absolute path on the cluster (see [`SlurmRestClient::with_home_dir`])
+    fn absolute_path(&self, path: &str) -> Result<String, Error> {
+        if path.starts_with('/') {
+            return Ok(path.to_string());
+        }
+        let home_dir = self.home_dir.as_ref().ok_or_else(|| {
+            Error::msg(format!(
+                "Relative path {path:?} requires a home directory (see `SlurmRestClient::with_home_dir`)."
+            ))
+        })?;
+        let relative = path.strip_prefix('~').unwrap_or(path);
+        Ok(join_path(home_dir, relative))
+    }
+
+    /// Use the given version of the REST API (e.g., `v0.0.41`) instead of [`DEFAULT_API_VERSION`]
+    pub fn with_api_version(mut self, api_version: impl Into<String>) -> Self {
+        self.api_version = api_version.into();
+        self
+    }
+
+    /// Replace the JWT token (e.g., after the previous one expired)
+    pub fn set_token(&mut self, token: impl Into<String>) {
+        self.token = token.into();
+    }
+
+    /// Send a request to the endpoint at `path` (relative to `/slurm/<version>/`), returning the JSON response
+    ///
+    /// Fails if the response reports any errors.
+    async fn request(
+        &self,
+        method: Method,
+        path: &str,
+        body: Option<Value>,
+    ) -> Result<Value, Error> {
+        let url = format!("{}/slurm/{}/{path}", self.base_url, self.api_version);
+        let mut req = self
+            .http
+            .request(method, url)
+            .header("X-SLURM-USER-NAME", &self.user_name)
+            .header("X-SLURM-USER-TOKEN", &self.token);
+        if let Some(body) = body {
+            req = req.json(&body);
+        }
+        let res = req.send().await?;
+        let status = res.status();
+        let text = res.text().await?;
+        let val: Value = serde_json::from_str(&text).map_err(|_| {
+            Error::msg(format!(
+                "Invalid response from slurmrestd ({status}): {}",
+                text.trim()
+            ))
+        })?;
+        check_response_errors(&val).map_err(Error::new)?;
+        if !status.is_success() {
+            return Err(Error::msg(format!("slurmrestd request failed ({status}).")));
+        }
+        Ok(val)
+    }
+
+    /// Check if `slurmrestd` is reachable and the token is accepted
+    pub async fn ping(&self) -> Result<(), Error> {
+        self.request(Method::GET, "ping", None).await?;
+        Ok(())
+    }
+
+    /// Get the jobs matching `mode` as JSON (in the format of `squeue --json`)
+    ///
+    /// The REST API only supports filtering by job IDs, so only these are requested individually,
+    /// while all jobs are requested for other modes (filtered by [`parse_squeue_json_value`]).
+    async fn get_jobs_json(&self, mode: &SqueueMode) -> Result<Value, Error> {
+        let Some(job_ids) = job_ids_of_mode(mode) else {
+            return self.request(Method::GET, "jobs", None).await;
+        };
+        let responses = futures::future::join_all(job_ids.iter().map(|job_id| async move {
+            self.request(Method::GET, &format!("job/{job_id}"), None)
+                .await
+        }))
+        .await;
+        let mut jobs = Vec::new();
+        for res in responses {
+            match res {
+                Ok(val) => {
+                    if let Some(Value::Array(job)) = val.get("jobs") {
+                        jobs.extend(job.iter().cloned());
+                    }
+                }
+                // Jobs which are no longer known are not listed (as by `squeue`)
+                Err(e) if is_invalid_job_id(&e) => {}
+                Err(e) => return Err(e),
+            }
+        }
+        Ok(json!({ "jobs": jobs }))
+    }
+
+    /// Get all jobs matching `mode`, equivalent to [`get_squeue_res`](crate::data_extraction::get_squeue_res) (with [`SqueueSource::Json`](crate::data_extraction::SqueueSource::Json))
+    ///
+    /// Only jobs of the user of the token are included for [`SqueueMode::MINE`].
+    pub async fn get_squeue_res(
+        &self,
+        mode: &SqueueMode,
+    ) -> Result<(DateTime<Utc>, Vec<SqueueRow>), Error> {
+        let val = self.get_jobs_json(mode).await?;
+        let time: DateTime<Utc> = SystemTime::now().into();
+        Ok((
+            time,
+            parse_squeue_json_value(&val, &self.resolve_mode(mode))?,
+        ))
+    }
+
+    /// Replace [`SqueueMode::MINE`] by the user of the token, as the REST API is not aware of an active user
+    fn resolve_mode(&self, mode: &SqueueMode) -> SqueueMode {
+        match mode {
+            SqueueMode::MINE => SqueueMode::USERS(vec![self.user_name.clone()]),
+            SqueueMode::COMBINED(modes) => {
+                SqueueMode::COMBINED(modes.iter().map(|m| self.resolve_mode(m)).collect())
+            }
+            mode => mode.clone(),
+        }
+    }
+
+    /// Get the status of a SLURM job, given its ID (see [`get_job_status`](crate::job_management::get_job_status))
+    pub async fn get_job_status(&self, job_id: &str) -> Result<JobStatus, Error> {
+        let mode = SqueueMode::JOBIDS(vec![job_id.to_string()]);
+        let val = self.get_jobs_json(&mode).await?;
+        Ok(parse_squeue_json_value(&val, &mode)?
+            .first()
+            .map_or(JobStatus::NotFound, job_status_of_row))
+    }
+
+    /// Submit a job (see [`submit_job`](crate::job_management::submit_job))
+    ///
+    /// As no commands can be executed on the cluster, no job folder is created:
+    /// The job is started in the root directory of the submission (and relative output paths refer to it).
+    /// Relative root directories are resolved using the home directory (see [`SlurmRestClient::with_home_dir`]).
+    /// Uploading files is not supported.
+    pub async fn submit_job(&self, job: impl Into<JobSubmission>) -> Result<SubmittedJob, Error> {
+        let job = job.into();
+        if !job.files_to_upload.is_empty() {
+            return Err(Error::msg(
+                "Uploading files is not supported by the Slurm REST API.",
+            ));
+        }
+        job.script.check_env()?;
+        let working_dir = self.absolute_path(&job.root_dir)?;
+        let body = json!({ "job": job_description(&job.script, &working_dir, &self.environment) });
+        let val = self.request(Method::POST, "job/submit", Some(body)).await?;
+        let job_id = match val.get("job_id") {
+            Some(Value::Number(id)) => id.to_string(),
+            Some(Value::String(id)) => id.clone(),
+            _ => return Err(Error::msg("No job ID returned by slurmrestd.")),
+        };
+        Ok(SubmittedJob {
+            job_id,
+            cluster: None,
+        })
+    }
+
+    /// Cancel a job, given its ID (see [`cancel_job`](crate::job_management::cancel_job))
+    pub async fn cancel_job(&self, job_id: &str) -> Result<(), Error> {
+        self.request(Method::DELETE, &format!("job/{job_id}"), None)
+            .await?;
+        Ok(())
+    }
+
+    /// Emulate `scancel [-s <signal>] [--batch] <job IDs>`, reporting errors as `scancel` does
+    /// (see [`cancel_jobs`](crate::job_management::cancel_jobs) and [`signal_job`](crate::job_management::signal_job))
+    async fn scancel(&self, args: &[String]) -> CommandOutput {
+        let mut query = Vec::new();
+        let mut job_ids = Vec::new();
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-s" | "--signal" => {
+                    query.push(format!("signal={}", args.next().map_or("", |s| s.as_str())))
+                }
+                "-b" | "--batch" => query.push("flags=BATCH_JOB".to_string()),
+                arg if arg.starts_with('-') => {
+                    return CommandOutput {
+                        stdout: String::new(),
+                        stderr: format!(
+                            "Option {arg} of scancel is not supported by the Slurm REST API"
+                        ),
+                        exit_status: 1,
+                    }
+                }
+                _ => job_ids.push(arg),
+            }
+        }
+        let query = if query.is_empty() {
+            String::new()
+        } else {
+            format!("?{}", query.join("&"))
+        };
+        let query = &query;
+        let results = futures::future::join_all(job_ids.iter().map(|job_id| async move {
+            self.request(Method::DELETE, &format!("job/{job_id}{query}"), None)
+                .await
+        }))
+        .await;
+        let stderr: String = job_ids
+            .iter()
+            .zip(results)
+            .filter_map(|(job_id, res)| {
+                let e = res.err()?;
+                let message = match e.downcast_ref::<SlurmRestError>() {
+                    Some(e) if e.is_invalid_job_id() => "Invalid job id specified".to_string(),
+                    _ => format!("{e}"),
+                };
+                Some(format!(
+                    "scancel: error: Kill job error on job id {job_id}: {message}\n"
+                ))
+            })
+            .collect();
+        CommandOutput {
+            stdout: String::new(),
+            exit_status: u32::from(!stderr.is_empty()),
+            stderr,
+        }
+    }
+}
+
+impl CommandExecutor for SlurmRestClient {
+    /// Execute `command` using the REST API, if supported
+    ///
+    /// Supported are `squeue --json` (only filtering for job IDs and `--me`, see [`SlurmRestClient::get_squeue_res`])
+    /// and `scancel [-s <signal>] [--batch] <job IDs>`. Other commands fail with exit status `127` (as unknown commands in a shell).
+    async fn execute(&self, command: &str) -> Result<CommandOutput, Error> {
+        let args = split_shell_words(command);
+        match args.first().map(String::as_str) {
+            Some("squeue") if args.iter().any(|a| a == "--json") => {
+                let mut modes = Vec::new();
+                if let Some(ids) = args
+                    .iter()
+                    .position(|a| a == "-j")
+                    .and_then(|i| args.get(i + 1))
+                {
+                    modes.push(SqueueMode::JOBIDS(
+                        ids.split(',').map(String::from).collect(),
+                    ));
+                }
+                if args.iter().any(|a| a == "--me") {
+                    modes.push(SqueueMode::MINE);
+                }
+                // Other filters are applied when parsing the output (see `get_squeue_res_with_source`)
+                let mode = SqueueMode::COMBINED(modes);
+                let mut val = self.get_jobs_json(&mode).await?;
+                if let Some(Value::Array(jobs)) = val.get_mut("jobs") {
+                    let mode = self.resolve_mode(&mode);
+                    jobs.retain(|job| {
+                        let job = json!({ "jobs": [job] });
+                        parse_squeue_json_value(&job, &mode).is_ok_and(|rows| !rows.is_empty())
+                    });
+                }
+                Ok(CommandOutput {
+                    stdout: val.to_string(),
+                    ..Default::default()
+                })
+            }
+            Some("scancel") => Ok(self.scancel(&args[1..]).await),
+            _ => Ok(CommandOutput {
+                stdout: String::new(),
+                stderr: format!("Command not supported by the Slurm REST API: {command}"),
+                exit_status: 127,
+            }),
+        }
+    }
+
+    async fn upload(&self, _local_path: &Path, _remote_path: &str) -> Result<(), Error> {
+        Err(Error::msg(
+            "Uploading files is not supported by the Slurm REST API.",
+        ))
+    }
+
+    async fn download(&self, _remote_path: &str, _local_path: &Path) -> Result<(), Error> {
+        Err(Error::msg(
+            "Downloading files is not supported by the Slurm REST API.",
+        ))
+    }
+}
+
+/// Job IDs to request individually for `mode` (i.e., filtering on the server), if it filters for job IDs
+fn job_ids_of_mode(mode: &SqueueMode) -> Option<Vec<String>> {
+    match mode {
+        SqueueMode::JOBIDS(ids) => Some(ids.clone()),
+        SqueueMode::COMBINED(modes) => modes.iter().find_map(job_ids_of_mode),
+        _ => None,
+    }
+}
+
+/// Split a shell command into words, removing single quotes (as added by `shell_quote`) and backslash escapes
+fn split_shell_words(command: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word: Option<String> = None;
+    let mut chars = command.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                let word = word.get_or_insert_with(String::new);
+                word.extend(chars.by_ref().take_while(|c| *c != '\''));
+            }
+            '\\' => word.get_or_insert_with(String::new).extend(chars.next()),
+            c if c.is_whitespace() => words.extend(word.take()),
+            c => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    words.extend(word);
+    words
+}
+
+/// Join a (relative) `path` to `dir`
+fn join_path(dir: &str, path: &str) -> String {
+    match path.trim_start_matches('/') {
+        "" => dir.to_string(),
+        path => format!("{}/{path}", dir.trim_end_matches('/')),
+    }
+}
+
+/// Check the `errors` of a Slurm REST API response, failing if they are not empty
+fn check_response_errors(val: &Value) -> Result<(), SlurmRestError> {
+    let errors: Vec<(Option<i64>, String)> = val
+        .get("errors")
+        .and_then(|errors| errors.as_array())
+        .into_iter()
+        .flatten()
+        .map(|err| {
+            let message = ["description", "error"]
+                .iter()
+                .filter_map(|key| err.get(key).and_then(|v| v.as_str()))
+                .find(|s| !s.is_empty())
+                .unwrap_or("Unknown error")
+                .to_string();
+            (err.get("error_number").and_then(|n| n.as_i64()), message)
+        })
+        .collect();
+    if errors.is_empty() {
+        Ok(())
+    } else {
+        Err(SlurmRestError { errors })
+    }
+}
+
+/// A number as expected by the Slurm REST API (e.g., `{"set": true, "infinite": false, "number": 42}`)
+fn api_number(number: u64) -> Value {
+    json!({ "set": true, "infinite": false, "number": number })
+}
+
+/// Convert the directives of a batch script into a job description of the Slurm REST API, starting the job in the (absolute) `working_dir`
+///
+/// Relative output paths are resolved against the working directory.
+/// Directives not supported by the job description (e.g., `--exclusive` or [`SbatchScript::extra_directives`]) are only contained in the script itself.
+fn job_description(script: &SbatchScript, working_dir: &str, environment: &[String]) -> Value {
+    let mut job = Map::new();
+    job.insert("script".into(), json!(script.to_string()));
+    job.insert("current_working_directory".into(), json!(working_dir));
+    // The environment is required, variables of the script are exported in the script itself
+    job.insert("environment".into(), json!(environment));
+    let absolute = |path: &String| {
+        if path.starts_with('/') {
+            json!(path)
+        } else {
+            json!(join_path(working_dir, path))
+        }
+    };
+    let mut set = |key: &str, value: Option<Value>| {
+        if let Some(value) = value {
+            job.insert(key.to_string(), value);
+        }
+    };
+    set("name", script.job_name.as_ref().map(|n| json!(n)));
+    set("tasks", script.ntasks.map(|n| json!(n)));
+    set("nodes", script.nodes.as_ref().map(|n| json!(n)));
+    set("cpus_per_task", script.cpus_per_task.map(|n| json!(n)));
+    set(
+        "time_limit",
+        script.time.as_ref().and_then(|time| match time {
+            // Time limits are given in minutes
+            SlurmDuration::Finite(dur) => Some(api_number(dur.as_secs().div_ceil(60))),
+            SlurmDuration::Unlimited => Some(json!({ "set": true, "infinite": true })),
+            SlurmDuration::NotSet => None,
+        }),
+    );
+    if let Some(memory) = &script.memory {
+        let key = if memory.per_cpu {
+            "memory_per_cpu"
+        } else {
+            "memory_per_node"
+        };
+        set(key, Some(api_number(memory.bytes >> 20)));
+    }
+    set("partition", script.partition.as_ref().map(|p| json!(p)));
+    set("account", script.account.as_ref().map(|a| json!(a)));
+    set("qos", script.qos.as_ref().map(|q| json!(q)));
+    set(
+        "tres_per_node",
+        (!script.gres.is_empty()).then(|| {
+            json!(script
+                .gres
+                .iter()
+                .map(|g| g.to_string())
+                .collect::<Vec<_>>()
+                .join(","))
+        }),
+    );
+    set("constraints", script.constraint.as_ref().map(|c| json!(c)));
+    set(
+        "mail_type",
+        (!script.mail_types.is_empty()).then(|| {
+            json!(script
+                .mail_types
+                .iter()
+                .map(|t| t.to_string())
+                .collect::<Vec<_>>())
+        }),
+    );
+    set("mail_user", script.mail_user.as_ref().map(|u| json!(u)));
+    set("array", script.array.as_ref().map(|a| json!(a)));
+    set("dependency", script.dependency.as_ref().map(|d| json!(d)));
+    set("standard_output", script.output.as_ref().map(absolute));
+    set("standard_error", script.error.as_ref().map(absolute));
+    set("requeue", script.requeue.then_some(Value::Bool(true)));
+    Value::Object(job)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::{
+        check_response_errors, job_description, split_shell_words, SlurmRestClient,
+        DEFAULT_ENVIRONMENT,
+    };
+    use crate::{
+        job_management::{test_job_options, JobOptions, JobSubmission, MailType},
+        SlurmDuration,
+    };
+
+    #[test]
+    fn test_job_description() {
+        let job = JobSubmission::from(JobOptions {
+            name: Some("sweep".to_string()),
+            partition: Some("c23ms".to_string()),
+            memory: Some("3900Mc".parse().unwrap()),
+            gres: vec!["gpu:2".parse().unwrap()],
+            mail_types: vec![MailType::END, MailType::FAIL],
+            mail_user: Some("user@example.com".to_string()),
+            time: SlurmDuration::Finite(std::time::Duration::from_secs(90)),
+            ..test_job_options()
+        });
+        let client = SlurmRestClient::new("http://localhost:6820", "ab123456", "token")
+            .with_home_dir("/home/ab123456");
+        let working_dir = client.absolute_path(&job.root_dir).unwrap();
+        let environment: Vec<String> = DEFAULT_ENVIRONMENT.iter().map(|e| e.to_string()).collect();
+        let desc = job_description(&job.script, &working_dir, &environment);
+        assert_eq!(desc["name"], json!("sweep"));
+        assert_eq!(
+            desc["current_working_directory"],
+            json!("/home/ab123456/hpc_experiments")
+        );
+        assert_eq!(desc["environment"], json!(environment));
+        assert_eq!(desc["time_limit"]["number"], json!(2));
+        assert_eq!(desc["memory_per_cpu"]["number"], json!(3900));
+        assert!(desc.get("memory_per_node").is_none());
+        assert_eq!(desc["tres_per_node"], json!("gpu:2"));
+        assert_eq!(desc["mail_type"], json!(["END", "FAIL"]));
+        assert_eq!(
+            desc["standard_output"],
+            json!("/home/ab123456/hpc_experiments/stdout.txt")
+        );
+        assert!(desc["script"].as_str().unwrap().ends_with("./run.sh\n"));
+
+        assert!(check_response_errors(&json!({ "errors": [], "job_id": 1 })).is_ok());
+        let err = check_response_errors(&json!({
+            "errors": [{ "error": "Invalid job id", "description": "", "error_number": 2017 }]
+        }))
+        .unwrap_err();
+        assert_eq!(err.to_string(), "slurmrestd: Invalid job id");
+        assert!(err.is_invalid_job_id());
+
+        assert_eq!(
+            client.absolute_path("~/jobs").unwrap(),
+            "/home/ab123456/jobs"
+        );
+        assert_eq!(client.absolute_path("/work/jobs").unwrap(), "/work/jobs");
+        let client = SlurmRestClient::new("http://localhost:6820", "ab123456", "token")
+            .with_environment([("PATH", "/usr/bin"), ("HOME", "/home/ab123456")]);
+        assert!(client.absolute_path("hpc_experiments").is_err());
+        assert_eq!(client.environment, ["PATH=/usr/bin", "HOME=/home/ab123456"]);
+    }
+
+    #[test]
+    fn test_split_shell_words() {
+        assert_eq!(
+            split_shell_words("scancel -s 'USR1' --batch  '12'\\''3' 456"),
+            ["scancel", "-s", "USR1", "--batch", "12'3", "456"]
+        );
+        assert_eq!(
+            split_shell_words("squeue -a --json -j 1,2 -p 'gpu'"),
+            ["squeue", "-a", "--json", "-j", "1,2", "-p", "gpu"]
+        );
+    }
+}
@@ -1,10 +1,19 @@
-use std::{
-    collections::{HashMap, HashSet},
-    path::PathBuf,
-};
+use std::{collections::HashMap, path::PathBuf, time::Duration};
 
 use clap::Parser;
-use slurry::data_extraction::{get_squeue_res_locally, squeue_diff, SqueueMode};
+use slurry::{
+    data_extraction::{
+        get_job_efficiency_locally, get_sacct_res_locally, get_sinfo_nodes_locally,
+        get_squeue_res_locally, sinfo_diff_with_store, squeue_diff_with_final_states, ClusterInfo,
+        SqueueMode,
+    },
+    event_data_extraction::{
+        export_split_ocels, extract_ocel_from_slurm_diffs_with_progress, CancellationToken,
+        ExtractionConfig, OcelAccumulator, OcelFormat, SplitBy,
+    },
+    misc::polling::AdaptiveInterval,
+    recording::{CountingStore, JsonDirStore, JsonlStore, RecordingState, RecordingStore},
+};
 
 /// Run squeue loop and save delta data
 #[derive(Parser, Debug)]
@@ -17,25 +26,167 @@ struct Args {
     /// Number of seconds to wait in between calls
     #[arg(short, long, default_value_t = 5)]
     delay: u64,
+
+    /// Compress all saved files using zstd
+    #[arg(short, long)]
+    compress: bool,
+
+    /// Append all data of this session to a single JSONL file (instead of one folder per job)
+    #[arg(long, conflicts_with = "compress")]
+    jsonl: bool,
+
+    /// Additionally record node state changes (using sinfo)
+    #[arg(long)]
+    nodes: bool,
+
+    /// Also save the original squeue output line of each newly discovered job
+    #[arg(long)]
+    raw_lines: bool,
+
+    /// Write a manifest with checksums of all written files, allowing to detect corrupted files later on
+    #[arg(long, conflicts_with = "jsonl")]
+    manifest: bool,
+
+    /// Log the CPU and memory efficiency of jobs once they ended (similar to seff)
+    #[arg(long)]
+    efficiency: bool,
+
+    /// Adapt the delay between calls to the number of changes (between --min-delay and --max-delay)
+    #[arg(long)]
+    adaptive: bool,
+
+    /// Minimal number of seconds to wait in between calls (with --adaptive)
+    #[arg(long, default_value_t = 1, requires = "adaptive")]
+    min_delay: u64,
+
+    /// Maximal number of seconds to wait in between calls (with --adaptive)
+    #[arg(long, default_value_t = 60, requires = "adaptive")]
+    max_delay: u64,
+
+    /// Continuously export an OCEL of all recorded jobs to this file (after every call)
+    #[arg(long)]
+    live_ocel: Option<PathBuf>,
+
+    /// Instead of recording, extract an OCEL from the recording at --path into this file and print a summary
+    ///
+    /// The OCEL is exported as XML for `.xml` files, and as JSON otherwise.
+    #[arg(long)]
+    extract_ocel: Option<PathBuf>,
+
+    /// With --extract-ocel, write one OCEL per `partition` or `account` into the directory given there
+    #[arg(long, requires = "extract_ocel")]
+    split_by: Option<SplitBy>,
 }
 
 #[tokio::main(flavor = "current_thread")]
 async fn main() {
     let args = Args::parse();
-    let mut known_jobs = HashMap::default();
-    let mut all_ids = HashSet::default();
+    if let Some(dest) = &args.extract_ocel {
+        if let Some(split_by) = args.split_by {
+            let paths =
+                export_split_ocels(&args.path, dest, &ExtractionConfig::default(), split_by)
+                    .unwrap();
+            println!("Wrote {} OCEL to {dest:?}", paths.len());
+            return;
+        }
+        let (ocel, report) = extract_ocel_from_slurm_diffs_with_progress(
+            &args.path,
+            &ExtractionConfig::default(),
+            |_| {},
+            &CancellationToken::new(),
+        )
+        .unwrap();
+        ocel.export_path(dest, OcelFormat::from_path(dest)).unwrap();
+        print!("{report}");
+        return;
+    }
+    let tz = ClusterInfo::detect_timezone_locally().await.unwrap();
+    // Resume an existing JSON recording (JSONL sessions are always written to a new file)
+    let RecordingState {
+        mut known_jobs,
+        mut all_ids,
+    } = if args.jsonl {
+        RecordingState::default()
+    } else {
+        RecordingState::load_from(&args.path).unwrap()
+    };
+    let store: Box<dyn RecordingStore> = if args.jsonl {
+        let session_start = chrono::Utc::now().to_rfc3339().replace(":", "_");
+        Box::new(
+            JsonlStore::open(args.path.join(format!("{session_start}.jsonl")))
+                .unwrap()
+                .with_raw_lines(args.raw_lines),
+        )
+    } else if args.compress {
+        Box::new(
+            JsonDirStore::new(&args.path)
+                .with_zstd(0)
+                .with_raw_lines(args.raw_lines)
+                .with_manifest(args.manifest),
+        )
+    } else {
+        Box::new(
+            JsonDirStore::new(&args.path)
+                .with_raw_lines(args.raw_lines)
+                .with_manifest(args.manifest),
+        )
+    };
+    let store = CountingStore::new(store.as_ref());
+    let accumulator = OcelAccumulator::new(&store, ExtractionConfig::default());
+    let recorder: &dyn RecordingStore = if args.live_ocel.is_some() {
+        // Include the jobs recorded before resuming
+        if !args.jsonl {
+            accumulator.add_jobs(store.load_jobs().unwrap_or_default());
+        }
+        &accumulator
+    } else {
+        &store
+    };
+    let mut interval = AdaptiveInterval::new(
+        Duration::from_secs(args.min_delay),
+        Duration::from_secs(args.max_delay),
+    )
+    .starting_at(Duration::from_secs(args.delay));
+    let mut known_nodes = HashMap::default();
     let mut i = 0;
     loop {
-        squeue_diff(
-            || get_squeue_res_locally(&SqueueMode::ALL),
-            &args.path,
+        squeue_diff_with_final_states(
+            || get_squeue_res_locally(&SqueueMode::ALL, &tz),
+            |job_ids| async move {
+                let final_states = get_sacct_res_locally(&job_ids, &tz).await;
+                if args.efficiency {
+                    for job_id in &job_ids {
+                        match get_job_efficiency_locally(job_id).await {
+                            Ok(efficiency) => println!("{efficiency}"),
+                            Err(e) => eprintln!("Failed to get efficiency of {job_id}: {e:?}"),
+                        }
+                    }
+                }
+                final_states
+            },
+            recorder,
             &mut known_jobs,
             &mut all_ids,
         )
         .await
         .unwrap();
+        if args.nodes {
+            sinfo_diff_with_store(get_sinfo_nodes_locally, recorder, &mut known_nodes)
+                .await
+                .unwrap();
+        }
+        if let Some(live_ocel) = &args.live_ocel {
+            if let Err(e) = accumulator.ocel().export_json_path(live_ocel) {
+                eprintln!("Failed to export OCEL: {e:?}");
+            }
+        }
         i += 1;
-        println!("Ran for {} iterations, sleeping...", i);
-        tokio::time::sleep(tokio::time::Duration::from_secs(args.delay)).await;
+        let delay = if args.adaptive {
+            interval.update(store.take_changes())
+        } else {
+            Duration::from_secs(args.delay)
+        };
+        println!("Ran for {} iterations, sleeping for {:?}...", i, delay);
+        tokio::time::sleep(delay).await;
     }
 }
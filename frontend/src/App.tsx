@@ -99,7 +99,8 @@ export default function App({ context }: { context: AppContextType }) {
 
 function JobSubmission() {
   const [jobID, setJobID] = useState<string>();
-  const [jobState, setJobState] = useState<{ status: "PENDING", start_time: String | undefined } | { status: "RUNNING", start_time: String | undefined, end_time: String | undefined } | { status: "ENDED", state: string } | { status: "NOT_FOUND" }>();
+  const [jobEfficiency, setJobEfficiency] = useState<string>();
+  const [jobState, setJobState] = useState<{ status: "PENDING", start_time: String | undefined, reason: string | undefined } | { status: "RUNNING", start_time: String | undefined, end_time: String | undefined, exec_host: string | undefined, node_list: string | undefined, nodes: number } | { status: "ENDED", state: string } | { status: "NOT_FOUND" }>();
   const context = useContext(AppContext);
 
   useEffect(() => {
@@ -116,6 +117,14 @@ function JobSubmission() {
       }
     }
   }, [jobID])
+
+  useEffect(() => {
+    if (jobID && jobState?.status === "ENDED" && jobEfficiency === undefined) {
+      context.getJobEfficiency(jobID)
+        .then((e) => setJobEfficiency(e))
+        .catch((e) => console.error("Failed to get job efficiency: " + e))
+    }
+  }, [jobID, jobState?.status])
   return <div className="flex flex-col gap-1 justify-center items-center">
     <Button disabled={jobID !== undefined} onClick={() => {
       toast.promise(context.startTestJob(), { loading: "Starting job...", success: (s) => "Started job: " + s, error: (e) => "Failed to start job: " + e }).then((j) => {
@@ -135,9 +144,12 @@ function JobSubmission() {
       {jobState.status === "RUNNING" && <>
         <span>Start:</span> <span>{jobState.start_time}</span>
         <span>End:</span> <span>{jobState.end_time}</span>
+        <span>Host:</span> <span>{jobState.exec_host} ({jobState.nodes} node(s))</span>
+        {jobState.node_list && <><span>Nodes:</span> <span>{jobState.node_list}</span></>}
         </>}
         {jobState.status === "PENDING" && <>
         <span>Start:</span> <span>{jobState.start_time}</span>
+        <span>Reason:</span> <span>{jobState.reason}</span>
         </>}
 
         {jobState.status === "ENDED" && <>
@@ -145,8 +157,9 @@ function JobSubmission() {
         </>}
       
       </div>
+      {jobEfficiency !== undefined && <pre className="text-sm mt-2">{jobEfficiency}</pre>}
     </div>}
-    {jobID !== undefined && <Button onClick={() => { setJobID(undefined); setJobState(undefined) }} variant="destructive">
+    {jobID !== undefined && <Button onClick={() => { setJobID(undefined); setJobState(undefined); setJobEfficiency(undefined) }} variant="destructive">
       Reset
     </Button>}
   </div>
@@ -31,20 +31,27 @@ export const connectionFormSchema = z.object({
 });
 
 export type SqueueRow = {account: string, state: string}
+export type OCELExportFormat = "json" | "xml";
+export type OCELExtractionOptions = {format: OCELExportFormat, includeResourceUsage: boolean};
+export type OCELExtractionProgress = {jobs_processed: number, total_jobs: number};
 export type AppContextType = {
   runSqueue: () => Promise<string>;
   startSqueueLoop: (second_interval: number) => Promise<string>;
   stopSqueueLoop: () => Promise<string>,
   getLoopInfo: () => Promise<{secondInterval: number, runningSince: string, path: string}>,
   getSqueue: () => Promise<[string,SqueueRow[]]>,
-  extractOCEL: () => Promise<string>;
+  extractOCEL: (options: OCELExtractionOptions) => Promise<string>;
+  cancelOCELExtraction: () => Promise<string>;
+  // Return unlisten function (to de-register)
+  listenOCELExtractionProgress: (a: (progress: OCELExtractionProgress) => unknown) => Promise<() => unknown>,
   login: (cfg: z.infer<typeof connectionFormSchema>) => Promise<string>;
   logout: () => Promise<string>,
   isLoggedIn: () => Promise<boolean>,
   // Return unlisten function (to de-register)
   listenSqueue: (a: (timeAndRows: [string,SqueueRow[]]) => unknown) => Promise<() => unknown>,
   startTestJob: () => Promise<string>,
-  checkJobStatus: (jobID: string) => Promise<{status: "PENDING", start_time: String|undefined} |{status: "RUNNING", start_time: String|undefined, end_time: String|undefined} | {status: "ENDED", state: string}  | {status: "NOT_FOUND"}>,
+  checkJobStatus: (jobID: string) => Promise<{status: "PENDING", start_time: String|undefined, reason: string|undefined} |{status: "RUNNING", start_time: String|undefined, end_time: String|undefined, exec_host: string|undefined, node_list: string|undefined, nodes: number} | {status: "ENDED", state: string}  | {status: "NOT_FOUND"}>,
+  getJobEfficiency: (jobID: string) => Promise<string>,
 };
 
 const throwNoContext = () => {
@@ -57,11 +64,14 @@ export const DEFAULT_NO_CONTEXT: AppContextType = {
   stopSqueueLoop: throwNoContext,
   getLoopInfo: throwNoContext,
   extractOCEL: throwNoContext,
+  cancelOCELExtraction: throwNoContext,
+  listenOCELExtractionProgress: throwNoContext,
   login: throwNoContext,
   logout: throwNoContext,
   isLoggedIn: throwNoContext,
   listenSqueue: throwNoContext,
   startTestJob: throwNoContext,
-  checkJobStatus: throwNoContext
+  checkJobStatus: throwNoContext,
+  getJobEfficiency: throwNoContext
 };
 export const AppContext = createContext<AppContextType>(DEFAULT_NO_CONTEXT);
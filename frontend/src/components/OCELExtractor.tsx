@@ -1,7 +1,10 @@
-import { AppContext } from "@/AppContext";
-import { useContext } from "react";
+import { AppContext, OCELExportFormat, OCELExtractionProgress } from "@/AppContext";
+import { useContext, useEffect, useState } from "react";
 import toast from "react-hot-toast";
 import { Button } from "./ui/button";
+import { Checkbox } from "./ui/checkbox";
+import { Label } from "./ui/label";
+import { ToggleGroup, ToggleGroupItem } from "./ui/toggle-group";
 // type FileWithInfo = { file: File; timestamp: string | null };
 
 export default function OCELExtractor() {
@@ -12,17 +15,48 @@ export default function OCELExtractor() {
   // }>();
   // const [loading, setLoading] = useState(false);
   const backend = useContext(AppContext);
+  const [format, setFormat] = useState<OCELExportFormat>("json");
+  const [includeResourceUsage, setIncludeResourceUsage] = useState(false);
+  const [extracting, setExtracting] = useState(false);
+  const [progress, setProgress] = useState<OCELExtractionProgress>();
+  useEffect(() => {
+    const unregisterPromise = backend.listenOCELExtractionProgress((p) => setProgress(p));
+    return () => {
+      unregisterPromise.then((unregister) => unregister());
+    };
+  }, []);
   return (
     <div className="text-center">
-      <Button onClick={() => {
-        toast.promise(backend.extractOCEL(),{
+      <div className="flex flex-col items-center gap-1 mb-2">
+        <Label className="text-base">Export Format</Label>
+        <ToggleGroup variant="outline" type="single" value={format} onValueChange={(f) => setFormat(f === "xml" ? "xml" : "json")}>
+          <ToggleGroupItem value="json">JSON</ToggleGroupItem>
+          <ToggleGroupItem value="xml">XML</ToggleGroupItem>
+        </ToggleGroup>
+        <Label className="flex items-center">
+          <Checkbox className="mr-1" checked={includeResourceUsage} onCheckedChange={(c) => setIncludeResourceUsage(c === true)} />
+          Include resource usage of finished jobs
+        </Label>
+      </div>
+      <Button disabled={extracting} onClick={() => {
+        setExtracting(true);
+        setProgress(undefined);
+        toast.promise(backend.extractOCEL({ format, includeResourceUsage }),{
           loading: "Extracting...",
           success: (s) => s,
           error: (e) => `Failed to extract: ${String(e)}`
-        });
+        }).finally(() => setExtracting(false));
       }}>
         Extract OCEL
       </Button>
+      {extracting && <div className="flex flex-col items-center gap-1 mt-2">
+        {progress !== undefined && <span>Processed {progress.jobs_processed}/{progress.total_jobs} jobs</span>}
+        <Button variant="destructive" size="sm" onClick={() => {
+          backend.cancelOCELExtraction().then((s) => toast(s)).catch((e) => toast.error(String(e)));
+        }}>
+          Cancel
+        </Button>
+      </div>}
       {/* <DropZone
         onFilesAdded={(newFiles) => {
           setFiles((fs) => [